@@ -7,6 +7,8 @@
     clippy::needless_return,
     clippy::not_unsafe_ptr_arg_deref,
     clippy::uninlined_format_args,
+    unknown_lints,
+    static_mut_refs,
     unused_variables,
     dead_code
 )]
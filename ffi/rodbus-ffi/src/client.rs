@@ -131,6 +131,7 @@ pub(crate) unsafe fn client_channel_create_tls(
 
     let channel = rodbus::client::spawn_tls_client_task(
         host_addr,
+        rodbus::client::TcpOptions::default(),
         max_queued_requests as usize,
         retry_strategy.into(),
         tls_config,
@@ -40,6 +40,14 @@ impl From<rodbus::RequestError> for ffi::RequestError {
             rodbus::RequestError::Exception(ex) => ex.into(),
             rodbus::RequestError::Io(_) => ffi::RequestError::IoError,
             rodbus::RequestError::BadResponse(_) => ffi::RequestError::BadResponse,
+            // unreachable: the bindings never enable `Channel::set_fail_when_queue_full` and
+            // don't expose the verified writes, so there is no error code for these
+            rodbus::RequestError::QueueFull | rodbus::RequestError::WriteMismatch(_) => {
+                tracing::error!("unexpected error returned to the bindings: {}", err);
+                ffi::RequestError::InternalError
+            }
+            // errors added to the library before the bindings are updated to report them
+            _ => ffi::RequestError::InternalError,
         }
     }
 }
@@ -123,6 +131,7 @@ impl From<ffi::SerialPortSettings> for rodbus::SerialSettings {
                 ffi::StopBits::One => rodbus::StopBits::One,
                 ffi::StopBits::Two => rodbus::StopBits::Two,
             },
+            inter_frame_delay: None,
         }
     }
 }
@@ -33,6 +33,12 @@ enum Command {
     WriteSingleCoil(Indexed<bool>),
     WriteMultipleCoils(WriteMultiple<bool>),
     WriteMultipleRegisters(WriteMultiple<u16>),
+    MaskWriteRegister(MaskWriteRegister),
+    ReadWriteMultipleRegisters(ReadWriteMultiple<u16>),
+    ReadFifoQueue(u16),
+    ReadExceptionStatus,
+    GetCommEventCounter,
+    ReportServerId,
 }
 
 struct Args {
@@ -129,6 +135,40 @@ async fn run_command(
                 .write_multiple_registers(params, arg.clone())
                 .await?;
         }
+        Command::MaskWriteRegister(arg) => {
+            channel.mask_write_register(params, *arg).await?;
+        }
+        Command::ReadWriteMultipleRegisters(arg) => {
+            for x in channel
+                .read_write_multiple_registers(params, arg.clone())
+                .await?
+            {
+                println!("index: {} value: {}", x.index, x.value)
+            }
+        }
+        Command::ReadExceptionStatus => {
+            let status = channel.read_exception_status(params).await?;
+            println!("status: {:#04X}", status.value)
+        }
+        Command::GetCommEventCounter => {
+            let counter = channel.get_comm_event_counter(params).await?;
+            println!(
+                "status: {:#06X} event count: {}",
+                counter.status, counter.event_count
+            )
+        }
+        Command::ReportServerId => {
+            let id = channel.report_server_id(params).await?;
+            println!(
+                "data: {:02X?} run indicator: {:#04X}",
+                id.data, id.run_indicator
+            )
+        }
+        Command::ReadFifoQueue(address) => {
+            for value in channel.read_fifo_queue(params, *address).await? {
+                println!("value: {value}")
+            }
+        }
     }
     Ok(())
 }
@@ -231,6 +271,42 @@ fn get_command(matches: &ArgMatches) -> Result<Command, Error> {
         )?));
     }
 
+    if let Some(matches) = matches.subcommand_matches("mwr") {
+        let index = get_index(matches)?;
+        let and_mask = u16::from_str(matches.value_of("and_mask").unwrap())?;
+        let or_mask = u16::from_str(matches.value_of("or_mask").unwrap())?;
+        return Ok(Command::MaskWriteRegister(MaskWriteRegister::new(
+            index, and_mask, or_mask,
+        )));
+    }
+
+    if let Some(matches) = matches.subcommand_matches("rwmr") {
+        let read_range = get_address_range(matches)?;
+        let write_start = u16::from_str(matches.value_of("write_start").unwrap())?;
+        let values = get_register_values(matches)?;
+        return Ok(Command::ReadWriteMultipleRegisters(ReadWriteMultiple::new(
+            read_range,
+            write_start,
+            values,
+        )?));
+    }
+
+    if matches.subcommand_matches("res").is_some() {
+        return Ok(Command::ReadExceptionStatus);
+    }
+
+    if matches.subcommand_matches("gcec").is_some() {
+        return Ok(Command::GetCommEventCounter);
+    }
+
+    if matches.subcommand_matches("rsi").is_some() {
+        return Ok(Command::ReportServerId);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("rfq") {
+        return Ok(Command::ReadFifoQueue(get_index(matches)?));
+    }
+
     Err(Error::MissingSubCommand)
 }
 
@@ -424,6 +500,85 @@ fn parse_args() -> Result<Args, Error> {
                         .help("the values of the registers specified as a comma delimited list (e.g. 1,4,7)"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("mwr")
+                .about("mask write register")
+                .arg(
+                    Arg::with_name("index")
+                        .short("i")
+                        .long("index")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the address of the register"),
+                )
+                .arg(
+                    Arg::with_name("and_mask")
+                        .short("a")
+                        .long("and")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the AND mask"),
+                )
+                .arg(
+                    Arg::with_name("or_mask")
+                        .short("o")
+                        .long("or")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the OR mask"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rwmr")
+                .about("read/write multiple registers")
+                .arg(
+                    Arg::with_name("start")
+                        .short("s")
+                        .long("start")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the starting address of the registers to read"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .short("q")
+                        .long("quantity")
+                        .required(true)
+                        .takes_value(true)
+                        .help("quantity of registers to read"),
+                )
+                .arg(
+                    Arg::with_name("write_start")
+                        .short("w")
+                        .long("write-start")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the starting address of the registers to write"),
+                )
+                .arg(
+                    Arg::with_name("values")
+                        .short("v")
+                        .long("values")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the values of the registers to write specified as a comma delimited list (e.g. 1,4,7)"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("res").about("read exception status"))
+        .subcommand(SubCommand::with_name("gcec").about("get comm event counter"))
+        .subcommand(SubCommand::with_name("rsi").about("report server id"))
+        .subcommand(
+            SubCommand::with_name("rfq")
+                .about("read FIFO queue")
+                .arg(
+                    Arg::with_name("index")
+                        .short("i")
+                        .long("index")
+                        .required(true)
+                        .takes_value(true)
+                        .help("the address of the FIFO pointer register"),
+                ),
+        )
         .get_matches();
 
     let address = SocketAddr::from_str(matches.value_of("host").unwrap())?;
@@ -28,21 +28,26 @@ async fn main() -> Result<(), Box<dyn Error>> {
         [_, x] => x,
         _ => {
             eprintln!("please specify a transport:");
-            eprintln!("usage: outstation <transport> (tcp, rtu, tls-ca, tls-self-signed)");
+            eprintln!(
+                "usage: outstation <transport> (tcp, udp, rtu, ascii, tls-ca, tls-self-signed)"
+            );
             exit(-1);
         }
     };
     match transport {
         "tcp" => run_tcp().await,
+        "udp" => run_udp().await,
         #[cfg(feature = "serial")]
         "rtu" => run_rtu().await,
+        #[cfg(feature = "serial")]
+        "ascii" => run_ascii().await,
         #[cfg(feature = "tls")]
         "tls-ca" => run_tls(get_ca_chain_config()?).await,
         #[cfg(feature = "tls")]
         "tls-self-signed" => run_tls(get_self_signed_config()?).await,
         _ => {
             eprintln!(
-                "unknown transport '{transport}', options are (tcp, rtu, tls-ca, tls-self-signed)"
+                "unknown transport '{transport}', options are (tcp, udp, rtu, ascii, tls-ca, tls-self-signed)"
             );
             exit(-1);
         }
@@ -74,6 +79,19 @@ async fn run_tcp() -> Result<(), Box<dyn std::error::Error>> {
     run_channel(channel).await
 }
 
+async fn run_udp() -> Result<(), Box<dyn std::error::Error>> {
+    let channel = spawn_udp_client_task(
+        HostAddr::ip(IpAddr::V4(Ipv4Addr::LOCALHOST), 502),
+        1,                        // max queued requests
+        2,                        // retransmissions
+        default_retry_strategy(), // retry delays
+        DecodeLevel::default(),
+        Some(Box::new(LoggingListener)),
+    );
+
+    run_channel(channel).await
+}
+
 #[cfg(feature = "serial")]
 async fn run_rtu() -> Result<(), Box<dyn std::error::Error>> {
     // ANCHOR: create_rtu_channel
@@ -94,11 +112,30 @@ async fn run_rtu() -> Result<(), Box<dyn std::error::Error>> {
     run_channel(channel).await
 }
 
+#[cfg(feature = "serial")]
+async fn run_ascii() -> Result<(), Box<dyn std::error::Error>> {
+    let channel = spawn_ascii_client_task(
+        "/dev/ttySIM0",                    // path
+        rodbus::SerialSettings::default(), // serial settings
+        1,                                 // max queued requests
+        default_retry_strategy(),          // retry delays
+        DecodeLevel::new(
+            AppDecodeLevel::DataValues,
+            FrameDecodeLevel::Payload,
+            PhysDecodeLevel::Nothing,
+        ),
+        Some(Box::new(LoggingListener)),
+    );
+
+    run_channel(channel).await
+}
+
 #[cfg(feature = "tls")]
 async fn run_tls(tls_config: TlsClientConfig) -> Result<(), Box<dyn std::error::Error>> {
     // ANCHOR: create_tls_channel
     let channel = spawn_tls_client_task(
         HostAddr::ip(IpAddr::V4(Ipv4Addr::LOCALHOST), 802),
+        TcpOptions::default(),
         1,
         default_retry_strategy(),
         tls_config,
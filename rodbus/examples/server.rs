@@ -142,7 +142,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         [_, x] => x,
         _ => {
             eprintln!("please specify a transport:");
-            eprintln!("usage: outstation <transport> (tcp, rtu, tls-ca, tls-self-signed)");
+            eprintln!("usage: outstation <transport> (tcp, rtu, ascii, tls-ca, tls-self-signed)");
             exit(-1);
         }
     };
@@ -150,13 +150,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "tcp" => run_tcp().await,
         #[cfg(feature = "serial")]
         "rtu" => run_rtu().await,
+        #[cfg(feature = "serial")]
+        "ascii" => run_ascii().await,
         #[cfg(feature = "tls")]
         "tls-ca" => run_tls(get_ca_chain_config()?).await,
         #[cfg(feature = "tls")]
         "tls-self-signed" => run_tls(get_self_signed_config()?).await,
         _ => {
             eprintln!(
-                "unknown transport '{transport}', options are (tcp, rtu, tls-ca, tls-self-signed)"
+                "unknown transport '{transport}', options are (tcp, rtu, ascii, tls-ca, tls-self-signed)"
             );
             exit(-1);
         }
@@ -201,6 +203,25 @@ async fn run_rtu() -> Result<(), Box<dyn std::error::Error>> {
     run_server(server, handler).await
 }
 
+#[cfg(feature = "serial")]
+async fn run_ascii() -> Result<(), Box<dyn std::error::Error>> {
+    let (handler, map) = create_handler();
+
+    let server = rodbus::server::spawn_ascii_server_task(
+        "/dev/ttySIM1",
+        rodbus::SerialSettings::default(),
+        default_retry_strategy(),
+        map,
+        DecodeLevel::new(
+            AppDecodeLevel::DataValues,
+            FrameDecodeLevel::Payload,
+            PhysDecodeLevel::Data,
+        ),
+    )?;
+
+    run_server(server, handler).await
+}
+
 #[cfg(feature = "tls")]
 async fn run_tls(tls_config: TlsServerConfig) -> Result<(), Box<dyn std::error::Error>> {
     let (handler, map) = create_handler();
@@ -0,0 +1,229 @@
+use std::future::Future;
+
+use crate::client::{Channel, HostAddr, RequestParam, WriteMultiple};
+use crate::decode::DecodeLevel;
+use crate::error::{RequestError, Shutdown};
+use crate::retry::RetryStrategy;
+use crate::types::{AddressRange, Indexed};
+
+/// Channel whose methods block the calling thread until they complete
+///
+/// It owns a Tokio runtime which runs the channel task in the background, so the connection is
+/// maintained between requests. Its methods must not be called from within an asynchronous
+/// context.
+#[derive(Debug)]
+pub struct SyncChannel {
+    runtime: tokio::runtime::Runtime,
+    channel: Channel,
+}
+
+/// Requests of a [`SyncChannel`] that share the same [`RequestParam`]
+#[derive(Debug)]
+pub struct SyncSession<'a> {
+    channel: &'a mut SyncChannel,
+    param: RequestParam,
+}
+
+impl SyncChannel {
+    /// Create a runtime and spawn a channel onto it with `spawn`, e.g. a closure that calls
+    /// [`spawn_tcp_client_task`](crate::client::spawn_tcp_client_task)
+    ///
+    /// Fails if the runtime can't be created.
+    pub fn new<F>(spawn: F) -> std::io::Result<Self>
+    where
+        F: FnOnce() -> Channel,
+    {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        let channel = {
+            let _guard = runtime.enter();
+            spawn()
+        };
+        Ok(Self { runtime, channel })
+    }
+
+    /// Spawn a TCP channel, see [`spawn_tcp_client_task`](crate::client::spawn_tcp_client_task)
+    pub fn spawn_tcp(
+        host: HostAddr,
+        max_queued_requests: usize,
+        retry: Box<dyn RetryStrategy>,
+        decode: DecodeLevel,
+    ) -> std::io::Result<Self> {
+        Self::new(|| {
+            crate::client::spawn_tcp_client_task(host, max_queued_requests, retry, decode, None)
+        })
+    }
+
+    /// Asynchronous handle to the channel, e.g. to make a request that has no blocking method
+    /// with [`SyncChannel::block_on`]
+    pub fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    /// Run a future on the runtime of the channel until it completes
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Requests sent with the same parameters
+    pub fn session(&mut self, param: RequestParam) -> SyncSession<'_> {
+        SyncSession {
+            channel: self,
+            param,
+        }
+    }
+
+    /// Enable communications
+    pub fn enable(&mut self) -> Result<(), Shutdown> {
+        self.runtime.block_on(self.channel.enable())
+    }
+
+    /// Disable communications
+    pub fn disable(&mut self) -> Result<(), Shutdown> {
+        self.runtime.block_on(self.channel.disable())
+    }
+
+    /// Read coils from the server
+    pub fn read_coils(
+        &mut self,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        self.runtime.block_on(self.channel.read_coils(param, range))
+    }
+
+    /// Read discrete inputs from the server
+    pub fn read_discrete_inputs(
+        &mut self,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        self.runtime
+            .block_on(self.channel.read_discrete_inputs(param, range))
+    }
+
+    /// Read holding registers from the server
+    pub fn read_holding_registers(
+        &mut self,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        self.runtime
+            .block_on(self.channel.read_holding_registers(param, range))
+    }
+
+    /// Read input registers from the server
+    pub fn read_input_registers(
+        &mut self,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        self.runtime
+            .block_on(self.channel.read_input_registers(param, range))
+    }
+
+    /// Write a single coil on the server
+    pub fn write_single_coil(
+        &mut self,
+        param: RequestParam,
+        request: Indexed<bool>,
+    ) -> Result<Indexed<bool>, RequestError> {
+        self.runtime
+            .block_on(self.channel.write_single_coil(param, request))
+    }
+
+    /// Write a single register on the server
+    pub fn write_single_register(
+        &mut self,
+        param: RequestParam,
+        request: Indexed<u16>,
+    ) -> Result<Indexed<u16>, RequestError> {
+        self.runtime
+            .block_on(self.channel.write_single_register(param, request))
+    }
+
+    /// Write multiple contiguous coils on the server
+    pub fn write_multiple_coils(
+        &mut self,
+        param: RequestParam,
+        request: WriteMultiple<bool>,
+    ) -> Result<AddressRange, RequestError> {
+        self.runtime
+            .block_on(self.channel.write_multiple_coils(param, request))
+    }
+
+    /// Write multiple contiguous registers on the server
+    pub fn write_multiple_registers(
+        &mut self,
+        param: RequestParam,
+        request: WriteMultiple<u16>,
+    ) -> Result<AddressRange, RequestError> {
+        self.runtime
+            .block_on(self.channel.write_multiple_registers(param, request))
+    }
+}
+
+impl SyncSession<'_> {
+    /// Read coils from the server
+    pub fn read_coils(&mut self, range: AddressRange) -> Result<Vec<Indexed<bool>>, RequestError> {
+        self.channel.read_coils(self.param, range)
+    }
+
+    /// Read discrete inputs from the server
+    pub fn read_discrete_inputs(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        self.channel.read_discrete_inputs(self.param, range)
+    }
+
+    /// Read holding registers from the server
+    pub fn read_holding_registers(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        self.channel.read_holding_registers(self.param, range)
+    }
+
+    /// Read input registers from the server
+    pub fn read_input_registers(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        self.channel.read_input_registers(self.param, range)
+    }
+
+    /// Write a single coil on the server
+    pub fn write_single_coil(
+        &mut self,
+        request: Indexed<bool>,
+    ) -> Result<Indexed<bool>, RequestError> {
+        self.channel.write_single_coil(self.param, request)
+    }
+
+    /// Write a single register on the server
+    pub fn write_single_register(
+        &mut self,
+        request: Indexed<u16>,
+    ) -> Result<Indexed<u16>, RequestError> {
+        self.channel.write_single_register(self.param, request)
+    }
+
+    /// Write multiple contiguous coils on the server
+    pub fn write_multiple_coils(
+        &mut self,
+        request: WriteMultiple<bool>,
+    ) -> Result<AddressRange, RequestError> {
+        self.channel.write_multiple_coils(self.param, request)
+    }
+
+    /// Write multiple contiguous registers on the server
+    pub fn write_multiple_registers(
+        &mut self,
+        request: WriteMultiple<u16>,
+    ) -> Result<AddressRange, RequestError> {
+        self.channel.write_multiple_registers(self.param, request)
+    }
+}
@@ -2,16 +2,52 @@ use crate::Shutdown;
 
 /// wrap a Tokio receiver and only provide a recv() that returns a Result<T, Shutdown>
 /// that makes it harder to misuse.
-pub(crate) struct Receiver<T>(tokio::sync::mpsc::Receiver<T>);
+///
+/// An optional second receiver holds high priority values, which are always received first.
+pub(crate) struct Receiver<T> {
+    normal: tokio::sync::mpsc::Receiver<T>,
+    high: Option<tokio::sync::mpsc::Receiver<T>>,
+}
 
 impl<T> From<tokio::sync::mpsc::Receiver<T>> for Receiver<T> {
     fn from(value: tokio::sync::mpsc::Receiver<T>) -> Self {
-        Self(value)
+        Self {
+            normal: value,
+            high: None,
+        }
     }
 }
 
 impl<T> Receiver<T> {
+    pub(crate) fn with_priority(
+        normal: tokio::sync::mpsc::Receiver<T>,
+        high: tokio::sync::mpsc::Receiver<T>,
+    ) -> Self {
+        Self {
+            normal,
+            high: Some(high),
+        }
+    }
+
     pub(crate) async fn recv(&mut self) -> Result<T, Shutdown> {
-        self.0.recv().await.ok_or(Shutdown)
+        match &mut self.high {
+            None => self.normal.recv().await.ok_or(Shutdown),
+            Some(high) => tokio::select! {
+                biased;
+                // disabled once the high priority senders are dropped
+                Some(value) = high.recv() => Ok(value),
+                value = self.normal.recv() => value.ok_or(Shutdown),
+            },
+        }
+    }
+
+    /// receive a high priority value that is already queued
+    pub(crate) fn try_recv_high(&mut self) -> Option<T> {
+        self.high.as_mut().and_then(|x| x.try_recv().ok())
+    }
+
+    /// receive a normal priority value that is already queued
+    pub(crate) fn try_recv_normal(&mut self) -> Option<T> {
+        self.normal.try_recv().ok()
     }
 }
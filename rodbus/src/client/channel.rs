@@ -1,24 +1,121 @@
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::client::message::{Command, Promise, Request, RequestDetails, Setting};
+use crate::client::message::{Command, CommandReceiver, Promise, Request, RequestDetails, Setting};
+use crate::client::requests::diagnostics::Diagnostics;
+use crate::client::requests::get_comm_event_counter::GetCommEventCounter;
+use crate::client::requests::get_comm_event_log::GetCommEventLog;
+use crate::client::requests::mei_transport::MeiTransport;
+use crate::client::requests::raw_pdu::RawPdu;
 use crate::client::requests::read_bits::ReadBits;
+use crate::client::requests::read_device_id::{DeviceIdResponse, ReadDeviceId};
+use crate::client::requests::read_exception_status::ReadExceptionStatus;
+use crate::client::requests::read_fifo::ReadFifoQueue;
+use crate::client::requests::read_file_record::ReadFileRecords;
 use crate::client::requests::read_registers::ReadRegisters;
+use crate::client::requests::read_write_multiple::{ReadWriteMultiple, ReadWriteMultipleRegisters};
+use crate::client::requests::report_server_id::ReportServerId;
+use crate::client::requests::write_file_record::WriteFileRecords;
 use crate::client::requests::write_multiple::{MultipleWriteRequest, WriteMultiple};
 use crate::client::requests::write_single::SingleWrite;
+use crate::client::statistics::{ChannelStatistics, SharedStatistics};
+use crate::device_id::{DeviceIdCategory, DeviceIdentification};
+use crate::diagnostic::{CommEventLog, DiagnosticCounter, DiagnosticSubFunction};
 use crate::error::*;
-use crate::types::{AddressRange, BitIterator, Indexed, RegisterIterator, UnitId};
+use crate::exception::ExceptionCode;
+use crate::types::{
+    AddressRange, CommEventCounter, ExceptionStatus, FileRecord, FileRecordRange, Indexed,
+    MaskWriteRegister, ServerId, UnitId,
+};
 use crate::DecodeLevel;
 
 /// Async channel used to make requests
+///
+/// The futures returned by its methods are cancellation safe: dropping one before it completes,
+/// e.g. in a `select!` or with a timeout, only discards the response. A request that was
+/// already queued is still performed and the channel can keep being used.
 #[derive(Debug, Clone)]
 pub struct Channel {
     pub(crate) tx: tokio::sync::mpsc::Sender<Command>,
+    pub(crate) high_tx: tokio::sync::mpsc::Sender<Command>,
+    priority: RequestPriority,
+    fail_when_full: bool,
+    split_reads: bool,
+    retries: Option<usize>,
+    statistics: SharedStatistics,
+}
+
+/// Priority of the requests sent with a [`Channel`] handle
+///
+/// High priority requests are sent before the normal priority requests waiting in the queue,
+/// e.g. so that writes initiated by an operator don't wait behind background polls. The
+/// request being processed is never interrupted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestPriority {
+    /// Requests are processed in the order they are queued
+    #[default]
+    Normal,
+    /// Requests are processed before any queued normal priority request
+    High,
+}
+
+/// When an enabled [`Channel`] establishes its connection, see [`Channel::set_connect_mode`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConnectMode {
+    /// Connect as soon as the channel is enabled and reconnect whenever the connection is lost
+    #[default]
+    Eager,
+    /// Connect when a request is made, and after the connection is lost, when the next one is
+    Lazy,
+}
+
+/// What a [`Channel`] does with the requests made while it isn't connected, see
+/// [`Channel::set_disconnected_policy`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DisconnectedPolicy {
+    /// Fail the requests immediately with [`RequestError::NoConnection`]
+    #[default]
+    Fail,
+    /// Keep the requests and perform them once connected
+    Queue {
+        /// Maximum number of requests kept, the requests made afterwards fail immediately
+        max_queued: usize,
+        /// Requests that are still waiting after this duration fail
+        max_age: Duration,
+    },
+}
+
+/// Request sent by the keep-alive of a [`Channel`], see [`KeepAlive`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeepAliveProbe {
+    /// Diagnostics (FC08) Return Query Data with a single word
+    ReturnQueryData,
+    /// A read, for servers that don't support diagnostics
+    Read(crate::client::PollRequest),
+}
+
+/// Request sent when a connection has been idle for some time, to detect half-open
+/// connections before a real request fails, see [`Channel::set_keep_alive`]
+///
+/// The connection is closed and re-established if the probe isn't answered within its timeout.
+/// Any response, including an exception, shows that the link is up.
+#[derive(Copy, Clone, Debug)]
+pub struct KeepAlive {
+    /// Idle time after which the probe is sent
+    pub idle: Duration,
+    /// Unit id and response timeout of the probe
+    pub param: RequestParam,
+    /// Request sent as the probe
+    pub probe: KeepAliveProbe,
 }
 
 /// Request parameters to dispatch the request to the proper device
 #[derive(Debug, Clone, Copy)]
 pub struct RequestParam {
     /// Unit ID of the target device
+    ///
+    /// On serial channels, write requests sent to [`UnitId::broadcast()`] complete as soon as
+    /// they are sent because servers never answer a broadcast. Other requests are rejected.
     pub id: UnitId,
     /// Response timeout
     pub response_timeout: Duration,
@@ -34,19 +131,169 @@ impl RequestParam {
     }
 }
 
+/// Settings of the requests made with a [`Session`], see [`Channel::create_session_with`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Response timeout
+    pub timeout: Duration,
+    /// Number of times a request that failed is sent again, overriding the
+    /// [`RequestRetryPolicy`](crate::retry::RequestRetryPolicy) of the channel. `None` uses the
+    /// policy of the channel.
+    ///
+    /// The errors that are retried and the backoff are those of the policy of the channel. If it
+    /// has none, response timeouts and [`ExceptionCode::ServerDeviceBusy`] are retried
+    /// immediately.
+    ///
+    /// [`ExceptionCode::ServerDeviceBusy`]: crate::ExceptionCode::ServerDeviceBusy
+    pub retries: Option<usize>,
+    /// Maximum number of requests of the session and its clones waiting in the queue or for a
+    /// response, so that a busy session can't fill the queue shared with the other sessions of
+    /// the channel. `None` doesn't limit them.
+    pub max_outstanding: Option<usize>,
+}
+
+impl SessionConfig {
+    /// Create a configuration with a response timeout that uses the retry policy of the channel
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            retries: None,
+            max_outstanding: None,
+        }
+    }
+}
+
+/// Requests made to a single device through a [`Channel`] with the settings of that device
+///
+/// This lets devices with very different response times, e.g. behind the same gateway, share
+/// a channel without global settings.
+///
+/// The requests made with a session and its clones are sent in the order they are made. When
+/// the number of outstanding requests is limited, the requests that wait for their turn do so
+/// in the same order.
+#[derive(Debug, Clone)]
+pub struct Session {
+    channel: Channel,
+    param: RequestParam,
+    outstanding: Option<Arc<tokio::sync::Semaphore>>,
+}
+
 impl Channel {
+    /// create a handle and the receiver of its requests, with a queue of `capacity` requests for
+    /// each priority
+    pub(crate) fn create(capacity: usize) -> (Self, CommandReceiver) {
+        let (tx, rx) = tokio::sync::mpsc::channel(capacity);
+        let (high_tx, high_rx) = tokio::sync::mpsc::channel(capacity);
+        let statistics = ChannelStatistics::shared();
+        let channel = Self {
+            tx,
+            high_tx,
+            priority: RequestPriority::Normal,
+            fail_when_full: false,
+            split_reads: true,
+            retries: None,
+            statistics: statistics.clone(),
+        };
+        let rx = CommandReceiver {
+            rx: crate::channel::Receiver::with_priority(rx, high_rx),
+            statistics,
+        };
+        (channel, rx)
+    }
+
+    /// Set the priority of the requests sent with this handle
+    ///
+    /// The setting only applies to this handle and the handles cloned from it afterwards,
+    /// so a clone can be dedicated to high priority requests. Settings such as
+    /// [`Channel::enable`] are always queued with a normal priority.
+    pub fn set_priority(&mut self, priority: RequestPriority) {
+        self.priority = priority;
+    }
+
+    /// Fail requests with [`RequestError::QueueFull`] instead of waiting when the request queue
+    /// of the channel is full
+    ///
+    /// This lets applications that poll faster than the device answers detect the backlog and
+    /// shed load. The setting only applies to this handle and the handles cloned from it
+    /// afterwards. The depth of the queue is set when the channel is spawned.
+    pub fn set_fail_when_queue_full(&mut self, enabled: bool) {
+        self.fail_when_full = enabled;
+    }
+
+    /// Split reads of more points than a single request allows, e.g. 500 holding registers,
+    /// into consecutive requests whose results are joined. Enabled by default.
+    ///
+    /// When disabled, such reads fail with [`InvalidRange::CountTooLargeForType`] as required by
+    /// strict devices. A split read fails as a whole if any of its requests fails. The setting
+    /// only applies to this handle and the handles cloned from it afterwards.
+    ///
+    /// [`InvalidRange::CountTooLargeForType`]: crate::InvalidRange::CountTooLargeForType
+    pub fn set_split_large_reads(&mut self, enabled: bool) {
+        self.split_reads = enabled;
+    }
+
+    /// ranges read by each request of a read, which are split if enabled
+    fn read_ranges(&self, range: AddressRange, max: u16) -> Vec<AddressRange> {
+        if self.split_reads {
+            range.split(max).collect()
+        } else {
+            vec![range]
+        }
+    }
+
+    /// Counters of the requests and connections of the channel, shared by all its handles
+    pub fn statistics(&self) -> ChannelStatistics {
+        *self.statistics.lock().unwrap()
+    }
+
+    /// Create a [`Session`] that makes requests to the unit `id` with the settings of `config`
+    ///
+    /// The session uses a clone of this handle, so it keeps its priority and queue settings.
+    pub fn create_session_with(&self, id: UnitId, config: SessionConfig) -> Session {
+        let mut channel = self.clone();
+        channel.retries = config.retries;
+        Session {
+            channel,
+            param: RequestParam::new(id, config.timeout),
+            outstanding: config
+                .max_outstanding
+                .map(|x| Arc::new(tokio::sync::Semaphore::new(x.max(1)))),
+        }
+    }
+
+    /// queue a request, waiting for room in the queue unless the handle fails when it's full
+    async fn submit(&self, mut command: Command) -> Result<(), RequestError> {
+        if let Command::Request(request) = &mut command {
+            request.retries = self.retries;
+        }
+        let tx = match self.priority {
+            RequestPriority::Normal => &self.tx,
+            RequestPriority::High => &self.high_tx,
+        };
+        if self.fail_when_full {
+            return tx.try_send(command).map_err(|err| match err {
+                tokio::sync::mpsc::error::TrySendError::Full(_) => RequestError::QueueFull,
+                tokio::sync::mpsc::error::TrySendError::Closed(_) => RequestError::Shutdown,
+            });
+        }
+        tx.send(command).await?;
+        Ok(())
+    }
+
     #[cfg(feature = "serial")]
-    pub(crate) fn spawn_rtu(
+    pub(crate) fn spawn_serial(
         path: &str,
         serial_settings: crate::serial::SerialSettings,
+        framing: crate::types::Framing,
         max_queued_requests: usize,
         retry: Box<dyn crate::retry::RetryStrategy>,
         decode: DecodeLevel,
         listener: Option<Box<dyn crate::client::Listener<crate::client::PortState>>>,
     ) -> Self {
-        let (handle, task) = Self::create_rtu_handle_and_task(
+        let (handle, task) = Self::create_serial_handle_and_task(
             path,
             serial_settings,
+            framing,
             max_queued_requests,
             retry,
             decode,
@@ -57,9 +304,10 @@ impl Channel {
     }
 
     #[cfg(feature = "serial")]
-    pub(crate) fn create_rtu_handle_and_task(
+    pub(crate) fn create_serial_handle_and_task(
         path: &str,
         serial_settings: crate::serial::SerialSettings,
+        framing: crate::types::Framing,
         max_queued_requests: usize,
         retry: Box<dyn crate::retry::RetryStrategy>,
         decode: DecodeLevel,
@@ -68,21 +316,33 @@ impl Channel {
         use tracing::Instrument;
 
         let path = path.to_string();
-        let (tx, rx) = tokio::sync::mpsc::channel(max_queued_requests);
+        let (channel, rx) = Channel::create(max_queued_requests);
+        let span = match framing {
+            crate::types::Framing::Rtu => {
+                tracing::info_span!("Modbus-Client-RTU", "port" = ?path)
+            }
+            crate::types::Framing::Ascii => {
+                tracing::info_span!("Modbus-Client-ASCII", "port" = ?path)
+            }
+            crate::types::Framing::Mbap => {
+                tracing::info_span!("Modbus-Client-Serial", "port" = ?path)
+            }
+        };
         let task = async move {
             let _ = crate::serial::client::SerialChannelTask::new(
                 &path,
                 serial_settings,
-                rx.into(),
+                framing,
+                rx,
                 retry,
                 decode,
                 listener.unwrap_or_else(|| crate::client::NullListener::create()),
             )
             .run()
-            .instrument(tracing::info_span!("Modbus-Client-RTU", "port" = ?path))
+            .instrument(span)
             .await;
         };
-        (Channel { tx }, task)
+        (channel, task)
     }
 
     /// Enable communications
@@ -97,18 +357,51 @@ impl Channel {
         Ok(())
     }
 
+    /// Shut down the task of the channel and wait for it to terminate
+    ///
+    /// The connection is closed and the requests still waiting in the queue fail with
+    /// [`RequestError::Shutdown`], as do the requests made afterwards with any handle to the
+    /// channel. The request being processed, if any, completes first. Dropping every handle to
+    /// the channel has the same effect, but only once the queued requests are processed.
+    ///
+    /// Returns immediately if the task has already terminated.
+    pub async fn shutdown(&self) {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if self
+            .high_tx
+            .send(Command::Setting(Setting::Shutdown(tx)))
+            .await
+            .is_ok()
+        {
+            // the sender is never used, it's dropped when the task ends
+            let _ = rx.await;
+        }
+    }
+
     /// Read coils from the server
     pub async fn read_coils(
         &mut self,
         param: RequestParam,
         range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        let mut values = Vec::new();
+        for range in self.read_ranges(range, crate::constants::limits::MAX_READ_COILS_COUNT) {
+            values.extend(self.read_coils_once(param, range).await?);
+        }
+        Ok(values)
+    }
+
+    async fn read_coils_once(
+        &self,
+        param: RequestParam,
+        range: AddressRange,
     ) -> Result<Vec<Indexed<bool>>, RequestError> {
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<Indexed<bool>>, RequestError>>();
         let request = wrap(
             param,
             RequestDetails::ReadCoils(ReadBits::channel(range.of_read_bits()?, tx)),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -117,13 +410,25 @@ impl Channel {
         &mut self,
         param: RequestParam,
         range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        let mut values = Vec::new();
+        for range in self.read_ranges(range, crate::constants::limits::MAX_READ_COILS_COUNT) {
+            values.extend(self.read_discrete_inputs_once(param, range).await?);
+        }
+        Ok(values)
+    }
+
+    async fn read_discrete_inputs_once(
+        &self,
+        param: RequestParam,
+        range: AddressRange,
     ) -> Result<Vec<Indexed<bool>>, RequestError> {
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<Indexed<bool>>, RequestError>>();
         let request = wrap(
             param,
             RequestDetails::ReadDiscreteInputs(ReadBits::channel(range.of_read_bits()?, tx)),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -132,6 +437,18 @@ impl Channel {
         &mut self,
         param: RequestParam,
         range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let mut values = Vec::new();
+        for range in self.read_ranges(range, crate::constants::limits::MAX_READ_REGISTERS_COUNT) {
+            values.extend(self.read_holding_registers_once(param, range).await?);
+        }
+        Ok(values)
+    }
+
+    async fn read_holding_registers_once(
+        &self,
+        param: RequestParam,
+        range: AddressRange,
     ) -> Result<Vec<Indexed<u16>>, RequestError> {
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<Indexed<u16>>, RequestError>>();
         let request = wrap(
@@ -141,7 +458,7 @@ impl Channel {
                 tx,
             )),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -150,6 +467,18 @@ impl Channel {
         &mut self,
         param: RequestParam,
         range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let mut values = Vec::new();
+        for range in self.read_ranges(range, crate::constants::limits::MAX_READ_REGISTERS_COUNT) {
+            values.extend(self.read_input_registers_once(param, range).await?);
+        }
+        Ok(values)
+    }
+
+    async fn read_input_registers_once(
+        &self,
+        param: RequestParam,
+        range: AddressRange,
     ) -> Result<Vec<Indexed<u16>>, RequestError> {
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<Indexed<u16>>, RequestError>>();
         let request = wrap(
@@ -159,7 +488,7 @@ impl Channel {
                 tx,
             )),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -174,7 +503,7 @@ impl Channel {
             param,
             RequestDetails::WriteSingleCoil(SingleWrite::new(request, Promise::channel(tx))),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -189,7 +518,7 @@ impl Channel {
             param,
             RequestDetails::WriteSingleRegister(SingleWrite::new(request, Promise::channel(tx))),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -199,6 +528,7 @@ impl Channel {
         param: RequestParam,
         request: WriteMultiple<bool>,
     ) -> Result<AddressRange, RequestError> {
+        request.range.of_write_coils()?;
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<AddressRange, RequestError>>();
         let request = wrap(
             param,
@@ -207,7 +537,7 @@ impl Channel {
                 Promise::channel(tx),
             )),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -217,6 +547,7 @@ impl Channel {
         param: RequestParam,
         request: WriteMultiple<u16>,
     ) -> Result<AddressRange, RequestError> {
+        request.range.of_write_registers()?;
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<AddressRange, RequestError>>();
         let request = wrap(
             param,
@@ -225,7 +556,437 @@ impl Channel {
                 Promise::channel(tx),
             )),
         );
-        self.tx.send(request).await?;
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read the eight exception status outputs of the server
+    pub async fn read_exception_status(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<ExceptionStatus, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<ExceptionStatus, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReadExceptionStatus(ReadExceptionStatus::new(Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Perform a diagnostics request with an arbitrary sub-function and data, returning the
+    /// data of the response
+    ///
+    /// The sub-function echoed in the response is validated, but the data is returned as-is.
+    /// Over RTU, only requests and responses with a single data register can be framed.
+    pub async fn diagnostics(
+        &mut self,
+        param: RequestParam,
+        sub_function: DiagnosticSubFunction,
+        data: Vec<u16>,
+    ) -> Result<Vec<u16>, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<u16>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::Diagnostics(Diagnostics::new(sub_function, data, Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Ask the server to echo the data back, verifying that it did so
+    pub async fn return_query_data(
+        &mut self,
+        param: RequestParam,
+        data: Vec<u16>,
+    ) -> Result<(), RequestError> {
+        let response = self
+            .diagnostics(param, DiagnosticSubFunction::ReturnQueryData, data.clone())
+            .await?;
+        if response != data {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Restart the communications port of the server, optionally clearing its communication
+    /// event log
+    pub async fn restart_communications(
+        &mut self,
+        param: RequestParam,
+        clear_log: bool,
+    ) -> Result<(), RequestError> {
+        let data = if clear_log { 0xFF00 } else { 0x0000 };
+        self.diagnostics_echo(param, DiagnosticSubFunction::RestartCommunications, data)
+            .await
+    }
+
+    /// Read the diagnostic register of the server
+    pub async fn read_diagnostic_register(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<u16, RequestError> {
+        self.diagnostics_value(param, DiagnosticSubFunction::ReturnDiagnosticRegister)
+            .await
+    }
+
+    /// Clear all counters and the diagnostic register of the server
+    pub async fn clear_diagnostic_counters(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<(), RequestError> {
+        self.diagnostics_echo(param, DiagnosticSubFunction::ClearCounters, 0x0000)
+            .await
+    }
+
+    /// Read one of the diagnostic counters of the server
+    pub async fn read_diagnostic_counter(
+        &mut self,
+        param: RequestParam,
+        counter: DiagnosticCounter,
+    ) -> Result<u16, RequestError> {
+        self.diagnostics_value(param, counter.into()).await
+    }
+
+    async fn diagnostics_echo(
+        &mut self,
+        param: RequestParam,
+        sub_function: DiagnosticSubFunction,
+        data: u16,
+    ) -> Result<(), RequestError> {
+        let response = self.diagnostics(param, sub_function, vec![data]).await?;
+        if response != [data] {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+        Ok(())
+    }
+
+    async fn diagnostics_value(
+        &mut self,
+        param: RequestParam,
+        sub_function: DiagnosticSubFunction,
+    ) -> Result<u16, RequestError> {
+        match self.diagnostics(param, sub_function, vec![0x0000]).await?[..] {
+            [value] => Ok(value),
+            ref other => Err(AduParseError::ByteCountMismatch(2, 2 * other.len()).into()),
+        }
+    }
+
+    /// Read the status word and event counter of the server's communication port
+    pub async fn get_comm_event_counter(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<CommEventCounter, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<CommEventCounter, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::GetCommEventCounter(GetCommEventCounter::new(Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read the status word, counters, and communication event log of the server
+    pub async fn get_comm_event_log(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<CommEventLog, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<CommEventLog, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::GetCommEventLog(GetCommEventLog::new(Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read the device specific id and the run indicator status of the server
+    pub async fn report_server_id(
+        &mut self,
+        param: RequestParam,
+    ) -> Result<ServerId, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<ServerId, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReportServerId(ReportServerId::new(Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read the identification objects of the server in the requested category
+    ///
+    /// If the objects don't fit in a single response, additional requests are made until all
+    /// of them have been read.
+    pub async fn read_device_identification(
+        &mut self,
+        param: RequestParam,
+        category: DeviceIdCategory,
+    ) -> Result<DeviceIdentification, RequestError> {
+        let mut result = DeviceIdentification::default();
+        let mut object_id = 0;
+        loop {
+            let response = self
+                .read_device_id(param, category.read_device_id_code(), object_id)
+                .await?;
+            result.conformity_level = response.conformity_level;
+            result.objects.extend(response.objects);
+            match response.next_object_id {
+                None => return Ok(result),
+                // the object id must increase to guarantee that this terminates
+                Some(next) if next > object_id => object_id = next,
+                Some(next) => return Err(AduParseError::BadNextObjectId(next).into()),
+            }
+        }
+    }
+
+    async fn read_device_id(
+        &mut self,
+        param: RequestParam,
+        read_device_id_code: u8,
+        object_id: u8,
+    ) -> Result<DeviceIdResponse, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<DeviceIdResponse, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReadDeviceId(ReadDeviceId::new(
+                read_device_id_code,
+                object_id,
+                Promise::channel(tx),
+            )),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Modify a single register on the server using an AND mask and an OR mask
+    pub async fn mask_write_register(
+        &mut self,
+        param: RequestParam,
+        request: MaskWriteRegister,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<MaskWriteRegister, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::MaskWriteRegister(SingleWrite::new(request, Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Set the bits of `mask` in a register on the server, leaving the other bits unchanged
+    pub async fn set_register_bits(
+        &mut self,
+        param: RequestParam,
+        address: u16,
+        mask: u16,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        self.mask_write_register(param, MaskWriteRegister::set_bits(address, mask))
+            .await
+    }
+
+    /// Clear the bits of `mask` in a register on the server, leaving the other bits unchanged
+    pub async fn clear_register_bits(
+        &mut self,
+        param: RequestParam,
+        address: u16,
+        mask: u16,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        self.mask_write_register(param, MaskWriteRegister::clear_bits(address, mask))
+            .await
+    }
+
+    /// Write multiple contiguous coils and read them back, failing with
+    /// [`RequestError::WriteMismatch`] if the device didn't store the values written
+    pub async fn write_and_verify_coils(
+        &mut self,
+        param: RequestParam,
+        request: WriteMultiple<bool>,
+    ) -> Result<AddressRange, RequestError> {
+        let written = request.values.clone();
+        let range = self.write_multiple_coils(param, request).await?;
+        let read = self.read_coils(param, range).await?;
+        verify(&written, &read)?;
+        Ok(range)
+    }
+
+    /// Write multiple contiguous registers and read them back, failing with
+    /// [`RequestError::WriteMismatch`] if the device clamped or didn't store the values written
+    ///
+    /// This is meant for commissioning, where silently rejected values must be detected.
+    pub async fn write_and_verify_registers(
+        &mut self,
+        param: RequestParam,
+        request: WriteMultiple<u16>,
+    ) -> Result<AddressRange, RequestError> {
+        let written = request.values.clone();
+        let range = self.write_multiple_registers(param, request).await?;
+        let read = self.read_holding_registers(param, range).await?;
+        verify(&written, &read)?;
+        Ok(range)
+    }
+
+    /// Read a holding register, compute its new value with `update` and write it back,
+    /// returning the new value
+    ///
+    /// Only the bits changed by `update` are written with a mask write (FC22), so that the other
+    /// bits keep the value another client may write between the read and the write. Servers that
+    /// don't support mask writes receive the whole value with a single register write (FC06),
+    /// which overwrites such concurrent changes. Nothing is written if the value is unchanged.
+    pub async fn update_register<F>(
+        &mut self,
+        param: RequestParam,
+        address: u16,
+        update: F,
+    ) -> Result<u16, RequestError>
+    where
+        F: FnOnce(u16) -> u16,
+    {
+        let current = self
+            .read_holding_registers(param, AddressRange::try_from(address, 1)?)
+            .await?
+            .first()
+            .map(|x| x.value)
+            .ok_or(AduParseError::InsufficientBytes)?;
+        let new = update(current);
+        if new == current {
+            return Ok(new);
+        }
+
+        let mask = MaskWriteRegister::change(address, current, new);
+        match self.mask_write_register(param, mask).await {
+            Err(RequestError::Exception(ExceptionCode::IllegalFunction)) => {
+                tracing::info!("mask write register not supported, writing the whole register");
+                self.write_single_register(param, Indexed::new(address, new))
+                    .await?;
+                Ok(new)
+            }
+            Err(err) => Err(err),
+            Ok(_) => Ok(new),
+        }
+    }
+
+    /// Write multiple contiguous registers and then read a range of holding registers in a single transaction
+    pub async fn read_write_multiple_registers(
+        &mut self,
+        param: RequestParam,
+        request: ReadWriteMultiple<u16>,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        request.read_range.of_read_registers()?;
+        request.write.range.of_read_write_registers()?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<Indexed<u16>>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReadWriteMultipleRegisters(ReadWriteMultipleRegisters::channel(
+                request, tx,
+            )),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read the contents of a FIFO queue of registers on the server
+    pub async fn read_fifo_queue(
+        &mut self,
+        param: RequestParam,
+        address: u16,
+    ) -> Result<Vec<u16>, RequestError> {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<u16>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReadFifoQueue(ReadFifoQueue::new(address, Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Read one or more records of registers from files on the server in a single transaction
+    pub async fn read_file_records(
+        &mut self,
+        param: RequestParam,
+        request: Vec<FileRecordRange>,
+    ) -> Result<Vec<FileRecord>, RequestError> {
+        ReadFileRecords::validate(&request)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<FileRecord>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::ReadFileRecord(ReadFileRecords::new(request, Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Write one or more records of registers to files on the server in a single transaction
+    pub async fn write_file_records(
+        &mut self,
+        param: RequestParam,
+        request: Vec<FileRecord>,
+    ) -> Result<(), RequestError> {
+        WriteFileRecords::validate(&request)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::WriteFileRecord(WriteFileRecords::new(request, Promise::channel(tx))),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Send a FC43 Encapsulated Interface Transport request with an arbitrary MEI type, returning
+    /// the data of the response that follows the echoed MEI type
+    ///
+    /// This can be used to address devices using MEI types the library doesn't interpret, e.g.
+    /// CANopen General Reference (MEI type 13). Exception responses are returned as
+    /// [`RequestError::Exception`].
+    ///
+    /// Note: on serial channels, only responses to MEI type 14 (Read Device Identification)
+    /// can be framed because RTU frames don't carry the length of the other MEI types.
+    pub async fn encapsulated_interface_transport(
+        &mut self,
+        param: RequestParam,
+        mei_type: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, RequestError> {
+        MeiTransport::validate(data)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<u8>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::MeiTransport(MeiTransport::new(
+                mei_type,
+                data.to_vec(),
+                Promise::channel(tx),
+            )),
+        );
+        self.submit(request).await?;
+        rx.await?
+    }
+
+    /// Send a request PDU made of an arbitrary function code and data, returning the data of the
+    /// response PDU that follows the function code
+    ///
+    /// This allows using vendor-specific function codes that the library doesn't implement. The
+    /// request still gets framing, transaction matching, the response timeout and exception
+    /// decoding. No interpretation of the data is done in either direction.
+    ///
+    /// Note: the RTU framing of a response depends on its function code, so on serial channels
+    /// this only works with function codes that the library knows how to frame.
+    pub async fn send_raw_pdu(
+        &mut self,
+        param: RequestParam,
+        function_code: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, RequestError> {
+        RawPdu::validate(function_code, data)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<u8>, RequestError>>();
+        let request = wrap(
+            param,
+            RequestDetails::RawPdu(RawPdu::new(
+                function_code,
+                data.to_vec(),
+                Promise::channel(tx),
+            )),
+        );
+        self.submit(request).await?;
         rx.await?
     }
 
@@ -236,157 +997,516 @@ impl Channel {
             .await?;
         Ok(())
     }
+
+    /// Change when the connection is established once the channel is enabled
+    ///
+    /// In [`ConnectMode::Lazy`], the channel stays in [`ClientState::Disabled`] until a request
+    /// is made, so that a link isn't held open when nothing is requested. A disabled channel
+    /// never connects and fails requests immediately with [`RequestError::NoConnection`],
+    /// whatever the mode.
+    ///
+    /// [`ClientState::Disabled`]: crate::client::ClientState::Disabled
+    pub async fn set_connect_mode(&mut self, mode: ConnectMode) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::ConnectMode(mode)))
+            .await?;
+        Ok(())
+    }
+
+    /// Change what the channel does with the requests made while it is enabled but not
+    /// connected, e.g. while it waits to reconnect
+    ///
+    /// Requests made while the channel is disabled always fail immediately with
+    /// [`RequestError::NoConnection`], and so do the queued ones when it's disabled.
+    pub async fn set_disconnected_policy(
+        &mut self,
+        policy: DisconnectedPolicy,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::DisconnectedPolicy(policy)))
+            .await?;
+        Ok(())
+    }
+
+    /// Send a keep-alive probe whenever the connection is idle for some time, or stop doing so
+    /// with `None` (the default)
+    pub async fn set_keep_alive(&mut self, keep_alive: Option<KeepAlive>) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::KeepAlive(keep_alive)))
+            .await?;
+        Ok(())
+    }
+
+    /// Close the connection when no request is made for `period`, and only reconnect once the
+    /// next request is made, or keep it open with `None` (the default)
+    ///
+    /// This is meant for devices that limit the number of concurrent connections. Requests
+    /// still wait for the connection to be re-established, and a keep-alive probe sent within
+    /// the period keeps the connection open.
+    pub async fn set_idle_disconnect(&mut self, period: Option<Duration>) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::IdleDisconnect(period)))
+            .await?;
+        Ok(())
+    }
+
+    /// Accept a response whose transaction id doesn't match the one of the request, logging a
+    /// warning, or discard it with `false` (the default)
+    ///
+    /// This is meant for gateways that always answer with the same transaction id, e.g. 0. It
+    /// only applies while a single request is in flight, see [`Channel::set_max_in_flight`], and
+    /// a late response to a request that timed out may then be taken for the response to the
+    /// next one.
+    pub async fn set_lenient_tx_id(&mut self, enabled: bool) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::LenientTxId(enabled)))
+            .await?;
+        Ok(())
+    }
+
+    /// Accept the responses whose MBAP protocol id isn't 0, logging a warning, or reject them
+    /// as malformed frames with `false` (the default)
+    ///
+    /// This is meant for RTU to TCP converters that don't set the field properly, and has no
+    /// effect on serial channels.
+    pub async fn set_lenient_protocol_id(&mut self, enabled: bool) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::LenientProtocolId(enabled)))
+            .await?;
+        Ok(())
+    }
+
+    /// Accept responses whose function code and body are up to `length` bytes long, instead of
+    /// the 253 bytes allowed by the spec (the default)
+    ///
+    /// This is meant for non-compliant devices that return larger responses, and resizes the
+    /// receive buffer of the channel accordingly. The length is at least 253 bytes and at most
+    /// 65534, the longest ADU described by an MBAP header.
+    pub async fn set_max_adu_length(&mut self, length: usize) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::MaxAduLength(length)))
+            .await?;
+        Ok(())
+    }
+
+    /// Coalesce the reads waiting in the queue of the channel, or stop doing so with `None`
+    /// (the default)
+    ///
+    /// Reads of the same type from the same unit id whose ranges overlap or are at most
+    /// `max_gap` addresses apart are performed with a single request, and each caller receives
+    /// its own values. This reduces the number of transactions on slow links, but a gap may
+    /// include addresses that the device rejects, failing every read of the group. Reads are
+    /// never moved ahead of a write queued before them, and only the first few requests of the
+    /// queue are examined.
+    pub async fn set_read_coalescing(&mut self, max_gap: Option<u16>) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::ReadCoalescing(max_gap)))
+            .await?;
+        Ok(())
+    }
+
+    /// Allow up to `count` requests to wait for a response at the same time, instead of one
+    /// (the default)
+    ///
+    /// Responses are matched to their request using the transaction id, so this only applies
+    /// to TCP, TLS and UDP channels and requires a server that processes requests concurrently or
+    /// queues them, such as most gateways. Requests in flight are retried and retransmitted like
+    /// the others, while the next requests are sent.
+    pub async fn set_max_in_flight(&mut self, count: usize) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::MaxInFlight(count)))
+            .await?;
+        Ok(())
+    }
+
+    /// Retry the requests that fail with a transient error according to a policy, or never
+    /// retry them with `None` (the default)
+    ///
+    /// The policy applies to the requests of every handle to the channel, starting with the
+    /// next request it processes.
+    pub async fn set_request_retry_policy(
+        &mut self,
+        policy: Option<crate::retry::RequestRetryPolicy>,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::RequestRetry(policy)))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the listener notified of the connection events of a TCP or TLS channel, replacing the
+    /// previous one
+    ///
+    /// The listener receives the address of the server once connected and the reason each
+    /// connection was closed. Other channels don't report connection events.
+    pub async fn set_connection_listener(
+        &mut self,
+        listener: Box<dyn crate::client::Listener<crate::client::ConnectionEvent>>,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::ConnectionListener(listener)))
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the TLS configuration, e.g. to rotate the certificate and private key
+    ///
+    /// The current connection and the queued requests are not affected. The new configuration
+    /// is used for the next connection. It has no effect on channels that don't use TLS.
+    #[cfg(feature = "tls")]
+    pub async fn set_tls_config(
+        &mut self,
+        config: crate::tcp::tls::TlsClientConfig,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(Command::Setting(Setting::TlsConfig(config)))
+            .await?;
+        Ok(())
+    }
 }
 
-/// Callback-based session
-///
-/// This interface removes some allocations when returning results.
-/// Its primary use is for the bindings. Rust users should prefer
-/// interacting with the channel directly.
-#[deprecated(
-    since = "1.4.0",
-    note = "Use Channel. This type will be removed in 2.0"
-)]
-#[derive(Debug, Clone)]
-pub struct CallbackSession {
-    tx: tokio::sync::mpsc::Sender<Command>,
-    param: RequestParam,
+impl KeepAlive {
+    /// request whose result is only used to tell if the link is up
+    pub(crate) fn request(&self) -> Result<Request, RequestError> {
+        let details = match self.probe {
+            KeepAliveProbe::ReturnQueryData => RequestDetails::Diagnostics(Diagnostics::new(
+                DiagnosticSubFunction::ReturnQueryData,
+                vec![0x0000],
+                Promise::new(|_| {}),
+            )),
+            KeepAliveProbe::Read(read) => read.details()?,
+        };
+        Ok(Request::new(
+            self.param.id,
+            self.param.response_timeout,
+            details,
+        ))
+    }
 }
 
-#[allow(deprecated)]
-impl CallbackSession {
-    /// Create a [CallbackSession] from a [Channel] and the specified [RequestParam]
-    pub fn new(channel: Channel, param: RequestParam) -> Self {
-        CallbackSession {
-            tx: channel.tx,
-            param,
+impl Session {
+    /// Parameters of the requests made with the session
+    pub fn param(&self) -> RequestParam {
+        self.param
+    }
+
+    /// wait until the session may have another request outstanding
+    async fn acquire(&self) -> Result<Option<tokio::sync::OwnedSemaphorePermit>, RequestError> {
+        match &self.outstanding {
+            // the semaphore is never closed
+            Some(x) => x
+                .clone()
+                .acquire_owned()
+                .await
+                .map(Some)
+                .map_err(|_| RequestError::Shutdown),
+            None => Ok(None),
         }
     }
 
     /// Read coils from the server
-    pub async fn read_coils<C>(&mut self, range: AddressRange, callback: C)
-    where
-        C: FnOnce(Result<BitIterator, RequestError>) + Send + Sync + 'static,
-    {
-        self.read_bits(range, callback, RequestDetails::ReadCoils)
-            .await;
+    pub async fn read_coils(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_coils(self.param, range).await
     }
 
     /// Read discrete inputs from the server
-    pub async fn read_discrete_inputs<C>(&mut self, range: AddressRange, callback: C)
-    where
-        C: FnOnce(Result<BitIterator, RequestError>) + Send + Sync + 'static,
-    {
-        self.read_bits(range, callback, RequestDetails::ReadDiscreteInputs)
-            .await;
+    pub async fn read_discrete_inputs(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<bool>>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_discrete_inputs(self.param, range).await
     }
 
     /// Read holding registers from the server
-    pub async fn read_holding_registers<C>(&mut self, range: AddressRange, callback: C)
-    where
-        C: FnOnce(Result<RegisterIterator, RequestError>) + Send + Sync + 'static,
-    {
-        self.read_registers(range, callback, RequestDetails::ReadHoldingRegisters)
-            .await;
+    pub async fn read_holding_registers(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_holding_registers(self.param, range).await
     }
 
     /// Read input registers from the server
-    pub async fn read_input_registers<C>(&mut self, range: AddressRange, callback: C)
-    where
-        C: FnOnce(Result<RegisterIterator, RequestError>) + Send + Sync + 'static,
-    {
-        self.read_registers(range, callback, RequestDetails::ReadInputRegisters)
-            .await;
+    pub async fn read_input_registers(
+        &mut self,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_input_registers(self.param, range).await
     }
 
-    /// Write a single coil to the server
-    pub async fn write_single_coil<C>(&mut self, value: Indexed<bool>, callback: C)
-    where
-        C: FnOnce(Result<Indexed<bool>, RequestError>) + Send + Sync + 'static,
-    {
-        self.send(wrap(
-            self.param,
-            RequestDetails::WriteSingleCoil(SingleWrite::new(value, Promise::new(callback))),
-        ))
-        .await;
+    /// Write a single coil on the server
+    pub async fn write_single_coil(
+        &mut self,
+        request: Indexed<bool>,
+    ) -> Result<Indexed<bool>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.write_single_coil(self.param, request).await
     }
 
-    /// Write a single registers to the server
-    pub async fn write_single_register<C>(&mut self, value: Indexed<u16>, callback: C)
-    where
-        C: FnOnce(Result<Indexed<u16>, RequestError>) + Send + Sync + 'static,
-    {
-        self.send(wrap(
-            self.param,
-            RequestDetails::WriteSingleRegister(SingleWrite::new(value, Promise::new(callback))),
-        ))
-        .await;
+    /// Write a single register on the server
+    pub async fn write_single_register(
+        &mut self,
+        request: Indexed<u16>,
+    ) -> Result<Indexed<u16>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .write_single_register(self.param, request)
+            .await
     }
 
-    /// Write multiple contiguous registers to the server
-    pub async fn write_multiple_registers<C>(&mut self, value: WriteMultiple<u16>, callback: C)
-    where
-        C: FnOnce(Result<AddressRange, RequestError>) + Send + Sync + 'static,
-    {
-        self.send(wrap(
-            self.param,
-            RequestDetails::WriteMultipleRegisters(MultipleWriteRequest::new(
-                value,
-                Promise::new(callback),
-            )),
-        ))
-        .await;
+    /// Write multiple contiguous coils on the server
+    pub async fn write_multiple_coils(
+        &mut self,
+        request: WriteMultiple<bool>,
+    ) -> Result<AddressRange, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.write_multiple_coils(self.param, request).await
     }
 
-    /// Write multiple contiguous coils to the server
-    pub async fn write_multiple_coils<C>(&mut self, value: WriteMultiple<bool>, callback: C)
-    where
-        C: FnOnce(Result<AddressRange, RequestError>) + Send + Sync + 'static,
-    {
-        self.send(wrap(
-            self.param,
-            RequestDetails::WriteMultipleCoils(MultipleWriteRequest::new(
-                value,
-                Promise::new(callback),
-            )),
-        ))
-        .await;
+    /// Write multiple contiguous registers on the server
+    pub async fn write_multiple_registers(
+        &mut self,
+        request: WriteMultiple<u16>,
+    ) -> Result<AddressRange, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .write_multiple_registers(self.param, request)
+            .await
     }
 
-    async fn read_bits<C, W>(&mut self, range: AddressRange, callback: C, wrap_req: W)
+    /// Read-modify-write a holding register, see [`Channel::update_register`]
+    pub async fn update_register<F>(&mut self, address: u16, update: F) -> Result<u16, RequestError>
     where
-        C: FnOnce(Result<BitIterator, RequestError>) + Send + Sync + 'static,
-        W: Fn(ReadBits) -> RequestDetails,
+        F: FnOnce(u16) -> u16,
     {
-        let mut promise = crate::client::requests::read_bits::Promise::new(callback);
-        let range = match range.of_read_bits() {
-            Ok(x) => x,
-            Err(err) => return promise.failure(err.into()),
-        };
-        self.send(wrap(self.param, wrap_req(ReadBits::new(range, promise))))
-            .await;
+        let _permit = self.acquire().await?;
+        self.channel
+            .update_register(self.param, address, update)
+            .await
     }
 
-    async fn read_registers<C, W>(&mut self, range: AddressRange, callback: C, wrap_req: W)
-    where
-        C: FnOnce(Result<RegisterIterator, RequestError>) + Send + Sync + 'static,
-        W: Fn(ReadRegisters) -> RequestDetails,
-    {
-        let mut promise = crate::client::requests::read_registers::Promise::new(callback);
-        let range = match range.of_read_registers() {
-            Ok(x) => x,
-            Err(err) => return promise.failure(err.into()),
-        };
-        self.send(wrap(
-            self.param,
-            wrap_req(ReadRegisters::new(range, promise)),
-        ))
-        .await;
+    /// Read the eight exception status outputs of the server
+    pub async fn read_exception_status(&mut self) -> Result<ExceptionStatus, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_exception_status(self.param).await
+    }
+
+    /// Perform a diagnostics request, see [`Channel::diagnostics`]
+    pub async fn diagnostics(
+        &mut self,
+        sub_function: DiagnosticSubFunction,
+        data: Vec<u16>,
+    ) -> Result<Vec<u16>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .diagnostics(self.param, sub_function, data)
+            .await
     }
 
-    async fn send(&mut self, command: Command) {
-        // dropping the command will automatically fail requests with SHUTDOWN
-        let _ = self.tx.send(command).await;
+    /// Ask the server to echo the data back, verifying that it did so
+    pub async fn return_query_data(&mut self, data: Vec<u16>) -> Result<(), RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.return_query_data(self.param, data).await
+    }
+
+    /// Restart the communications port of the server, optionally clearing its communication
+    /// event log
+    pub async fn restart_communications(&mut self, clear_log: bool) -> Result<(), RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .restart_communications(self.param, clear_log)
+            .await
+    }
+
+    /// Read the diagnostic register of the server
+    pub async fn read_diagnostic_register(&mut self) -> Result<u16, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_diagnostic_register(self.param).await
+    }
+
+    /// Clear all counters and the diagnostic register of the server
+    pub async fn clear_diagnostic_counters(&mut self) -> Result<(), RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.clear_diagnostic_counters(self.param).await
+    }
+
+    /// Read one of the diagnostic counters of the server
+    pub async fn read_diagnostic_counter(
+        &mut self,
+        counter: DiagnosticCounter,
+    ) -> Result<u16, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .read_diagnostic_counter(self.param, counter)
+            .await
+    }
+
+    /// Read the status word and event counter of the server's communication port
+    pub async fn get_comm_event_counter(&mut self) -> Result<CommEventCounter, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.get_comm_event_counter(self.param).await
+    }
+
+    /// Read the status word, counters, and communication event log of the server
+    pub async fn get_comm_event_log(&mut self) -> Result<CommEventLog, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.get_comm_event_log(self.param).await
+    }
+
+    /// Read the device specific id and the run indicator status of the server
+    pub async fn report_server_id(&mut self) -> Result<ServerId, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.report_server_id(self.param).await
+    }
+
+    /// Read the identification objects of the server in the requested category, see
+    /// [`Channel::read_device_identification`]
+    pub async fn read_device_identification(
+        &mut self,
+        category: DeviceIdCategory,
+    ) -> Result<DeviceIdentification, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .read_device_identification(self.param, category)
+            .await
+    }
+
+    /// Modify a single register on the server using an AND mask and an OR mask
+    pub async fn mask_write_register(
+        &mut self,
+        request: MaskWriteRegister,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.mask_write_register(self.param, request).await
+    }
+
+    /// Set the bits of `mask` in a register on the server, leaving the other bits unchanged
+    pub async fn set_register_bits(
+        &mut self,
+        address: u16,
+        mask: u16,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .set_register_bits(self.param, address, mask)
+            .await
+    }
+
+    /// Clear the bits of `mask` in a register on the server, leaving the other bits unchanged
+    pub async fn clear_register_bits(
+        &mut self,
+        address: u16,
+        mask: u16,
+    ) -> Result<MaskWriteRegister, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .clear_register_bits(self.param, address, mask)
+            .await
+    }
+
+    /// Write multiple contiguous coils and read them back, see [`Channel::write_and_verify_coils`]
+    pub async fn write_and_verify_coils(
+        &mut self,
+        request: WriteMultiple<bool>,
+    ) -> Result<AddressRange, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .write_and_verify_coils(self.param, request)
+            .await
+    }
+
+    /// Write multiple contiguous registers and read them back, see
+    /// [`Channel::write_and_verify_registers`]
+    pub async fn write_and_verify_registers(
+        &mut self,
+        request: WriteMultiple<u16>,
+    ) -> Result<AddressRange, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .write_and_verify_registers(self.param, request)
+            .await
+    }
+
+    /// Write multiple contiguous registers and then read a range of holding registers in a single
+    /// transaction
+    pub async fn read_write_multiple_registers(
+        &mut self,
+        request: ReadWriteMultiple<u16>,
+    ) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .read_write_multiple_registers(self.param, request)
+            .await
+    }
+
+    /// Read the contents of a FIFO queue of registers on the server
+    pub async fn read_fifo_queue(&mut self, address: u16) -> Result<Vec<u16>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_fifo_queue(self.param, address).await
+    }
+
+    /// Read one or more records of registers from files on the server in a single transaction
+    pub async fn read_file_records(
+        &mut self,
+        request: Vec<FileRecordRange>,
+    ) -> Result<Vec<FileRecord>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.read_file_records(self.param, request).await
+    }
+
+    /// Write one or more records of registers to files on the server in a single transaction
+    pub async fn write_file_records(
+        &mut self,
+        request: Vec<FileRecord>,
+    ) -> Result<(), RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel.write_file_records(self.param, request).await
+    }
+
+    /// Send a FC43 request with an arbitrary MEI type, see
+    /// [`Channel::encapsulated_interface_transport`]
+    pub async fn encapsulated_interface_transport(
+        &mut self,
+        mei_type: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .encapsulated_interface_transport(self.param, mei_type, data)
+            .await
+    }
+
+    /// Send a request PDU with an arbitrary function code, see [`Channel::send_raw_pdu`]
+    pub async fn send_raw_pdu(
+        &mut self,
+        function_code: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, RequestError> {
+        let _permit = self.acquire().await?;
+        self.channel
+            .send_raw_pdu(self.param, function_code, data)
+            .await
+    }
+}
+
+/// compare the values written with the values read back
+fn verify<T>(written: &[T], read: &[Indexed<T>]) -> Result<(), WriteMismatch>
+where
+    T: Copy + PartialEq + Into<u16>,
+{
+    match written.iter().zip(read).find(|(w, r)| **w != r.value) {
+        Some((w, r)) => Err(WriteMismatch {
+            address: r.index,
+            written: (*w).into(),
+            read: r.value.into(),
+        }),
+        None => Ok(()),
     }
 }
 
@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+
+use crate::client::message::{Command, Request, RequestDetails};
+use crate::client::requests::read_bits::{self, ReadBits};
+use crate::client::requests::read_registers::{self, ReadRegisters};
+use crate::constants::limits::{MAX_READ_COILS_COUNT, MAX_READ_REGISTERS_COUNT};
+use crate::decode::AppDecodeLevel;
+use crate::error::RequestError;
+use crate::types::{AddressRange, BitIterator, RegisterIterator};
+
+/// number of queued commands taken ahead of time, e.g. to find the reads that can be coalesced
+pub(crate) const MAX_LOOKAHEAD: usize = 8;
+
+/// reads that can be coalesced
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ReadKind {
+    Coils,
+    DiscreteInputs,
+    HoldingRegisters,
+    InputRegisters,
+}
+
+/// queued reads of the same kind and unit id performed with a single request
+pub(crate) struct ReadGroup {
+    kind: ReadKind,
+    range: AddressRange,
+    requests: Vec<Request>,
+}
+
+impl ReadKind {
+    fn max_count(self) -> u16 {
+        match self {
+            ReadKind::Coils | ReadKind::DiscreteInputs => MAX_READ_COILS_COUNT,
+            ReadKind::HoldingRegisters | ReadKind::InputRegisters => MAX_READ_REGISTERS_COUNT,
+        }
+    }
+}
+
+impl Request {
+    fn read(&self) -> Option<(ReadKind, AddressRange)> {
+        match &self.details {
+            RequestDetails::ReadCoils(x) => Some((ReadKind::Coils, x.request.get())),
+            RequestDetails::ReadDiscreteInputs(x) => {
+                Some((ReadKind::DiscreteInputs, x.request.get()))
+            }
+            RequestDetails::ReadHoldingRegisters(x) => {
+                Some((ReadKind::HoldingRegisters, x.request.get()))
+            }
+            RequestDetails::ReadInputRegisters(x) => {
+                Some((ReadKind::InputRegisters, x.request.get()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// range covering `a` and `b` if there are at most `max_gap` addresses between them and
+/// it contains at most `max_count` addresses
+fn merge(a: AddressRange, b: AddressRange, max_gap: u16, max_count: u16) -> Option<AddressRange> {
+    let (a_start, a_end) = (a.start as u32, a.start as u32 + a.count as u32);
+    let (b_start, b_end) = (b.start as u32, b.start as u32 + b.count as u32);
+    let gap = b_start
+        .saturating_sub(a_end)
+        .max(a_start.saturating_sub(b_end));
+    let (start, end) = (a_start.min(b_start), a_end.max(b_end));
+    if gap > max_gap as u32 || end - start > max_count as u32 {
+        return None;
+    }
+    Some(AddressRange {
+        start: start as u16,
+        count: (end - start) as u16,
+    })
+}
+
+impl ReadGroup {
+    /// group `first` with the reads waiting in `pending` that can be performed with the same
+    /// request, or give it back if there are none
+    ///
+    /// Reads queued after a write, a setting or another read of the same device are never moved
+    /// ahead of it.
+    pub(crate) fn take(
+        first: Request,
+        pending: &mut VecDeque<Command>,
+        max_gap: u16,
+    ) -> Result<Self, Request> {
+        let (kind, range) = match first.read() {
+            Some(x) => x,
+            None => return Err(first),
+        };
+        let mut group = Self {
+            kind,
+            range,
+            requests: vec![first],
+        };
+
+        // a merged read may bridge the gap to one that was left, so repeat until stable
+        while let Some(index) = group.next_mergeable(pending, max_gap) {
+            if let Some(Command::Request(request)) = pending.remove(index) {
+                group.push(request, max_gap);
+            }
+        }
+
+        if group.requests.len() == 1 {
+            return Err(group.requests.remove(0));
+        }
+        Ok(group)
+    }
+
+    fn next_mergeable(&self, pending: &VecDeque<Command>, max_gap: u16) -> Option<usize> {
+        let first = &self.requests[0];
+        for (index, cmd) in pending.iter().enumerate() {
+            let (request, kind, range) = match cmd {
+                Command::Request(request) => match request.read() {
+                    // reads of other devices may be overtaken
+                    Some(_) if request.id != first.id => continue,
+                    Some((kind, range)) => (request, kind, range),
+                    None => return None,
+                },
+                Command::Setting(_) => return None,
+            };
+            // a session may retry its requests differently from the channel
+            if request.retries == first.retries
+                && kind == self.kind
+                && merge(self.range, range, max_gap, kind.max_count()).is_some()
+            {
+                return Some(index);
+            }
+            // the requests of a device are sent in the order they are made, so a later read
+            // can't be merged ahead of this one
+            return None;
+        }
+        None
+    }
+
+    fn push(&mut self, request: Request, max_gap: u16) {
+        if let Some((_, range)) = request.read() {
+            if let Some(range) = merge(self.range, range, max_gap, self.kind.max_count()) {
+                self.range = range;
+            }
+        }
+        self.requests.push(request);
+    }
+
+    /// the request reading the whole group, which completes each read of the group
+    pub(crate) fn into_request(self) -> Request {
+        let first = &self.requests[0];
+        let id = first.id;
+        let retries = first.retries;
+        let timeout = self
+            .requests
+            .iter()
+            .map(|x| x.timeout)
+            .max()
+            .unwrap_or(first.timeout);
+        let function = first.details.function();
+        let range = self.range;
+        let count = self.requests.len();
+        let mut requests = self.requests;
+
+        let details = match self.kind {
+            ReadKind::Coils | ReadKind::DiscreteInputs => {
+                let promise = read_bits::Promise::new(move |result: Result<BitIterator, _>| {
+                    complete(&mut requests, result.map(|x| bits_response(range, x)))
+                });
+                // the range was validated when it was merged
+                let read = ReadBits::new(range.of_read_bits().unwrap(), promise);
+                match self.kind {
+                    ReadKind::Coils => RequestDetails::ReadCoils(read),
+                    _ => RequestDetails::ReadDiscreteInputs(read),
+                }
+            }
+            ReadKind::HoldingRegisters | ReadKind::InputRegisters => {
+                let promise =
+                    read_registers::Promise::new(move |result: Result<RegisterIterator, _>| {
+                        complete(&mut requests, result.map(|x| registers_response(range, x)))
+                    });
+                let read = ReadRegisters::new(range.of_read_registers().unwrap(), promise);
+                match self.kind {
+                    ReadKind::HoldingRegisters => RequestDetails::ReadHoldingRegisters(read),
+                    _ => RequestDetails::ReadInputRegisters(read),
+                }
+            }
+        };
+
+        tracing::info!(
+            "coalesced {} reads with function {} into {}",
+            count,
+            function,
+            range
+        );
+        let mut request = Request::new(id, timeout, details);
+        request.retries = retries;
+        request
+    }
+}
+
+/// values of the merged read, from which the response to each read of the group is built
+enum Values {
+    Bits(AddressRange, Vec<bool>),
+    Registers(AddressRange, Vec<u16>),
+}
+
+fn bits_response(range: AddressRange, iter: BitIterator) -> Values {
+    Values::Bits(range, iter.map(|x| x.value).collect())
+}
+
+fn registers_response(range: AddressRange, iter: RegisterIterator) -> Values {
+    Values::Registers(range, iter.map(|x| x.value).collect())
+}
+
+impl Values {
+    /// payload of the response to a read of `range` within the merged range
+    fn payload(&self, function: u8, range: AddressRange) -> Vec<u8> {
+        let mut payload = vec![function];
+        match self {
+            Values::Bits(merged, values) => {
+                let offset = (range.start - merged.start) as usize;
+                let values = &values[offset..offset + range.count as usize];
+                let bytes: Vec<u8> = values
+                    .chunks(8)
+                    .map(|x| {
+                        x.iter()
+                            .enumerate()
+                            .fold(0, |acc, (i, bit)| acc | ((*bit as u8) << i))
+                    })
+                    .collect();
+                payload.push(bytes.len() as u8);
+                payload.extend(bytes);
+            }
+            Values::Registers(merged, values) => {
+                let offset = (range.start - merged.start) as usize;
+                let values = &values[offset..offset + range.count as usize];
+                payload.push((2 * values.len()) as u8);
+                payload.extend(values.iter().flat_map(|x| x.to_be_bytes()));
+            }
+        }
+        payload
+    }
+}
+
+fn complete(requests: &mut Vec<Request>, result: Result<Values, RequestError>) {
+    for mut request in requests.drain(..) {
+        let result = match &result {
+            Ok(values) => match request.read() {
+                Some((_, range)) => {
+                    let function = request.details.function().get_value();
+                    // the response was already logged when it was received
+                    request
+                        .handle_response(&values.payload(function, range), AppDecodeLevel::Nothing)
+                }
+                None => Ok(()),
+            },
+            Err(err) => Err(*err),
+        };
+        if let Err(err) = result {
+            request.details.fail(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u16, count: u16) -> AddressRange {
+        AddressRange::try_from(start, count).unwrap()
+    }
+
+    #[test]
+    fn merges_ranges_within_the_gap_tolerance() {
+        assert_eq!(merge(range(0, 2), range(2, 3), 0, 125), Some(range(0, 5)));
+        assert_eq!(merge(range(5, 2), range(0, 10), 0, 125), Some(range(0, 10)));
+        assert_eq!(merge(range(10, 2), range(0, 2), 8, 125), Some(range(0, 12)));
+        assert_eq!(merge(range(10, 2), range(0, 2), 7, 125), None);
+        assert_eq!(merge(range(0, 100), range(100, 26), 0, 125), None);
+    }
+
+    #[test]
+    fn builds_responses_for_each_read() {
+        let values = Values::Bits(range(10, 10), (0..10).map(|x| x % 3 == 0).collect());
+        // addresses 13, 16 and 19 are set
+        assert_eq!(
+            values.payload(0x01, range(12, 8)),
+            [0x01, 0x01, 0b1001_0010]
+        );
+
+        let values = Values::Registers(range(10, 4), vec![1, 2, 3, 4]);
+        assert_eq!(
+            values.payload(0x03, range(11, 2)),
+            [0x03, 0x04, 0x00, 0x02, 0x00, 0x03]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::client::HostAddr;
+
+/// Controls which endpoint of an [`EndpointList`] is used after a connection is lost
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FailoverPolicy {
+    /// Advance to the next endpoint in the list, wrapping around to the primary after the last one
+    RoundRobin,
+    /// Return to the primary endpoint whenever a connection to a backup endpoint is lost
+    PreferPrimary,
+}
+
+/// Ordered list of endpoints for the same server, e.g. primary and backup front-ends
+///
+/// A channel connects to the first endpoint, the primary, and moves to the next one when a
+/// connection attempt fails. The retry strategy delay is only applied once every endpoint
+/// in the list has failed.
+#[derive(Clone, Debug)]
+pub struct EndpointList {
+    endpoints: Vec<HostAddr>,
+    policy: FailoverPolicy,
+    current: usize,
+    failed: usize,
+}
+
+impl EndpointList {
+    /// Create a list containing only the primary endpoint
+    pub fn new(primary: HostAddr, policy: FailoverPolicy) -> Self {
+        Self {
+            endpoints: vec![primary],
+            policy,
+            current: 0,
+            failed: 0,
+        }
+    }
+
+    /// Add a backup endpoint to the end of the list
+    pub fn add(&mut self, endpoint: HostAddr) {
+        self.endpoints.push(endpoint);
+    }
+
+    pub(crate) fn single(host: HostAddr) -> Self {
+        Self::new(host, FailoverPolicy::RoundRobin)
+    }
+
+    pub(crate) fn current(&self) -> &HostAddr {
+        &self.endpoints[self.current]
+    }
+
+    pub(crate) fn on_connected(&mut self) {
+        self.failed = 0;
+    }
+
+    /// move to the next endpoint, returning true if every endpoint has failed in a row
+    pub(crate) fn on_failed_connect(&mut self) -> bool {
+        self.advance();
+        self.failed += 1;
+        if self.failed < self.endpoints.len() {
+            return false;
+        }
+        self.failed = 0;
+        true
+    }
+
+    pub(crate) fn on_disconnect(&mut self) {
+        match self.policy {
+            FailoverPolicy::PreferPrimary if self.current != 0 => self.current = 0,
+            _ => self.advance(),
+        }
+    }
+
+    fn advance(&mut self) {
+        self.current = (self.current + 1) % self.endpoints.len();
+    }
+}
+
+impl std::fmt::Display for EndpointList {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut first = true;
+        for endpoint in &self.endpoints {
+            if !first {
+                f.write_str(", ")?;
+            }
+            first = false;
+            write!(f, "{endpoint}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn endpoints(policy: FailoverPolicy) -> EndpointList {
+        let mut list = EndpointList::new(
+            HostAddr::ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 502),
+            policy,
+        );
+        list.add(HostAddr::ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 502));
+        list.add(HostAddr::ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3)), 502));
+        list
+    }
+
+    #[test]
+    fn only_waits_after_every_endpoint_failed() {
+        let mut list = endpoints(FailoverPolicy::RoundRobin);
+        assert!(!list.on_failed_connect());
+        assert_eq!(list.current().to_string(), "10.0.0.2:502");
+        assert!(!list.on_failed_connect());
+        assert_eq!(list.current().to_string(), "10.0.0.3:502");
+        assert!(list.on_failed_connect());
+        assert_eq!(list.current().to_string(), "10.0.0.1:502");
+    }
+
+    #[test]
+    fn successful_connection_restarts_the_count() {
+        let mut list = endpoints(FailoverPolicy::RoundRobin);
+        assert!(!list.on_failed_connect());
+        list.on_connected();
+        assert!(!list.on_failed_connect());
+        assert!(!list.on_failed_connect());
+        assert!(list.on_failed_connect());
+    }
+
+    #[test]
+    fn round_robin_advances_after_disconnect() {
+        let mut list = endpoints(FailoverPolicy::RoundRobin);
+        list.on_failed_connect();
+        list.on_disconnect();
+        assert_eq!(list.current().to_string(), "10.0.0.3:502");
+        list.on_disconnect();
+        assert_eq!(list.current().to_string(), "10.0.0.1:502");
+    }
+
+    #[test]
+    fn prefer_primary_returns_to_primary_after_disconnect() {
+        let mut list = endpoints(FailoverPolicy::PreferPrimary);
+        list.on_disconnect();
+        assert_eq!(list.current().to_string(), "10.0.0.2:502");
+        list.on_disconnect();
+        assert_eq!(list.current().to_string(), "10.0.0.1:502");
+    }
+}
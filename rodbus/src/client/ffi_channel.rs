@@ -143,6 +143,7 @@ impl FfiChannel {
     where
         C: FnOnce(Result<AddressRange, RequestError>) + Send + Sync + 'static,
     {
+        value.range.of_write_registers()?;
         self.send(crate::client::channel::wrap(
             param,
             RequestDetails::WriteMultipleRegisters(MultipleWriteRequest::new(
@@ -162,6 +163,7 @@ impl FfiChannel {
     where
         C: FnOnce(Result<AddressRange, RequestError>) + Send + Sync + 'static,
     {
+        value.range.of_write_coils()?;
         self.send(crate::client::channel::wrap(
             param,
             RequestDetails::WriteMultipleCoils(MultipleWriteRequest::new(
@@ -1,3 +1,6 @@
+use std::net::SocketAddr;
+
+use crate::client::HostAddr;
 use crate::MaybeAsync;
 
 /// Generic listener type that can be invoked multiple times
@@ -25,6 +28,19 @@ impl<T> Listener<T> for NullListener {
     }
 }
 
+/// Publishes every value to the receivers of a `watch` channel, e.g. so that a UI can show
+/// the latest [`ClientState`] of a channel without implementing a listener
+impl<T> Listener<T> for tokio::sync::watch::Sender<T>
+where
+    T: Send + Sync,
+{
+    fn update(&mut self, value: T) -> MaybeAsync<()> {
+        // there may be no receiver at the moment, the value is kept for the next one
+        self.send_replace(value);
+        MaybeAsync::ready(())
+    }
+}
+
 /// State of TCP/TLS client connection
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ClientState {
@@ -42,6 +58,43 @@ pub enum ClientState {
     Shutdown,
 }
 
+/// Connection event of a TCP/TLS client channel, see [`crate::client::Channel::set_connection_listener`]
+///
+/// Unlike [`ClientState`], events carry the address of the server and the reason a connection
+/// ended, so that a UI can show the link status without inferring it from request errors.
+///
+/// Note: User code cannot exhaustively match against this enum as new variants may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// Attempting to connect to an endpoint
+    Connecting(HostAddr),
+    /// Connected to the server at this address, after the TLS handshake if any
+    Connected(SocketAddr),
+    /// The connection was closed
+    Disconnected(DisconnectReason),
+    /// Waiting before the next connection attempt
+    WaitingToRetry(std::time::Duration),
+}
+
+/// Reason a client connection was closed
+///
+/// Note: User code cannot exhaustively match against this enum as new variants may be added in the future.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// An I/O error occurred on the connection
+    Io(std::io::ErrorKind),
+    /// A frame that the channel cannot recover from was received
+    BadFrame,
+    /// The channel was disabled
+    Disabled,
+    /// No request was made during the idle disconnect period
+    Idle,
+    /// The channel was shut down
+    Shutdown,
+}
+
 /// State of the serial port
 #[cfg(feature = "serial")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -55,3 +108,16 @@ pub enum PortState {
     /// Port has been shut down
     Shutdown,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn watch_sender_keeps_the_latest_state() {
+        let (mut tx, rx) = tokio::sync::watch::channel(ClientState::Disabled);
+        tx.update(ClientState::Connecting).get().await;
+        tx.update(ClientState::Connected).get().await;
+        assert_eq!(*rx.borrow(), ClientState::Connected);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::common::frame::FunctionField;
 use crate::common::function::FunctionCode;
 use crate::common::traits::Loggable;
 use crate::decode::AppDecodeLevel;
@@ -6,12 +7,24 @@ use crate::error::*;
 use crate::exception::ExceptionCode;
 use crate::DecodeLevel;
 
+use crate::client::requests::diagnostics::Diagnostics;
+use crate::client::requests::get_comm_event_counter::GetCommEventCounter;
+use crate::client::requests::get_comm_event_log::GetCommEventLog;
+use crate::client::requests::mei_transport::MeiTransport;
+use crate::client::requests::raw_pdu::RawPdu;
 use crate::client::requests::read_bits::ReadBits;
+use crate::client::requests::read_device_id::ReadDeviceId;
+use crate::client::requests::read_exception_status::ReadExceptionStatus;
+use crate::client::requests::read_fifo::ReadFifoQueue;
+use crate::client::requests::read_file_record::ReadFileRecords;
 use crate::client::requests::read_registers::ReadRegisters;
+use crate::client::requests::read_write_multiple::ReadWriteMultipleRegisters;
+use crate::client::requests::report_server_id::ReportServerId;
+use crate::client::requests::write_file_record::WriteFileRecords;
 use crate::client::requests::write_multiple::MultipleWriteRequest;
 use crate::client::requests::write_single::SingleWrite;
 use crate::common::traits::Serialize;
-use crate::types::{Indexed, UnitId};
+use crate::types::{Indexed, MaskWriteRegister, UnitId};
 
 use scursor::{ReadCursor, WriteCursor};
 use std::time::Duration;
@@ -20,6 +33,21 @@ pub(crate) enum Setting {
     DecodeLevel(DecodeLevel),
     Enable,
     Disable,
+    RequestRetry(Option<crate::retry::RequestRetryPolicy>),
+    ReadCoalescing(Option<u16>),
+    MaxInFlight(usize),
+    ConnectMode(crate::client::ConnectMode),
+    DisconnectedPolicy(crate::client::DisconnectedPolicy),
+    KeepAlive(Option<crate::client::KeepAlive>),
+    IdleDisconnect(Option<Duration>),
+    LenientTxId(bool),
+    LenientProtocolId(bool),
+    MaxAduLength(usize),
+    ConnectionListener(Box<dyn crate::client::Listener<crate::client::ConnectionEvent>>),
+    /// terminate the task, the sender is dropped once it's done
+    Shutdown(tokio::sync::oneshot::Sender<()>),
+    #[cfg(feature = "tls")]
+    TlsConfig(crate::tcp::tls::TlsClientConfig),
 }
 
 pub(crate) enum Command {
@@ -29,9 +57,17 @@ pub(crate) enum Command {
     Setting(Setting),
 }
 
+/// receiving end of the queue of a channel, along with the statistics its task records
+pub(crate) struct CommandReceiver {
+    pub(crate) rx: crate::channel::Receiver<Command>,
+    pub(crate) statistics: crate::client::statistics::SharedStatistics,
+}
+
 pub(crate) struct Request {
     pub(crate) id: UnitId,
     pub(crate) timeout: Duration,
+    /// number of retries overriding the retry policy of the channel
+    pub(crate) retries: Option<usize>,
     pub(crate) details: RequestDetails,
 }
 
@@ -45,6 +81,19 @@ pub(crate) enum RequestDetails {
     WriteSingleRegister(SingleWrite<Indexed<u16>>),
     WriteMultipleCoils(MultipleWriteRequest<bool>),
     WriteMultipleRegisters(MultipleWriteRequest<u16>),
+    MaskWriteRegister(SingleWrite<MaskWriteRegister>),
+    ReadWriteMultipleRegisters(ReadWriteMultipleRegisters),
+    ReadFifoQueue(ReadFifoQueue),
+    ReadFileRecord(ReadFileRecords),
+    WriteFileRecord(WriteFileRecords),
+    ReadExceptionStatus(ReadExceptionStatus),
+    Diagnostics(Diagnostics),
+    GetCommEventCounter(GetCommEventCounter),
+    GetCommEventLog(GetCommEventLog),
+    ReportServerId(ReportServerId),
+    ReadDeviceId(ReadDeviceId),
+    MeiTransport(MeiTransport),
+    RawPdu(RawPdu),
 }
 
 impl Request {
@@ -52,6 +101,7 @@ impl Request {
         Self {
             id,
             timeout,
+            retries: None,
             details,
         }
     }
@@ -82,7 +132,7 @@ impl Request {
 
     fn get_error_for(
         function: u8,
-        expected_function: FunctionCode,
+        expected_function: FunctionField,
         mut cursor: ReadCursor,
     ) -> RequestError {
         if function == expected_function.as_error() {
@@ -119,8 +169,8 @@ impl Request {
 }
 
 impl RequestDetails {
-    pub(crate) fn function(&self) -> FunctionCode {
-        match self {
+    pub(crate) fn function(&self) -> FunctionField {
+        let function = match self {
             RequestDetails::ReadCoils(_) => FunctionCode::ReadCoils,
             RequestDetails::ReadDiscreteInputs(_) => FunctionCode::ReadDiscreteInputs,
             RequestDetails::ReadHoldingRegisters(_) => FunctionCode::ReadHoldingRegisters,
@@ -129,6 +179,48 @@ impl RequestDetails {
             RequestDetails::WriteSingleRegister(_) => FunctionCode::WriteSingleRegister,
             RequestDetails::WriteMultipleCoils(_) => FunctionCode::WriteMultipleCoils,
             RequestDetails::WriteMultipleRegisters(_) => FunctionCode::WriteMultipleRegisters,
+            RequestDetails::MaskWriteRegister(_) => FunctionCode::MaskWriteRegister,
+            RequestDetails::ReadWriteMultipleRegisters(_) => {
+                FunctionCode::ReadWriteMultipleRegisters
+            }
+            RequestDetails::ReadFifoQueue(_) => FunctionCode::ReadFifoQueue,
+            RequestDetails::ReadFileRecord(_) => FunctionCode::ReadFileRecord,
+            RequestDetails::WriteFileRecord(_) => FunctionCode::WriteFileRecord,
+            RequestDetails::ReadExceptionStatus(_) => FunctionCode::ReadExceptionStatus,
+            RequestDetails::Diagnostics(_) => FunctionCode::Diagnostics,
+            RequestDetails::GetCommEventCounter(_) => FunctionCode::GetCommEventCounter,
+            RequestDetails::GetCommEventLog(_) => FunctionCode::GetCommEventLog,
+            RequestDetails::ReportServerId(_) => FunctionCode::ReportServerId,
+            RequestDetails::ReadDeviceId(_) => FunctionCode::EncapsulatedInterfaceTransport,
+            RequestDetails::MeiTransport(_) => FunctionCode::EncapsulatedInterfaceTransport,
+            RequestDetails::RawPdu(x) => return FunctionField::Raw(x.function),
+        };
+        FunctionField::Valid(function)
+    }
+
+    pub(crate) fn supports_broadcast(&self) -> bool {
+        matches!(
+            self,
+            RequestDetails::WriteSingleCoil(_)
+                | RequestDetails::WriteSingleRegister(_)
+                | RequestDetails::WriteMultipleCoils(_)
+                | RequestDetails::WriteMultipleRegisters(_)
+                | RequestDetails::MaskWriteRegister(_)
+                | RequestDetails::WriteFileRecord(_)
+        )
+    }
+
+    // complete the request once a broadcast has been sent, i.e. without a response
+    pub(crate) fn complete_broadcast(&mut self) {
+        match self {
+            RequestDetails::WriteSingleCoil(x) => x.complete_broadcast(),
+            RequestDetails::WriteSingleRegister(x) => x.complete_broadcast(),
+            RequestDetails::WriteMultipleCoils(x) => x.complete_broadcast(),
+            RequestDetails::WriteMultipleRegisters(x) => x.complete_broadcast(),
+            RequestDetails::MaskWriteRegister(x) => x.complete_broadcast(),
+            RequestDetails::WriteFileRecord(x) => x.complete_broadcast(),
+            // rejected before being sent
+            _ => self.fail(InvalidRequest::BroadcastNotSupported.into()),
         }
     }
 
@@ -142,6 +234,19 @@ impl RequestDetails {
             RequestDetails::WriteSingleRegister(x) => x.failure(err),
             RequestDetails::WriteMultipleCoils(x) => x.failure(err),
             RequestDetails::WriteMultipleRegisters(x) => x.failure(err),
+            RequestDetails::MaskWriteRegister(x) => x.failure(err),
+            RequestDetails::ReadWriteMultipleRegisters(x) => x.failure(err),
+            RequestDetails::ReadFifoQueue(x) => x.failure(err),
+            RequestDetails::ReadFileRecord(x) => x.failure(err),
+            RequestDetails::WriteFileRecord(x) => x.failure(err),
+            RequestDetails::ReadExceptionStatus(x) => x.failure(err),
+            RequestDetails::Diagnostics(x) => x.failure(err),
+            RequestDetails::GetCommEventCounter(x) => x.failure(err),
+            RequestDetails::GetCommEventLog(x) => x.failure(err),
+            RequestDetails::ReportServerId(x) => x.failure(err),
+            RequestDetails::ReadDeviceId(x) => x.failure(err),
+            RequestDetails::MeiTransport(x) => x.failure(err),
+            RequestDetails::RawPdu(x) => x.failure(err),
         }
     }
 
@@ -162,6 +267,21 @@ impl RequestDetails {
             RequestDetails::WriteMultipleRegisters(x) => {
                 x.handle_response(cursor, function, decode)
             }
+            RequestDetails::MaskWriteRegister(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::ReadWriteMultipleRegisters(x) => {
+                x.handle_response(cursor, function, decode)
+            }
+            RequestDetails::ReadFifoQueue(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::ReadFileRecord(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::WriteFileRecord(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::ReadExceptionStatus(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::Diagnostics(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::GetCommEventCounter(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::GetCommEventLog(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::ReportServerId(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::ReadDeviceId(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::MeiTransport(x) => x.handle_response(cursor, function, decode),
+            RequestDetails::RawPdu(x) => x.handle_response(cursor, function, decode),
         }
     }
 }
@@ -177,6 +297,19 @@ impl Serialize for RequestDetails {
             RequestDetails::WriteSingleRegister(x) => x.serialize(cursor),
             RequestDetails::WriteMultipleCoils(x) => x.serialize(cursor),
             RequestDetails::WriteMultipleRegisters(x) => x.serialize(cursor),
+            RequestDetails::MaskWriteRegister(x) => x.serialize(cursor),
+            RequestDetails::ReadWriteMultipleRegisters(x) => x.serialize(cursor),
+            RequestDetails::ReadFifoQueue(x) => x.serialize(cursor),
+            RequestDetails::ReadFileRecord(x) => x.serialize(cursor),
+            RequestDetails::WriteFileRecord(x) => x.serialize(cursor),
+            RequestDetails::ReadExceptionStatus(x) => x.serialize(cursor),
+            RequestDetails::Diagnostics(x) => x.serialize(cursor),
+            RequestDetails::GetCommEventCounter(x) => x.serialize(cursor),
+            RequestDetails::GetCommEventLog(x) => x.serialize(cursor),
+            RequestDetails::ReportServerId(x) => x.serialize(cursor),
+            RequestDetails::ReadDeviceId(x) => x.serialize(cursor),
+            RequestDetails::MeiTransport(x) => x.serialize(cursor),
+            RequestDetails::RawPdu(x) => x.serialize(cursor),
         }
     }
 }
@@ -241,6 +374,81 @@ impl std::fmt::Display for RequestDetailsDisplay<'_> {
                         }
                     }
                 }
+                RequestDetails::MaskWriteRegister(details) => {
+                    write!(f, "{}", details.request)?;
+                }
+                RequestDetails::ReadWriteMultipleRegisters(details) => {
+                    write!(
+                        f,
+                        "read: {} write: {}",
+                        details.request.read_range, details.request.write.range
+                    )?;
+                    if self.level.data_values() {
+                        for x in details.request.write.iter() {
+                            write!(f, "\n{x}")?;
+                        }
+                    }
+                }
+                RequestDetails::ReadFifoQueue(details) => {
+                    write!(f, "idx: {:#06X}", details.address)?;
+                }
+                RequestDetails::ReadFileRecord(details) => {
+                    for (i, x) in details.request.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str("\n")?;
+                        }
+                        write!(f, "{x}")?;
+                    }
+                }
+                RequestDetails::WriteFileRecord(details) => {
+                    for (i, x) in details.request.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str("\n")?;
+                        }
+                        write!(f, "{x}")?;
+                        if self.level.data_values() {
+                            for value in x.values.iter() {
+                                write!(f, "\nvalue: {value:#06X}")?;
+                            }
+                        }
+                    }
+                }
+                RequestDetails::ReadExceptionStatus(_) => {}
+                RequestDetails::GetCommEventCounter(_) => {}
+                RequestDetails::GetCommEventLog(_) => {}
+                RequestDetails::ReportServerId(_) => {}
+                RequestDetails::ReadDeviceId(details) => {
+                    write!(
+                        f,
+                        "read device id code: {:#04X} object id: {:#04X}",
+                        details.read_device_id_code, details.object_id
+                    )?;
+                }
+                RequestDetails::MeiTransport(details) => {
+                    write!(
+                        f,
+                        "MEI type: {:#04X} length: {}",
+                        details.mei_type,
+                        details.data.len()
+                    )?;
+                    if self.level.data_values() {
+                        write!(f, "\n{:02X?}", details.data)?;
+                    }
+                }
+                RequestDetails::RawPdu(details) => {
+                    write!(f, "length: {}", details.data.len())?;
+                    if self.level.data_values() {
+                        write!(f, "\n{:02X?}", details.data)?;
+                    }
+                }
+                RequestDetails::Diagnostics(details) => {
+                    write!(f, "{}", details.sub_function)?;
+                    if self.level.data_values() {
+                        for value in details.data.iter() {
+                            write!(f, "\nvalue: {value:#06X}")?;
+                        }
+                    }
+                }
             }
         }
 
@@ -1,33 +1,50 @@
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tracing::Instrument;
 
 use crate::decode::DecodeLevel;
+use crate::types::Framing;
 
 /// persistent communication channel such as a TCP connection
 pub(crate) mod channel;
+pub(crate) mod coalesce;
+pub(crate) mod endpoint;
 pub(crate) mod listener;
 pub(crate) mod message;
+pub(crate) mod poll;
 pub(crate) mod requests;
+pub(crate) mod statistics;
 pub(crate) mod task;
+pub(crate) mod transport;
 
 #[cfg(feature = "ffi")]
 /// Only enabled for FFI builds
 mod ffi_channel;
 
 pub use crate::client::channel::*;
+pub use crate::client::endpoint::*;
 pub use crate::client::listener::*;
+pub use crate::client::poll::*;
+pub use crate::client::requests::read_write_multiple::ReadWriteMultiple;
 pub use crate::client::requests::write_multiple::WriteMultiple;
+pub use crate::client::statistics::ChannelStatistics;
 pub use crate::retry::*;
 
 #[cfg(feature = "ffi")]
 pub use ffi_channel::*;
 
+#[cfg(feature = "native-tls")]
+pub use crate::tcp::native_tls::NativeTlsClientConfig;
 #[cfg(feature = "tls")]
 pub use crate::tcp::tls::client::TlsClientConfig;
 #[cfg(feature = "tls")]
 pub use crate::tcp::tls::*;
+/// `native-tls` version used by [`NativeTlsClientConfig`], for building connectors
+#[cfg(feature = "native-tls")]
+pub use tokio_native_tls::native_tls;
 
 /// Represents the address of a remote host
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HostAddr {
     addr: HostType,
     port: u16,
@@ -48,12 +65,90 @@ impl std::fmt::Display for HostAddr {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum HostType {
     Dns(String),
     IpAddr(IpAddr),
 }
 
+/// Socket options applied to each TCP connection established by a client channel
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TcpOptions {
+    /// Disable Nagle's algorithm (TCP_NODELAY) so that requests are sent without delay
+    pub no_delay: bool,
+    /// Idle time before TCP keep-alive probes are sent. Keep-alive is disabled when `None`.
+    pub keep_alive: Option<std::time::Duration>,
+    /// Linger timeout (SO_LINGER) when the connection is closed. The OS default is used when `None`.
+    pub linger: Option<std::time::Duration>,
+    /// Local address to which the socket is bound before connecting, e.g. to select the
+    /// network interface on a multi-homed host. The OS chooses when `None`.
+    pub local_address: Option<IpAddr>,
+    /// Maximum time to wait for a connection to be established, independent of the request
+    /// timeout. The OS default, which can be several minutes, applies when `None`.
+    pub connect_timeout: Option<std::time::Duration>,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        Self {
+            no_delay: true,
+            keep_alive: None,
+            linger: None,
+            local_address: None,
+            connect_timeout: None,
+        }
+    }
+}
+
+impl TcpOptions {
+    pub(crate) fn apply(&self, socket: &tokio::net::TcpStream) {
+        if let Err(err) = socket.set_nodelay(self.no_delay) {
+            tracing::warn!("unable to set TCP_NODELAY: {}", err);
+        }
+        let socket = socket2::SockRef::from(socket);
+        if let Some(time) = self.keep_alive {
+            let keep_alive = socket2::TcpKeepalive::new().with_time(time);
+            if let Err(err) = socket.set_tcp_keepalive(&keep_alive) {
+                tracing::warn!("unable to enable TCP keep-alive: {}", err);
+            }
+        }
+        if self.linger.is_some() {
+            if let Err(err) = socket.set_linger(self.linger) {
+                tracing::warn!("unable to set SO_LINGER: {}", err);
+            }
+        }
+    }
+}
+
+/// Configuration of a TCP client channel spawned with [`spawn_tcp_client_task_with_config`]
+#[derive(Clone, Debug)]
+pub struct TcpClientConfig {
+    /// Endpoints of the server, starting with the primary
+    pub endpoints: EndpointList,
+    /// Framing of the messages exchanged on the TCP connection, e.g. [`Framing::Rtu`] for
+    /// devices behind serial-to-Ethernet converters that forward RTU frames as-is
+    pub framing: Framing,
+    /// Socket options applied to each new connection
+    pub options: TcpOptions,
+}
+
+impl TcpClientConfig {
+    /// Configuration of a channel to a single host using MBAP framing and the default socket options
+    pub fn new(host: HostAddr) -> Self {
+        Self::from(EndpointList::single(host))
+    }
+}
+
+impl From<EndpointList> for TcpClientConfig {
+    fn from(endpoints: EndpointList) -> Self {
+        Self {
+            endpoints,
+            framing: Framing::Mbap,
+            options: TcpOptions::default(),
+        }
+    }
+}
+
 impl HostAddr {
     /// Construct a `HostAddr` from an IP address and port
     pub fn ip(ip: IpAddr, port: u16) -> Self {
@@ -71,12 +166,73 @@ impl HostAddr {
         }
     }
 
-    pub(crate) async fn connect(&self) -> std::io::Result<tokio::net::TcpStream> {
+    pub(crate) async fn connect(
+        &self,
+        local_address: Option<IpAddr>,
+    ) -> std::io::Result<tokio::net::TcpStream> {
+        let local_address = match local_address {
+            Some(x) => x,
+            None => {
+                return match &self.addr {
+                    HostType::Dns(x) => {
+                        tokio::net::TcpStream::connect((x.as_str(), self.port)).await
+                    }
+                    HostType::IpAddr(x) => tokio::net::TcpStream::connect((*x, self.port)).await,
+                }
+            }
+        };
+
+        // try each resolved address of the same family as the local address
+        let mut last_error = None;
+        for remote in self.resolve().await? {
+            if remote.is_ipv4() != local_address.is_ipv4() {
+                continue;
+            }
+            let socket = if remote.is_ipv4() {
+                tokio::net::TcpSocket::new_v4()?
+            } else {
+                tokio::net::TcpSocket::new_v6()?
+            };
+            socket.bind(SocketAddr::new(local_address, 0))?;
+            match socket.connect(remote).await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::AddrNotAvailable,
+                format!("{self} has no address in the same family as {local_address}"),
+            )
+        }))
+    }
+
+    async fn resolve(&self) -> std::io::Result<Vec<SocketAddr>> {
         match &self.addr {
-            HostType::Dns(x) => tokio::net::TcpStream::connect((x.as_str(), self.port)).await,
-            HostType::IpAddr(x) => tokio::net::TcpStream::connect((*x, self.port)).await,
+            HostType::Dns(x) => Ok(tokio::net::lookup_host((x.as_str(), self.port))
+                .await?
+                .collect()),
+            HostType::IpAddr(x) => Ok(vec![SocketAddr::new(*x, self.port)]),
         }
     }
+
+    pub(crate) async fn bind_udp(&self) -> std::io::Result<tokio::net::UdpSocket> {
+        let remote = self
+            .resolve()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+        let local: SocketAddr = if remote.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = tokio::net::UdpSocket::bind(local).await?;
+        socket.connect(remote).await?;
+        Ok(socket)
+    }
 }
 
 /// Spawns a channel task onto the runtime that maintains a TCP connection and processes
@@ -99,8 +255,156 @@ pub fn spawn_tcp_client_task(
     listener: Option<Box<dyn Listener<ClientState>>>,
 ) -> Channel {
     crate::tcp::client::spawn_tcp_channel(
+        EndpointList::single(host),
+        Framing::Mbap,
+        TcpOptions::default(),
+        max_queued_requests,
+        retry,
+        decode,
+        listener.unwrap_or_else(|| NullListener::create()),
+    )
+}
+
+/// Spawns a channel task onto the runtime that maintains a TCP connection as described by a
+/// [`TcpClientConfig`] and processes requests. The task completes when the returned channel
+/// handle is dropped.
+///
+/// The configuration selects the endpoints to fail over between, the framing and the socket
+/// options. When a connection attempt fails, the channel immediately tries the next endpoint.
+/// The provided [`RetryStrategy`] is used to pause once every endpoint has failed.
+///
+/// * `config` - Endpoints, framing and socket options of the channel
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retry` - A boxed trait object that controls when the connection is retried on failure
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the TCP connection state
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+pub fn spawn_tcp_client_task_with_config(
+    config: TcpClientConfig,
+    max_queued_requests: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<ClientState>>>,
+) -> Channel {
+    crate::tcp::client::spawn_tcp_channel(
+        config.endpoints,
+        config.framing,
+        config.options,
+        max_queued_requests,
+        retry,
+        decode,
+        listener.unwrap_or_else(|| NullListener::create()),
+    )
+}
+
+/// Spawns a channel task onto the runtime that exchanges MBAP framed requests and responses
+/// with a server as UDP datagrams. The task completes when the returned channel handle is dropped.
+///
+/// UDP is connectionless, so a request that isn't answered within its timeout is sent again
+/// up to `retransmissions` times before it fails with [`crate::RequestError::ResponseTimeout`].
+/// Each attempt waits for the full request timeout.
+///
+/// The channel uses the provided [`RetryStrategy`] to pause before re-opening the socket after an I/O error
+///
+/// * `host` - Address/port of the remote server. Can be a IP address or name on which to perform DNS resolution.
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retransmissions` - The number of times an unanswered request is resent
+/// * `retry` - A boxed trait object that controls when the socket is re-opened on failure
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the state of the socket
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+pub fn spawn_udp_client_task(
+    host: HostAddr,
+    max_queued_requests: usize,
+    retransmissions: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<ClientState>>>,
+) -> Channel {
+    crate::udp::client::spawn_udp_channel(
         host,
         max_queued_requests,
+        retransmissions,
+        retry,
+        decode,
+        listener.unwrap_or_else(|| NullListener::create()),
+    )
+}
+
+/// Spawns a channel task onto the runtime that processes requests over a byte stream
+/// created by a user-supplied async `connect` function. The task completes when the
+/// returned channel handle is dropped.
+///
+/// `connect` is called each time the channel needs a new connection, which makes it possible
+/// to communicate through SOCKS proxies, SSH tunnels or in-memory test doubles.
+///
+/// The channel uses the provided [`RetryStrategy`] to pause between failed connection attempts
+///
+/// * `connect` - Function that establishes a new stream to the server
+/// * `framing` - Framing of the messages exchanged on the stream
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retry` - A boxed trait object that controls when the connection is retried on failure
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the connection state
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+pub fn spawn_custom_client_task<F, Fut, T>(
+    connect: F,
+    framing: Framing,
+    max_queued_requests: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<ClientState>>>,
+) -> Channel
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = std::io::Result<T>> + Send,
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let (channel, rx) = Channel::create(max_queued_requests);
+    let listener = listener.unwrap_or_else(|| NullListener::create());
+    let task = async move {
+        crate::client::transport::TransportChannelTask::new(
+            connect, rx, framing, retry, decode, listener,
+        )
+        .run()
+        .instrument(tracing::info_span!("Modbus-Client-Custom", framing = ?framing))
+        .await;
+    };
+    tokio::spawn(task);
+    channel
+}
+
+/// Spawns a channel task onto the runtime that maintains a connection to a Unix domain socket
+/// and processes requests. The task completes when the returned channel handle is dropped.
+///
+/// This is useful for talking to local simulators without exposing a TCP port.
+///
+/// The channel uses the provided [`RetryStrategy`] to pause between failed connection attempts
+///
+/// * `path` - Path of the Unix domain socket on which the server is listening
+/// * `framing` - Framing of the messages exchanged on the socket
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retry` - A boxed trait object that controls when the connection is retried on failure
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the connection state
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+#[cfg(unix)]
+pub fn spawn_unix_client_task(
+    path: &std::path::Path,
+    framing: Framing,
+    max_queued_requests: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<ClientState>>>,
+) -> Channel {
+    crate::unix::client::spawn_unix_channel(
+        path,
+        framing,
+        max_queued_requests,
         retry,
         decode,
         listener.unwrap_or_else(|| NullListener::create()),
@@ -131,9 +435,45 @@ pub fn spawn_rtu_client_task(
     decode: DecodeLevel,
     listener: Option<Box<dyn Listener<PortState>>>,
 ) -> Channel {
-    Channel::spawn_rtu(
+    Channel::spawn_serial(
+        path,
+        serial_settings,
+        crate::types::Framing::Rtu,
+        max_queued_requests,
+        retry,
+        decode,
+        listener,
+    )
+}
+
+/// Spawns a channel task onto the runtime that opens a serial port and processes
+/// requests using Modbus ASCII framing. The task completes when the returned channel handle
+/// is dropped.
+///
+/// The channel uses the provided [`RetryStrategy`] to pause between failed attempts to open the
+/// serial port or after the serial port fails.
+///
+/// * `path` - Path to the serial device. Generally `/dev/tty0` on Linux and `COM1` on Windows.
+/// * `serial_settings` = Serial port settings
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retry` - A boxed trait object that controls when opening the serial port is retried on failure
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the state of the serial port
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+#[cfg(feature = "serial")]
+pub fn spawn_ascii_client_task(
+    path: &str,
+    serial_settings: crate::serial::SerialSettings,
+    max_queued_requests: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<PortState>>>,
+) -> Channel {
+    Channel::spawn_serial(
         path,
         serial_settings,
+        crate::types::Framing::Ascii,
         max_queued_requests,
         retry,
         decode,
@@ -148,6 +488,7 @@ pub fn spawn_rtu_client_task(
 /// The channel uses the provided [`RetryStrategy`] to pause between failed connection attempts
 ///
 /// * `host` - Address/port of the remote server. Can be a IP address or name on which to perform DNS resolution.
+/// * `options` - Socket options applied to each new connection
 /// * `max_queued_requests` - The maximum size of the request queue
 /// * `retry` - A boxed trait object that controls when the connection is retried on failure
 /// * `tls_config` - TLS configuration
@@ -158,6 +499,7 @@ pub fn spawn_rtu_client_task(
 #[cfg(feature = "tls")]
 pub fn spawn_tls_client_task(
     host: HostAddr,
+    options: TcpOptions,
     max_queued_requests: usize,
     retry: Box<dyn RetryStrategy>,
     tls_config: TlsClientConfig,
@@ -166,6 +508,7 @@ pub fn spawn_tls_client_task(
 ) -> Channel {
     spawn_tls_channel(
         host,
+        options,
         max_queued_requests,
         retry,
         tls_config,
@@ -173,3 +516,90 @@ pub fn spawn_tls_client_task(
         listener.unwrap_or_else(|| NullListener::create()),
     )
 }
+
+/// Spawns a channel task onto the runtime that maintains a TLS connection using the
+/// platform TLS implementation and processes requests. The task completes when the
+/// returned channel handle is dropped.
+///
+/// The channel uses the provided [`RetryStrategy`] to pause between failed connection attempts
+///
+/// * `host` - Address/port of the remote server. Can be a IP address or name on which to perform DNS resolution.
+/// * `options` - Socket options applied to each new connection
+/// * `max_queued_requests` - The maximum size of the request queue
+/// * `retry` - A boxed trait object that controls when the connection is retried on failure
+/// * `tls_config` - Native TLS configuration
+/// * `decode` - Decode log level
+/// * `listener` - Optional callback to monitor the TLS connection state
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+#[cfg(feature = "native-tls")]
+pub fn spawn_native_tls_client_task(
+    host: HostAddr,
+    options: TcpOptions,
+    max_queued_requests: usize,
+    retry: Box<dyn RetryStrategy>,
+    tls_config: NativeTlsClientConfig,
+    decode: DecodeLevel,
+    listener: Option<Box<dyn Listener<ClientState>>>,
+) -> Channel {
+    crate::tcp::native_tls::spawn_native_tls_channel(
+        host,
+        options,
+        max_queued_requests,
+        retry,
+        tls_config,
+        decode,
+        listener.unwrap_or_else(|| NullListener::create()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn applies_tcp_options_to_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket = tokio::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let options = TcpOptions {
+            no_delay: false,
+            keep_alive: Some(Duration::from_secs(30)),
+            linger: Some(Duration::from_secs(1)),
+            local_address: None,
+            connect_timeout: None,
+        };
+        options.apply(&socket);
+
+        assert!(!socket.nodelay().unwrap());
+        let socket = socket2::SockRef::from(&socket);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.linger().unwrap(), Some(Duration::from_secs(1)));
+    }
+
+    // the whole 127.0.0.0/8 block is routed to the loopback interface on Linux
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn binds_to_local_address_before_connecting() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = HostAddr::from(listener.local_addr().unwrap());
+        let local: IpAddr = "127.0.0.2".parse().unwrap();
+
+        let (socket, accepted) = tokio::join!(host.connect(Some(local)), listener.accept());
+        assert_eq!(socket.unwrap().local_addr().unwrap().ip(), local);
+        assert_eq!(accepted.unwrap().1.ip(), local);
+    }
+
+    #[tokio::test]
+    async fn fails_when_local_address_family_does_not_match() {
+        let host = HostAddr::ip(IpAddr::V4(Ipv4Addr::LOCALHOST), 502);
+        let err = host
+            .connect(Some(IpAddr::V6(Ipv6Addr::LOCALHOST)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrNotAvailable);
+    }
+}
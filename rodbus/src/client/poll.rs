@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::client::message::RequestDetails;
+use crate::client::requests::read_bits::{self, ReadBits};
+use crate::client::requests::read_registers::{self, ReadRegisters};
+use crate::client::{Channel, RequestParam};
+use crate::error::{RequestError, Shutdown};
+use crate::types::{AddressRange, Indexed};
+
+/// Identifies a poll registered with a [`PollHandle`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PollId(u64);
+
+/// Read performed periodically by a poll
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PollRequest {
+    /// Read coils
+    ReadCoils(AddressRange),
+    /// Read discrete inputs
+    ReadDiscreteInputs(AddressRange),
+    /// Read holding registers
+    ReadHoldingRegisters(AddressRange),
+    /// Read input registers
+    ReadInputRegisters(AddressRange),
+}
+
+/// Values read by a poll
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PollValues {
+    /// Coils or discrete inputs
+    Bits(Vec<Indexed<bool>>),
+    /// Holding or input registers
+    Registers(Vec<Indexed<u16>>),
+}
+
+/// Receives the result of every poll performed by a [`PollHandle`]
+///
+/// The callback is invoked from the poll task and should return quickly.
+pub trait PollHandler: Send + 'static {
+    /// The poll `id` performing `request` completed
+    fn poll_complete(
+        &mut self,
+        id: PollId,
+        request: PollRequest,
+        result: Result<PollValues, RequestError>,
+    );
+}
+
+/// Forwards the results to a stream, e.g. to process them in another task
+impl PollHandler
+    for tokio::sync::mpsc::UnboundedSender<(PollId, Result<PollValues, RequestError>)>
+{
+    fn poll_complete(
+        &mut self,
+        id: PollId,
+        _request: PollRequest,
+        result: Result<PollValues, RequestError>,
+    ) {
+        // the results are dropped once the receiver is gone
+        let _ = self.send((id, result));
+    }
+}
+
+/// Handle to a task that performs recurring reads on a [`Channel`]
+///
+/// The task terminates when every handle is dropped or when the channel is shut down.
+#[derive(Debug, Clone)]
+pub struct PollHandle {
+    tx: tokio::sync::mpsc::Sender<PollCommand>,
+}
+
+#[derive(Debug)]
+enum PollCommand {
+    Add(Poll, tokio::sync::oneshot::Sender<PollId>),
+    Remove(PollId),
+    Enable(PollId, bool),
+    PollNow(PollId),
+}
+
+#[derive(Debug)]
+struct Poll {
+    param: RequestParam,
+    request: PollRequest,
+    period: Duration,
+    enabled: bool,
+    /// when the poll is next performed if it's enabled
+    next: Instant,
+    /// perform the poll as soon as possible, even if it's disabled
+    now: bool,
+}
+
+struct PollTask {
+    channel: Channel,
+    handler: Box<dyn PollHandler>,
+    rx: tokio::sync::mpsc::Receiver<PollCommand>,
+    polls: BTreeMap<PollId, Poll>,
+    next_id: u64,
+}
+
+impl PollHandle {
+    /// Spawn a task that performs the polls registered with the returned handle using `channel`
+    /// and reports their results to `handler`
+    ///
+    /// Polls share the queue of the channel with the other requests made on it.
+    ///
+    /// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+    pub fn spawn(channel: Channel, handler: Box<dyn PollHandler>) -> Self {
+        Self::spawn_until(channel, handler, std::future::pending())
+    }
+
+    /// Spawn a poll task like [`PollHandle::spawn`] that also terminates when `shutdown`
+    /// completes, e.g. `token.cancelled_owned()` for a `tokio_util` cancellation token
+    ///
+    /// A poll in progress is abandoned, which leaves the channel usable by other handles.
+    ///
+    /// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+    pub fn spawn_until<F>(channel: Channel, handler: Box<dyn PollHandler>, shutdown: F) -> Self
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let task = PollTask {
+            channel,
+            handler,
+            rx,
+            polls: BTreeMap::new(),
+            next_id: 0,
+        };
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = task.run() => {}
+                _ = shutdown => tracing::info!("poll task cancelled"),
+            }
+        });
+        Self { tx }
+    }
+
+    /// Register a read performed every `period`, starting immediately
+    pub async fn add_poll(
+        &self,
+        param: RequestParam,
+        request: PollRequest,
+        period: Duration,
+    ) -> Result<PollId, Shutdown> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let poll = Poll {
+            param,
+            request,
+            period,
+            enabled: true,
+            next: Instant::now(),
+            now: false,
+        };
+        self.tx.send(PollCommand::Add(poll, tx)).await?;
+        rx.await.map_err(|_| Shutdown)
+    }
+
+    /// Stop performing a poll. Does nothing if the poll doesn't exist.
+    pub async fn remove_poll(&self, id: PollId) -> Result<(), Shutdown> {
+        self.tx.send(PollCommand::Remove(id)).await?;
+        Ok(())
+    }
+
+    /// Resume a poll disabled with [`PollHandle::disable_poll`], performing it immediately
+    pub async fn enable_poll(&self, id: PollId) -> Result<(), Shutdown> {
+        self.tx.send(PollCommand::Enable(id, true)).await?;
+        Ok(())
+    }
+
+    /// Suspend a poll until it's enabled again. It can still be performed with
+    /// [`PollHandle::poll_now`].
+    pub async fn disable_poll(&self, id: PollId) -> Result<(), Shutdown> {
+        self.tx.send(PollCommand::Enable(id, false)).await?;
+        Ok(())
+    }
+
+    /// Perform a poll as soon as possible, even if it's disabled
+    ///
+    /// The next periodic read of an enabled poll is scheduled one period afterwards.
+    pub async fn poll_now(&self, id: PollId) -> Result<(), Shutdown> {
+        self.tx.send(PollCommand::PollNow(id)).await?;
+        Ok(())
+    }
+}
+
+impl Poll {
+    fn deadline(&self) -> Option<Instant> {
+        if self.now {
+            Some(Instant::now())
+        } else if self.enabled {
+            Some(self.next)
+        } else {
+            None
+        }
+    }
+}
+
+impl PollRequest {
+    /// request performing the read whose result is discarded
+    pub(crate) fn details(self) -> Result<RequestDetails, RequestError> {
+        let details = match self {
+            PollRequest::ReadCoils(range) => RequestDetails::ReadCoils(ReadBits::new(
+                range.of_read_bits()?,
+                read_bits::Promise::new(|_| {}),
+            )),
+            PollRequest::ReadDiscreteInputs(range) => RequestDetails::ReadDiscreteInputs(
+                ReadBits::new(range.of_read_bits()?, read_bits::Promise::new(|_| {})),
+            ),
+            PollRequest::ReadHoldingRegisters(range) => {
+                RequestDetails::ReadHoldingRegisters(ReadRegisters::new(
+                    range.of_read_registers()?,
+                    read_registers::Promise::new(|_| {}),
+                ))
+            }
+            PollRequest::ReadInputRegisters(range) => {
+                RequestDetails::ReadInputRegisters(ReadRegisters::new(
+                    range.of_read_registers()?,
+                    read_registers::Promise::new(|_| {}),
+                ))
+            }
+        };
+        Ok(details)
+    }
+
+    async fn execute(
+        self,
+        channel: &mut Channel,
+        param: RequestParam,
+    ) -> Result<PollValues, RequestError> {
+        match self {
+            PollRequest::ReadCoils(range) => {
+                channel.read_coils(param, range).await.map(PollValues::Bits)
+            }
+            PollRequest::ReadDiscreteInputs(range) => channel
+                .read_discrete_inputs(param, range)
+                .await
+                .map(PollValues::Bits),
+            PollRequest::ReadHoldingRegisters(range) => channel
+                .read_holding_registers(param, range)
+                .await
+                .map(PollValues::Registers),
+            PollRequest::ReadInputRegisters(range) => channel
+                .read_input_registers(param, range)
+                .await
+                .map(PollValues::Registers),
+        }
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(x) => tokio::time::sleep_until(x).await,
+        None => std::future::pending().await,
+    }
+}
+
+impl PollTask {
+    async fn run(mut self) {
+        loop {
+            let next = self
+                .polls
+                .iter()
+                .filter_map(|(id, poll)| poll.deadline().map(|x| (x, *id)))
+                .min();
+
+            tokio::select! {
+                cmd = self.rx.recv() => match cmd {
+                    Some(cmd) => self.handle(cmd),
+                    None => return,
+                },
+                _ = sleep_until(next.map(|(x, _)| x)) => {
+                    if let Some((_, id)) = next {
+                        if let Err(Shutdown) = self.perform(id).await {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, cmd: PollCommand) {
+        match cmd {
+            PollCommand::Add(poll, reply) => {
+                let id = PollId(self.next_id);
+                self.next_id += 1;
+                self.polls.insert(id, poll);
+                let _ = reply.send(id);
+            }
+            PollCommand::Remove(id) => {
+                self.polls.remove(&id);
+            }
+            PollCommand::Enable(id, enabled) => {
+                if let Some(poll) = self.polls.get_mut(&id) {
+                    if enabled && !poll.enabled {
+                        poll.next = Instant::now();
+                    }
+                    poll.enabled = enabled;
+                }
+            }
+            PollCommand::PollNow(id) => {
+                if let Some(poll) = self.polls.get_mut(&id) {
+                    poll.now = true;
+                }
+            }
+        }
+    }
+
+    async fn perform(&mut self, id: PollId) -> Result<(), Shutdown> {
+        let (param, request) = match self.polls.get(&id) {
+            Some(poll) => (poll.param, poll.request),
+            None => return Ok(()),
+        };
+
+        let result = request.execute(&mut self.channel, param).await;
+        let shutdown = result == Err(RequestError::Shutdown);
+        self.handler.poll_complete(id, request, result);
+        if shutdown {
+            return Err(Shutdown);
+        }
+
+        // commands are only processed between polls, so the poll still exists
+        if let Some(poll) = self.polls.get_mut(&id) {
+            poll.now = false;
+            poll.next = Instant::now() + poll.period;
+        }
+        Ok(())
+    }
+}
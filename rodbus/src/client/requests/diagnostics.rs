@@ -0,0 +1,131 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::diagnostic::DiagnosticSubFunction;
+use crate::error::{AduParseError, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) struct Diagnostics {
+    pub(crate) sub_function: DiagnosticSubFunction,
+    pub(crate) data: Vec<u16>,
+    promise: Promise<Vec<u16>>,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(
+        sub_function: DiagnosticSubFunction,
+        data: Vec<u16>,
+        promise: Promise<Vec<u16>>,
+    ) -> Self {
+        Self {
+            sub_function,
+            data,
+            promise,
+        }
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u16_be(self.sub_function.into())?;
+        for value in self.data.iter() {
+            cursor.write_u16_be(*value)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let data = self.parse_response(&mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {} {}", function, self.sub_function);
+            if decode.data_values() {
+                for value in data.iter() {
+                    tracing::info!("value: {:#06X}", value);
+                }
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(data);
+        Ok(())
+    }
+
+    fn parse_response(&self, cursor: &mut ReadCursor) -> Result<Vec<u16>, RequestError> {
+        // the sub-function is always echoed
+        if cursor.read_u16_be()? != u16::from(self.sub_function) {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+        let mut data = Vec::with_capacity(cursor.remaining() / 2);
+        while !cursor.is_empty() {
+            data.push(cursor.read_u16_be()?);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sub_function: DiagnosticSubFunction, bytes: &[u8]) -> Result<Vec<u16>, RequestError> {
+        let request = Diagnostics::new(sub_function, vec![0x0000], Promise::new(|_| {}));
+        let mut cursor = ReadCursor::new(bytes);
+        request.parse_response(&mut cursor)
+    }
+
+    #[test]
+    fn serializes_request() {
+        let request = Diagnostics::new(
+            DiagnosticSubFunction::ReturnQueryData,
+            vec![0xA537],
+            Promise::new(|_| {}),
+        );
+        let mut buffer = [0u8; 4];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(buffer, [0x00, 0x00, 0xA5, 0x37]);
+    }
+
+    #[test]
+    fn parses_response_data() {
+        assert_eq!(
+            parse(
+                DiagnosticSubFunction::ReturnBusMessageCount,
+                &[0x00, 0x0B, 0x01, 0x02]
+            ),
+            Ok(vec![0x0102])
+        );
+    }
+
+    #[test]
+    fn fails_when_sub_function_is_not_echoed() {
+        assert_eq!(
+            parse(
+                DiagnosticSubFunction::ReturnBusMessageCount,
+                &[0x00, 0x0C, 0x01, 0x02]
+            ),
+            Err(AduParseError::ReplyEchoMismatch.into())
+        );
+    }
+
+    #[test]
+    fn fails_on_odd_number_of_data_bytes() {
+        assert_eq!(
+            parse(
+                DiagnosticSubFunction::ReturnBusMessageCount,
+                &[0x00, 0x0B, 0x01]
+            ),
+            Err(AduParseError::InsufficientBytes.into())
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::RequestError;
+use crate::types::CommEventCounter;
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) struct GetCommEventCounter {
+    promise: Promise<CommEventCounter>,
+}
+
+impl GetCommEventCounter {
+    pub(crate) fn new(promise: Promise<CommEventCounter>) -> Self {
+        Self { promise }
+    }
+
+    pub(crate) fn serialize(&self, _cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the request has no payload
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let counter = CommEventCounter::new(cursor.read_u16_be()?, cursor.read_u16_be()?);
+        cursor.expect_empty()?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {} {}", function, counter);
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(counter);
+        Ok(())
+    }
+}
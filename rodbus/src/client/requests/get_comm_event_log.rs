@@ -0,0 +1,142 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::diagnostic::{CommEvent, CommEventLog};
+use crate::error::{AduParseError, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+// status, event count, and message count
+const FIXED_LENGTH: usize = 6;
+const MAX_EVENTS: usize = 64;
+
+pub(crate) struct GetCommEventLog {
+    promise: Promise<CommEventLog>,
+}
+
+impl GetCommEventLog {
+    pub(crate) fn new(promise: Promise<CommEventLog>) -> Self {
+        Self { promise }
+    }
+
+    pub(crate) fn serialize(&self, _cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the request has no payload
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let log = Self::parse_log(&mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!(
+                "PDU RX - {} status: {:#06X} event count: {} message count: {}",
+                function,
+                log.status,
+                log.event_count,
+                log.message_count
+            );
+            if decode.data_values() {
+                for event in log.events.iter() {
+                    tracing::info!("{:?}", event);
+                }
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(log);
+        Ok(())
+    }
+
+    fn parse_log(cursor: &mut ReadCursor) -> Result<CommEventLog, RequestError> {
+        let byte_count = cursor.read_u8()? as usize;
+        if byte_count != cursor.remaining() {
+            return Err(AduParseError::InsufficientBytesForByteCount(
+                byte_count,
+                cursor.remaining(),
+            )
+            .into());
+        }
+        if !(FIXED_LENGTH..=FIXED_LENGTH + MAX_EVENTS).contains(&byte_count) {
+            return Err(AduParseError::ByteCountMismatch(FIXED_LENGTH, byte_count).into());
+        }
+
+        let status = cursor.read_u16_be()?;
+        let event_count = cursor.read_u16_be()?;
+        let message_count = cursor.read_u16_be()?;
+        let mut events = Vec::with_capacity(cursor.remaining());
+        while !cursor.is_empty() {
+            events.push(CommEvent::from(cursor.read_u8()?));
+        }
+
+        Ok(CommEventLog {
+            status,
+            event_count,
+            message_count,
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::ReceiveEvent;
+
+    fn parse(bytes: &[u8]) -> Result<CommEventLog, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        GetCommEventLog::parse_log(&mut cursor)
+    }
+
+    #[test]
+    fn parses_log() {
+        // example from the Modbus application protocol specification
+        assert_eq!(
+            parse(&[0x08, 0x00, 0x00, 0x01, 0x08, 0x01, 0x21, 0x20, 0x00]),
+            Ok(CommEventLog {
+                status: 0x0000,
+                event_count: 0x0108,
+                message_count: 0x0121,
+                events: vec![CommEvent::Unknown(0x20), CommEvent::CommunicationRestart],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_receive_events() {
+        assert_eq!(
+            parse(&[0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0xC0])
+                .unwrap()
+                .events,
+            vec![CommEvent::Receive(ReceiveEvent {
+                broadcast_received: true,
+                ..Default::default()
+            })]
+        );
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_remaining() {
+        assert_eq!(
+            parse(&[0x08, 0x00, 0x00, 0x01, 0x08, 0x01, 0x21, 0x20]),
+            Err(AduParseError::InsufficientBytesForByteCount(8, 7).into())
+        );
+    }
+
+    #[test]
+    fn fails_when_byte_count_too_small() {
+        assert_eq!(
+            parse(&[0x04, 0x00, 0x00, 0x01, 0x08]),
+            Err(AduParseError::ByteCountMismatch(6, 4).into())
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, InvalidRequest, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+/// Maximum length of the data following the function code and MEI type in a PDU
+const MAX_DATA_LENGTH: usize = crate::common::frame::constants::MAX_ADU_LENGTH - 2;
+
+pub(crate) struct MeiTransport {
+    pub(crate) mei_type: u8,
+    pub(crate) data: Vec<u8>,
+    promise: Promise<Vec<u8>>,
+}
+
+impl MeiTransport {
+    pub(crate) fn new(mei_type: u8, data: Vec<u8>, promise: Promise<Vec<u8>>) -> Self {
+        Self {
+            mei_type,
+            data,
+            promise,
+        }
+    }
+
+    pub(crate) fn validate(data: &[u8]) -> Result<(), InvalidRequest> {
+        if data.len() > MAX_DATA_LENGTH {
+            return Err(InvalidRequest::PduTooLarge(data.len(), MAX_DATA_LENGTH));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u8(self.mei_type)?;
+        cursor.write_bytes(&self.data)?;
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let data = self.parse_response(&mut cursor)?;
+
+        if decode.data_values() {
+            tracing::info!(
+                "PDU RX - {} MEI type: {:#04X} length: {}\n{:02X?}",
+                function,
+                self.mei_type,
+                data.len(),
+                data
+            );
+        } else if decode.data_headers() {
+            tracing::info!(
+                "PDU RX - {} MEI type: {:#04X} length: {}",
+                function,
+                self.mei_type,
+                data.len()
+            );
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(data);
+        Ok(())
+    }
+
+    fn parse_response(&self, cursor: &mut ReadCursor) -> Result<Vec<u8>, RequestError> {
+        if cursor.read_u8()? != self.mei_type {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+        Ok(cursor.read_bytes(cursor.remaining())?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANOPEN_GENERAL_REFERENCE: u8 = 0x0D;
+
+    fn parse(bytes: &[u8]) -> Result<Vec<u8>, RequestError> {
+        let request = MeiTransport::new(CANOPEN_GENERAL_REFERENCE, vec![], Promise::new(|_| {}));
+        let mut cursor = ReadCursor::new(bytes);
+        request.parse_response(&mut cursor)
+    }
+
+    #[test]
+    fn serializes_request() {
+        let request = MeiTransport::new(
+            CANOPEN_GENERAL_REFERENCE,
+            vec![0x01, 0x02],
+            Promise::new(|_| {}),
+        );
+        let mut buffer = [0u8; 3];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(buffer, [0x0D, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn returns_data_following_the_mei_type() {
+        assert_eq!(parse(&[0x0D, 0xCA, 0xFE]), Ok(vec![0xCA, 0xFE]));
+        assert_eq!(parse(&[0x0D]), Ok(vec![]));
+    }
+
+    #[test]
+    fn fails_when_mei_type_is_not_echoed() {
+        assert_eq!(
+            parse(&[0x0E, 0xCA, 0xFE]),
+            Err(AduParseError::ReplyEchoMismatch.into())
+        );
+    }
+
+    #[test]
+    fn rejects_data_that_does_not_fit_in_a_pdu() {
+        assert_eq!(MeiTransport::validate(&[0; MAX_DATA_LENGTH]), Ok(()));
+        assert_eq!(
+            MeiTransport::validate(&[0; MAX_DATA_LENGTH + 1]),
+            Err(InvalidRequest::PduTooLarge(
+                MAX_DATA_LENGTH + 1,
+                MAX_DATA_LENGTH
+            ))
+        );
+    }
+}
@@ -1,4 +1,16 @@
+pub(crate) mod diagnostics;
+pub(crate) mod get_comm_event_counter;
+pub(crate) mod get_comm_event_log;
+pub(crate) mod mei_transport;
+pub(crate) mod raw_pdu;
 pub(crate) mod read_bits;
+pub(crate) mod read_device_id;
+pub(crate) mod read_exception_status;
+pub(crate) mod read_fifo;
+pub(crate) mod read_file_record;
 pub(crate) mod read_registers;
+pub(crate) mod read_write_multiple;
+pub(crate) mod report_server_id;
+pub(crate) mod write_file_record;
 pub(crate) mod write_multiple;
 pub(crate) mod write_single;
@@ -0,0 +1,109 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::{InvalidRequest, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+/// Maximum length of the data following the function code in a PDU
+const MAX_DATA_LENGTH: usize = crate::common::frame::constants::MAX_ADU_LENGTH - 1;
+
+pub(crate) struct RawPdu {
+    pub(crate) function: u8,
+    pub(crate) data: Vec<u8>,
+    promise: Promise<Vec<u8>>,
+}
+
+impl RawPdu {
+    pub(crate) fn new(function: u8, data: Vec<u8>, promise: Promise<Vec<u8>>) -> Self {
+        Self {
+            function,
+            data,
+            promise,
+        }
+    }
+
+    pub(crate) fn validate(function: u8, data: &[u8]) -> Result<(), InvalidRequest> {
+        // the high bit is reserved for exception responses
+        if function & 0x80 != 0 {
+            return Err(InvalidRequest::BadFunctionCode(function));
+        }
+        if data.len() > MAX_DATA_LENGTH {
+            return Err(InvalidRequest::PduTooLarge(data.len(), MAX_DATA_LENGTH));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_bytes(&self.data)?;
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let data = cursor.read_bytes(cursor.remaining())?.to_vec();
+
+        if decode.data_values() {
+            tracing::info!(
+                "PDU RX - {} length: {}\n{:02X?}",
+                function,
+                data.len(),
+                data
+            );
+        } else if decode.data_headers() {
+            tracing::info!("PDU RX - {} length: {}", function, data.len());
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_exception_function_codes() {
+        assert_eq!(
+            RawPdu::validate(0xC1, &[]),
+            Err(InvalidRequest::BadFunctionCode(0xC1))
+        );
+    }
+
+    #[test]
+    fn rejects_data_that_does_not_fit_in_a_pdu() {
+        assert_eq!(RawPdu::validate(0x41, &[0; MAX_DATA_LENGTH]), Ok(()));
+        assert_eq!(
+            RawPdu::validate(0x41, &[0; MAX_DATA_LENGTH + 1]),
+            Err(InvalidRequest::PduTooLarge(
+                MAX_DATA_LENGTH + 1,
+                MAX_DATA_LENGTH
+            ))
+        );
+    }
+
+    #[test]
+    fn completes_promise_with_the_response_data() {
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        let mut request = RawPdu::new(0x41, vec![0x01], Promise::channel(tx));
+        request
+            .handle_response(
+                ReadCursor::new(&[0xCA, 0xFE]),
+                FunctionField::Raw(0x41),
+                AppDecodeLevel::Nothing,
+            )
+            .unwrap();
+        assert_eq!(rx.try_recv().unwrap(), Ok(vec![0xCA, 0xFE]));
+    }
+}
@@ -1,7 +1,7 @@
-use crate::common::function::FunctionCode;
+use crate::common::frame::FunctionField;
 use crate::common::traits::Serialize;
 use crate::decode::AppDecodeLevel;
-use crate::error::RequestError;
+use crate::error::{AduParseError, RequestError};
 use crate::types::{AddressRange, BitIterator, BitIteratorDisplay, ReadBitsRange};
 use crate::Indexed;
 
@@ -82,7 +82,7 @@ impl ReadBits {
     pub(crate) fn handle_response(
         &mut self,
         mut cursor: ReadCursor,
-        function: FunctionCode,
+        function: FunctionField,
         decode: AppDecodeLevel,
     ) -> Result<(), RequestError> {
         let response = Self::parse_bits_response(self.request.get(), &mut cursor)?;
@@ -103,9 +103,62 @@ impl ReadBits {
         range: AddressRange,
         cursor: &'a mut ReadCursor,
     ) -> Result<BitIterator<'a>, RequestError> {
-        // there's a byte-count here that we don't actually need
-        cursor.read_u8()?;
+        // the byte count must agree with the quantity of bits that was requested
+        let byte_count = cursor.read_u8()? as usize;
+        let expected = crate::common::bits::num_bytes_for_bits(range.count);
+        if byte_count != expected {
+            return Err(AduParseError::ByteCountMismatch(expected, byte_count).into());
+        }
+        // and with the length of the frame, so a short or padded response isn't mis-parsed
+        let remaining = cursor.remaining();
+        if byte_count > remaining {
+            return Err(AduParseError::InsufficientBytesForByteCount(byte_count, remaining).into());
+        }
+        if byte_count < remaining {
+            return Err(AduParseError::TrailingBytes(remaining - byte_count).into());
+        }
         // the rest is a sequence of bits
         BitIterator::parse_all(range, cursor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(range: AddressRange, bytes: &[u8]) -> Result<Vec<Indexed<bool>>, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        ReadBits::parse_bits_response(range, &mut cursor).map(|x| x.collect())
+    }
+
+    #[test]
+    fn parses_bits_when_byte_count_matches_quantity() {
+        let range = AddressRange::try_from(1, 10).unwrap();
+        let bits = parse(range, &[0x02, 0x01, 0x02]).unwrap();
+        assert_eq!(bits.len(), 10);
+        assert_eq!(bits[0], Indexed::new(1, true));
+        assert_eq!(bits[9], Indexed::new(10, true));
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_quantity() {
+        let range = AddressRange::try_from(1, 10).unwrap();
+        assert_eq!(
+            parse(range, &[0x01, 0xFF]),
+            Err(AduParseError::ByteCountMismatch(2, 1).into())
+        );
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_frame_length() {
+        let range = AddressRange::try_from(1, 10).unwrap();
+        assert_eq!(
+            parse(range, &[0x02, 0xFF]),
+            Err(AduParseError::InsufficientBytesForByteCount(2, 1).into())
+        );
+        assert_eq!(
+            parse(range, &[0x02, 0xFF, 0x03, 0x00]),
+            Err(AduParseError::TrailingBytes(1).into())
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) const MEI_TYPE_READ_DEVICE_ID: u8 = 0x0E;
+
+/// Objects returned by a single `read device identification` transaction
+#[derive(Debug, PartialEq)]
+pub(crate) struct DeviceIdResponse {
+    pub(crate) conformity_level: u8,
+    pub(crate) next_object_id: Option<u8>,
+    pub(crate) objects: Vec<(u8, Vec<u8>)>,
+}
+
+pub(crate) struct ReadDeviceId {
+    pub(crate) read_device_id_code: u8,
+    pub(crate) object_id: u8,
+    promise: Promise<DeviceIdResponse>,
+}
+
+impl ReadDeviceId {
+    pub(crate) fn new(
+        read_device_id_code: u8,
+        object_id: u8,
+        promise: Promise<DeviceIdResponse>,
+    ) -> Self {
+        Self {
+            read_device_id_code,
+            object_id,
+            promise,
+        }
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u8(MEI_TYPE_READ_DEVICE_ID)?;
+        cursor.write_u8(self.read_device_id_code)?;
+        cursor.write_u8(self.object_id)?;
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let response = self.parse_response(&mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!(
+                "PDU RX - {} conformity level: {:#04X} objects: {}",
+                function,
+                response.conformity_level,
+                response.objects.len()
+            );
+            if decode.data_values() {
+                for (id, value) in response.objects.iter() {
+                    tracing::info!("id: {:#04X} value: {:02X?}", id, value);
+                }
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(response);
+        Ok(())
+    }
+
+    fn parse_response(&self, cursor: &mut ReadCursor) -> Result<DeviceIdResponse, RequestError> {
+        if cursor.read_u8()? != MEI_TYPE_READ_DEVICE_ID
+            || cursor.read_u8()? != self.read_device_id_code
+        {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+        let conformity_level = cursor.read_u8()?;
+        let more_follows = cursor.read_u8()? == 0xFF;
+        let next_object_id = cursor.read_u8()?;
+        let count = cursor.read_u8()?;
+
+        let mut objects = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let id = cursor.read_u8()?;
+            let length = cursor.read_u8()? as usize;
+            objects.push((id, cursor.read_bytes(length)?.to_vec()));
+        }
+        cursor.expect_empty()?;
+
+        Ok(DeviceIdResponse {
+            conformity_level,
+            next_object_id: if more_follows {
+                Some(next_object_id)
+            } else {
+                None
+            },
+            objects,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Result<DeviceIdResponse, RequestError> {
+        let request = ReadDeviceId::new(0x01, 0x00, Promise::new(|_| {}));
+        let mut cursor = ReadCursor::new(bytes);
+        request.parse_response(&mut cursor)
+    }
+
+    #[test]
+    fn serializes_request() {
+        let request = ReadDeviceId::new(0x01, 0x00, Promise::new(|_| {}));
+        let mut buffer = [0u8; 3];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(buffer, [0x0E, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn parses_objects() {
+        assert_eq!(
+            parse(&[0x0E, 0x01, 0x01, 0x00, 0x00, 0x02, 0x00, 0x02, b'A', b'B', 0x01, 0x01, b'C']),
+            Ok(DeviceIdResponse {
+                conformity_level: 0x01,
+                next_object_id: None,
+                objects: vec![(0x00, b"AB".to_vec()), (0x01, b"C".to_vec())],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_more_follows() {
+        assert_eq!(
+            parse(&[0x0E, 0x01, 0x01, 0xFF, 0x01, 0x01, 0x00, 0x01, b'A'])
+                .unwrap()
+                .next_object_id,
+            Some(0x01)
+        );
+    }
+
+    #[test]
+    fn fails_when_read_device_id_code_is_not_echoed() {
+        assert_eq!(
+            parse(&[0x0E, 0x02, 0x01, 0x00, 0x00, 0x00]),
+            Err(AduParseError::ReplyEchoMismatch.into())
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::RequestError;
+use crate::types::ExceptionStatus;
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) struct ReadExceptionStatus {
+    promise: Promise<ExceptionStatus>,
+}
+
+impl ReadExceptionStatus {
+    pub(crate) fn new(promise: Promise<ExceptionStatus>) -> Self {
+        Self { promise }
+    }
+
+    pub(crate) fn serialize(&self, _cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the request has no payload
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let status = ExceptionStatus::new(cursor.read_u8()?);
+        cursor.expect_empty()?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {} {}", function, status);
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(status);
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, RequestError};
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) struct ReadFifoQueue {
+    pub(crate) address: u16,
+    promise: Promise<Vec<u16>>,
+}
+
+impl ReadFifoQueue {
+    pub(crate) fn new(address: u16, promise: Promise<Vec<u16>>) -> Self {
+        Self { address, promise }
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u16_be(self.address)?;
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let values = Self::parse_fifo_response(&mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {} count: {}", function, values.len());
+            if decode.data_values() {
+                for value in values.iter() {
+                    tracing::info!("value: {:#06X}", value);
+                }
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(values);
+        Ok(())
+    }
+
+    fn parse_fifo_response(cursor: &mut ReadCursor) -> Result<Vec<u16>, RequestError> {
+        // the byte count covers the FIFO count and the registers that follow it
+        let byte_count = cursor.read_u16_be()? as usize;
+        let fifo_count = cursor.read_u16_be()?;
+        if fifo_count > crate::constants::limits::MAX_READ_FIFO_COUNT {
+            return Err(AduParseError::FifoCountTooLarge(
+                fifo_count,
+                crate::constants::limits::MAX_READ_FIFO_COUNT,
+            )
+            .into());
+        }
+        let expected = 2 + 2 * fifo_count as usize;
+        if byte_count != expected {
+            return Err(AduParseError::ByteCountMismatch(expected, byte_count).into());
+        }
+
+        let mut values = Vec::with_capacity(fifo_count as usize);
+        for _ in 0..fifo_count {
+            values.push(cursor.read_u16_be()?);
+        }
+        cursor.expect_empty()?;
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Result<Vec<u16>, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        ReadFifoQueue::parse_fifo_response(&mut cursor)
+    }
+
+    #[test]
+    fn parses_fifo_values() {
+        // example from the Modbus application protocol specification
+        assert_eq!(
+            parse(&[0x00, 0x06, 0x00, 0x02, 0x01, 0xB8, 0x12, 0x84]),
+            Ok(vec![0x01B8, 0x1284])
+        );
+    }
+
+    #[test]
+    fn parses_empty_fifo() {
+        assert_eq!(parse(&[0x00, 0x02, 0x00, 0x00]), Ok(vec![]));
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_fifo_count() {
+        assert_eq!(
+            parse(&[0x00, 0x04, 0x00, 0x02, 0x01, 0xB8, 0x12, 0x84]),
+            Err(AduParseError::ByteCountMismatch(6, 4).into())
+        );
+    }
+
+    #[test]
+    fn fails_when_fifo_count_exceeds_limit() {
+        assert_eq!(
+            parse(&[0x00, 0x42, 0x00, 0x20]),
+            Err(AduParseError::FifoCountTooLarge(32, 31).into())
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::constants::FILE_RECORD_REFERENCE_TYPE;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, InvalidRange, InvalidRequest, RequestError};
+use crate::types::{FileRecord, FileRecordRange};
+
+use scursor::{ReadCursor, WriteCursor};
+
+// reference type, file number, record number, and record length
+const SUB_REQUEST_LENGTH: usize = 7;
+// function code and byte count
+const PDU_HEADER_LENGTH: usize = 2;
+
+pub(crate) struct ReadFileRecords {
+    pub(crate) request: Vec<FileRecordRange>,
+    promise: Promise<Vec<FileRecord>>,
+}
+
+impl ReadFileRecords {
+    pub(crate) fn new(request: Vec<FileRecordRange>, promise: Promise<Vec<FileRecord>>) -> Self {
+        Self { request, promise }
+    }
+
+    /// check the sub-requests and that both the request and the response fit in a PDU
+    pub(crate) fn validate(request: &[FileRecordRange]) -> Result<(), InvalidRequest> {
+        if request.is_empty() {
+            return Err(InvalidRange::CountOfZero.into());
+        }
+        for range in request {
+            range.validate()?;
+        }
+
+        let max = crate::common::frame::constants::MAX_ADU_LENGTH;
+        let request_size = PDU_HEADER_LENGTH + SUB_REQUEST_LENGTH * request.len();
+        if request_size > max {
+            return Err(InvalidRequest::PduTooLarge(request_size, max));
+        }
+        let response_size = PDU_HEADER_LENGTH + Self::response_data_length(request);
+        if response_size > max {
+            return Err(InvalidRequest::PduTooLarge(response_size, max));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // validated to fit in the PDU
+        cursor.write_u8((SUB_REQUEST_LENGTH * self.request.len()) as u8)?;
+        for range in self.request.iter() {
+            cursor.write_u8(FILE_RECORD_REFERENCE_TYPE)?;
+            cursor.write_u16_be(range.file_number)?;
+            cursor.write_u16_be(range.record_number)?;
+            cursor.write_u16_be(range.record_length)?;
+        }
+        Ok(())
+    }
+
+    /// parse the sub-requests of a request received by a server
+    pub(crate) fn parse_request(
+        cursor: &mut ReadCursor,
+    ) -> Result<Vec<FileRecordRange>, RequestError> {
+        let data_length = cursor.read_u8()? as usize;
+        if data_length != cursor.remaining() {
+            return Err(AduParseError::InsufficientBytesForByteCount(
+                data_length,
+                cursor.remaining(),
+            )
+            .into());
+        }
+
+        let mut request = Vec::with_capacity(data_length / SUB_REQUEST_LENGTH);
+        while !cursor.is_empty() {
+            let reference_type = cursor.read_u8()?;
+            if reference_type != FILE_RECORD_REFERENCE_TYPE {
+                return Err(AduParseError::BadReferenceType(reference_type).into());
+            }
+            request.push(FileRecordRange::new(
+                cursor.read_u16_be()?,
+                cursor.read_u16_be()?,
+                cursor.read_u16_be()?,
+            ));
+        }
+        Ok(request)
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let records = Self::parse_records_response(&self.request, &mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {}", function);
+            for record in records.iter() {
+                tracing::info!("{}", record);
+                if decode.data_values() {
+                    for value in record.values.iter() {
+                        tracing::info!("value: {:#06X}", value);
+                    }
+                }
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(records);
+        Ok(())
+    }
+
+    // each sub-response is a length byte, the reference type, and the registers
+    fn response_data_length(request: &[FileRecordRange]) -> usize {
+        request
+            .iter()
+            .map(|x| 2 + 2 * x.record_length as usize)
+            .sum()
+    }
+
+    fn parse_records_response(
+        request: &[FileRecordRange],
+        cursor: &mut ReadCursor,
+    ) -> Result<Vec<FileRecord>, RequestError> {
+        let data_length = cursor.read_u8()? as usize;
+        let expected = Self::response_data_length(request);
+        if data_length != expected {
+            return Err(AduParseError::ByteCountMismatch(expected, data_length).into());
+        }
+
+        let mut records = Vec::with_capacity(request.len());
+        for range in request {
+            let length = cursor.read_u8()? as usize;
+            let expected = 1 + 2 * range.record_length as usize;
+            if length != expected {
+                return Err(AduParseError::ByteCountMismatch(expected, length).into());
+            }
+            let reference_type = cursor.read_u8()?;
+            if reference_type != FILE_RECORD_REFERENCE_TYPE {
+                return Err(AduParseError::BadReferenceType(reference_type).into());
+            }
+            let mut values = Vec::with_capacity(range.record_length as usize);
+            for _ in 0..range.record_length {
+                values.push(cursor.read_u16_be()?);
+            }
+            records.push(FileRecord::new(
+                range.file_number,
+                range.record_number,
+                values,
+            ));
+        }
+        cursor.expect_empty()?;
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // example from the Modbus application protocol specification
+    fn spec_request() -> Vec<FileRecordRange> {
+        vec![FileRecordRange::new(4, 1, 2), FileRecordRange::new(3, 9, 2)]
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Vec<FileRecord>, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        ReadFileRecords::parse_records_response(&spec_request(), &mut cursor)
+    }
+
+    #[test]
+    fn serializes_request() {
+        let request = ReadFileRecords::new(spec_request(), Promise::new(|_| {}));
+        let mut buffer = [0u8; 15];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(
+            buffer,
+            [
+                0x0E, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09, 0x00,
+                0x02
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_request() {
+        let mut cursor = ReadCursor::new(&[
+            0x0E, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09, 0x00,
+            0x02,
+        ]);
+        assert_eq!(
+            ReadFileRecords::parse_request(&mut cursor),
+            Ok(spec_request())
+        );
+        let mut cursor = ReadCursor::new(&[0x07, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00]);
+        assert_eq!(
+            ReadFileRecords::parse_request(&mut cursor),
+            Err(AduParseError::InsufficientBytesForByteCount(7, 6).into())
+        );
+    }
+
+    #[test]
+    fn parses_records() {
+        assert_eq!(
+            parse(&[0x0C, 0x05, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40]),
+            Ok(vec![
+                FileRecord::new(4, 1, vec![0x0DFE, 0x0020]),
+                FileRecord::new(3, 9, vec![0x33CD, 0x0040]),
+            ])
+        );
+    }
+
+    #[test]
+    fn fails_on_bad_reference_type() {
+        assert_eq!(
+            parse(&[0x0C, 0x05, 0x07, 0x0D, 0xFE, 0x00, 0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40]),
+            Err(AduParseError::BadReferenceType(0x07).into())
+        );
+    }
+
+    #[test]
+    fn fails_when_sub_response_length_does_not_match() {
+        assert_eq!(
+            parse(&[0x0C, 0x03, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40]),
+            Err(AduParseError::ByteCountMismatch(5, 3).into())
+        );
+    }
+
+    #[test]
+    fn validates_requests() {
+        assert_eq!(ReadFileRecords::validate(&spec_request()), Ok(()));
+        assert_eq!(
+            ReadFileRecords::validate(&[]),
+            Err(InvalidRange::CountOfZero.into())
+        );
+        assert_eq!(
+            ReadFileRecords::validate(&[FileRecordRange::new(1, 0x2710, 1)]),
+            Err(InvalidRequest::RecordNumberTooLarge(0x2710, 0x270F))
+        );
+        assert_eq!(
+            ReadFileRecords::validate(&[FileRecordRange::new(1, 0, 125)]),
+            Err(InvalidRequest::PduTooLarge(254, 253))
+        );
+    }
+}
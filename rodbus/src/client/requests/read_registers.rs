@@ -1,7 +1,7 @@
-use crate::common::function::FunctionCode;
+use crate::common::frame::FunctionField;
 use crate::common::traits::Serialize;
 use crate::decode::AppDecodeLevel;
-use crate::error::RequestError;
+use crate::error::{AduParseError, RequestError};
 use crate::types::{
     AddressRange, Indexed, ReadRegistersRange, RegisterIterator, RegisterIteratorDisplay,
 };
@@ -85,7 +85,7 @@ impl ReadRegisters {
     pub(crate) fn handle_response(
         &mut self,
         mut cursor: ReadCursor,
-        function: FunctionCode,
+        function: FunctionField,
         decode: AppDecodeLevel,
     ) -> Result<(), RequestError> {
         let response = Self::parse_registers_response(self.request.get(), &mut cursor)?;
@@ -102,13 +102,66 @@ impl ReadRegisters {
         Ok(())
     }
 
-    fn parse_registers_response<'a>(
+    pub(crate) fn parse_registers_response<'a>(
         range: AddressRange,
         cursor: &'a mut ReadCursor,
     ) -> Result<RegisterIterator<'a>, RequestError> {
-        // there's a byte-count here that we don't actually need
-        cursor.read_u8()?;
-        // the reset is a sequence of bits
+        // the byte count must agree with the quantity of registers that was requested
+        let byte_count = cursor.read_u8()? as usize;
+        let expected = 2 * range.count as usize;
+        if byte_count != expected {
+            return Err(AduParseError::ByteCountMismatch(expected, byte_count).into());
+        }
+        // and with the length of the frame, so a short or padded response isn't mis-parsed
+        let remaining = cursor.remaining();
+        if byte_count > remaining {
+            return Err(AduParseError::InsufficientBytesForByteCount(byte_count, remaining).into());
+        }
+        if byte_count < remaining {
+            return Err(AduParseError::TrailingBytes(remaining - byte_count).into());
+        }
+        // the rest is a sequence of registers
         RegisterIterator::parse_all(range, cursor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(range: AddressRange, bytes: &[u8]) -> Result<Vec<Indexed<u16>>, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        ReadRegisters::parse_registers_response(range, &mut cursor).map(|x| x.collect())
+    }
+
+    #[test]
+    fn parses_registers_when_byte_count_matches_quantity() {
+        let range = AddressRange::try_from(1, 2).unwrap();
+        assert_eq!(
+            parse(range, &[0x04, 0xCA, 0xFE, 0x00, 0x01]),
+            Ok(vec![Indexed::new(1, 0xCAFE), Indexed::new(2, 0x0001)])
+        );
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_quantity() {
+        let range = AddressRange::try_from(1, 2).unwrap();
+        assert_eq!(
+            parse(range, &[0x02, 0xCA, 0xFE, 0x00, 0x01]),
+            Err(AduParseError::ByteCountMismatch(4, 2).into())
+        );
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_frame_length() {
+        let range = AddressRange::try_from(1, 2).unwrap();
+        assert_eq!(
+            parse(range, &[0x04, 0xCA, 0xFE, 0x00]),
+            Err(AduParseError::InsufficientBytesForByteCount(4, 3).into())
+        );
+        assert_eq!(
+            parse(range, &[0x04, 0xCA, 0xFE, 0x00, 0x01, 0x00]),
+            Err(AduParseError::TrailingBytes(1).into())
+        );
+    }
+}
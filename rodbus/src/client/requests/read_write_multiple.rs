@@ -0,0 +1,119 @@
+use crate::client::requests::read_registers::{Promise, ReadRegisters};
+use crate::client::requests::write_multiple::WriteMultiple;
+use crate::common::frame::FunctionField;
+use crate::common::traits::Serialize;
+use crate::decode::AppDecodeLevel;
+use crate::error::{InvalidRequest, RequestError};
+use crate::types::{AddressRange, Indexed, RegisterIteratorDisplay};
+
+use scursor::{ReadCursor, WriteCursor};
+
+/// Range to read and values to write in a single `read/write multiple registers` request
+///
+/// The server performs the write before the read
+#[derive(Debug, Clone)]
+pub struct ReadWriteMultiple<T> {
+    /// range of holding registers to read
+    pub(crate) read_range: AddressRange,
+    /// starting address and values to write
+    pub(crate) write: WriteMultiple<T>,
+}
+
+impl<T> ReadWriteMultiple<T> {
+    /// Create a new request from the range to read and the starting address and values to write
+    pub fn new(
+        read_range: AddressRange,
+        write_start: u16,
+        values: Vec<T>,
+    ) -> Result<Self, InvalidRequest> {
+        Ok(Self {
+            read_range,
+            write: WriteMultiple::from(write_start, values)?,
+        })
+    }
+}
+
+pub(crate) struct ReadWriteMultipleRegisters {
+    pub(crate) request: ReadWriteMultiple<u16>,
+    promise: Promise,
+}
+
+impl ReadWriteMultipleRegisters {
+    pub(crate) fn new(request: ReadWriteMultiple<u16>, promise: Promise) -> Self {
+        Self { request, promise }
+    }
+
+    pub(crate) fn channel(
+        request: ReadWriteMultiple<u16>,
+        tx: tokio::sync::oneshot::Sender<Result<Vec<Indexed<u16>>, RequestError>>,
+    ) -> Self {
+        Self::new(
+            request,
+            Promise::new(|x: Result<crate::RegisterIterator, RequestError>| {
+                let _ = tx.send(x.map(|x| x.collect()));
+            }),
+        )
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        self.request.serialize(cursor)
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        // the response has the same format as a read holding registers response
+        let response =
+            ReadRegisters::parse_registers_response(self.request.read_range, &mut cursor)?;
+
+        if decode.enabled() {
+            tracing::info!(
+                "PDU RX - {} {}",
+                function,
+                RegisterIteratorDisplay::new(decode, response)
+            );
+        }
+
+        self.promise.success(response);
+        Ok(())
+    }
+}
+
+impl Serialize for ReadWriteMultiple<u16> {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        self.read_range.serialize(cursor)?;
+        self.write.serialize(cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_request() {
+        let request = ReadWriteMultiple::new(
+            AddressRange::try_from(3, 6).unwrap(),
+            14,
+            vec![0x00FF, 0x00FF, 0x00FF],
+        )
+        .unwrap();
+        let mut buffer = [0u8; 15];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(
+            buffer,
+            [
+                0x00, 0x03, 0x00, 0x06, 0x00, 0x0E, 0x00, 0x03, 0x06, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+                0xFF
+            ]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, RequestError};
+use crate::types::ServerId;
+
+use scursor::{ReadCursor, WriteCursor};
+
+pub(crate) struct ReportServerId {
+    promise: Promise<ServerId>,
+}
+
+impl ReportServerId {
+    pub(crate) fn new(promise: Promise<ServerId>) -> Self {
+        Self { promise }
+    }
+
+    pub(crate) fn serialize(&self, _cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the request has no payload
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let server_id = Self::parse_server_id(&mut cursor)?;
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {} {}", function, server_id);
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(server_id);
+        Ok(())
+    }
+
+    fn parse_server_id(cursor: &mut ReadCursor) -> Result<ServerId, RequestError> {
+        let byte_count = cursor.read_u8()? as usize;
+        if byte_count != cursor.remaining() {
+            return Err(AduParseError::InsufficientBytesForByteCount(
+                byte_count,
+                cursor.remaining(),
+            )
+            .into());
+        }
+        // the run indicator is the last byte
+        let bytes = cursor.read_bytes(byte_count)?;
+        match bytes.split_last() {
+            Some((run_indicator, data)) => Ok(ServerId::new(data.to_vec(), *run_indicator)),
+            None => Err(AduParseError::InsufficientBytes.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Result<ServerId, RequestError> {
+        let mut cursor = ReadCursor::new(bytes);
+        ReportServerId::parse_server_id(&mut cursor)
+    }
+
+    #[test]
+    fn parses_server_id_and_run_indicator() {
+        let server_id = parse(&[0x03, 0x2A, 0x01, 0xFF]).unwrap();
+        assert_eq!(server_id, ServerId::new(vec![0x2A, 0x01], 0xFF));
+        assert!(server_id.is_running());
+    }
+
+    #[test]
+    fn fails_on_empty_payload() {
+        assert_eq!(parse(&[0x00]), Err(AduParseError::InsufficientBytes.into()));
+    }
+
+    #[test]
+    fn fails_when_byte_count_does_not_match_remaining() {
+        assert_eq!(
+            parse(&[0x03, 0x2A, 0xFF]),
+            Err(AduParseError::InsufficientBytesForByteCount(3, 2).into())
+        );
+    }
+}
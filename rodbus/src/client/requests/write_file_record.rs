@@ -0,0 +1,186 @@
+use crate::client::message::Promise;
+use crate::common::frame::FunctionField;
+use crate::constants::FILE_RECORD_REFERENCE_TYPE;
+use crate::decode::AppDecodeLevel;
+use crate::error::{AduParseError, InvalidRange, InvalidRequest, RequestError};
+use crate::types::FileRecord;
+
+use scursor::{ReadCursor, WriteCursor};
+
+// reference type, file number, record number, and record length
+const SUB_REQUEST_HEADER_LENGTH: usize = 7;
+// function code and request data length
+const PDU_HEADER_LENGTH: usize = 2;
+
+pub(crate) struct WriteFileRecords {
+    pub(crate) request: Vec<FileRecord>,
+    promise: Promise<()>,
+}
+
+impl WriteFileRecords {
+    pub(crate) fn new(request: Vec<FileRecord>, promise: Promise<()>) -> Self {
+        Self { request, promise }
+    }
+
+    /// check the records and that the request (and the echoed response) fits in a PDU
+    pub(crate) fn validate(request: &[FileRecord]) -> Result<(), InvalidRequest> {
+        if request.is_empty() {
+            return Err(InvalidRange::CountOfZero.into());
+        }
+        for record in request {
+            record.validate()?;
+        }
+
+        let max = crate::common::frame::constants::MAX_ADU_LENGTH;
+        let size = PDU_HEADER_LENGTH + Self::request_data_length(request);
+        if size > max {
+            return Err(InvalidRequest::PduTooLarge(size, max));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // validated to fit in the PDU
+        cursor.write_u8(Self::request_data_length(&self.request) as u8)?;
+        for record in self.request.iter() {
+            cursor.write_u8(FILE_RECORD_REFERENCE_TYPE)?;
+            cursor.write_u16_be(record.file_number)?;
+            cursor.write_u16_be(record.record_number)?;
+            cursor.write_u16_be(record.values.len() as u16)?;
+            for value in record.values.iter() {
+                cursor.write_u16_be(*value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn failure(&mut self, err: RequestError) {
+        self.promise.failure(err)
+    }
+
+    pub(crate) fn complete_broadcast(&mut self) {
+        self.promise.success(())
+    }
+
+    pub(crate) fn handle_response(
+        &mut self,
+        mut cursor: ReadCursor,
+        function: FunctionField,
+        decode: AppDecodeLevel,
+    ) -> Result<(), RequestError> {
+        let response = Self::parse_records(&mut cursor)?;
+        if response != self.request {
+            return Err(AduParseError::ReplyEchoMismatch.into());
+        }
+
+        if decode.data_headers() {
+            tracing::info!("PDU RX - {}", function);
+            for record in response.iter() {
+                tracing::info!("{}", record);
+            }
+        } else if decode.header() {
+            tracing::info!("PDU RX - {}", function);
+        }
+
+        self.promise.success(());
+        Ok(())
+    }
+
+    fn request_data_length(request: &[FileRecord]) -> usize {
+        request
+            .iter()
+            .map(|x| SUB_REQUEST_HEADER_LENGTH + 2 * x.values.len())
+            .sum()
+    }
+
+    /// parse the records of a request received by a server, or of the echoed response
+    pub(crate) fn parse_records(cursor: &mut ReadCursor) -> Result<Vec<FileRecord>, RequestError> {
+        let data_length = cursor.read_u8()? as usize;
+        if data_length != cursor.remaining() {
+            return Err(AduParseError::InsufficientBytesForByteCount(
+                data_length,
+                cursor.remaining(),
+            )
+            .into());
+        }
+
+        let mut records = Vec::new();
+        while !cursor.is_empty() {
+            let reference_type = cursor.read_u8()?;
+            if reference_type != FILE_RECORD_REFERENCE_TYPE {
+                return Err(AduParseError::BadReferenceType(reference_type).into());
+            }
+            let file_number = cursor.read_u16_be()?;
+            let record_number = cursor.read_u16_be()?;
+            let record_length = cursor.read_u16_be()?;
+            let mut values = Vec::with_capacity(record_length as usize);
+            for _ in 0..record_length {
+                values.push(cursor.read_u16_be()?);
+            }
+            records.push(FileRecord::new(file_number, record_number, values));
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::function::FunctionCode;
+
+    // example from the Modbus application protocol specification
+    const SPEC_PDU: &[u8] = &[
+        0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE, 0x10, 0x0D,
+    ];
+
+    fn spec_request() -> Vec<FileRecord> {
+        vec![FileRecord::new(4, 7, vec![0x06AF, 0x04BE, 0x100D])]
+    }
+
+    #[test]
+    fn serializes_request() {
+        let request = WriteFileRecords::new(spec_request(), Promise::new(|_| {}));
+        let mut buffer = [0u8; 14];
+        let mut cursor = WriteCursor::new(&mut buffer);
+        request.serialize(&mut cursor).unwrap();
+        assert_eq!(buffer, SPEC_PDU);
+    }
+
+    #[test]
+    fn parses_echoed_records() {
+        let mut cursor = ReadCursor::new(SPEC_PDU);
+        assert_eq!(
+            WriteFileRecords::parse_records(&mut cursor),
+            Ok(spec_request())
+        );
+    }
+
+    #[test]
+    fn fails_when_response_does_not_echo_request() {
+        let mut request = WriteFileRecords::new(
+            vec![FileRecord::new(4, 7, vec![0x06AF, 0x04BE, 0x100E])],
+            Promise::new(|_| {}),
+        );
+        assert_eq!(
+            request.handle_response(
+                ReadCursor::new(SPEC_PDU),
+                FunctionField::Valid(FunctionCode::WriteFileRecord),
+                AppDecodeLevel::Nothing
+            ),
+            Err(AduParseError::ReplyEchoMismatch.into())
+        );
+    }
+
+    #[test]
+    fn validates_requests() {
+        assert_eq!(WriteFileRecords::validate(&spec_request()), Ok(()));
+        assert_eq!(
+            WriteFileRecords::validate(&[FileRecord::new(4, 7, vec![])]),
+            Err(InvalidRange::CountOfZero.into())
+        );
+        assert_eq!(
+            WriteFileRecords::validate(&[FileRecord::new(4, 7, vec![0; 123])]),
+            Err(InvalidRequest::PduTooLarge(255, 253))
+        );
+    }
+}
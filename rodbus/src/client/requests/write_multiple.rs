@@ -1,5 +1,5 @@
 use crate::client::message::Promise;
-use crate::common::function::FunctionCode;
+use crate::common::frame::FunctionField;
 use crate::common::traits::{Parse, Serialize};
 use crate::decode::AppDecodeLevel;
 use crate::error::RequestError;
@@ -102,10 +102,15 @@ where
         self.promise.failure(err)
     }
 
+    pub(crate) fn complete_broadcast(&mut self) {
+        // there is no response to a broadcast, so complete with what was written
+        self.promise.success(self.request.range)
+    }
+
     pub(crate) fn handle_response(
         &mut self,
         cursor: ReadCursor,
-        function: FunctionCode,
+        function: FunctionField,
         decode: AppDecodeLevel,
     ) -> Result<(), RequestError> {
         let response = self.parse_all(cursor)?;
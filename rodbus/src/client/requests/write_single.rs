@@ -1,11 +1,11 @@
 use std::fmt::Display;
 
 use crate::client::message::Promise;
-use crate::common::function::FunctionCode;
+use crate::common::frame::FunctionField;
 use crate::decode::AppDecodeLevel;
 use crate::error::AduParseError;
 use crate::error::RequestError;
-use crate::types::{coil_from_u16, coil_to_u16, Indexed};
+use crate::types::{coil_from_u16, coil_to_u16, Indexed, MaskWriteRegister};
 
 use scursor::{ReadCursor, WriteCursor};
 
@@ -38,10 +38,18 @@ where
         self.promise.failure(err)
     }
 
+    pub(crate) fn complete_broadcast(&mut self)
+    where
+        T: Copy,
+    {
+        // there is no response to a broadcast, so complete with what was written
+        self.promise.success(self.request)
+    }
+
     pub(crate) fn handle_response(
         &mut self,
         cursor: ReadCursor,
-        function: FunctionCode,
+        function: FunctionField,
         decode: AppDecodeLevel,
     ) -> Result<(), RequestError> {
         let response = self.parse_all(cursor)?;
@@ -92,3 +100,20 @@ impl SingleWriteOperation for Indexed<u16> {
         Ok(Indexed::new(cursor.read_u16_be()?, cursor.read_u16_be()?))
     }
 }
+
+impl SingleWriteOperation for MaskWriteRegister {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u16_be(self.address)?;
+        cursor.write_u16_be(self.and_mask)?;
+        cursor.write_u16_be(self.or_mask)?;
+        Ok(())
+    }
+
+    fn parse(cursor: &mut ReadCursor) -> Result<Self, RequestError> {
+        Ok(MaskWriteRegister::new(
+            cursor.read_u16_be()?,
+            cursor.read_u16_be()?,
+            cursor.read_u16_be()?,
+        ))
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::error::RequestError;
+
+/// Snapshot of the counters of a client channel, see [`Channel::statistics`]
+///
+/// The counters start at zero when the channel is spawned and are never reset.
+///
+/// [`Channel::statistics`]: crate::client::Channel::statistics
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelStatistics {
+    /// Number of requests written, including retries and retransmissions
+    pub requests_sent: u64,
+    /// Number of responses received for a request, including exceptions
+    pub responses_received: u64,
+    /// Number of requests that weren't answered within their timeout
+    pub timeouts: u64,
+    /// Number of malformed frames and responses that don't match their request
+    pub protocol_errors: u64,
+    /// Number of connections established after the first one
+    pub reconnects: u64,
+    /// Number of bytes written to the connections
+    pub bytes_sent: u64,
+    /// Number of bytes read from the connections
+    pub bytes_received: u64,
+    /// When a response was last received, or `None` if none was
+    pub last_exchange: Option<SystemTime>,
+}
+
+/// statistics shared by the task of a channel and its handles
+pub(crate) type SharedStatistics = Arc<Mutex<ChannelStatistics>>;
+
+impl ChannelStatistics {
+    pub(crate) fn shared() -> SharedStatistics {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    /// record the outcome of a request whose response was received
+    pub(crate) fn record_response(&mut self, result: &Result<(), RequestError>) {
+        match result {
+            Ok(()) | Err(RequestError::Exception(_)) => {
+                self.responses_received += 1;
+                self.last_exchange = Some(SystemTime::now());
+            }
+            Err(RequestError::BadFrame(_) | RequestError::BadResponse(_)) => {
+                self.protocol_errors += 1
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AduParseError;
+    use crate::exception::ExceptionCode;
+
+    #[test]
+    fn counts_responses_and_protocol_errors() {
+        let mut statistics = ChannelStatistics::default();
+        statistics.record_response(&Ok(()));
+        statistics.record_response(&Err(RequestError::Exception(
+            ExceptionCode::IllegalDataAddress,
+        )));
+        statistics.record_response(&Err(AduParseError::UnitIdMismatch(2, 1).into()));
+        statistics.record_response(&Err(RequestError::ResponseTimeout));
+
+        assert_eq!(statistics.responses_received, 2);
+        assert_eq!(statistics.protocol_errors, 1);
+        assert_eq!(statistics.timeouts, 0);
+        assert!(statistics.last_exchange.is_some());
+    }
+}
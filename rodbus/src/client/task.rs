@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, VecDeque};
 use std::time::Duration;
 
 use tracing::Instrument;
@@ -5,9 +6,17 @@ use tracing::Instrument;
 use crate::common::phys::PhysLayer;
 use tokio::time::Instant;
 
-use crate::client::message::{Command, Request, Setting};
-use crate::common::frame::{FrameHeader, FrameWriter, FramedReader, TxId};
+use crate::client::coalesce::{self, ReadGroup};
+use crate::client::message::{Command, CommandReceiver, Request, Setting};
+use crate::client::statistics::SharedStatistics;
+use crate::client::{
+    ConnectionEvent, DisconnectReason, DisconnectedPolicy, KeepAlive, Listener, NullListener,
+};
+use crate::common::frame::{Frame, FrameHeader, FrameWriter, FramedReader, TxId};
 use crate::error::*;
+use crate::exception::ExceptionCode;
+use crate::retry::RequestRetryPolicy;
+use crate::types::UnitId;
 use crate::DecodeLevel;
 
 /**
@@ -21,6 +30,8 @@ pub(crate) enum SessionError {
     BadFrame,
     /// channel was disabled
     Disabled,
+    /// no request was made for the idle period of the channel
+    Idle,
     /// the mpsc is closed (dropped) on the sender side
     Shutdown,
 }
@@ -55,6 +66,9 @@ impl std::fmt::Display for SessionError {
             SessionError::Disabled => {
                 write!(f, "Channel was disabled")
             }
+            SessionError::Idle => {
+                write!(f, "Connection was idle")
+            }
             SessionError::Shutdown => {
                 write!(f, "Shutdown was requested")
             }
@@ -63,6 +77,16 @@ impl std::fmt::Display for SessionError {
 }
 
 impl SessionError {
+    pub(crate) fn disconnect_reason(&self) -> DisconnectReason {
+        match self {
+            SessionError::IoError(kind) => DisconnectReason::Io(*kind),
+            SessionError::BadFrame => DisconnectReason::BadFrame,
+            SessionError::Disabled => DisconnectReason::Disabled,
+            SessionError::Idle => DisconnectReason::Idle,
+            SessionError::Shutdown => DisconnectReason::Shutdown,
+        }
+    }
+
     pub(crate) fn from_request_err(err: RequestError) -> Option<Self> {
         match err {
             RequestError::Io(x) => Some(SessionError::IoError(x)),
@@ -73,6 +97,23 @@ impl SessionError {
     }
 }
 
+/// request written to the connection and waiting for its response
+struct InFlight {
+    request: Request,
+    deadline: Instant,
+    /// number of the attempt, starting at 1
+    attempt: usize,
+    /// number of times it may still be resent with the same transaction id
+    retransmissions: usize,
+}
+
+/// request in flight that failed and is sent again once its delay elapses
+struct PendingRetry {
+    request: Request,
+    attempt: usize,
+    at: Instant,
+}
+
 pub(crate) struct ClientLoop {
     rx: crate::channel::Receiver<Command>,
     writer: FrameWriter,
@@ -80,64 +121,488 @@ pub(crate) struct ClientLoop {
     tx_id: TxId,
     decode: DecodeLevel,
     enabled: bool,
+    retransmissions: usize,
+    retry_policy: Option<RequestRetryPolicy>,
+    /// gap tolerance when queued reads are coalesced, or `None` if they aren't
+    max_read_gap: Option<u16>,
+    /// commands taken from the queue to coalesce reads, processed before the queue
+    pending: VecDeque<Command>,
+    /// only connect once a request is made
+    lazy: bool,
+    disconnected_policy: DisconnectedPolicy,
+    /// requests made while disconnected and kept for the next session, with their expiration
+    parked: VecDeque<(Instant, Request)>,
+    /// number of requests that may be waiting for a response at the same time
+    max_in_flight: usize,
+    /// requests waiting for a response when several are allowed, by transaction id
+    in_flight: BTreeMap<u16, InFlight>,
+    /// requests that were in flight and are retried once their delay elapses
+    retrying: Vec<PendingRetry>,
+    /// set when a shutdown was requested, dropped along with the loop once the task ends
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    /// probe sent when the connection is idle
+    keep_alive: Option<KeepAlive>,
+    /// idle period after which the connection is closed
+    idle_disconnect: Option<Duration>,
+    /// set when the last session was closed because it was idle
+    idle_closed: bool,
+    /// accept a response whose transaction id doesn't match when a single request is in flight
+    lenient_tx_id: bool,
+    statistics: SharedStatistics,
+    /// set once a session was run, so that the next ones are counted as reconnects
+    connected: bool,
+    connection_listener: Box<dyn Listener<ConnectionEvent>>,
+    #[cfg(feature = "tls")]
+    tls_config: Option<crate::tcp::tls::TlsClientConfig>,
 }
 
 impl ClientLoop {
     pub(crate) fn new(
-        rx: crate::channel::Receiver<Command>,
+        rx: CommandReceiver,
         writer: FrameWriter,
         reader: FramedReader,
         decode: DecodeLevel,
     ) -> Self {
         Self {
-            rx,
+            rx: rx.rx,
             writer,
             reader,
             tx_id: TxId::default(),
             decode,
             enabled: false,
+            retransmissions: 0,
+            retry_policy: None,
+            max_read_gap: None,
+            pending: VecDeque::new(),
+            lazy: false,
+            disconnected_policy: DisconnectedPolicy::Fail,
+            parked: VecDeque::new(),
+            max_in_flight: 1,
+            in_flight: BTreeMap::new(),
+            retrying: Vec::new(),
+            shutdown: None,
+            keep_alive: None,
+            idle_disconnect: None,
+            idle_closed: false,
+            lenient_tx_id: false,
+            statistics: rx.statistics,
+            connected: false,
+            connection_listener: NullListener::create(),
+            #[cfg(feature = "tls")]
+            tls_config: None,
         }
     }
 
+    /// resend a request that isn't answered within its timeout up to `count` times
+    /// before failing it, which is only useful on transports that can lose frames
+    pub(crate) fn with_retransmissions(mut self, count: usize) -> Self {
+        self.retransmissions = count;
+        self
+    }
+
     pub(crate) fn is_enabled(&self) -> bool {
         self.enabled
     }
 
     async fn run_cmd(&mut self, cmd: Command, io: &mut PhysLayer) -> Result<(), SessionError> {
         match cmd {
-            Command::Setting(setting) => {
-                self.change_setting(setting);
-                if !self.enabled {
-                    return Err(SessionError::Disabled);
-                }
-                Ok(())
+            Command::Setting(setting) => self.apply_setting(setting),
+            Command::Request(request) => {
+                let mut request = self.coalesce(request);
+                self.run_one_request(io, &mut request).await
+            }
+        }
+    }
+
+    fn apply_setting(&mut self, setting: Setting) -> Result<(), SessionError> {
+        self.change_setting(setting);
+        if self.shutdown.is_some() {
+            return Err(SessionError::Shutdown);
+        }
+        if !self.enabled {
+            return Err(SessionError::Disabled);
+        }
+        Ok(())
+    }
+
+    /// merge the request with the queued reads it can be performed with, if enabled
+    ///
+    /// Only the first few commands of the queue are examined, so that the queue still applies
+    /// backpressure, and high priority commands are left in their own queue.
+    fn coalesce(&mut self, request: Request) -> Request {
+        let max_gap = match self.max_read_gap {
+            Some(x) => x,
+            None => return request,
+        };
+        while self.pending.len() < coalesce::MAX_LOOKAHEAD {
+            match self.rx.try_recv_normal() {
+                Some(cmd) => self.pending.push_back(cmd),
+                None => break,
             }
-            Command::Request(mut request) => self.run_one_request(io, &mut request).await,
+        }
+        match ReadGroup::take(request, &mut self.pending, max_gap) {
+            Ok(group) => group.into_request(),
+            Err(request) => request,
         }
     }
 
+    /// wait until the channel is enabled and, in lazy mode, until a request is made
     pub(crate) async fn wait_for_enabled(&mut self) -> Result<(), Shutdown> {
         loop {
-            if self.enabled {
-                return Ok(());
-            }
+            // after an idle disconnect, only reconnect once a request is made
+            let result = match (self.enabled, self.lazy || self.idle_closed) {
+                (true, false) => return Ok(()),
+                (true, true) if self.has_pending_request() => return Ok(()),
+                (true, true) => self.wait_for_request().await,
+                (false, _) => self.fail_next_request(true).await,
+            };
 
-            if let Err(StateChange::Shutdown) = self.fail_next_request().await {
+            if let Err(StateChange::Shutdown) = result {
                 return Err(Shutdown);
             }
         }
     }
 
+    fn has_pending_request(&self) -> bool {
+        !self.parked.is_empty()
+            || self
+                .pending
+                .iter()
+                .any(|x| matches!(x, Command::Request(_)))
+    }
+
+    /// keep a request made while disconnected if the policy allows it, or fail it
+    fn park(&mut self, mut request: Request) {
+        match self.disconnected_policy {
+            DisconnectedPolicy::Queue {
+                max_queued,
+                max_age,
+            } if self.enabled && self.parked.len() < max_queued => {
+                self.parked.push_back((Instant::now() + max_age, request));
+            }
+            _ => request.details.fail(RequestError::NoConnection),
+        }
+    }
+
+    /// fail the parked requests that expired, or all of them
+    fn expire_parked(&mut self, all: bool) {
+        let now = Instant::now();
+        let mut kept = VecDeque::new();
+        for (expiration, mut request) in self.parked.drain(..) {
+            if all || expiration <= now {
+                request.details.fail(RequestError::NoConnection);
+            } else {
+                kept.push_back((expiration, request));
+            }
+        }
+        self.parked = kept;
+    }
+
+    /// perform the parked requests before any other
+    fn unpark(&mut self) {
+        self.expire_parked(false);
+        for (_, request) in self.parked.drain(..).rev() {
+            self.pending.push_front(Command::Request(request));
+        }
+    }
+
+    /// keep the next request for the session, applying the settings that come before it
+    async fn wait_for_request(&mut self) -> Result<(), StateChange> {
+        match self.rx.recv().await? {
+            Command::Request(request) => {
+                self.pending.push_back(Command::Request(request));
+                Ok(())
+            }
+            Command::Setting(x) => {
+                self.change_setting(x);
+                if self.shutdown.is_some() {
+                    Err(StateChange::Shutdown)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
     pub(crate) async fn run(&mut self, io: &mut PhysLayer) -> SessionError {
+        if std::mem::replace(&mut self.connected, true) {
+            self.statistics.lock().unwrap().reconnects += 1;
+        }
+        self.idle_closed = false;
+        self.unpark();
         loop {
-            if let Err(err) = self.poll(io).await {
+            let result = if self.is_pipelined() {
+                self.poll_pipelined(io).await
+            } else {
+                self.poll(io).await
+            };
+            self.record_bytes(io);
+            if let Err(err) = result {
+                if let SessionError::BadFrame = err {
+                    self.statistics.lock().unwrap().protocol_errors += 1;
+                }
                 tracing::warn!("ending session: {}", err);
+                self.fail_in_flight(&err);
                 return err;
             }
         }
     }
 
+    fn record_bytes(&mut self, io: &mut PhysLayer) {
+        let (read, written) = io.take_byte_counts();
+        let mut statistics = self.statistics.lock().unwrap();
+        statistics.bytes_received += read;
+        statistics.bytes_sent += written;
+    }
+
+    fn record_response(&mut self, result: &Result<(), RequestError>) {
+        self.statistics.lock().unwrap().record_response(result);
+    }
+
+    fn is_pipelined(&self) -> bool {
+        // requests still in flight are completed after the limit is lowered
+        (self.max_in_flight > 1 && self.writer.supports_pipelining()) || self.is_busy()
+    }
+
+    /// true if requests that were written are still in flight or waiting to be retried
+    fn is_busy(&self) -> bool {
+        !self.in_flight.is_empty() || !self.retrying.is_empty()
+    }
+
+    async fn poll_pipelined(&mut self, io: &mut PhysLayer) -> Result<(), SessionError> {
+        let accept = self.in_flight.len() < self.max_in_flight;
+        if accept {
+            if let Some(retry) = self.take_due_retry() {
+                return self.start_request(retry.request, retry.attempt, io).await;
+            }
+            if let Some(cmd) = self.next_pending() {
+                return self.start_cmd(cmd, io).await;
+            }
+        }
+
+        let deadline = self.in_flight.values().map(|x| x.deadline).min();
+        let retry = self.retrying.iter().map(|x| x.at).min().filter(|_| accept);
+        let idle = self.keep_alive.filter(|_| !self.is_busy()).map(|x| x.idle);
+        let idle_disconnect = self.idle_disconnect.filter(|_| !self.is_busy());
+        tokio::select! {
+            frame = self.reader.next_frame(io, self.decode) => {
+                match frame {
+                    Ok(frame) => {
+                        self.complete_in_flight(frame);
+                        Ok(())
+                    }
+                    Err(err) => match SessionError::from_request_err(err) {
+                        Some(err) => Err(err),
+                        None => Ok(()),
+                    }
+                }
+            }
+            res = self.rx.recv(), if accept => {
+                let cmd: Command = res?;
+                self.start_cmd(cmd, io).await
+            }
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                self.expire_in_flight(io).await
+            }
+            _ = tokio::time::sleep_until(retry.unwrap_or_else(Instant::now)), if retry.is_some() => {
+                // the retry is started on the next poll
+                Ok(())
+            }
+            _ = tokio::time::sleep(idle.unwrap_or_default()), if idle.is_some() => {
+                self.probe(io).await
+            }
+            _ = tokio::time::sleep(idle_disconnect.unwrap_or_default()), if idle_disconnect.is_some() => {
+                self.close_idle()
+            }
+        }
+    }
+
+    /// write a request without waiting for its response
+    async fn start_cmd(&mut self, cmd: Command, io: &mut PhysLayer) -> Result<(), SessionError> {
+        let request = match cmd {
+            Command::Setting(setting) => return self.apply_setting(setting),
+            Command::Request(request) => self.coalesce(request),
+        };
+        self.start_request(request, 1, io).await
+    }
+
+    /// write attempt number `attempt` of a request without waiting for its response
+    async fn start_request(
+        &mut self,
+        mut request: Request,
+        attempt: usize,
+        io: &mut PhysLayer,
+    ) -> Result<(), SessionError> {
+        // skip the ids of the requests that are still waiting for a response
+        let mut tx_id = self.tx_id.next();
+        while self.in_flight.contains_key(&tx_id.to_u16()) {
+            tx_id = self.tx_id.next();
+        }
+
+        if let Err(err) = self.write_in_flight(io, &request, tx_id).await {
+            return fail_written_request(&mut request, err);
+        }
+
+        let deadline = Instant::now() + request.timeout;
+        self.in_flight.insert(
+            tx_id.to_u16(),
+            InFlight {
+                request,
+                deadline,
+                attempt,
+                retransmissions: self.retransmissions,
+            },
+        );
+        Ok(())
+    }
+
+    async fn write_in_flight(
+        &mut self,
+        io: &mut PhysLayer,
+        request: &Request,
+        tx_id: TxId,
+    ) -> Result<(), RequestError> {
+        let bytes = self.writer.format_request(
+            FrameHeader::new_tcp_header(request.id, tx_id),
+            request.details.function(),
+            &request.details,
+            self.decode,
+        )?;
+        io.write(bytes, self.decode.physical).await?;
+        self.statistics.lock().unwrap().requests_sent += 1;
+        Ok(())
+    }
+
+    fn complete_in_flight(&mut self, frame: Frame) {
+        let received = frame.header.tx_id;
+        let in_flight = match received.and_then(|x| self.in_flight.remove(&x.to_u16())) {
+            Some(x) => x,
+            // the response can only be for the one request in flight
+            None if self.lenient_tx_id && self.in_flight.len() == 1 => {
+                tracing::warn!(
+                    "accepting {:?} for the only request in flight (lenient transaction id)",
+                    received
+                );
+                match self.in_flight.pop_first() {
+                    Some((_, x)) => x,
+                    None => return,
+                }
+            }
+            None => {
+                tracing::warn!("received {:?} which matches no request in flight", received);
+                return;
+            }
+        };
+        let mut request = in_flight.request;
+
+        let unit_id = frame.header.destination.value();
+        let result = if unit_id != request.id.value {
+            Err(AduParseError::UnitIdMismatch(unit_id, request.id.value).into())
+        } else {
+            request.handle_response(frame.payload(), self.decode.app)
+        };
+        self.record_response(&result);
+        if let Err(err) = result {
+            self.retry_or_fail(request, in_flight.attempt, err);
+        }
+    }
+
+    async fn expire_in_flight(&mut self, io: &mut PhysLayer) -> Result<(), SessionError> {
+        let now = Instant::now();
+        let expired: Vec<u16> = self
+            .in_flight
+            .iter()
+            .filter(|(_, x)| x.deadline <= now)
+            .map(|(tx_id, _)| *tx_id)
+            .collect();
+        for tx_id in expired {
+            let mut x = match self.in_flight.remove(&tx_id) {
+                Some(x) => x,
+                None => continue,
+            };
+            self.statistics.lock().unwrap().timeouts += 1;
+            if x.retransmissions == 0 {
+                self.retry_or_fail(x.request, x.attempt, RequestError::ResponseTimeout);
+                continue;
+            }
+
+            // the transaction id is unchanged so a late response to a previous attempt is still accepted
+            tracing::warn!(
+                "no response within {:?}, retransmitting request",
+                x.request.timeout
+            );
+            x.retransmissions -= 1;
+            if let Err(err) = self.write_in_flight(io, &x.request, TxId::new(tx_id)).await {
+                return fail_written_request(&mut x.request, err);
+            }
+            x.deadline = Instant::now() + x.request.timeout;
+            self.in_flight.insert(tx_id, x);
+        }
+        Ok(())
+    }
+
+    /// retry a request that was in flight and failed with `err` if it's allowed, or fail it
+    fn retry_or_fail(&mut self, mut request: Request, attempt: usize, err: RequestError) {
+        match self.retry_delay(&request, attempt, err) {
+            Some(delay) => {
+                tracing::warn!("request error: {}, retrying in {:?}", err, delay);
+                self.retrying.push(PendingRetry {
+                    request,
+                    attempt: attempt + 1,
+                    at: Instant::now() + delay,
+                });
+            }
+            None => {
+                tracing::warn!("request error: {}", err);
+                request.details.fail(err);
+            }
+        }
+    }
+
+    fn warn_lenient_pipelining(&self) {
+        if self.lenient_tx_id && self.max_in_flight > 1 {
+            tracing::warn!(
+                "the lenient transaction id only applies while a single request is in flight"
+            );
+        }
+    }
+
+    fn take_due_retry(&mut self) -> Option<PendingRetry> {
+        let now = Instant::now();
+        let index = self.retrying.iter().position(|x| x.at <= now)?;
+        Some(self.retrying.remove(index))
+    }
+
+    fn fail_in_flight(&mut self, err: &SessionError) {
+        let err = match err {
+            SessionError::IoError(x) => RequestError::Io(*x),
+            SessionError::Shutdown => RequestError::Shutdown,
+            SessionError::BadFrame | SessionError::Disabled | SessionError::Idle => {
+                RequestError::NoConnection
+            }
+        };
+        for (_, mut x) in std::mem::take(&mut self.in_flight) {
+            x.request.details.fail(err);
+        }
+        for mut x in std::mem::take(&mut self.retrying) {
+            x.request.details.fail(err);
+        }
+    }
+
+    /// the next command taken from the queue, unless a high priority command is waiting
+    fn next_pending(&mut self) -> Option<Command> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.rx.try_recv_high().or_else(|| self.pending.pop_front())
+    }
+
     async fn poll(&mut self, io: &mut PhysLayer) -> Result<(), SessionError> {
+        if let Some(cmd) = self.next_pending() {
+            return self.run_cmd(cmd, io).await;
+        }
+
+        let idle = self.keep_alive.map(|x| x.idle);
         tokio::select! {
             frame = self.reader.next_frame(io, self.decode) => {
                 match frame {
@@ -155,6 +620,43 @@ impl ClientLoop {
                 let cmd: Command = res?;
                 self.run_cmd(cmd, io).await
             }
+            _ = tokio::time::sleep(idle.unwrap_or_default()), if idle.is_some() => {
+                self.probe(io).await
+            }
+            _ = tokio::time::sleep(self.idle_disconnect.unwrap_or_default()), if self.idle_disconnect.is_some() => {
+                self.close_idle()
+            }
+        }
+    }
+
+    fn close_idle(&mut self) -> Result<(), SessionError> {
+        tracing::info!("closing idle connection");
+        self.idle_closed = true;
+        Err(SessionError::Idle)
+    }
+
+    /// send the keep-alive probe, ending the session if it isn't answered
+    async fn probe(&mut self, io: &mut PhysLayer) -> Result<(), SessionError> {
+        let mut request = match self.keep_alive.as_ref().map(KeepAlive::request) {
+            Some(Ok(x)) => x,
+            Some(Err(err)) => {
+                tracing::warn!("unable to send keep-alive probe: {}", err);
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+
+        let tx_id = self.tx_id.next();
+        match self.execute_request(io, &mut request, tx_id).await {
+            Err(RequestError::ResponseTimeout) => {
+                tracing::warn!("keep-alive probe wasn't answered");
+                Err(SessionError::IoError(std::io::ErrorKind::TimedOut))
+            }
+            Err(err) => match SessionError::from_request_err(err) {
+                Some(err) => Err(err),
+                None => Ok(()),
+            },
+            Ok(()) => Ok(()),
         }
     }
 
@@ -163,11 +665,29 @@ impl ClientLoop {
         io: &mut PhysLayer,
         request: &mut Request,
     ) -> Result<(), SessionError> {
-        let tx_id = self.tx_id.next();
-        let result = self
-            .execute_request(io, request, tx_id)
-            .instrument(tracing::info_span!("Transaction", tx_id = %tx_id))
-            .await;
+        let mut attempt = 1;
+        let result = loop {
+            let tx_id = self.tx_id.next();
+            let result = self
+                .execute_request(io, request, tx_id)
+                .instrument(tracing::info_span!("Transaction", tx_id = %tx_id))
+                .await;
+
+            match result {
+                Err(err) => match self.retry_delay(request, attempt, err) {
+                    Some(delay) => {
+                        tracing::warn!("request error: {}, retrying in {:?}", err, delay);
+                        if let Err(session) = self.wait_to_retry(delay).await {
+                            request.details.fail(err);
+                            return Err(session);
+                        }
+                        attempt += 1;
+                    }
+                    None => break Err(err),
+                },
+                Ok(()) => break Ok(()),
+            }
+        };
 
         if let Err(err) = result {
             // Fail the request in ONE place. If the whole future
@@ -185,12 +705,64 @@ impl ClientLoop {
         Ok(())
     }
 
+    /// wait before retrying a request while applying the settings received in the meantime,
+    /// so that disabling or shutting down the channel doesn't wait for the backoff to elapse
+    ///
+    /// Requests received while waiting are kept for later, up to the same bound as read
+    /// coalescing so that the queue still applies backpressure.
+    async fn wait_to_retry(&mut self, delay: Duration) -> Result<(), SessionError> {
+        let deadline = Instant::now() + delay;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => return Ok(()),
+                res = self.rx.recv(), if self.pending.len() < coalesce::MAX_LOOKAHEAD => {
+                    match res? {
+                        Command::Setting(setting) => self.apply_setting(setting)?,
+                        cmd => self.pending.push_back(cmd),
+                    }
+                }
+            }
+        }
+    }
+
+    /// delay before retrying a request that failed with `err` on attempt number `attempt`,
+    /// or `None` if it isn't retried
+    fn retry_delay(
+        &self,
+        request: &Request,
+        attempt: usize,
+        err: RequestError,
+    ) -> Option<Duration> {
+        let max_attempts = match (request.retries, &self.retry_policy) {
+            (Some(retries), _) => retries.saturating_add(1),
+            (None, Some(policy)) => policy.max_attempts,
+            (None, None) => 1,
+        };
+        if attempt >= max_attempts {
+            return None;
+        }
+        match &self.retry_policy {
+            Some(policy) => policy.is_retryable(err).then(|| policy.backoff(attempt)),
+            None => matches!(
+                err,
+                RequestError::ResponseTimeout
+                    | RequestError::Exception(ExceptionCode::ServerDeviceBusy)
+            )
+            .then_some(Duration::ZERO),
+        }
+    }
+
     async fn execute_request(
         &mut self,
         io: &mut PhysLayer,
         request: &mut Request,
         tx_id: TxId,
     ) -> Result<(), RequestError> {
+        let broadcast = self.writer.supports_broadcast() && request.id == UnitId::broadcast();
+        if broadcast && !request.details.supports_broadcast() {
+            return Err(InvalidRequest::BroadcastNotSupported.into());
+        }
+
         let bytes = self.writer.format_request(
             FrameHeader::new_tcp_header(request.id, tx_id),
             request.details.function(),
@@ -199,14 +771,32 @@ impl ClientLoop {
         )?;
 
         io.write(bytes, self.decode.physical).await?;
+        self.statistics.lock().unwrap().requests_sent += 1;
 
-        let deadline = Instant::now() + request.timeout;
+        // servers never answer a broadcast, so there's nothing to wait for
+        if broadcast {
+            request.details.complete_broadcast();
+            return Ok(());
+        }
+
+        let mut deadline = Instant::now() + request.timeout;
+        let mut retransmissions = self.retransmissions;
 
         // loop until we get a response with the correct tx id or we timeout
         let response = loop {
             let frame = tokio::select! {
                 _ = tokio::time::sleep_until(deadline) => {
-                    return Err(RequestError::ResponseTimeout);
+                    self.statistics.lock().unwrap().timeouts += 1;
+                    if retransmissions == 0 {
+                        return Err(RequestError::ResponseTimeout);
+                    }
+                    retransmissions -= 1;
+                    // the transaction id is unchanged so a late response to a previous attempt is still accepted
+                    tracing::warn!("no response within {:?}, retransmitting request", request.timeout);
+                    io.write(bytes, self.decode.physical).await?;
+                    self.statistics.lock().unwrap().requests_sent += 1;
+                    deadline = Instant::now() + request.timeout;
+                    continue;
                 }
                 frame = self.reader.next_frame(io, self.decode) => {
                     frame?
@@ -216,6 +806,14 @@ impl ClientLoop {
             if let Some(received_tx_id) = frame.header.tx_id {
                 // Check that the received transaction ID matches (only in TCP MBAP)
                 if received_tx_id != tx_id {
+                    if self.lenient_tx_id {
+                        tracing::warn!(
+                            "accepting {:?} while expecting {:?} (lenient transaction id)",
+                            received_tx_id,
+                            tx_id
+                        );
+                        break frame;
+                    }
                     tracing::warn!("received {:?} while expecting {:?}", received_tx_id, tx_id);
                     continue; // next iteration of loop
                 }
@@ -224,9 +822,21 @@ impl ClientLoop {
             break frame;
         };
 
-        // once we have a response, handle it. This may complete a promise
-        // successfully or bubble up an error
-        request.handle_response(response.payload(), self.decode.app)
+        let unit_id = response.header.destination.value();
+        let result = if unit_id != request.id.value {
+            tracing::warn!(
+                "received a response from unit id {} while expecting {}",
+                unit_id,
+                request.id
+            );
+            Err(AduParseError::UnitIdMismatch(unit_id, request.id.value).into())
+        } else {
+            // once we have a response, handle it. This may complete a promise
+            // successfully or bubble up an error
+            request.handle_response(response.payload(), self.decode.app)
+        };
+        self.record_response(&result);
+        result
     }
 
     pub(crate) fn change_setting(&mut self, setting: Setting) {
@@ -246,19 +856,113 @@ impl ClientLoop {
                     self.enabled = false;
                     tracing::info!("channel disabled");
                 }
+                self.expire_parked(true);
+            }
+            Setting::RequestRetry(policy) => {
+                tracing::info!("request retry policy changed: {:?}", policy);
+                self.retry_policy = policy;
+            }
+            Setting::ReadCoalescing(max_gap) => {
+                tracing::info!("read coalescing changed: {:?}", max_gap);
+                self.max_read_gap = max_gap;
+            }
+            Setting::MaxInFlight(count) => {
+                tracing::info!("maximum number of requests in flight changed: {}", count);
+                self.max_in_flight = count.max(1);
+                self.warn_lenient_pipelining();
+            }
+            Setting::DisconnectedPolicy(policy) => {
+                tracing::info!("disconnected policy changed: {:?}", policy);
+                self.disconnected_policy = policy;
+            }
+            Setting::IdleDisconnect(period) => {
+                tracing::info!("idle disconnect changed: {:?}", period);
+                self.idle_disconnect = period;
+            }
+            Setting::LenientTxId(enabled) => {
+                tracing::info!("lenient transaction id changed: {}", enabled);
+                self.lenient_tx_id = enabled;
+                self.warn_lenient_pipelining();
+            }
+            Setting::LenientProtocolId(enabled) => {
+                tracing::info!("lenient protocol id changed: {}", enabled);
+                self.reader.set_lenient_protocol_id(enabled);
+            }
+            Setting::MaxAduLength(length) => {
+                let length = length.clamp(
+                    crate::common::frame::constants::MAX_ADU_LENGTH,
+                    crate::common::frame::constants::MAX_CONFIGURABLE_ADU_LENGTH,
+                );
+                tracing::info!("maximum ADU length changed: {}", length);
+                self.reader.set_max_adu_length(length);
+            }
+            Setting::KeepAlive(keep_alive) => {
+                tracing::info!("keep-alive changed: {:?}", keep_alive);
+                self.keep_alive = keep_alive;
+            }
+            Setting::ConnectMode(mode) => {
+                tracing::info!("connect mode changed: {:?}", mode);
+                self.lazy = mode == crate::client::ConnectMode::Lazy;
+            }
+            Setting::ConnectionListener(listener) => {
+                tracing::info!("connection listener changed");
+                self.connection_listener = listener;
+            }
+            Setting::Shutdown(done) => {
+                tracing::info!("channel shutdown requested");
+                self.shutdown = Some(done);
+            }
+            #[cfg(feature = "tls")]
+            Setting::TlsConfig(config) => {
+                tracing::info!(
+                    "TLS configuration changed, it will be used for the next connection"
+                );
+                self.tls_config = Some(config);
             }
         }
     }
 
-    async fn fail_next_request(&mut self) -> Result<(), StateChange> {
-        match self.rx.recv().await? {
-            Command::Request(mut req) => {
-                req.details.fail(RequestError::NoConnection);
+    /// notify the connection listener of a connection event of the transport
+    pub(crate) async fn notify(&mut self, event: ConnectionEvent) {
+        self.connection_listener.update(event).get().await;
+    }
+
+    /// take the TLS configuration that was changed since the last call, if any
+    #[cfg(feature = "tls")]
+    pub(crate) fn take_tls_config(&mut self) -> Option<crate::tcp::tls::TlsClientConfig> {
+        self.tls_config.take()
+    }
+
+    /// fail the next request, starting with the ones taken from the queue if `pending` is set
+    async fn fail_next_request(&mut self, pending: bool) -> Result<(), StateChange> {
+        let next = if pending {
+            self.pending.pop_front()
+        } else {
+            None
+        };
+        let cmd = match next {
+            Some(x) => x,
+            None => {
+                let expiration = self.parked.iter().map(|(x, _)| *x).min();
+                tokio::select! {
+                    cmd = self.rx.recv() => cmd?,
+                    _ = tokio::time::sleep_until(expiration.unwrap_or_else(Instant::now)), if expiration.is_some() => {
+                        self.expire_parked(false);
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        match cmd {
+            Command::Request(req) => {
+                self.park(req);
                 Ok(())
             }
             Command::Setting(x) => {
                 self.change_setting(x);
-                if self.enabled {
+                if self.shutdown.is_some() {
+                    Err(StateChange::Shutdown)
+                } else if self.enabled {
                     Ok(())
                 } else {
                     Err(StateChange::Disable)
@@ -267,9 +971,15 @@ impl ClientLoop {
         }
     }
 
+    /// fail the requests made while connecting, keeping the ones already taken from the queue
+    /// for the session, e.g. the request that triggered a lazy connection
     pub(crate) async fn fail_requests(&mut self) -> StateChange {
+        self.fail_requests_and(false).await
+    }
+
+    async fn fail_requests_and(&mut self, pending: bool) -> StateChange {
         loop {
-            if let Err(err) = self.fail_next_request().await {
+            if let Err(err) = self.fail_next_request(pending).await {
                 return err;
             }
         }
@@ -285,19 +995,30 @@ impl ClientLoop {
                 // Timeout occurred
                 Ok(())
             }
-            x = self.fail_requests() => {
+            x = self.fail_requests_and(true) => {
                 Err(x)
             }
         }
     }
 }
 
+/// fail a request that couldn't be written, ending the session if the error requires it
+fn fail_written_request(request: &mut Request, err: RequestError) -> Result<(), SessionError> {
+    tracing::warn!("request error: {}", err);
+    request.details.fail(err);
+    match SessionError::from_request_err(err) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::ErrorKind;
 
     use super::*;
-    use crate::client::{Channel, RequestParam};
+    use crate::client::{Channel, KeepAliveProbe, RequestParam, SessionConfig, WriteMultiple};
+    use crate::common::frame::FunctionField;
     use crate::common::function::FunctionCode;
     use crate::common::traits::{Loggable, Serialize};
     use crate::decode::*;
@@ -312,19 +1033,29 @@ mod tests {
         tokio::task::JoinHandle<SessionError>,
         sfio_tokio_mock_io::Handle,
     ) {
-        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        spawn_client_loop_with(FrameWriter::tcp(), FramedReader::tcp())
+    }
+
+    fn spawn_client_loop_with(
+        writer: FrameWriter,
+        reader: FramedReader,
+    ) -> (
+        Channel,
+        tokio::task::JoinHandle<SessionError>,
+        sfio_tokio_mock_io::Handle,
+    ) {
+        let (channel, rx) = Channel::create(16);
         let (mock, io_handle) = sfio_tokio_mock_io::mock();
         let mut client_loop = ClientLoop::new(
-            rx.into(),
-            FrameWriter::tcp(),
-            FramedReader::tcp(),
+            rx,
+            writer,
+            reader,
             DecodeLevel::default().application(AppDecodeLevel::DataValues),
         );
         let join_handle = tokio::spawn(async move {
             let mut phys = PhysLayer::new_mock(mock);
             client_loop.run(&mut phys).await
         });
-        let channel = Channel { tx };
         (channel, join_handle, io_handle)
     }
 
@@ -335,16 +1066,65 @@ mod tests {
         let mut fmt = FrameWriter::tcp();
         let header = FrameHeader::new_tcp_header(UnitId::new(1), TxId::new(0));
         let bytes = fmt
-            .format_request(header, function, payload, DecodeLevel::nothing())
+            .format_request(
+                header,
+                FunctionField::Valid(function),
+                payload,
+                DecodeLevel::nothing(),
+            )
             .unwrap();
         Vec::from(bytes)
     }
 
+    #[cfg(feature = "serial")]
     #[tokio::test]
-    async fn task_completes_with_shutdown_error_when_all_channels_dropped() {
-        let (channel, task, _io) = spawn_client_loop();
-        drop(channel);
-        assert_eq!(task.await.unwrap(), SessionError::Shutdown);
+    async fn broadcast_write_completes_without_waiting_for_a_response() {
+        let (mut channel, _task, mut io) =
+            spawn_client_loop_with(FrameWriter::rtu(), FramedReader::rtu_response());
+
+        let value = Indexed::new(0x0010, 0x1234);
+        let request_task = tokio::spawn(async move {
+            channel
+                .write_single_register(
+                    RequestParam::new(UnitId::broadcast(), Duration::from_secs(5)),
+                    value,
+                )
+                .await
+        });
+
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![0x00, 0x06, 0x00, 0x10, 0x12, 0x34, 0x84, 0xA9])
+        );
+        assert_eq!(request_task.await.unwrap(), Ok(value));
+    }
+
+    #[cfg(feature = "serial")]
+    #[tokio::test]
+    async fn broadcast_read_is_rejected() {
+        let (mut channel, _task, _io) =
+            spawn_client_loop_with(FrameWriter::rtu(), FramedReader::rtu_response());
+
+        let result = channel
+            .read_coils(
+                RequestParam::new(UnitId::broadcast(), Duration::from_secs(5)),
+                AddressRange::try_from(7, 2).unwrap(),
+            )
+            .await;
+
+        assert_eq!(
+            result,
+            Err(RequestError::BadRequest(
+                InvalidRequest::BroadcastNotSupported
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn task_completes_with_shutdown_error_when_all_channels_dropped() {
+        let (channel, task, _io) = spawn_client_loop();
+        drop(channel);
+        assert_eq!(task.await.unwrap(), SessionError::Shutdown);
     }
 
     #[tokio::test]
@@ -464,4 +1244,1099 @@ mod tests {
             vec![Indexed::new(7, true), Indexed::new(8, false)]
         );
     }
+
+    #[tokio::test]
+    async fn fails_requests_when_the_queue_is_full() {
+        // nothing consumes the queue
+        let (mut channel, _rx) = Channel::create(1);
+        channel.set_fail_when_queue_full(true);
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let range = AddressRange::try_from(7, 1).unwrap();
+
+        let mut first = channel.clone();
+        let _pending = tokio::spawn(async move { first.read_coils(params, range).await });
+        while channel.tx.capacity() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(
+            channel.read_coils(params, range).await,
+            Err(RequestError::QueueFull)
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_high_priority_requests_first() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        let mut operator = channel.clone();
+        operator.set_priority(crate::client::RequestPriority::High);
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let read = |mut channel: Channel, start: u16| {
+            tokio::spawn(async move {
+                channel
+                    .read_coils(params, AddressRange::try_from(start, 1).unwrap())
+                    .await
+            })
+        };
+        let request = |tx_id: u8, start: u8| {
+            Event::Write(vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, start, 0x00, 0x01,
+            ])
+        };
+        let response = |tx_id: u8| [0x00, tx_id, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00];
+
+        // the first poll is in progress when the others are queued
+        let first = read(channel.clone(), 1);
+        assert_eq!(io.next_event().await, request(0, 1));
+        let second = read(channel.clone(), 2);
+        while channel.tx.capacity() == 16 {
+            tokio::task::yield_now().await;
+        }
+        let write = read(operator, 3);
+        while channel.high_tx.capacity() == 16 {
+            tokio::task::yield_now().await;
+        }
+
+        io.read(&response(0));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, request(1, 3));
+        io.read(&response(1));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, request(2, 2));
+        io.read(&response(2));
+
+        for task in [first, second, write] {
+            assert!(task.await.unwrap().is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_fails_queued_requests_and_ends_the_task() {
+        let (channel, task, mut io) = spawn_client_loop();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let read = |mut channel: Channel| {
+            tokio::spawn(async move {
+                channel
+                    .read_coils(params, AddressRange::try_from(1, 1).unwrap())
+                    .await
+            })
+        };
+
+        // the first request is in progress when the second one and the shutdown are queued
+        let first = read(channel.clone());
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        let second = read(channel.clone());
+        while channel.tx.capacity() == 16 {
+            tokio::task::yield_now().await;
+        }
+        let handle = channel.clone();
+        let shutdown = tokio::spawn(async move { handle.shutdown().await });
+        while channel.high_tx.capacity() == 16 {
+            tokio::task::yield_now().await;
+        }
+
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00]);
+        assert!(first.await.unwrap().is_ok());
+        assert_eq!(task.await.unwrap(), SessionError::Shutdown);
+        assert_eq!(second.await.unwrap(), Err(RequestError::Shutdown));
+        shutdown.await.unwrap();
+
+        // other handles are still alive, but the channel is gone
+        assert_eq!(
+            read(channel.clone()).await.unwrap(),
+            Err(RequestError::Shutdown)
+        );
+        channel.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn splits_large_reads() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let range = AddressRange::try_from(0, 126).unwrap();
+
+        let mut strict = channel.clone();
+        strict.set_split_large_reads(false);
+        assert_eq!(
+            strict.read_holding_registers(params, range).await,
+            Err(InvalidRange::CountTooLargeForType(126, 125).into())
+        );
+
+        let read = tokio::spawn(async move { channel.read_holding_registers(params, range).await });
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x7D
+            ])
+        );
+        let mut response = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0xFD, 0x01, 0x03, 0xFA];
+        response.extend((0..125u16).flat_map(|x| x.to_be_bytes()));
+        io.read(&response);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x7D, 0x00, 0x01
+            ])
+        );
+        io.read(&[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x7D,
+        ]);
+
+        let values = read.await.unwrap().unwrap();
+        assert_eq!(values.len(), 126);
+        assert!(values.iter().all(|x| x.index == x.value));
+    }
+
+    #[tokio::test]
+    async fn coalesces_queued_reads() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_read_coalescing(Some(2)).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let read = |mut channel: Channel, start: u16| {
+            tokio::spawn(async move {
+                channel
+                    .read_holding_registers(params, AddressRange::try_from(start, 2).unwrap())
+                    .await
+            })
+        };
+        let queue = |channel: &Channel, count: usize| {
+            let tx = channel.tx.clone();
+            async move {
+                while tx.capacity() != 16 - count {
+                    tokio::task::yield_now().await;
+                }
+            }
+        };
+
+        // the reads are queued while a first request is in progress
+        let mut first = channel.clone();
+        let first = tokio::spawn(async move {
+            first
+                .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+                .await
+        });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        let low = read(channel.clone(), 0);
+        queue(&channel, 1).await;
+        let high = read(channel.clone(), 4);
+        queue(&channel, 2).await;
+        let mut writer = channel.clone();
+        let write = tokio::spawn(async move {
+            writer
+                .write_single_register(params, Indexed::new(7, 0x1234))
+                .await
+        });
+        queue(&channel, 3).await;
+        // queued after the write, so it can't be performed before it
+        let middle = read(channel.clone(), 2);
+        queue(&channel, 4).await;
+
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00]);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert!(first.await.unwrap().is_ok());
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x06
+            ])
+        );
+        io.read(&[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0F, 0x01, 0x03, 0x0C, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x05,
+        ]);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(
+            low.await.unwrap(),
+            Ok(vec![Indexed::new(0, 0), Indexed::new(1, 1)])
+        );
+        assert_eq!(
+            high.await.unwrap(),
+            Ok(vec![Indexed::new(4, 4), Indexed::new(5, 5)])
+        );
+
+        let request = [
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x06, 0x01, 0x06, 0x00, 0x07, 0x12, 0x34,
+        ];
+        assert_eq!(io.next_event().await, Event::Write(request.to_vec()));
+        io.read(&request);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert!(write.await.unwrap().is_ok());
+
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![
+                0x00, 0x03, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x02, 0x00, 0x02
+            ])
+        );
+        io.read(&[
+            0x00, 0x03, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x00, 0x09, 0x00, 0x09,
+        ]);
+        assert_eq!(
+            middle.await.unwrap(),
+            Ok(vec![Indexed::new(2, 9), Indexed::new(3, 9)])
+        );
+    }
+
+    fn read_registers(
+        channel: &Channel,
+        params: RequestParam,
+        start: u16,
+    ) -> tokio::task::JoinHandle<Result<Vec<Indexed<u16>>, RequestError>> {
+        let mut channel = channel.clone();
+        tokio::spawn(async move {
+            channel
+                .read_holding_registers(params, AddressRange::try_from(start, 1).unwrap())
+                .await
+        })
+    }
+
+    async fn wait_for_queued(channel: &Channel, count: usize) {
+        while channel.tx.capacity() != 16 - count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn coalescing_only_examines_the_start_of_the_queue() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_read_coalescing(Some(2)).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+        let _first = read_registers(&channel, params, 0);
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        // none of these reads can be merged
+        let mut reads = Vec::new();
+        for i in 1..=12 {
+            reads.push(read_registers(&channel, params, 100 * i));
+            wait_for_queued(&channel, i as usize).await;
+        }
+
+        io.read(&[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x00,
+        ]);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        // the next read and the ones examined to coalesce it are the only ones taken
+        assert_eq!(channel.tx.capacity(), 16 - 12 + 1 + coalesce::MAX_LOOKAHEAD);
+    }
+
+    #[tokio::test]
+    async fn coalescing_doesnt_delay_high_priority_requests() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_read_coalescing(Some(2)).await.unwrap();
+        let mut operator = channel.clone();
+        operator.set_priority(crate::client::RequestPriority::High);
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let request = |tx_id: u8, start: u8| {
+            Event::Write(vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, start, 0x00, 0x01,
+            ])
+        };
+        let response = |tx_id: u8| {
+            [
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x00,
+            ]
+        };
+
+        let _first = read_registers(&channel, params, 0);
+        assert_eq!(io.next_event().await, request(0, 0));
+        let mut reads = Vec::new();
+        for i in 1..=3 {
+            reads.push(read_registers(&channel, params, 50 * i));
+            wait_for_queued(&channel, i as usize).await;
+        }
+        io.read(&response(0));
+        assert_eq!(io.next_event().await, Event::Read);
+        // the other reads were examined to coalesce this one
+        assert_eq!(io.next_event().await, request(1, 50));
+
+        let _high = read_registers(&operator, params, 7);
+        while channel.high_tx.capacity() != 16 {
+            tokio::task::yield_now().await;
+        }
+        io.read(&response(1));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, request(2, 7));
+    }
+
+    #[tokio::test]
+    async fn coalesced_reads_keep_the_retries_of_their_session() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_read_coalescing(Some(2)).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let config = SessionConfig {
+            timeout: Duration::from_secs(1),
+            retries: Some(1),
+            max_outstanding: None,
+        };
+        let read = |start: u16| {
+            let mut session = channel.create_session_with(UnitId::new(1), config);
+            tokio::spawn(async move {
+                session
+                    .read_holding_registers(AddressRange::try_from(start, 1).unwrap())
+                    .await
+            })
+        };
+
+        let _first = read_registers(&channel, params, 0);
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        let low = read(10);
+        wait_for_queued(&channel, 1).await;
+        let high = read(11);
+        wait_for_queued(&channel, 2).await;
+        io.read(&[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x00,
+        ]);
+        assert_eq!(io.next_event().await, Event::Read);
+
+        let request = |tx_id: u8| {
+            Event::Write(vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x0A, 0x00, 0x02,
+            ])
+        };
+        assert_eq!(io.next_event().await, request(1));
+        io.read(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x83, 0x06]);
+        assert_eq!(io.next_event().await, Event::Read);
+
+        // the merged read is retried like the reads of the session
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(1), io.next_event()).await,
+            Ok(request(2))
+        );
+        io.read(&[
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x00, 0x01, 0x00, 0x02,
+        ]);
+        assert_eq!(low.await.unwrap(), Ok(vec![Indexed::new(10, 1)]));
+        assert_eq!(high.await.unwrap(), Ok(vec![Indexed::new(11, 2)]));
+    }
+
+    #[tokio::test]
+    async fn coalescing_keeps_the_order_of_the_reads_of_a_device() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_read_coalescing(Some(2)).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let request = |tx_id: u8, start: u8| {
+            Event::Write(vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, start, 0x00, 0x01,
+            ])
+        };
+        let response = |tx_id: u8| {
+            [
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x00,
+            ]
+        };
+
+        let _first = read_registers(&channel, params, 0);
+        assert_eq!(io.next_event().await, request(0, 0));
+        let _low = read_registers(&channel, params, 10);
+        wait_for_queued(&channel, 1).await;
+        let _coil = read_coil(&channel, params, 0);
+        wait_for_queued(&channel, 2).await;
+        // mergeable with the first read, but made after the read of the coil
+        let _high = read_registers(&channel, params, 11);
+        wait_for_queued(&channel, 3).await;
+
+        io.read(&response(0));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, request(1, 10));
+        io.read(&response(1));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, read_coil_request(2, 0));
+        io.read(&read_coil_response(2, 0));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, request(3, 11));
+    }
+
+    #[tokio::test]
+    async fn pipelines_requests() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_max_in_flight(2).await.unwrap();
+        let read = |mut channel: Channel, start: u16, timeout: u64| {
+            let params = RequestParam::new(UnitId::new(1), Duration::from_millis(timeout));
+            tokio::spawn(async move {
+                channel
+                    .read_coils(params, AddressRange::try_from(start, 1).unwrap())
+                    .await
+            })
+        };
+        let request = |tx_id: u8, start: u8| {
+            Event::Write(vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, start, 0x00, 0x01,
+            ])
+        };
+        let response =
+            |tx_id: u8, value: u8| [0x00, tx_id, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, value];
+
+        let first = read(channel.clone(), 1, 1000);
+        assert_eq!(io.next_event().await, request(0, 1));
+        let second = read(channel.clone(), 2, 1000);
+        assert_eq!(io.next_event().await, request(1, 2));
+        let third = read(channel.clone(), 3, 50);
+        tokio::task::yield_now().await;
+
+        // the responses are matched by transaction id, whatever their order
+        io.read(&response(1, 1));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(second.await.unwrap(), Ok(vec![Indexed::new(2, true)]));
+        assert_eq!(io.next_event().await, request(2, 3));
+        io.read(&response(0, 0));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(first.await.unwrap(), Ok(vec![Indexed::new(1, false)]));
+
+        // each request has its own timeout
+        assert_eq!(third.await.unwrap(), Err(RequestError::ResponseTimeout));
+    }
+
+    fn read_coil(
+        channel: &Channel,
+        params: RequestParam,
+        start: u16,
+    ) -> tokio::task::JoinHandle<Result<Vec<Indexed<bool>>, RequestError>> {
+        let mut channel = channel.clone();
+        tokio::spawn(async move {
+            channel
+                .read_coils(params, AddressRange::try_from(start, 1).unwrap())
+                .await
+        })
+    }
+
+    fn read_coil_request(tx_id: u8, start: u8) -> Event {
+        Event::Write(vec![
+            0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, start, 0x00, 0x01,
+        ])
+    }
+
+    fn read_coil_response(tx_id: u8, value: u8) -> [u8; 10] {
+        [0x00, tx_id, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, value]
+    }
+
+    fn busy_response(tx_id: u8) -> [u8; 9] {
+        [0x00, tx_id, 0x00, 0x00, 0x00, 0x03, 0x01, 0x81, 0x06]
+    }
+
+    #[tokio::test]
+    async fn retries_requests_in_flight_according_to_the_policy() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_max_in_flight(2).await.unwrap();
+        channel
+            .set_request_retry_policy(Some(RequestRetryPolicy::new(
+                2,
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+            )))
+            .await
+            .unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+        let first = read_coil(&channel, params, 1);
+        assert_eq!(io.next_event().await, read_coil_request(0, 1));
+        let second = read_coil(&channel, params, 2);
+        assert_eq!(io.next_event().await, read_coil_request(1, 2));
+
+        // the first request is retried while the second one is in flight
+        io.read(&busy_response(0));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(io.next_event().await, read_coil_request(2, 1));
+        io.read(&read_coil_response(2, 1));
+        assert_eq!(io.next_event().await, Event::Read);
+        io.read(&read_coil_response(1, 0));
+        assert_eq!(first.await.unwrap(), Ok(vec![Indexed::new(1, true)]));
+        assert_eq!(second.await.unwrap(), Ok(vec![Indexed::new(2, false)]));
+    }
+
+    #[tokio::test]
+    async fn retries_requests_in_flight_according_to_their_session() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_max_in_flight(2).await.unwrap();
+        let config = SessionConfig {
+            timeout: Duration::from_secs(1),
+            retries: Some(1),
+            max_outstanding: None,
+        };
+        let mut session = channel.create_session_with(UnitId::new(1), config);
+        let range = AddressRange::try_from(1, 1).unwrap();
+
+        let coils = tokio::spawn(async move { session.read_coils(range).await });
+        for tx_id in 0..2 {
+            assert_eq!(io.next_event().await, read_coil_request(tx_id, 1));
+            io.read(&busy_response(tx_id));
+            assert_eq!(io.next_event().await, Event::Read);
+        }
+        assert_eq!(
+            coils.await.unwrap(),
+            Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+        );
+    }
+
+    #[tokio::test]
+    async fn retransmits_requests_in_flight() {
+        let (mut channel, rx) = Channel::create(16);
+        let (mock, mut io) = sfio_tokio_mock_io::mock();
+        let mut client_loop = ClientLoop::new(
+            rx,
+            FrameWriter::tcp(),
+            FramedReader::tcp(),
+            DecodeLevel::default(),
+        )
+        .with_retransmissions(1);
+        let _task = tokio::spawn(async move {
+            let mut phys = PhysLayer::new_mock(mock);
+            client_loop.run(&mut phys).await
+        });
+        channel.enable().await.unwrap();
+        channel.set_max_in_flight(2).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+        let coils = read_coil(&channel, params, 1);
+        assert_eq!(io.next_event().await, read_coil_request(0, 1));
+        tokio::time::pause();
+        // resent with the same transaction id
+        assert_eq!(io.next_event().await, read_coil_request(0, 1));
+        tokio::time::resume();
+        io.read(&read_coil_response(0, 1));
+        assert_eq!(coils.await.unwrap(), Ok(vec![Indexed::new(1, true)]));
+    }
+
+    #[tokio::test]
+    async fn accepts_mismatched_transaction_ids_with_a_single_request_in_flight() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_max_in_flight(2).await.unwrap();
+        channel.set_lenient_tx_id(true).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+        let first = read_coil(&channel, params, 1);
+        assert_eq!(io.next_event().await, read_coil_request(0, 1));
+        io.read(&read_coil_response(5, 1));
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(first.await.unwrap(), Ok(vec![Indexed::new(1, true)]));
+
+        // the response can't be matched when several requests are in flight
+        let first = read_coil(&channel, params, 1);
+        assert_eq!(io.next_event().await, read_coil_request(1, 1));
+        let second = read_coil(&channel, params, 2);
+        assert_eq!(io.next_event().await, read_coil_request(2, 2));
+        io.read(&read_coil_response(5, 1));
+        assert_eq!(io.next_event().await, Event::Read);
+        io.read(&read_coil_response(2, 0));
+        assert_eq!(io.next_event().await, Event::Read);
+        io.read(&read_coil_response(1, 0));
+        assert_eq!(first.await.unwrap(), Ok(vec![Indexed::new(1, false)]));
+        assert_eq!(second.await.unwrap(), Ok(vec![Indexed::new(2, false)]));
+    }
+
+    #[tokio::test]
+    async fn dropping_a_request_future_leaves_the_channel_usable() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let read = |mut channel: Channel| {
+            tokio::spawn(async move {
+                channel
+                    .read_coils(params, AddressRange::try_from(1, 1).unwrap())
+                    .await
+            })
+        };
+
+        // the caller gives up while the request is in progress
+        let cancelled = read(channel.clone());
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        cancelled.abort();
+        assert!(cancelled.await.unwrap_err().is_cancelled());
+
+        // the response is discarded and the next request uses the next transaction id
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00]);
+        assert_eq!(io.next_event().await, Event::Read);
+        let next = read(channel.clone());
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, 0x01, 0x00, 0x01
+            ])
+        );
+        io.read(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x01]);
+        assert_eq!(next.await.unwrap(), Ok(vec![Indexed::new(1, true)]));
+    }
+
+    #[tokio::test]
+    async fn rejects_responses_from_another_unit_id() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+
+        let range = AddressRange::try_from(7, 1).unwrap();
+        let coils = tokio::spawn(async move {
+            channel
+                .read_coils(
+                    RequestParam::new(UnitId::new(1), Duration::from_secs(1)),
+                    range,
+                )
+                .await
+        });
+
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x02, 0x01, 0x01, 0x01]);
+
+        assert_eq!(
+            coils.await.unwrap(),
+            Err(RequestError::BadResponse(AduParseError::UnitIdMismatch(
+                2, 1
+            )))
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_requests_according_to_the_policy() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel
+            .set_request_retry_policy(Some(RequestRetryPolicy::new(
+                3,
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+            )))
+            .await
+            .unwrap();
+
+        let range = AddressRange::try_from(7, 1).unwrap();
+        let adu = |tx_id: u8, pdu: &[u8]| {
+            let mut adu = vec![0x00, tx_id, 0x00, 0x00, 0x00, pdu.len() as u8 + 1, 0x01];
+            adu.extend_from_slice(pdu);
+            adu
+        };
+        let request = [0x01, 0x00, 0x07, 0x00, 0x01];
+
+        let coils = tokio::spawn(async move {
+            channel
+                .read_coils(
+                    RequestParam::new(UnitId::new(1), Duration::from_secs(1)),
+                    range,
+                )
+                .await
+        });
+
+        // busy, retried
+        assert_eq!(io.next_event().await, Event::Write(adu(0, &request)));
+        io.read(&adu(0, &[0x81, 0x06]));
+        assert_eq!(io.next_event().await, Event::Read);
+        // unanswered, retried
+        assert_eq!(io.next_event().await, Event::Write(adu(1, &request)));
+        tokio::time::pause();
+        assert_eq!(io.next_event().await, Event::Write(adu(2, &request)));
+        tokio::time::resume();
+        io.read(&adu(2, &[0x01, 0x01, 0x01]));
+
+        assert_eq!(coils.await.unwrap().unwrap(), vec![Indexed::new(7, true)]);
+    }
+
+    #[tokio::test]
+    async fn stops_retrying_after_the_last_attempt() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel
+            .set_request_retry_policy(Some(RequestRetryPolicy::new(
+                2,
+                Duration::ZERO,
+                Duration::ZERO,
+            )))
+            .await
+            .unwrap();
+
+        let range = AddressRange::try_from(7, 1).unwrap();
+        let coils = tokio::spawn(async move {
+            channel
+                .read_coils(
+                    RequestParam::new(UnitId::new(1), Duration::from_secs(1)),
+                    range,
+                )
+                .await
+        });
+
+        for tx_id in 0..2 {
+            assert!(matches!(io.next_event().await, Event::Write(_)));
+            io.read(&[0x00, tx_id, 0x00, 0x00, 0x00, 0x03, 0x01, 0x81, 0x06]);
+            assert_eq!(io.next_event().await, Event::Read);
+        }
+
+        assert_eq!(
+            coils.await.unwrap(),
+            Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+        );
+    }
+
+    #[tokio::test]
+    async fn disabling_the_channel_interrupts_the_retry_backoff() {
+        let (mut channel, task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel
+            .set_request_retry_policy(Some(RequestRetryPolicy::new(
+                2,
+                Duration::from_secs(3600),
+                Duration::from_secs(3600),
+            )))
+            .await
+            .unwrap();
+
+        let range = AddressRange::try_from(7, 1).unwrap();
+        let mut reader = channel.clone();
+        let coils = tokio::spawn(async move {
+            reader
+                .read_coils(
+                    RequestParam::new(UnitId::new(1), Duration::from_secs(1)),
+                    range,
+                )
+                .await
+        });
+
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x81, 0x06]);
+        assert_eq!(io.next_event().await, Event::Read);
+        channel.disable().await.unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(1), coils)
+            .await
+            .unwrap();
+        assert_eq!(
+            result.unwrap(),
+            Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+        );
+        assert_eq!(task.await.unwrap(), SessionError::Disabled);
+    }
+
+    #[tokio::test]
+    async fn keep_alive_probe_closes_unanswered_connections() {
+        let (mut channel, task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel
+            .set_keep_alive(Some(KeepAlive {
+                idle: Duration::from_secs(10),
+                param: RequestParam::new(UnitId::new(1), Duration::from_secs(1)),
+                probe: KeepAliveProbe::ReturnQueryData,
+            }))
+            .await
+            .unwrap();
+        tokio::time::pause();
+
+        let probe = |tx_id: u8| {
+            vec![
+                0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00,
+            ]
+        };
+
+        // an answered probe keeps the connection open
+        assert_eq!(io.next_event().await, Event::Write(probe(0)));
+        io.read(&[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        assert_eq!(io.next_event().await, Event::Read);
+
+        assert_eq!(io.next_event().await, Event::Write(probe(1)));
+        assert_eq!(
+            task.await.unwrap(),
+            SessionError::IoError(ErrorKind::TimedOut)
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_registers_with_a_mask_write_or_a_single_write() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let adu = |tx_id: u8, pdu: &[u8]| {
+            let mut adu = vec![0x00, tx_id, 0x00, 0x00, 0x00, pdu.len() as u8 + 1, 0x01];
+            adu.extend_from_slice(pdu);
+            adu
+        };
+        let read = [0x03, 0x00, 0x07, 0x00, 0x01];
+        let mask_write = [0x16, 0x00, 0x07, 0xF0, 0xFF, 0x0F, 0xF0];
+        let write = [0x06, 0x00, 0x07, 0x0F, 0xF0];
+
+        for (tx_id, supported) in [(0, true), (2, false)] {
+            let mut update = channel.clone();
+            let task =
+                tokio::spawn(
+                    async move { update.update_register(params, 7, |x| x | 0x0F00).await },
+                );
+
+            assert_eq!(io.next_event().await, Event::Write(adu(tx_id, &read)));
+            io.read(&adu(tx_id, &[0x03, 0x02, 0x00, 0xF0]));
+            assert_eq!(io.next_event().await, Event::Read);
+            // only the bits that changed are written
+            assert_eq!(
+                io.next_event().await,
+                Event::Write(adu(tx_id + 1, &mask_write))
+            );
+            if supported {
+                io.read(&adu(tx_id + 1, &mask_write));
+            } else {
+                io.read(&adu(tx_id + 1, &[0x96, 0x01]));
+                assert_eq!(io.next_event().await, Event::Read);
+                assert_eq!(io.next_event().await, Event::Write(adu(tx_id + 2, &write)));
+                io.read(&adu(tx_id + 2, &write));
+            }
+            assert_eq!(task.await.unwrap(), Ok(0x0FF0));
+            assert_eq!(io.next_event().await, Event::Read);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_values_that_differ_when_read_back() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let adu = |tx_id: u8, pdu: &[u8]| {
+            let mut adu = vec![0x00, tx_id, 0x00, 0x00, 0x00, pdu.len() as u8 + 1, 0x01];
+            adu.extend_from_slice(pdu);
+            adu
+        };
+
+        let task = tokio::spawn(async move {
+            let request = WriteMultiple::from(7, vec![1, 2]).unwrap();
+            channel.write_and_verify_registers(params, request).await
+        });
+
+        let write = [0x10, 0x00, 0x07, 0x00, 0x02, 0x04, 0x00, 0x01, 0x00, 0x02];
+        assert_eq!(io.next_event().await, Event::Write(adu(0, &write)));
+        io.read(&adu(0, &[0x10, 0x00, 0x07, 0x00, 0x02]));
+        assert_eq!(io.next_event().await, Event::Read);
+        let read = [0x03, 0x00, 0x07, 0x00, 0x02];
+        assert_eq!(io.next_event().await, Event::Write(adu(1, &read)));
+        // the device clamped the second value
+        io.read(&adu(1, &[0x03, 0x04, 0x00, 0x01, 0x00, 0x05]));
+
+        assert_eq!(
+            task.await.unwrap(),
+            Err(RequestError::WriteMismatch(WriteMismatch {
+                address: 8,
+                written: 2,
+                read: 5
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_the_outstanding_requests_of_a_session() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        let config = SessionConfig {
+            max_outstanding: Some(1),
+            ..SessionConfig::new(Duration::from_secs(1))
+        };
+        let session = channel.create_session_with(UnitId::new(1), config);
+        let read = |start: u16| {
+            let mut session = session.clone();
+            tokio::spawn(async move {
+                session
+                    .read_coils(AddressRange::try_from(start, 1).unwrap())
+                    .await
+            })
+        };
+
+        let first = read(1);
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        let second = read(2);
+        let third = read(3);
+        // the other requests of the session wait without filling the queue
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(channel.tx.capacity(), 16);
+
+        // and are sent in the order they were made
+        for (tx_id, start) in [(0u8, 1u8), (1, 2), (2, 3)] {
+            if tx_id > 0 {
+                assert_eq!(
+                    io.next_event().await,
+                    Event::Write(vec![
+                        0x00, tx_id, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, start, 0x00, 0x01
+                    ])
+                );
+            }
+            io.read(&[0x00, tx_id, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x01]);
+            assert_eq!(io.next_event().await, Event::Read);
+        }
+        for (task, start) in [(first, 1), (second, 2), (third, 3)] {
+            assert_eq!(task.await.unwrap(), Ok(vec![Indexed::new(start, true)]));
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_every_kind_of_request_of_a_session() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        let config = SessionConfig {
+            max_outstanding: Some(1),
+            ..SessionConfig::new(Duration::from_secs(1))
+        };
+        let mut session = channel.create_session_with(UnitId::new(1), config);
+
+        let mut reader = session.clone();
+        let first = tokio::spawn(async move {
+            reader
+                .read_coils(AddressRange::try_from(1, 1).unwrap())
+                .await
+        });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        let raw = tokio::spawn(async move { session.send_raw_pdu(0x41, &[0x55]).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(channel.tx.capacity(), 16);
+
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x01]);
+        assert_eq!(io.next_event().await, Event::Read);
+        assert_eq!(
+            io.next_event().await,
+            Event::Write(vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x41, 0x55])
+        );
+        io.read(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x41, 0xAA]);
+        assert!(first.await.unwrap().is_ok());
+        assert_eq!(raw.await.unwrap(), Ok(vec![0xAA]));
+    }
+
+    #[tokio::test]
+    async fn closes_idle_connections() {
+        let (mut channel, task, _io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel
+            .set_idle_disconnect(Some(Duration::from_secs(30)))
+            .await
+            .unwrap();
+        tokio::time::pause();
+
+        assert_eq!(task.await.unwrap(), SessionError::Idle);
+    }
+
+    #[tokio::test]
+    async fn accepts_mismatched_transaction_ids_when_lenient() {
+        let (mut channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        channel.set_lenient_tx_id(true).await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let range = AddressRange::try_from(1, 1).unwrap();
+
+        // the gateway always answers with transaction id 0
+        for value in [0x01, 0x00] {
+            let mut reader = channel.clone();
+            let coils = tokio::spawn(async move { reader.read_coils(params, range).await });
+            assert!(matches!(io.next_event().await, Event::Write(_)));
+            io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, value]);
+            assert_eq!(io.next_event().await, Event::Read);
+            assert_eq!(
+                coils.await.unwrap(),
+                Ok(vec![Indexed::new(1, value == 0x01)])
+            );
+        }
+
+        // the response is discarded once the channel is strict again
+        channel.set_lenient_tx_id(false).await.unwrap();
+        let mut reader = channel.clone();
+        let coils = tokio::spawn(async move { reader.read_coils(params, range).await });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x01]);
+        assert_eq!(io.next_event().await, Event::Read);
+        tokio::time::pause();
+        assert_eq!(coils.await.unwrap(), Err(RequestError::ResponseTimeout));
+    }
+
+    #[tokio::test]
+    async fn records_statistics() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+        let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+        let range = AddressRange::try_from(1, 1).unwrap();
+
+        let mut reader = channel.clone();
+        let coils = tokio::spawn(async move { reader.read_coils(params, range).await });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        io.read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x01]);
+        assert_eq!(io.next_event().await, Event::Read);
+        coils.await.unwrap().unwrap();
+
+        let mut reader = channel.clone();
+        let coils = tokio::spawn(async move { reader.read_coils(params, range).await });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        tokio::time::pause();
+        assert_eq!(coils.await.unwrap(), Err(RequestError::ResponseTimeout));
+
+        let statistics = channel.statistics();
+        assert_eq!(statistics.requests_sent, 2);
+        assert_eq!(statistics.responses_received, 1);
+        assert_eq!(statistics.timeouts, 1);
+        assert_eq!(statistics.protocol_errors, 0);
+        assert_eq!(statistics.reconnects, 0);
+        assert_eq!(statistics.bytes_sent, 24);
+        assert_eq!(statistics.bytes_received, 10);
+        assert!(statistics.last_exchange.is_some());
+    }
+
+    #[tokio::test]
+    async fn sessions_override_the_timeout_and_retries() {
+        let (channel, _task, mut io) = spawn_client_loop();
+        channel.enable().await.unwrap();
+
+        let range = AddressRange::try_from(7, 1).unwrap();
+        let config = SessionConfig {
+            timeout: Duration::from_secs(2),
+            retries: Some(1),
+            max_outstanding: None,
+        };
+        let mut slow = channel.create_session_with(UnitId::new(2), config);
+        assert_eq!(slow.param().id, UnitId::new(2));
+        assert_eq!(slow.param().response_timeout, Duration::from_secs(2));
+        let mut fast =
+            channel.create_session_with(UnitId::new(1), SessionConfig::new(Duration::from_secs(1)));
+
+        // the channel has no retry policy, so only the request of the session is retried
+        let coils = tokio::spawn(async move { slow.read_coils(range).await });
+        for tx_id in 0..2 {
+            assert!(matches!(io.next_event().await, Event::Write(_)));
+            io.read(&[0x00, tx_id, 0x00, 0x00, 0x00, 0x03, 0x02, 0x81, 0x06]);
+            assert_eq!(io.next_event().await, Event::Read);
+        }
+        assert_eq!(
+            coils.await.unwrap(),
+            Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+        );
+
+        let coils = tokio::spawn(async move { fast.read_coils(range).await });
+        assert!(matches!(io.next_event().await, Event::Write(_)));
+        io.read(&[0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x01, 0x81, 0x06]);
+        assert_eq!(
+            coils.await.unwrap(),
+            Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+        );
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn keeps_tls_config_until_the_next_connection() {
+        use tokio_rustls::rustls;
+
+        let config = rustls::ClientConfig::builder_with_provider(std::sync::Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(rustls::RootCertStore::empty())
+        .with_no_client_auth();
+        let config =
+            crate::tcp::tls::TlsClientConfig::from_rustls(None, std::sync::Arc::new(config))
+                .unwrap();
+
+        let (_channel, rx) = Channel::create(1);
+        let mut client_loop = ClientLoop::new(
+            rx,
+            FrameWriter::tcp(),
+            FramedReader::tcp(),
+            DecodeLevel::nothing(),
+        );
+
+        assert!(client_loop.take_tls_config().is_none());
+        client_loop.change_setting(Setting::TlsConfig(config));
+        assert!(client_loop.take_tls_config().is_some());
+        assert!(client_loop.take_tls_config().is_none());
+    }
 }
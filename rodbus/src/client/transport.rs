@@ -0,0 +1,123 @@
+use std::future::Future;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::client::{ClientState, Listener};
+use crate::common::phys::PhysLayer;
+use crate::decode::DecodeLevel;
+
+use crate::client::message::CommandReceiver;
+use crate::client::task::{ClientLoop, SessionError, StateChange};
+use crate::error::Shutdown;
+use crate::retry::RetryStrategy;
+use crate::types::Framing;
+
+/// Channel task that obtains its byte stream from a user-supplied connect function
+pub(crate) struct TransportChannelTask<F> {
+    connect: F,
+    connect_retry: Box<dyn RetryStrategy>,
+    client_loop: ClientLoop,
+    listener: Box<dyn Listener<ClientState>>,
+}
+
+impl<F, Fut, T> TransportChannelTask<F>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::io::Result<T>>,
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    pub(crate) fn new(
+        connect: F,
+        rx: CommandReceiver,
+        framing: Framing,
+        connect_retry: Box<dyn RetryStrategy>,
+        decode: DecodeLevel,
+        listener: Box<dyn Listener<ClientState>>,
+    ) -> Self {
+        Self {
+            connect,
+            connect_retry,
+            client_loop: ClientLoop::new(rx, framing.writer(), framing.response_reader(), decode),
+            listener,
+        }
+    }
+
+    // runs until it is shut down
+    pub(crate) async fn run(&mut self) -> Shutdown {
+        self.listener.update(ClientState::Disabled).get().await;
+        let ret = self.run_inner().await;
+        self.listener.update(ClientState::Shutdown).get().await;
+        ret
+    }
+
+    async fn run_inner(&mut self) -> Shutdown {
+        loop {
+            if let Err(Shutdown) = self.client_loop.wait_for_enabled().await {
+                return Shutdown;
+            }
+
+            if let Err(StateChange::Shutdown) = self.try_connect_and_run().await {
+                return Shutdown;
+            }
+
+            if !self.client_loop.is_enabled() {
+                self.listener.update(ClientState::Disabled).get().await;
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> Result<std::io::Result<T>, StateChange> {
+        tokio::select! {
+            res = (self.connect)() => {
+                Ok(res)
+            }
+            res = self.client_loop.fail_requests() => {
+                Err(res)
+            }
+        }
+    }
+
+    async fn try_connect_and_run(&mut self) -> Result<(), StateChange> {
+        self.listener.update(ClientState::Connecting).get().await;
+        match self.connect().await? {
+            Err(err) => {
+                let delay = self.connect_retry.after_failed_connect();
+                tracing::warn!(
+                    "failed to connect: {} - waiting {} ms before next attempt",
+                    err,
+                    delay.as_millis()
+                );
+                self.listener
+                    .update(ClientState::WaitAfterFailedConnect(delay))
+                    .get()
+                    .await;
+                self.client_loop.fail_requests_for(delay).await
+            }
+            Ok(stream) => {
+                tracing::info!("connected");
+                self.connect_retry.reset();
+                self.listener.update(ClientState::Connected).get().await;
+                let mut phys = PhysLayer::new_stream(Box::new(stream));
+                let err = self.client_loop.run(&mut phys).await;
+                // close the connection right away instead of keeping it while waiting
+                drop(phys);
+                match err {
+                    // the mpsc was closed, end the task
+                    SessionError::Shutdown => Err(StateChange::Shutdown),
+                    // reconnect once the next request is made
+                    SessionError::Idle => Ok(()),
+                    // re-establish the connection
+                    SessionError::Disabled | SessionError::IoError(_) | SessionError::BadFrame => {
+                        let delay = self.connect_retry.after_disconnect();
+                        tracing::warn!("waiting {:?} to reconnect", delay);
+                        self.listener
+                            .update(ClientState::WaitAfterDisconnect(delay))
+                            .get()
+                            .await;
+                        self.client_loop.fail_requests_for(delay).await
+                    }
+                }
+            }
+        }
+    }
+}
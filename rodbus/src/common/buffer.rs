@@ -4,7 +4,7 @@ use crate::error::InternalError;
 use crate::PhysDecodeLevel;
 
 pub(crate) struct ReadBuffer {
-    buffer: [u8; crate::common::frame::constants::MAX_FRAME_LENGTH],
+    buffer: Vec<u8>,
     begin: usize,
     end: usize,
 }
@@ -12,12 +12,21 @@ pub(crate) struct ReadBuffer {
 impl ReadBuffer {
     pub(crate) fn new() -> Self {
         ReadBuffer {
-            buffer: [0; crate::common::frame::constants::MAX_FRAME_LENGTH],
+            buffer: vec![0; crate::common::frame::constants::MAX_FRAME_LENGTH],
             begin: 0,
             end: 0,
         }
     }
 
+    /// change the size of the buffer, keeping the unread bytes at its start
+    pub(crate) fn resize(&mut self, size: usize) {
+        let length = self.len();
+        self.buffer.copy_within(self.begin..self.end, 0);
+        self.begin = 0;
+        self.end = length;
+        self.buffer.resize(size.max(length), 0);
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.end - self.begin
     }
@@ -26,6 +35,12 @@ impl ReadBuffer {
         self.begin == self.end
     }
 
+    /// discard the unread bytes
+    pub(crate) fn clear(&mut self) {
+        self.begin = 0;
+        self.end = 0;
+    }
+
     pub(crate) fn read(&mut self, count: usize) -> Result<&[u8], InternalError> {
         if self.len() < count {
             return Err(InternalError::InsufficientBytesForRead(count, self.len()));
@@ -127,6 +142,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keeps_unread_bytes_when_resized() {
+        let mut buffer = ReadBuffer::new();
+
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut phys = PhysLayer::new_mock(io);
+
+        {
+            let mut task = task::spawn(async {
+                buffer
+                    .read_some(&mut phys, PhysDecodeLevel::Nothing)
+                    .await
+                    .unwrap()
+            });
+            io_handle.read(&[0x01, 0x02, 0x03]);
+            assert_ready_eq!(task.poll(), 3);
+        }
+
+        assert_eq!(buffer.read(1).unwrap(), &[0x01]);
+        buffer.resize(1024);
+        assert_eq!(buffer.buffer.len(), 1024);
+        assert_eq!(buffer.read(2).unwrap(), &[0x02, 0x03]);
+    }
+
     #[test]
     fn preserves_data_over_multiple_reads() {
         let mut buffer = ReadBuffer::new();
@@ -6,7 +6,7 @@ use crate::common::function::FunctionCode;
 use crate::common::traits::{Loggable, LoggableDisplay, Serialize};
 use crate::error::RequestError;
 use crate::tcp::frame::{MbapDisplay, MbapHeader, MbapParser};
-use crate::types::UnitId;
+use crate::types::{Framing, UnitId};
 use crate::{DecodeLevel, ExceptionCode, FrameDecodeLevel};
 
 use scursor::WriteCursor;
@@ -22,9 +22,15 @@ pub(crate) mod constants {
 
     pub(crate) const MAX_ADU_LENGTH: usize = 253;
 
+    /// the longest ADU described by the length field of an MBAP header
+    pub(crate) const MAX_CONFIGURABLE_ADU_LENGTH: usize = u16::MAX as usize - 1;
+
     #[cfg(feature = "serial")]
     const fn serial_frame_size() -> usize {
-        crate::serial::frame::constants::MAX_FRAME_LENGTH
+        max(
+            crate::serial::frame::constants::MAX_FRAME_LENGTH,
+            crate::serial::ascii::constants::MAX_FORMAT_LENGTH,
+        )
     }
 
     #[cfg(not(feature = "serial"))]
@@ -144,6 +150,9 @@ pub(crate) struct Frame {
     pub(crate) header: FrameHeader,
     length: usize,
     pdu: [u8; constants::MAX_ADU_LENGTH],
+    /// PDUs longer than the spec allows, which the parsers accept when the maximum ADU length
+    /// is raised. Only these allocate.
+    large: Vec<u8>,
 }
 
 impl Frame {
@@ -152,28 +161,35 @@ impl Frame {
             header,
             length: 0,
             pdu: [0; constants::MAX_ADU_LENGTH],
+            large: Vec::new(),
         }
     }
 
-    pub(crate) fn set(&mut self, src: &[u8]) -> bool {
+    pub(crate) fn set(&mut self, src: &[u8]) {
+        self.length = src.len();
         if src.len() > self.pdu.len() {
-            return false;
+            self.large.clear();
+            self.large.extend_from_slice(src);
+        } else {
+            self.pdu[0..src.len()].copy_from_slice(src);
         }
-
-        self.pdu[0..src.len()].copy_from_slice(src);
-        self.length = src.len();
-        true
     }
 
     pub(crate) fn payload(&self) -> &[u8] {
-        &self.pdu[0..self.length]
+        if self.length > self.pdu.len() {
+            &self.large
+        } else {
+            &self.pdu[0..self.length]
+        }
     }
 }
 
-///  Defines an interface for parsing frames (TCP or RTU)
+///  Defines an interface for parsing frames (TCP, RTU or ASCII)
 pub(crate) enum FrameParser {
     #[cfg(feature = "serial")]
     Rtu(crate::serial::frame::RtuParser),
+    #[cfg(feature = "serial")]
+    Ascii(crate::serial::ascii::AsciiParser),
     Tcp(MbapParser),
 }
 
@@ -192,15 +208,54 @@ impl FrameParser {
         match self {
             #[cfg(feature = "serial")]
             FrameParser::Rtu(x) => x.parse(cursor, decode_level),
+            #[cfg(feature = "serial")]
+            FrameParser::Ascii(x) => x.parse(cursor, decode_level),
             FrameParser::Tcp(x) => x.parse(cursor, decode_level),
         }
     }
 
+    /// Accept MBAP frames whose protocol id isn't 0
+    pub(crate) fn set_lenient_protocol_id(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "serial")]
+            FrameParser::Rtu(_) | FrameParser::Ascii(_) => {}
+            FrameParser::Tcp(x) => x.lenient_protocol_id = enabled,
+        }
+    }
+
+    /// Accept frames whose function code and body are up to `length` bytes long
+    pub(crate) fn set_max_adu_length(&mut self, length: usize) {
+        match self {
+            #[cfg(feature = "serial")]
+            FrameParser::Rtu(x) => x.max_adu_length = length,
+            #[cfg(feature = "serial")]
+            FrameParser::Ascii(x) => x.max_adu_length = length,
+            FrameParser::Tcp(x) => x.max_adu_length = length,
+        }
+    }
+
+    /// The length of the longest frame that is accepted
+    pub(crate) fn max_frame_length(&self) -> usize {
+        match self {
+            #[cfg(feature = "serial")]
+            FrameParser::Rtu(x) => {
+                crate::serial::frame::constants::HEADER_LENGTH
+                    + x.max_adu_length
+                    + crate::serial::frame::constants::CRC_LENGTH
+            }
+            #[cfg(feature = "serial")]
+            FrameParser::Ascii(x) => x.max_frame_length(),
+            FrameParser::Tcp(x) => crate::tcp::frame::constants::HEADER_LENGTH + x.max_adu_length,
+        }
+    }
+
     /// Reset the parser state. Called whenever an error occurs
     pub(crate) fn reset(&mut self) {
         match self {
             #[cfg(feature = "serial")]
             FrameParser::Rtu(x) => x.reset(),
+            #[cfg(feature = "serial")]
+            FrameParser::Ascii(x) => x.reset(),
             FrameParser::Tcp(x) => x.reset(),
         }
     }
@@ -211,6 +266,9 @@ pub(crate) enum FrameType {
     #[cfg(feature = "serial")]
     // destination and CRC
     Rtu(FrameDestination, u16),
+    #[cfg(feature = "serial")]
+    // destination and LRC
+    Ascii(FrameDestination, u8),
 }
 
 pub(crate) struct FrameInfo {
@@ -233,6 +291,8 @@ enum FormatType {
     Tcp,
     #[cfg(feature = "serial")]
     Rtu,
+    #[cfg(feature = "serial")]
+    Ascii,
 }
 
 impl FormatType {
@@ -247,6 +307,42 @@ impl FormatType {
             FormatType::Tcp => crate::tcp::frame::format_mbap(cursor, header, function, body),
             #[cfg(feature = "serial")]
             FormatType::Rtu => crate::serial::frame::format_rtu_pdu(cursor, header, function, body),
+            #[cfg(feature = "serial")]
+            FormatType::Ascii => {
+                crate::serial::ascii::format_ascii_pdu(cursor, header, function, body)
+            }
+        }
+    }
+}
+
+impl Framing {
+    pub(crate) fn writer(self) -> FrameWriter {
+        // the same formatter is used for requests and responses
+        match self {
+            Framing::Mbap => FrameWriter::tcp(),
+            #[cfg(feature = "serial")]
+            Framing::Rtu => FrameWriter::rtu(),
+            #[cfg(feature = "serial")]
+            Framing::Ascii => FrameWriter::ascii(),
+        }
+    }
+
+    pub(crate) fn response_reader(self) -> FramedReader {
+        match self {
+            Framing::Mbap => FramedReader::tcp(),
+            #[cfg(feature = "serial")]
+            Framing::Rtu => FramedReader::rtu_response(),
+            #[cfg(feature = "serial")]
+            Framing::Ascii => FramedReader::ascii(),
+        }
+    }
+
+    #[cfg(feature = "serial")]
+    pub(crate) fn request_reader(self) -> FramedReader {
+        match self {
+            Framing::Mbap => FramedReader::tcp(),
+            Framing::Rtu => FramedReader::rtu_request(),
+            Framing::Ascii => FramedReader::ascii(),
         }
     }
 }
@@ -254,6 +350,8 @@ impl FormatType {
 pub(crate) struct FrameWriter {
     format_type: FormatType,
     buffer: [u8; constants::MAX_FRAME_LENGTH],
+    /// exception carried by the last formatted frame
+    exception: Option<ExceptionCode>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -261,6 +359,8 @@ pub(crate) enum FunctionField {
     Valid(FunctionCode),
     Exception(FunctionCode),
     UnknownFunction(u8),
+    /// function code sent by the client without interpretation
+    Raw(u8),
 }
 
 impl std::fmt::Display for FunctionField {
@@ -276,6 +376,9 @@ impl std::fmt::Display for FunctionField {
             FunctionField::UnknownFunction(_) => {
                 write!(f, "Unknown Function Exception: {value}")
             }
+            FunctionField::Raw(_) => {
+                write!(f, "RAW FUNCTION ({value:#04X})")
+            }
         }
     }
 }
@@ -290,8 +393,13 @@ impl FunctionField {
             FunctionField::Valid(x) => x.get_value(),
             FunctionField::Exception(x) => x.get_value() | 0x80,
             FunctionField::UnknownFunction(x) => x | 0x80,
+            FunctionField::Raw(x) => *x,
         }
     }
+
+    pub(crate) fn as_error(&self) -> u8 {
+        self.get_value() | 0x80
+    }
 }
 
 impl FrameWriter {
@@ -299,9 +407,15 @@ impl FrameWriter {
         Self {
             format_type,
             buffer: [0; constants::MAX_FRAME_LENGTH],
+            exception: None,
         }
     }
 
+    /// exception carried by the last formatted frame, if any
+    pub(crate) fn last_exception(&self) -> Option<ExceptionCode> {
+        self.exception
+    }
+
     pub(crate) fn format_reply<T>(
         &mut self,
         header: FrameHeader,
@@ -324,15 +438,14 @@ impl FrameWriter {
     pub(crate) fn format_request<T>(
         &mut self,
         header: FrameHeader,
-        function: FunctionCode,
+        function: FunctionField,
         body: &T,
         decode_level: DecodeLevel,
     ) -> Result<&[u8], RequestError>
     where
         T: Serialize + Loggable,
     {
-        let range =
-            self.format_generic(header, FunctionField::Valid(function), body, decode_level)?;
+        let range = self.format_generic(header, function, body, decode_level)?;
         Ok(&self.buffer[range])
     }
 
@@ -347,9 +460,11 @@ impl FrameWriter {
             FunctionField::Valid(x) => FunctionField::Exception(x),
             FunctionField::Exception(x) => FunctionField::Exception(x),
             FunctionField::UnknownFunction(x) => FunctionField::UnknownFunction(x),
+            FunctionField::Raw(x) => FunctionField::UnknownFunction(x),
         };
 
         let range = self.format_generic(header, function, &ex, decode_level)?;
+        self.exception = Some(ex);
 
         Ok(&self.buffer[range])
     }
@@ -364,6 +479,7 @@ impl FrameWriter {
     where
         T: Serialize + Loggable,
     {
+        self.exception = None;
         let (frame_type, frame_bytes, pdu_body) = {
             let mut cursor = WriteCursor::new(self.buffer.as_mut());
             let info = self
@@ -402,12 +518,41 @@ impl FrameWriter {
                         )
                     );
                 }
+                #[cfg(feature = "serial")]
+                FrameType::Ascii(dest, lrc) => {
+                    tracing::info!(
+                        "ASCII TX - {}",
+                        crate::serial::ascii::AsciiDisplay::new(
+                            decode_level.frame,
+                            dest,
+                            frame_bytes,
+                            lrc
+                        )
+                    );
+                }
             }
         }
 
         Ok(frame_bytes)
     }
 
+    /// Broadcast is only defined for serial lines, unit id 0 is a regular address over TCP
+    pub(crate) fn supports_broadcast(&self) -> bool {
+        match self.format_type {
+            FormatType::Tcp => false,
+            #[cfg(feature = "serial")]
+            FormatType::Rtu => true,
+            #[cfg(feature = "serial")]
+            FormatType::Ascii => true,
+        }
+    }
+
+    /// Only MBAP frames carry a transaction id that matches responses to one of several
+    /// requests in flight
+    pub(crate) fn supports_pipelining(&self) -> bool {
+        matches!(self.format_type, FormatType::Tcp)
+    }
+
     pub(crate) fn tcp() -> Self {
         Self::new(FormatType::Tcp)
     }
@@ -416,11 +561,18 @@ impl FrameWriter {
     pub(crate) fn rtu() -> Self {
         Self::new(FormatType::Rtu)
     }
+
+    #[cfg(feature = "serial")]
+    pub(crate) fn ascii() -> Self {
+        Self::new(FormatType::Ascii)
+    }
 }
 
 pub(crate) struct FramedReader {
     parser: FrameParser,
     buffer: ReadBuffer,
+    /// each read returns a whole datagram that contains a single ADU
+    datagram: bool,
 }
 
 impl FramedReader {
@@ -428,6 +580,14 @@ impl FramedReader {
         Self::new(FrameParser::Tcp(MbapParser::new()))
     }
 
+    /// MBAP reader that parses one ADU per datagram
+    pub(crate) fn udp() -> Self {
+        Self {
+            datagram: true,
+            ..Self::tcp()
+        }
+    }
+
     #[cfg(feature = "serial")]
     pub(crate) fn rtu_request() -> Self {
         Self::new(FrameParser::Rtu(
@@ -442,10 +602,43 @@ impl FramedReader {
         ))
     }
 
+    #[cfg(feature = "serial")]
+    pub(crate) fn ascii() -> Self {
+        Self::new(FrameParser::Ascii(crate::serial::ascii::AsciiParser::new()))
+    }
+
     fn new(parser: FrameParser) -> Self {
         Self {
             parser,
             buffer: ReadBuffer::new(),
+            datagram: false,
+        }
+    }
+
+    /// accept MBAP frames whose protocol id isn't 0, other frames have none
+    pub(crate) fn set_lenient_protocol_id(&mut self, enabled: bool) {
+        self.parser.set_lenient_protocol_id(enabled)
+    }
+
+    /// accept ADUs of up to `length` bytes, resizing the buffer to hold the longest frame
+    pub(crate) fn set_max_adu_length(&mut self, length: usize) {
+        self.parser.set_max_adu_length(length);
+        self.buffer.resize(
+            self.parser
+                .max_frame_length()
+                .max(constants::MAX_FRAME_LENGTH),
+        );
+    }
+
+    /// drop what remains of a datagram, so that it isn't mixed with the next one
+    fn discard_datagram(&mut self) {
+        if !self.buffer.is_empty() {
+            tracing::warn!(
+                "discarding {} bytes that don't form an ADU",
+                self.buffer.len()
+            );
+            self.buffer.clear();
+            self.parser.reset();
         }
     }
 
@@ -456,8 +649,16 @@ impl FramedReader {
     ) -> Result<Frame, RequestError> {
         loop {
             match self.parser.parse(&mut self.buffer, decode_level.frame) {
-                Ok(Some(frame)) => return Ok(frame),
+                Ok(Some(frame)) => {
+                    if self.datagram {
+                        self.discard_datagram();
+                    }
+                    return Ok(frame);
+                }
                 Ok(None) => {
+                    if self.datagram {
+                        self.discard_datagram();
+                    }
                     self.buffer.read_some(io, decode_level.physical).await?;
                 }
                 Err(err) => {
@@ -7,8 +7,19 @@ mod constants {
     pub(crate) const READ_INPUT_REGISTERS: u8 = 4;
     pub(crate) const WRITE_SINGLE_COIL: u8 = 5;
     pub(crate) const WRITE_SINGLE_REGISTER: u8 = 6;
+    pub(crate) const READ_EXCEPTION_STATUS: u8 = 7;
+    pub(crate) const DIAGNOSTICS: u8 = 8;
+    pub(crate) const GET_COMM_EVENT_COUNTER: u8 = 11;
+    pub(crate) const GET_COMM_EVENT_LOG: u8 = 12;
     pub(crate) const WRITE_MULTIPLE_COILS: u8 = 15;
     pub(crate) const WRITE_MULTIPLE_REGISTERS: u8 = 16;
+    pub(crate) const REPORT_SERVER_ID: u8 = 17;
+    pub(crate) const READ_FILE_RECORD: u8 = 20;
+    pub(crate) const WRITE_FILE_RECORD: u8 = 21;
+    pub(crate) const MASK_WRITE_REGISTER: u8 = 22;
+    pub(crate) const READ_WRITE_MULTIPLE_REGISTERS: u8 = 23;
+    pub(crate) const READ_FIFO_QUEUE: u8 = 24;
+    pub(crate) const ENCAPSULATED_INTERFACE_TRANSPORT: u8 = 43;
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -20,8 +31,19 @@ pub(crate) enum FunctionCode {
     ReadInputRegisters = constants::READ_INPUT_REGISTERS,
     WriteSingleCoil = constants::WRITE_SINGLE_COIL,
     WriteSingleRegister = constants::WRITE_SINGLE_REGISTER,
+    ReadExceptionStatus = constants::READ_EXCEPTION_STATUS,
+    Diagnostics = constants::DIAGNOSTICS,
+    GetCommEventCounter = constants::GET_COMM_EVENT_COUNTER,
+    GetCommEventLog = constants::GET_COMM_EVENT_LOG,
     WriteMultipleCoils = constants::WRITE_MULTIPLE_COILS,
     WriteMultipleRegisters = constants::WRITE_MULTIPLE_REGISTERS,
+    ReportServerId = constants::REPORT_SERVER_ID,
+    ReadFileRecord = constants::READ_FILE_RECORD,
+    WriteFileRecord = constants::WRITE_FILE_RECORD,
+    MaskWriteRegister = constants::MASK_WRITE_REGISTER,
+    ReadWriteMultipleRegisters = constants::READ_WRITE_MULTIPLE_REGISTERS,
+    ReadFifoQueue = constants::READ_FIFO_QUEUE,
+    EncapsulatedInterfaceTransport = constants::ENCAPSULATED_INTERFACE_TRANSPORT,
 }
 
 impl Display for FunctionCode {
@@ -43,12 +65,49 @@ impl Display for FunctionCode {
             FunctionCode::WriteSingleRegister => {
                 write!(f, "WRITE SINGLE REGISTER ({:#04X})", self.get_value())
             }
+            FunctionCode::ReadExceptionStatus => {
+                write!(f, "READ EXCEPTION STATUS ({:#04X})", self.get_value())
+            }
+            FunctionCode::Diagnostics => write!(f, "DIAGNOSTICS ({:#04X})", self.get_value()),
+            FunctionCode::GetCommEventCounter => {
+                write!(f, "GET COMM EVENT COUNTER ({:#04X})", self.get_value())
+            }
+            FunctionCode::GetCommEventLog => {
+                write!(f, "GET COMM EVENT LOG ({:#04X})", self.get_value())
+            }
             FunctionCode::WriteMultipleCoils => {
                 write!(f, "WRITE MULTIPLE COILS ({:#04X})", self.get_value())
             }
             FunctionCode::WriteMultipleRegisters => {
                 write!(f, "WRITE MULTIPLE REGISTERS ({:#04X})", self.get_value())
             }
+            FunctionCode::ReportServerId => {
+                write!(f, "REPORT SERVER ID ({:#04X})", self.get_value())
+            }
+            FunctionCode::ReadFileRecord => {
+                write!(f, "READ FILE RECORD ({:#04X})", self.get_value())
+            }
+            FunctionCode::WriteFileRecord => {
+                write!(f, "WRITE FILE RECORD ({:#04X})", self.get_value())
+            }
+            FunctionCode::MaskWriteRegister => {
+                write!(f, "MASK WRITE REGISTER ({:#04X})", self.get_value())
+            }
+            FunctionCode::ReadWriteMultipleRegisters => {
+                write!(
+                    f,
+                    "READ/WRITE MULTIPLE REGISTERS ({:#04X})",
+                    self.get_value()
+                )
+            }
+            FunctionCode::ReadFifoQueue => {
+                write!(f, "READ FIFO QUEUE ({:#04X})", self.get_value())
+            }
+            FunctionCode::EncapsulatedInterfaceTransport => write!(
+                f,
+                "ENCAPSULATED INTERFACE TRANSPORT ({:#04X})",
+                self.get_value()
+            ),
         }
     }
 }
@@ -58,10 +117,6 @@ impl FunctionCode {
         self as u8
     }
 
-    pub(crate) const fn as_error(self) -> u8 {
-        self.get_value() | 0x80
-    }
-
     pub(crate) fn get(value: u8) -> Option<Self> {
         match value {
             constants::READ_COILS => Some(FunctionCode::ReadCoils),
@@ -70,8 +125,23 @@ impl FunctionCode {
             constants::READ_INPUT_REGISTERS => Some(FunctionCode::ReadInputRegisters),
             constants::WRITE_SINGLE_COIL => Some(FunctionCode::WriteSingleCoil),
             constants::WRITE_SINGLE_REGISTER => Some(FunctionCode::WriteSingleRegister),
+            constants::READ_EXCEPTION_STATUS => Some(FunctionCode::ReadExceptionStatus),
+            constants::DIAGNOSTICS => Some(FunctionCode::Diagnostics),
+            constants::GET_COMM_EVENT_COUNTER => Some(FunctionCode::GetCommEventCounter),
+            constants::GET_COMM_EVENT_LOG => Some(FunctionCode::GetCommEventLog),
             constants::WRITE_MULTIPLE_COILS => Some(FunctionCode::WriteMultipleCoils),
             constants::WRITE_MULTIPLE_REGISTERS => Some(FunctionCode::WriteMultipleRegisters),
+            constants::REPORT_SERVER_ID => Some(FunctionCode::ReportServerId),
+            constants::READ_FILE_RECORD => Some(FunctionCode::ReadFileRecord),
+            constants::WRITE_FILE_RECORD => Some(FunctionCode::WriteFileRecord),
+            constants::MASK_WRITE_REGISTER => Some(FunctionCode::MaskWriteRegister),
+            constants::READ_WRITE_MULTIPLE_REGISTERS => {
+                Some(FunctionCode::ReadWriteMultipleRegisters)
+            }
+            constants::READ_FIFO_QUEUE => Some(FunctionCode::ReadFifoQueue),
+            constants::ENCAPSULATED_INTERFACE_TRANSPORT => {
+                Some(FunctionCode::EncapsulatedInterfaceTransport)
+            }
             _ => None,
         }
     }
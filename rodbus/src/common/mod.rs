@@ -6,4 +6,5 @@ pub(crate) mod buffer;
 pub(crate) mod frame;
 mod parse;
 pub(crate) mod phys;
+pub(crate) mod random;
 mod serialize;
@@ -1,14 +1,26 @@
 use crate::decode::PhysDecodeLevel;
 use std::fmt::Write;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Byte stream supplied by a user-defined transport
+pub(crate) trait AsyncStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> AsyncStream for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
 
 pub(crate) struct PhysLayer {
     layer: PhysLayerImpl,
+    /// bytes read and written since the counts were last taken
+    bytes_read: u64,
+    bytes_written: u64,
 }
 
 // encapsulates all possible physical layers as an enum
 pub(crate) enum PhysLayerImpl {
     Tcp(tokio::net::TcpStream),
+    Udp(tokio::net::UdpSocket),
+    // unconnected socket that answers the sender of the last datagram
+    UdpServer(tokio::net::UdpSocket, Option<std::net::SocketAddr>),
+    Stream(Box<dyn AsyncStream>),
     #[cfg(feature = "serial")]
     Serial(
         tokio_serial::SerialStream,
@@ -18,6 +30,8 @@ pub(crate) enum PhysLayerImpl {
     // TLS type is boxed because its size is huge
     #[cfg(feature = "tls")]
     Tls(Box<tokio_rustls::TlsStream<tokio::net::TcpStream>>),
+    #[cfg(feature = "native-tls")]
+    NativeTls(Box<tokio_native_tls::TlsStream<tokio::net::TcpStream>>),
     #[cfg(test)]
     Mock(sfio_tokio_mock_io::Mock),
 }
@@ -26,10 +40,15 @@ impl std::fmt::Debug for PhysLayer {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match &self.layer {
             PhysLayerImpl::Tcp(_) => f.write_str("Tcp"),
+            PhysLayerImpl::Udp(_) => f.write_str("Udp"),
+            PhysLayerImpl::UdpServer(_, _) => f.write_str("UdpServer"),
+            PhysLayerImpl::Stream(_) => f.write_str("Stream"),
             #[cfg(feature = "serial")]
             PhysLayerImpl::Serial(_, _, _) => f.write_str("Serial"),
             #[cfg(feature = "tls")]
             PhysLayerImpl::Tls(_) => f.write_str("Tls"),
+            #[cfg(feature = "native-tls")]
+            PhysLayerImpl::NativeTls(_) => f.write_str("NativeTls"),
             #[cfg(test)]
             PhysLayerImpl::Mock(_) => f.write_str("Mock"),
         }
@@ -37,32 +56,71 @@ impl std::fmt::Debug for PhysLayer {
 }
 
 impl PhysLayer {
-    pub(crate) fn new_tcp(socket: tokio::net::TcpStream) -> Self {
+    fn new(layer: PhysLayerImpl) -> Self {
         Self {
-            layer: PhysLayerImpl::Tcp(socket),
+            layer,
+            bytes_read: 0,
+            bytes_written: 0,
         }
     }
 
-    #[cfg(feature = "serial")]
-    pub(crate) fn new_serial(stream: tokio_serial::SerialStream) -> Self {
-        let calculate_inter_character_delay = calculate_inter_character_delay(&stream);
-        Self {
-            layer: PhysLayerImpl::Serial(stream, calculate_inter_character_delay, None),
+    /// number of bytes read and written since the last call
+    pub(crate) fn take_byte_counts(&mut self) -> (u64, u64) {
+        let counts = (self.bytes_read, self.bytes_written);
+        self.bytes_read = 0;
+        self.bytes_written = 0;
+        counts
+    }
+
+    pub(crate) fn new_tcp(socket: tokio::net::TcpStream) -> Self {
+        Self::new(PhysLayerImpl::Tcp(socket))
+    }
+
+    pub(crate) fn new_udp(socket: tokio::net::UdpSocket) -> Self {
+        Self::new(PhysLayerImpl::Udp(socket))
+    }
+
+    pub(crate) fn new_udp_server(socket: tokio::net::UdpSocket) -> Self {
+        Self::new(PhysLayerImpl::UdpServer(socket, None))
+    }
+
+    /// sender of the last datagram received by a UDP server
+    pub(crate) fn datagram_source(&self) -> Option<std::net::SocketAddr> {
+        match &self.layer {
+            PhysLayerImpl::UdpServer(_, source) => *source,
+            _ => None,
         }
     }
 
+    pub(crate) fn new_stream(stream: Box<dyn AsyncStream>) -> Self {
+        Self::new(PhysLayerImpl::Stream(stream))
+    }
+
+    #[cfg(feature = "serial")]
+    pub(crate) fn new_serial(
+        stream: tokio_serial::SerialStream,
+        inter_frame_delay: Option<tokio::time::Duration>,
+    ) -> Self {
+        let inter_frame_delay =
+            inter_frame_delay.unwrap_or_else(|| calculate_inter_character_delay(&stream));
+        Self::new(PhysLayerImpl::Serial(stream, inter_frame_delay, None))
+    }
+
     #[cfg(feature = "tls")]
     pub(crate) fn new_tls(socket: tokio_rustls::TlsStream<tokio::net::TcpStream>) -> Self {
-        Self {
-            layer: PhysLayerImpl::Tls(Box::new(socket)),
-        }
+        Self::new(PhysLayerImpl::Tls(Box::new(socket)))
+    }
+
+    #[cfg(feature = "native-tls")]
+    pub(crate) fn new_native_tls(
+        socket: tokio_native_tls::TlsStream<tokio::net::TcpStream>,
+    ) -> Self {
+        Self::new(PhysLayerImpl::NativeTls(Box::new(socket)))
     }
 
     #[cfg(test)]
     pub(crate) fn new_mock(mock: sfio_tokio_mock_io::Mock) -> Self {
-        Self {
-            layer: PhysLayerImpl::Mock(mock),
-        }
+        Self::new(PhysLayerImpl::Mock(mock))
     }
 
     pub(crate) async fn read(
@@ -72,13 +130,23 @@ impl PhysLayer {
     ) -> Result<usize, std::io::Error> {
         let length = match &mut self.layer {
             PhysLayerImpl::Tcp(x) => x.read(buffer).await?,
+            PhysLayerImpl::Udp(x) => x.recv(buffer).await?,
+            PhysLayerImpl::UdpServer(x, source) => {
+                let (length, addr) = x.recv_from(buffer).await?;
+                *source = Some(addr);
+                length
+            }
+            PhysLayerImpl::Stream(x) => x.read(buffer).await?,
             #[cfg(feature = "serial")]
             PhysLayerImpl::Serial(x, _, _) => x.read(buffer).await?,
             #[cfg(feature = "tls")]
             PhysLayerImpl::Tls(x) => x.read(buffer).await?,
+            #[cfg(feature = "native-tls")]
+            PhysLayerImpl::NativeTls(x) => x.read(buffer).await?,
             #[cfg(test)]
             PhysLayerImpl::Mock(x) => x.read(buffer).await?,
         };
+        self.bytes_read += length as u64;
 
         if decode_level.enabled() {
             if let Some(x) = buffer.get(0..length) {
@@ -98,8 +166,15 @@ impl PhysLayer {
             tracing::info!("PHYS TX - {}", PhysDisplay::new(decode_level, data));
         }
 
-        match &mut self.layer {
+        let result = match &mut self.layer {
             PhysLayerImpl::Tcp(x) => x.write_all(data).await,
+            // each ADU is sent as a single datagram
+            PhysLayerImpl::Udp(x) => x.send(data).await.map(|_| ()),
+            PhysLayerImpl::UdpServer(x, source) => match source {
+                Some(addr) => x.send_to(data, *addr).await.map(|_| ()),
+                None => Err(std::io::ErrorKind::NotConnected.into()),
+            },
+            PhysLayerImpl::Stream(x) => x.write_all(data).await,
             #[cfg(feature = "serial")]
             PhysLayerImpl::Serial(x, inter_char_delay, last_activity) => {
                 // Respect inter-character delay
@@ -112,9 +187,15 @@ impl PhysLayer {
             }
             #[cfg(feature = "tls")]
             PhysLayerImpl::Tls(x) => x.write_all(data).await,
+            #[cfg(feature = "native-tls")]
+            PhysLayerImpl::NativeTls(x) => x.write_all(data).await,
             #[cfg(test)]
             PhysLayerImpl::Mock(x) => x.write_all(data).await,
+        };
+        if result.is_ok() {
+            self.bytes_written += data.len() as u64;
         }
+        result
     }
 }
 
@@ -0,0 +1,14 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// random value that is good enough to spread delays, without pulling in a dependency
+pub(crate) fn random_u64() -> u64 {
+    // every RandomState is seeded with different keys
+    RandomState::new().build_hasher().finish()
+}
+
+/// random value in `[0, 1)`
+pub(crate) fn random_f64() -> f64 {
+    // the 53 most significant bits fill the mantissa exactly
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -1,14 +1,20 @@
 use std::convert::TryFrom;
 
+use crate::client::requests::read_device_id::MEI_TYPE_READ_DEVICE_ID;
 use crate::client::WriteMultiple;
 use crate::common::traits::Loggable;
 use crate::common::traits::Parse;
 use crate::common::traits::Serialize;
+use crate::constants::FILE_RECORD_REFERENCE_TYPE;
+use crate::diagnostic::CommEventLog;
 use crate::error::{InternalError, RequestError};
-use crate::server::response::{BitWriter, RegisterWriter};
+use crate::server::response::{
+    BitWriter, DeviceIdWriter, DiagnosticsWriter, FifoWriter, FileRecordEchoWriter,
+    FileRecordWriter, RawWriter, RegisterWriter,
+};
 use crate::types::{
-    coil_from_u16, coil_to_u16, AddressRange, BitIterator, BitIteratorDisplay, Indexed,
-    RegisterIterator, RegisterIteratorDisplay,
+    coil_from_u16, coil_to_u16, AddressRange, BitIterator, BitIteratorDisplay, CommEventCounter,
+    FileRecord, Indexed, RegisterIterator, RegisterIteratorDisplay,
 };
 
 use scursor::{ReadCursor, WriteCursor};
@@ -134,6 +140,276 @@ impl Loggable for Indexed<u16> {
     }
 }
 
+impl Serialize for RawWriter<'_> {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_bytes(self.data)?;
+        Ok(())
+    }
+}
+
+impl Loggable for RawWriter<'_> {
+    fn log(
+        &self,
+        payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(f, "length: {}", payload.len())?;
+        }
+        if level.data_values() {
+            write!(f, "\n{payload:02X?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for DiagnosticsWriter {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u16_be(self.sub_function.into())?;
+        cursor.write_u16_be(self.data)?;
+        Ok(())
+    }
+}
+
+impl Loggable for DiagnosticsWriter {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(f, "{} data: {:#06X}", self.sub_function, self.data)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for FileRecordWriter {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the sub-requests were validated to fit in a response
+        let data_length: usize = self.records.iter().map(|x| 2 + 2 * x.values.len()).sum();
+        cursor.write_u8(data_length as u8)?;
+        for record in self.records.iter() {
+            cursor.write_u8((1 + 2 * record.values.len()) as u8)?;
+            cursor.write_u8(FILE_RECORD_REFERENCE_TYPE)?;
+            for value in record.values.iter() {
+                cursor.write_u16_be(*value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Loggable for FileRecordWriter {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        log_file_records(&self.records, level, f)
+    }
+}
+
+impl Serialize for FileRecordEchoWriter<'_> {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // reference type, file number, record number and record length precede the values
+        let data_length: usize = self.records.iter().map(|x| 7 + 2 * x.values.len()).sum();
+        cursor.write_u8(data_length as u8)?;
+        for record in self.records.iter() {
+            cursor.write_u8(FILE_RECORD_REFERENCE_TYPE)?;
+            cursor.write_u16_be(record.file_number)?;
+            cursor.write_u16_be(record.record_number)?;
+            cursor.write_u16_be(record.values.len() as u16)?;
+            for value in record.values.iter() {
+                cursor.write_u16_be(*value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Loggable for FileRecordEchoWriter<'_> {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        log_file_records(self.records, level, f)
+    }
+}
+
+fn log_file_records(
+    records: &[FileRecord],
+    level: crate::decode::AppDecodeLevel,
+    f: &mut std::fmt::Formatter,
+) -> std::fmt::Result {
+    if level.data_headers() {
+        write!(f, "records: {}", records.len())?;
+        for record in records.iter() {
+            write!(f, "\n{record}")?;
+            if level.data_values() {
+                for value in record.values.iter() {
+                    write!(f, "\nvalue: {value:#06X}")?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Serialize for FifoWriter {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // the byte count includes the FIFO count
+        cursor.write_u16_be((2 + 2 * self.values.len()) as u16)?;
+        cursor.write_u16_be(self.values.len() as u16)?;
+        for value in self.values.iter() {
+            cursor.write_u16_be(*value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Loggable for FifoWriter {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(f, "count: {}", self.values.len())?;
+        }
+        if level.data_values() {
+            for value in self.values.iter() {
+                write!(
+                    f,
+                    "
+{value:#06X}"
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for CommEventCounter {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u16_be(self.status)?;
+        cursor.write_u16_be(self.event_count)?;
+        Ok(())
+    }
+}
+
+impl Loggable for CommEventCounter {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(f, "{self}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for CommEventLog {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        // status, event count and message count precede the events
+        cursor.write_u8((6 + self.events.len()) as u8)?;
+        cursor.write_u16_be(self.status)?;
+        cursor.write_u16_be(self.event_count)?;
+        cursor.write_u16_be(self.message_count)?;
+        for event in self.events.iter() {
+            cursor.write_u8((*event).into())?;
+        }
+        Ok(())
+    }
+}
+
+impl Loggable for CommEventLog {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(
+                f,
+                "status: {:#06X} event count: {} message count: {} events: {}",
+                self.status,
+                self.event_count,
+                self.message_count,
+                self.events.len()
+            )?;
+        }
+        if level.data_values() {
+            for event in self.events.iter() {
+                write!(f, "\n{event:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for DeviceIdWriter<'_> {
+    fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
+        cursor.write_u8(MEI_TYPE_READ_DEVICE_ID)?;
+        cursor.write_u8(self.read_device_id_code)?;
+        cursor.write_u8(self.conformity_level)?;
+        match self.next_object_id {
+            Some(id) => {
+                cursor.write_u8(0xFF)?;
+                cursor.write_u8(id)?;
+            }
+            None => {
+                cursor.write_u8(0x00)?;
+                cursor.write_u8(0x00)?;
+            }
+        }
+        cursor.write_u8(self.objects.len() as u8)?;
+        for (id, value) in self.objects.iter() {
+            cursor.write_u8(*id)?;
+            cursor.write_u8(value.len() as u8)?;
+            cursor.write_bytes(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Loggable for DeviceIdWriter<'_> {
+    fn log(
+        &self,
+        _payload: &[u8],
+        level: crate::decode::AppDecodeLevel,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        if level.data_headers() {
+            write!(
+                f,
+                "conformity level: {:#04X} objects: {}",
+                self.conformity_level,
+                self.objects.len()
+            )?;
+            if let Some(id) = self.next_object_id {
+                write!(f, " next object id: {id:#04X}")?;
+            }
+        }
+        if level.data_values() {
+            for (id, value) in self.objects.iter() {
+                write!(f, "\nid: {id:#04X} value: {value:02X?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Serialize for &[bool] {
     fn serialize(&self, cursor: &mut WriteCursor) -> Result<(), RequestError> {
         // how many bytes should we have?
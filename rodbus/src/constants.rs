@@ -4,8 +4,13 @@ pub(crate) mod coil {
     pub(crate) const ON: u16 = 0xFF00;
     /// u16 representation of COIL == OFF when performing write single coil
     pub(crate) const OFF: u16 = 0x0000;
+    /// u16 value some clients send instead of ON, accepted by servers in lenient mode
+    pub(crate) const LENIENT_ON: u16 = 0x0001;
 }
 
+/// Reference type of every file record sub-request and sub-response
+pub(crate) const FILE_RECORD_REFERENCE_TYPE: u8 = 0x06;
+
 /// Limits of request sizes
 pub mod limits {
     /// Maximum count allowed in a read coils/discrete inputs request
@@ -16,6 +21,12 @@ pub mod limits {
     pub const MAX_WRITE_COILS_COUNT: u16 = 0x07B0;
     /// Maximum count allowed in a `write multiple registers` request
     pub const MAX_WRITE_REGISTERS_COUNT: u16 = 0x007B;
+    /// Maximum write count allowed in a `read/write multiple registers` request
+    pub const MAX_READ_WRITE_WRITE_REGISTERS_COUNT: u16 = 0x0079;
+    /// Maximum number of registers returned in a `read FIFO queue` response
+    pub const MAX_READ_FIFO_COUNT: u16 = 31;
+    /// Maximum record number allowed in a file record request
+    pub const MAX_FILE_RECORD_NUMBER: u16 = 0x270F;
 }
 
 /// Modbus exception codes
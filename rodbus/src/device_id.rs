@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+/// Category of objects requested by a `read device identification` request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceIdCategory {
+    /// Mandatory objects: vendor name, product code, and revision
+    Basic,
+    /// Basic objects plus the optional regular objects (0x03 to 0x06)
+    Regular,
+    /// Regular objects plus the device specific extended objects (0x80 to 0xFF)
+    Extended,
+}
+
+/// Well-known object ids of the device identification
+pub mod object_id {
+    /// Vendor name (basic)
+    pub const VENDOR_NAME: u8 = 0x00;
+    /// Product code (basic)
+    pub const PRODUCT_CODE: u8 = 0x01;
+    /// Major and minor revision (basic)
+    pub const MAJOR_MINOR_REVISION: u8 = 0x02;
+    /// Vendor URL (regular)
+    pub const VENDOR_URL: u8 = 0x03;
+    /// Product name (regular)
+    pub const PRODUCT_NAME: u8 = 0x04;
+    /// Model name (regular)
+    pub const MODEL_NAME: u8 = 0x05;
+    /// User application name (regular)
+    pub const USER_APPLICATION_NAME: u8 = 0x06;
+}
+
+/// Objects returned by one or more `read device identification` requests
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceIdentification {
+    /// Conformity level reported by the server
+    pub conformity_level: u8,
+    /// Object values by object id
+    pub objects: BTreeMap<u8, Vec<u8>>,
+}
+
+impl DeviceIdCategory {
+    pub(crate) fn read_device_id_code(self) -> u8 {
+        match self {
+            DeviceIdCategory::Basic => 0x01,
+            DeviceIdCategory::Regular => 0x02,
+            DeviceIdCategory::Extended => 0x03,
+        }
+    }
+}
+
+impl DeviceIdentification {
+    /// Retrieve the value of an object as a string
+    ///
+    /// Returns None if the object is missing or isn't valid UTF-8
+    pub fn get_str(&self, id: u8) -> Option<&str> {
+        self.objects
+            .get(&id)
+            .and_then(|x| std::str::from_utf8(x).ok())
+    }
+
+    /// Vendor name of the device
+    pub fn vendor_name(&self) -> Option<&str> {
+        self.get_str(object_id::VENDOR_NAME)
+    }
+
+    /// Product code of the device
+    pub fn product_code(&self) -> Option<&str> {
+        self.get_str(object_id::PRODUCT_CODE)
+    }
+
+    /// Major and minor revision of the device
+    pub fn major_minor_revision(&self) -> Option<&str> {
+        self.get_str(object_id::MAJOR_MINOR_REVISION)
+    }
+}
@@ -0,0 +1,293 @@
+/// Sub-functions of the diagnostics (0x08) function code
+///
+/// `Force Listen Only Mode` is intentionally omitted because the server never responds to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticSubFunction {
+    /// Echo the request data back to the client
+    ReturnQueryData,
+    /// Restart the serial port of the server, optionally clearing its communication event log
+    RestartCommunications,
+    /// Return the contents of the diagnostic register
+    ReturnDiagnosticRegister,
+    /// Change the character used as the end of message delimiter in ASCII mode
+    ChangeAsciiInputDelimiter,
+    /// Clear all counters and the diagnostic register
+    ClearCounters,
+    /// Return the number of messages detected on the bus
+    ReturnBusMessageCount,
+    /// Return the number of CRC errors detected on the bus
+    ReturnBusCommunicationErrorCount,
+    /// Return the number of exception responses returned by the server
+    ReturnBusExceptionErrorCount,
+    /// Return the number of messages addressed to the server
+    ReturnServerMessageCount,
+    /// Return the number of messages addressed to the server for which it returned no response
+    ReturnServerNoResponseCount,
+    /// Return the number of negative acknowledge exception responses returned by the server
+    ReturnServerNakCount,
+    /// Return the number of server device busy exception responses returned by the server
+    ReturnServerBusyCount,
+    /// Return the number of messages the server could not handle due to a character overrun
+    ReturnBusCharacterOverrunCount,
+    /// Clear the overrun error counter and reset the error flag
+    ClearOverrunCounter,
+}
+
+/// Counters that can be read using the diagnostics function code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticCounter {
+    /// Number of messages detected on the bus
+    BusMessage,
+    /// Number of CRC errors detected on the bus
+    BusCommunicationError,
+    /// Number of exception responses returned by the server
+    BusExceptionError,
+    /// Number of messages addressed to the server
+    ServerMessage,
+    /// Number of messages addressed to the server for which it returned no response
+    ServerNoResponse,
+    /// Number of negative acknowledge exception responses returned by the server
+    ServerNak,
+    /// Number of server device busy exception responses returned by the server
+    ServerBusy,
+    /// Number of messages the server could not handle due to a character overrun
+    BusCharacterOverrun,
+}
+
+impl From<DiagnosticSubFunction> for u16 {
+    fn from(value: DiagnosticSubFunction) -> Self {
+        match value {
+            DiagnosticSubFunction::ReturnQueryData => 0x00,
+            DiagnosticSubFunction::RestartCommunications => 0x01,
+            DiagnosticSubFunction::ReturnDiagnosticRegister => 0x02,
+            DiagnosticSubFunction::ChangeAsciiInputDelimiter => 0x03,
+            DiagnosticSubFunction::ClearCounters => 0x0A,
+            DiagnosticSubFunction::ReturnBusMessageCount => 0x0B,
+            DiagnosticSubFunction::ReturnBusCommunicationErrorCount => 0x0C,
+            DiagnosticSubFunction::ReturnBusExceptionErrorCount => 0x0D,
+            DiagnosticSubFunction::ReturnServerMessageCount => 0x0E,
+            DiagnosticSubFunction::ReturnServerNoResponseCount => 0x0F,
+            DiagnosticSubFunction::ReturnServerNakCount => 0x10,
+            DiagnosticSubFunction::ReturnServerBusyCount => 0x11,
+            DiagnosticSubFunction::ReturnBusCharacterOverrunCount => 0x12,
+            DiagnosticSubFunction::ClearOverrunCounter => 0x14,
+        }
+    }
+}
+
+impl DiagnosticCounter {
+    /// counter returned by a sub-function, if any
+    pub(crate) fn from_sub_function(value: u16) -> Option<Self> {
+        match value {
+            0x0B => Some(Self::BusMessage),
+            0x0C => Some(Self::BusCommunicationError),
+            0x0D => Some(Self::BusExceptionError),
+            0x0E => Some(Self::ServerMessage),
+            0x0F => Some(Self::ServerNoResponse),
+            0x10 => Some(Self::ServerNak),
+            0x11 => Some(Self::ServerBusy),
+            0x12 => Some(Self::BusCharacterOverrun),
+            _ => None,
+        }
+    }
+}
+
+impl From<DiagnosticCounter> for DiagnosticSubFunction {
+    fn from(value: DiagnosticCounter) -> Self {
+        match value {
+            DiagnosticCounter::BusMessage => Self::ReturnBusMessageCount,
+            DiagnosticCounter::BusCommunicationError => Self::ReturnBusCommunicationErrorCount,
+            DiagnosticCounter::BusExceptionError => Self::ReturnBusExceptionErrorCount,
+            DiagnosticCounter::ServerMessage => Self::ReturnServerMessageCount,
+            DiagnosticCounter::ServerNoResponse => Self::ReturnServerNoResponseCount,
+            DiagnosticCounter::ServerNak => Self::ReturnServerNakCount,
+            DiagnosticCounter::ServerBusy => Self::ReturnServerBusyCount,
+            DiagnosticCounter::BusCharacterOverrun => Self::ReturnBusCharacterOverrunCount,
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticSubFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({:#06X})", self, u16::from(*self))
+    }
+}
+
+/// Event recorded in the communication event log of a serial line server
+///
+/// Events are returned by a `get comm event log` request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommEvent {
+    /// The server received a message
+    Receive(ReceiveEvent),
+    /// The server completed processing of a message
+    Send(SendEvent),
+    /// The server entered listen only mode
+    EnteredListenOnlyMode,
+    /// The server restarted its communication port
+    CommunicationRestart,
+    /// The event byte does not match any event defined in the specification
+    Unknown(u8),
+}
+
+/// Flags of a [CommEvent::Receive] event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReceiveEvent {
+    /// A communication error occurred
+    pub communication_error: bool,
+    /// A character overrun occurred
+    pub character_overrun: bool,
+    /// The server was in listen only mode
+    pub listen_only_mode: bool,
+    /// The message was a broadcast
+    pub broadcast_received: bool,
+}
+
+/// Flags of a [CommEvent::Send] event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SendEvent {
+    /// An exception response with code 1 to 3 was sent
+    pub read_exception: bool,
+    /// An exception response with code 4 was sent
+    pub server_abort_exception: bool,
+    /// An exception response with code 5 or 6 was sent
+    pub server_busy_exception: bool,
+    /// An exception response with code 7 was sent
+    pub server_nak_exception: bool,
+    /// A write timeout occurred
+    pub write_timeout: bool,
+    /// The server was in listen only mode
+    pub listen_only_mode: bool,
+}
+
+/// Communication event log returned by a `get comm event log` request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommEventLog {
+    /// Status word, 0xFFFF if the server is still processing a previous command
+    pub status: u16,
+    /// Number of successfully completed messages since the last restart or counter reset
+    pub event_count: u16,
+    /// Number of messages processed since the last restart or counter reset
+    pub message_count: u16,
+    /// Events from the most recent to the oldest
+    pub events: Vec<CommEvent>,
+}
+
+mod event {
+    pub(crate) const RECEIVE: u8 = 0x80;
+    pub(crate) const SEND: u8 = 0x40;
+    pub(crate) const ENTERED_LISTEN_ONLY_MODE: u8 = 0x04;
+    pub(crate) const COMMUNICATION_RESTART: u8 = 0x00;
+}
+
+fn bit(value: u8, index: u8) -> bool {
+    value & (1 << index) != 0
+}
+
+impl From<u8> for CommEvent {
+    fn from(value: u8) -> Self {
+        if value & event::RECEIVE != 0 {
+            return CommEvent::Receive(ReceiveEvent {
+                communication_error: bit(value, 1),
+                character_overrun: bit(value, 4),
+                listen_only_mode: bit(value, 5),
+                broadcast_received: bit(value, 6),
+            });
+        }
+        if value & event::SEND != 0 {
+            return CommEvent::Send(SendEvent {
+                read_exception: bit(value, 0),
+                server_abort_exception: bit(value, 1),
+                server_busy_exception: bit(value, 2),
+                server_nak_exception: bit(value, 3),
+                write_timeout: bit(value, 4),
+                listen_only_mode: bit(value, 5),
+            });
+        }
+        match value {
+            event::ENTERED_LISTEN_ONLY_MODE => CommEvent::EnteredListenOnlyMode,
+            event::COMMUNICATION_RESTART => CommEvent::CommunicationRestart,
+            _ => CommEvent::Unknown(value),
+        }
+    }
+}
+
+impl From<CommEvent> for u8 {
+    fn from(value: CommEvent) -> Self {
+        fn set(flag: bool, index: u8) -> u8 {
+            if flag {
+                1 << index
+            } else {
+                0
+            }
+        }
+
+        match value {
+            CommEvent::Receive(x) => {
+                event::RECEIVE
+                    | set(x.communication_error, 1)
+                    | set(x.character_overrun, 4)
+                    | set(x.listen_only_mode, 5)
+                    | set(x.broadcast_received, 6)
+            }
+            CommEvent::Send(x) => {
+                event::SEND
+                    | set(x.read_exception, 0)
+                    | set(x.server_abort_exception, 1)
+                    | set(x.server_busy_exception, 2)
+                    | set(x.server_nak_exception, 3)
+                    | set(x.write_timeout, 4)
+                    | set(x.listen_only_mode, 5)
+            }
+            CommEvent::EnteredListenOnlyMode => event::ENTERED_LISTEN_ONLY_MODE,
+            CommEvent::CommunicationRestart => event::COMMUNICATION_RESTART,
+            CommEvent::Unknown(x) => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_round_trip_through_sub_functions() {
+        for value in 0..=u16::MAX {
+            if let Some(counter) = DiagnosticCounter::from_sub_function(value) {
+                assert_eq!(u16::from(DiagnosticSubFunction::from(counter)), value);
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_events() {
+        assert_eq!(
+            CommEvent::from(0xC2),
+            CommEvent::Receive(ReceiveEvent {
+                communication_error: true,
+                broadcast_received: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            CommEvent::from(0x44),
+            CommEvent::Send(SendEvent {
+                server_busy_exception: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(CommEvent::from(0x04), CommEvent::EnteredListenOnlyMode);
+        assert_eq!(CommEvent::from(0x00), CommEvent::CommunicationRestart);
+        assert_eq!(CommEvent::from(0x01), CommEvent::Unknown(0x01));
+    }
+
+    #[test]
+    fn events_round_trip() {
+        // skip receive events with undefined bits set since those bits don't survive decoding
+        for value in 0..=u8::MAX {
+            if value & 0x8D == 0x80 || value & 0xC0 == 0x40 || value & 0xC0 == 0x00 {
+                assert_eq!(u8::from(CommEvent::from(value)), value);
+            }
+        }
+    }
+}
@@ -14,6 +14,7 @@ impl std::fmt::Display for Shutdown {
 
 /// Top level error type for the client API
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum RequestError {
     /// An I/O error occurred
     Io(::std::io::ErrorKind),
@@ -35,6 +36,11 @@ pub enum RequestError {
     NoConnection,
     /// Task processing requests has been shutdown
     Shutdown,
+    /// The request queue of the channel is full, see [`crate::client::Channel::set_fail_when_queue_full`]
+    QueueFull,
+    /// A value read back after a write differs from the value written, see
+    /// [`crate::client::Channel::write_and_verify_registers`]
+    WriteMismatch(WriteMismatch),
 }
 
 impl std::error::Error for RequestError {}
@@ -51,6 +57,8 @@ impl std::fmt::Display for RequestError {
             RequestError::ResponseTimeout => f.write_str("response timeout"),
             RequestError::NoConnection => f.write_str("no connection to server"),
             RequestError::Shutdown => f.write_str("channel shutdown"),
+            RequestError::QueueFull => f.write_str("request queue is full"),
+            RequestError::WriteMismatch(err) => err.fmt(f),
         }
     }
 }
@@ -68,6 +76,12 @@ impl From<WriteError> for RequestError {
     }
 }
 
+impl From<WriteMismatch> for RequestError {
+    fn from(err: WriteMismatch) -> Self {
+        RequestError::WriteMismatch(err)
+    }
+}
+
 impl From<std::io::Error> for RequestError {
     fn from(err: std::io::Error) -> Self {
         RequestError::Io(err.kind())
@@ -201,6 +215,7 @@ impl std::fmt::Display for InternalError {
 
 /// Errors that occur while parsing a frame off a stream (TCP or serial)
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Ord, Eq)]
+#[non_exhaustive]
 pub enum FrameParseError {
     /// Received TCP frame with the length field set to zero
     MbapLengthZero,
@@ -212,6 +227,12 @@ pub enum FrameParseError {
     UnknownFunctionCode(u8),
     /// RTU CRC validation failed
     CrcValidationFailure(u16, u16), // received CRC, expected CRC
+    /// ASCII frame contained a character that isn't a hexadecimal digit
+    InvalidAsciiCharacter(u8),
+    /// ASCII frame contained an odd number of characters or too few to be valid
+    InvalidAsciiFrameLength(usize),
+    /// ASCII LRC validation failed
+    LrcValidationFailure(u8, u8), // received LRC, expected LRC
 }
 
 impl std::error::Error for FrameParseError {}
@@ -238,17 +259,34 @@ impl std::fmt::Display for FrameParseError {
                     "Received incorrect CRC value {received:#06X}, expected {expected:#06X}"
                 )
             }
+            FrameParseError::InvalidAsciiCharacter(value) => {
+                write!(
+                    f,
+                    "Received non-hexadecimal character in ASCII frame: {value:#04X}"
+                )
+            }
+            FrameParseError::InvalidAsciiFrameLength(length) => write!(
+                f,
+                "Received ASCII frame with an invalid number of characters: {length}"
+            ),
+            FrameParseError::LrcValidationFailure(received, expected) => write!(
+                f,
+                "Received incorrect LRC value {received:#04X}, expected {expected:#04X}"
+            ),
         }
     }
 }
 
 /// Errors that occur while parsing requests and responses
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Ord, Eq)]
+#[non_exhaustive]
 pub enum AduParseError {
     /// Response is too short to be valid
     InsufficientBytes,
     /// Byte count doesn't match the actual number of bytes present
     InsufficientBytesForByteCount(usize, usize), // count / remaining
+    /// Byte count doesn't match the quantity of objects that were requested
+    ByteCountMismatch(usize, usize), // expected / received
     /// Response contains extra trailing bytes
     TrailingBytes(usize),
     /// Parameter expected to be echoed in the reply did not match
@@ -257,6 +295,14 @@ pub enum AduParseError {
     UnknownResponseFunction(u8, u8, u8), // actual, expected, expected error
     /// Bad value for the coil state
     UnknownCoilState(u16),
+    /// FIFO count exceeds the maximum allowed by the specification
+    FifoCountTooLarge(u16, u16), // count / max
+    /// File record reference type is not the value required by the specification
+    BadReferenceType(u8),
+    /// Device identification response asked to continue from an object id that doesn't make progress
+    BadNextObjectId(u8),
+    /// Unit id of the response doesn't match the one of the request
+    UnitIdMismatch(u8, u8), // actual / expected
 }
 
 impl std::error::Error for AduParseError {}
@@ -269,6 +315,10 @@ impl std::fmt::Display for AduParseError {
                 f,
                 "byte count ({count}) doesn't match the actual number of bytes remaining ({remaining})"
             ),
+            AduParseError::ByteCountMismatch(expected, received) => write!(
+                f,
+                "byte count ({received}) doesn't match the expected byte count for the requested quantity ({expected})"
+            ),
             AduParseError::TrailingBytes(remaining) => {
                 write!(f, "response contains {remaining} extra trailing bytes")
             }
@@ -283,10 +333,51 @@ impl std::fmt::Display for AduParseError {
                 f,
                 "received coil state with unspecified value: 0x{value:04X}"
             ),
+            AduParseError::FifoCountTooLarge(count, max) => write!(
+                f,
+                "FIFO count ({count}) exceeds the maximum allowed ({max})"
+            ),
+            AduParseError::BadReferenceType(value) => {
+                write!(f, "received file record reference type: 0x{value:02X}")
+            }
+            AduParseError::BadNextObjectId(value) => write!(
+                f,
+                "device identification response continues from an object id that was already read: 0x{value:02X}"
+            ),
+            AduParseError::UnitIdMismatch(actual, expected) => write!(
+                f,
+                "received a response from unit id {actual} to a request for unit id {expected}"
+            ),
         }
     }
 }
 
+/// Value read back after a write that differs from the value written, e.g. because the
+/// device clamped it or silently ignored the write
+///
+/// Coils are reported as 0 or 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteMismatch {
+    /// Address of the first point whose value differs
+    pub address: u16,
+    /// Value that was written
+    pub written: u16,
+    /// Value that was read back
+    pub read: u16,
+}
+
+impl std::error::Error for WriteMismatch {}
+
+impl std::fmt::Display for WriteMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "value read back from address {} is {} instead of the value written: {}",
+            self.address, self.read, self.written
+        )
+    }
+}
+
 /// Errors that result because of bad request parameter
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InvalidRequest {
@@ -296,6 +387,14 @@ pub enum InvalidRequest {
     CountTooBigForU16(usize),
     /// Count too big for specific request
     CountTooBigForType(u16, u16),
+    /// File record number exceeds the maximum allowed
+    RecordNumberTooLarge(u16, u16), // record / max
+    /// Request or its expected response would not fit in a single PDU
+    PduTooLarge(usize, usize), // size / max
+    /// Function code cannot be used in a request
+    BadFunctionCode(u8),
+    /// Only write requests can be sent to the broadcast address
+    BroadcastNotSupported,
 }
 
 impl std::error::Error for InvalidRequest {}
@@ -313,6 +412,21 @@ impl std::fmt::Display for InvalidRequest {
                 f,
                 "the request count of {count} exceeds maximum allowed count of {max} for this type"
             ),
+            InvalidRequest::RecordNumberTooLarge(record, max) => write!(
+                f,
+                "the record number {record} exceeds the maximum allowed record number of {max}"
+            ),
+            InvalidRequest::PduTooLarge(size, max) => write!(
+                f,
+                "the request or its response would require a PDU of {size} bytes which exceeds the maximum of {max}"
+            ),
+            InvalidRequest::BadFunctionCode(value) => write!(
+                f,
+                "function code {value:#04X} has the exception bit set and cannot be used in a request"
+            ),
+            InvalidRequest::BroadcastNotSupported => {
+                f.write_str("only write requests can be sent to the broadcast address")
+            }
         }
     }
 }
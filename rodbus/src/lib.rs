@@ -112,6 +112,8 @@
 /// Current version of the library
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Blocking client API for applications that don't use async/await
+pub mod blocking;
 /// Client API
 pub mod client;
 /// Public constant values related to the Modbus specification
@@ -123,6 +125,8 @@ pub mod server;
 // modules that are re-exported
 pub(crate) mod channel;
 pub(crate) mod decode;
+pub(crate) mod device_id;
+pub(crate) mod diagnostic;
 pub(crate) mod error;
 pub(crate) mod exception;
 pub(crate) mod maybe_async;
@@ -133,6 +137,8 @@ pub(crate) mod types;
 
 // re-exports
 pub use crate::decode::*;
+pub use crate::device_id::*;
+pub use crate::diagnostic::*;
 pub use crate::error::*;
 pub use crate::exception::*;
 pub use crate::maybe_async::*;
@@ -140,7 +146,13 @@ pub use crate::retry::*;
 #[cfg(feature = "serial")]
 pub use crate::serial::*;
 pub use crate::types::*;
+/// `rustls` version used by the TLS channels, for building custom configurations
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls;
 
 // internal modules
 mod common;
 mod tcp;
+mod udp;
+#[cfg(unix)]
+mod unix;
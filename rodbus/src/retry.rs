@@ -1,5 +1,9 @@
 use std::time::Duration;
 
+use crate::common::random::random_f64;
+use crate::error::RequestError;
+use crate::exception::ExceptionCode;
+
 /// Trait that controls how the channel retries failed connect (TCP/TLS) or open (serial) attempts
 pub trait RetryStrategy: Send {
     /// Reset internal state. Called when a connection is successful or a port is opened
@@ -20,6 +24,31 @@ pub fn doubling_retry_strategy(min: Duration, max: Duration) -> Box<dyn RetryStr
     Doubling::create(min, max)
 }
 
+/// Return a [`RetryStrategy`] that multiplies the delay by `factor` after each failed
+/// attempt, up to `max`
+///
+/// Each delay is then randomized by up to `jitter` times its value in either direction, so that
+/// many clients that lost their connection at the same time don't reconnect in lockstep.
+/// `factor` is at least 1 and `jitter` is between 0 and 1, other values are clamped.
+pub fn exponential_retry_strategy(
+    min: Duration,
+    max: Duration,
+    factor: f64,
+    jitter: f64,
+) -> Box<dyn RetryStrategy> {
+    Box::new(Exponential {
+        min,
+        max,
+        factor: factor.max(1.0),
+        jitter: if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        },
+        current: min,
+    })
+}
+
 struct Doubling {
     min: Duration,
     max: Duration,
@@ -51,3 +80,154 @@ impl RetryStrategy for Doubling {
         self.min
     }
 }
+
+struct Exponential {
+    min: Duration,
+    max: Duration,
+    factor: f64,
+    jitter: f64,
+    current: Duration,
+}
+
+impl Exponential {
+    fn with_jitter(&self, delay: Duration) -> Duration {
+        // uniform in [1 - jitter, 1 + jitter)
+        let scale = 1.0 + self.jitter * (2.0 * random_f64() - 1.0);
+        // a delay near Duration::MAX may not be representable once scaled up
+        Duration::try_from_secs_f64(delay.as_secs_f64() * scale).unwrap_or(self.max)
+    }
+}
+
+impl RetryStrategy for Exponential {
+    fn reset(&mut self) {
+        self.current = self.min;
+    }
+
+    fn after_failed_connect(&mut self) -> Duration {
+        let ret = self.current;
+        let next = self.current.as_secs_f64() * self.factor;
+        self.current = if next < self.max.as_secs_f64() {
+            Duration::from_secs_f64(next)
+        } else {
+            self.max
+        };
+        self.with_jitter(ret)
+    }
+
+    fn after_disconnect(&mut self) -> Duration {
+        self.with_jitter(self.min)
+    }
+}
+
+/// Policy that retries the failed requests of a channel before returning the result to the
+/// caller, e.g. to ride out a flaky radio link
+///
+/// Retries are sent on the same connection and delay the requests queued behind them. Requests
+/// that fail because the connection is lost are never retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestRetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: usize,
+    /// Delay before the first retry, doubled for each subsequent retry
+    pub min_backoff: Duration,
+    /// Maximum delay between two attempts
+    pub max_backoff: Duration,
+    /// Retry requests that aren't answered within their timeout
+    pub retry_timeouts: bool,
+    /// Exceptions returned by the server that cause a request to be retried
+    pub retry_exceptions: Vec<ExceptionCode>,
+}
+
+impl RequestRetryPolicy {
+    /// Create a policy that makes up to `max_attempts` attempts, retrying response timeouts and
+    /// [`ExceptionCode::ServerDeviceBusy`]
+    pub fn new(max_attempts: usize, min_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            min_backoff,
+            max_backoff,
+            retry_timeouts: true,
+            retry_exceptions: vec![ExceptionCode::ServerDeviceBusy],
+        }
+    }
+
+    /// true if a request that failed with `err` should be sent again
+    pub(crate) fn is_retryable(&self, err: RequestError) -> bool {
+        match err {
+            RequestError::ResponseTimeout => self.retry_timeouts,
+            RequestError::Exception(ex) => self.retry_exceptions.contains(&ex),
+            _ => false,
+        }
+    }
+
+    /// delay before the retry number `retry`, starting at 1
+    pub(crate) fn backoff(&self, retry: usize) -> Duration {
+        let exponent = u32::try_from(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.min_backoff
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_strategy_grows_by_factor_up_to_the_maximum() {
+        let mut strategy =
+            exponential_retry_strategy(Duration::from_secs(1), Duration::from_secs(5), 3.0, 0.0);
+        assert_eq!(strategy.after_failed_connect(), Duration::from_secs(1));
+        assert_eq!(strategy.after_failed_connect(), Duration::from_secs(3));
+        assert_eq!(strategy.after_failed_connect(), Duration::from_secs(5));
+        assert_eq!(strategy.after_failed_connect(), Duration::from_secs(5));
+        assert_eq!(strategy.after_disconnect(), Duration::from_secs(1));
+        strategy.reset();
+        assert_eq!(strategy.after_failed_connect(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn exponential_strategy_applies_jitter() {
+        let mut strategy =
+            exponential_retry_strategy(Duration::from_secs(10), Duration::from_secs(10), 2.0, 0.5);
+        for _ in 0..100 {
+            let delay = strategy.after_failed_connect();
+            assert!(
+                delay >= Duration::from_secs(5) && delay <= Duration::from_secs(15),
+                "{delay:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn exponential_strategy_jitter_doesnt_overflow() {
+        let mut strategy =
+            exponential_retry_strategy(Duration::from_secs(1), Duration::MAX, 1000.0, 1.0);
+        for _ in 0..100 {
+            strategy.after_failed_connect();
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy =
+            RequestRetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn only_retries_selected_errors() {
+        let mut policy = RequestRetryPolicy::new(3, Duration::ZERO, Duration::ZERO);
+        assert!(policy.is_retryable(RequestError::ResponseTimeout));
+        assert!(policy.is_retryable(RequestError::Exception(ExceptionCode::ServerDeviceBusy)));
+        assert!(!policy.is_retryable(RequestError::Exception(ExceptionCode::IllegalDataAddress)));
+        assert!(!policy.is_retryable(RequestError::NoConnection));
+
+        policy.retry_timeouts = false;
+        assert!(!policy.is_retryable(RequestError::ResponseTimeout));
+    }
+}
@@ -0,0 +1,356 @@
+use crate::common::buffer::ReadBuffer;
+use crate::common::frame::{
+    Frame, FrameDestination, FrameHeader, FrameInfo, FrameType, FunctionField,
+};
+use crate::common::traits::Serialize;
+use crate::decode::FrameDecodeLevel;
+use crate::error::{FrameParseError, RequestError};
+use crate::types::UnitId;
+
+use scursor::WriteCursor;
+
+pub(crate) mod constants {
+    pub(crate) const START: u8 = b':';
+    pub(crate) const CR: u8 = b'\r';
+    pub(crate) const LF: u8 = b'\n';
+    pub(crate) const ADDRESS_LENGTH: usize = 1;
+    pub(crate) const LRC_LENGTH: usize = 1;
+    /// address, PDU and LRC before hex encoding
+    pub(crate) const MAX_BINARY_LENGTH: usize =
+        ADDRESS_LENGTH + crate::common::frame::constants::MAX_ADU_LENGTH + LRC_LENGTH;
+    /// start character, hex encoded address, PDU and LRC, CR/LF
+    pub(crate) const MAX_FRAME_LENGTH: usize = 1 + 2 * MAX_BINARY_LENGTH + 2;
+    /// the formatter keeps a binary copy of the PDU body after the frame for logging
+    pub(crate) const MAX_FORMAT_LENGTH: usize =
+        MAX_FRAME_LENGTH + crate::common::frame::constants::MAX_ADU_LENGTH;
+}
+
+/// Longitudinal redundancy check: two's complement of the sum of the bytes
+fn lrc(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, x| sum.wrapping_add(*x))
+        .wrapping_neg()
+}
+
+fn to_hex(value: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    [
+        DIGITS[(value >> 4) as usize],
+        DIGITS[(value & 0x0F) as usize],
+    ]
+}
+
+fn from_hex(c: u8) -> Result<u8, FrameParseError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        // the spec requires upper case, but accept lower case from lenient devices
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        _ => Err(FrameParseError::InvalidAsciiCharacter(c)),
+    }
+}
+
+pub(crate) struct AsciiParser {
+    /// longest function code and body that is accepted
+    pub(crate) max_adu_length: usize,
+}
+
+impl AsciiParser {
+    pub(crate) fn new() -> Self {
+        Self {
+            max_adu_length: crate::common::frame::constants::MAX_ADU_LENGTH,
+        }
+    }
+
+    /// start character, hex encoded address, PDU and LRC, CR/LF
+    pub(crate) fn max_frame_length(&self) -> usize {
+        1 + 2 * (constants::ADDRESS_LENGTH + self.max_adu_length + constants::LRC_LENGTH) + 2
+    }
+
+    pub(crate) fn parse(
+        &mut self,
+        cursor: &mut ReadBuffer,
+        decode_level: FrameDecodeLevel,
+    ) -> Result<Option<Frame>, RequestError> {
+        // discard anything that precedes the start of a frame
+        while !cursor.is_empty() && cursor.peek_at(0)? != constants::START {
+            cursor.read_u8()?;
+        }
+
+        let max_frame_length = self.max_frame_length();
+        let length = match Self::find_end(cursor)? {
+            Some(length) => length,
+            None => {
+                if cursor.len() >= max_frame_length {
+                    return Err(RequestError::BadFrame(FrameParseError::FrameLengthTooBig(
+                        cursor.len(),
+                        max_frame_length,
+                    )));
+                }
+                return Ok(None);
+            }
+        };
+
+        if length > max_frame_length {
+            return Err(RequestError::BadFrame(FrameParseError::FrameLengthTooBig(
+                length,
+                max_frame_length,
+            )));
+        }
+
+        // decode everything between the start character and CR/LF
+        let chars = cursor.read(length)?;
+        let chars = &chars[1..length - 2];
+        if chars.len() % 2 != 0
+            || chars.len() < 2 * (constants::ADDRESS_LENGTH + 1 + constants::LRC_LENGTH)
+        {
+            return Err(RequestError::BadFrame(
+                FrameParseError::InvalidAsciiFrameLength(chars.len()),
+            ));
+        }
+
+        let mut binary = vec![0u8; chars.len() / 2];
+        for (dest, pair) in binary.iter_mut().zip(chars.chunks_exact(2)) {
+            *dest = (from_hex(pair[0])? << 4) | from_hex(pair[1])?;
+        }
+
+        let (data, received_lrc) = binary.split_at(binary.len() - constants::LRC_LENGTH);
+        let received_lrc = received_lrc[0];
+        let expected_lrc = lrc(data);
+        if received_lrc != expected_lrc {
+            return Err(RequestError::BadFrame(
+                FrameParseError::LrcValidationFailure(received_lrc, expected_lrc),
+            ));
+        }
+
+        let unit_id = UnitId::new(data[0]);
+        let destination = if unit_id == UnitId::broadcast() {
+            FrameDestination::Broadcast
+        } else {
+            FrameDestination::UnitId(unit_id)
+        };
+
+        let frame = {
+            let mut frame = Frame::new(FrameHeader::new_rtu_header(destination));
+            frame.set(&data[constants::ADDRESS_LENGTH..]);
+            frame
+        };
+
+        if decode_level.enabled() {
+            tracing::info!(
+                "ASCII RX - {}",
+                AsciiDisplay::new(decode_level, destination, frame.payload(), received_lrc)
+            );
+        }
+
+        Ok(Some(frame))
+    }
+
+    // returns the length of the frame including the start character and CR/LF
+    fn find_end(cursor: &mut ReadBuffer) -> Result<Option<usize>, RequestError> {
+        for i in 1..cursor.len() {
+            if cursor.peek_at(i - 1)? == constants::CR && cursor.peek_at(i)? == constants::LF {
+                return Ok(Some(i + 1));
+            }
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn reset(&mut self) {}
+}
+
+pub(crate) fn format_ascii_pdu(
+    cursor: &mut WriteCursor,
+    header: FrameHeader,
+    function: FunctionField,
+    msg: &dyn Serialize,
+) -> Result<FrameInfo, RequestError> {
+    // serialize the binary frame first so that the LRC can be computed
+    let mut binary = [0u8; constants::MAX_BINARY_LENGTH];
+    let (binary_length, body_length) = {
+        let mut binary_cursor = WriteCursor::new(&mut binary);
+        binary_cursor.write_u8(header.destination.value())?;
+        binary_cursor.write_u8(function.get_value())?;
+        let start_pdu_body = binary_cursor.position();
+        msg.serialize(&mut binary_cursor)?;
+        let end_pdu_body = binary_cursor.position();
+        (end_pdu_body, end_pdu_body - start_pdu_body)
+    };
+    let binary = &binary[0..binary_length];
+    let lrc = lrc(binary);
+
+    cursor.write_u8(constants::START)?;
+    for byte in binary.iter().chain(std::iter::once(&lrc)) {
+        cursor.write_bytes(&to_hex(*byte))?;
+    }
+    cursor.write_u8(constants::CR)?;
+    cursor.write_u8(constants::LF)?;
+    let end_frame = cursor.position();
+
+    // keep a binary copy of the PDU body after the frame so it can be logged
+    cursor.write_bytes(&binary[binary_length - body_length..])?;
+    cursor.seek_to(end_frame)?;
+
+    Ok(FrameInfo::new(
+        FrameType::Ascii(header.destination, lrc),
+        end_frame..end_frame + body_length,
+    ))
+}
+
+pub(crate) struct AsciiDisplay<'a> {
+    level: FrameDecodeLevel,
+    destination: FrameDestination,
+    payload: &'a [u8],
+    lrc: u8,
+}
+
+impl<'a> AsciiDisplay<'a> {
+    pub(crate) fn new(
+        level: FrameDecodeLevel,
+        destination: FrameDestination,
+        payload: &'a [u8],
+        lrc: u8,
+    ) -> Self {
+        AsciiDisplay {
+            level,
+            destination,
+            payload,
+            lrc,
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for AsciiDisplay<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "dest: {} lrc: {:#04X} (payload len = {})",
+            self.destination,
+            self.lrc,
+            self.payload.len(),
+        )?;
+        if self.level.payload_enabled() {
+            crate::common::phys::format_bytes(f, self.payload)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Poll;
+
+    use crate::common::frame::{FrameWriter, FramedReader};
+    use crate::common::function::FunctionCode;
+    use crate::common::phys::PhysLayer;
+    use crate::types::AddressRange;
+    use crate::DecodeLevel;
+
+    use super::*;
+
+    // example from the Modbus over serial line specification
+    const READ_HOLDING_REGISTERS_REQUEST: &[u8] = b":1103006B00037E\r\n";
+
+    fn parse(bytes: &[u8]) -> Result<Frame, RequestError> {
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut layer = PhysLayer::new_mock(io);
+        let mut reader = FramedReader::ascii();
+        let mut task =
+            tokio_test::task::spawn(reader.next_frame(&mut layer, DecodeLevel::nothing()));
+
+        io_handle.read(bytes);
+        match task.poll() {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("Task not ready"),
+        }
+    }
+
+    #[test]
+    fn computes_lrc() {
+        assert_eq!(lrc(&[0x11, 0x03, 0x00, 0x6B, 0x00, 0x03]), 0x7E);
+    }
+
+    #[test]
+    fn formats_request() {
+        let mut writer = FrameWriter::ascii();
+        let bytes = writer
+            .format_request(
+                FrameHeader::new_rtu_header(FrameDestination::new_unit_id(0x11)),
+                FunctionField::Valid(FunctionCode::ReadHoldingRegisters),
+                &AddressRange::try_from(0x006B, 3).unwrap(),
+                DecodeLevel::nothing(),
+            )
+            .unwrap();
+        assert_eq!(bytes, READ_HOLDING_REGISTERS_REQUEST);
+    }
+
+    #[test]
+    fn parses_request() {
+        let frame = parse(READ_HOLDING_REGISTERS_REQUEST).unwrap();
+        assert_eq!(frame.header.tx_id, None);
+        assert_eq!(
+            frame.header.destination,
+            FrameDestination::new_unit_id(0x11)
+        );
+        assert_eq!(frame.payload(), &[0x03, 0x00, 0x6B, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn skips_bytes_before_start_character() {
+        let frame = parse(b"\x00garbage:1103006B00037E\r\n").unwrap();
+        assert_eq!(frame.payload(), &[0x03, 0x00, 0x6B, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn parses_frame_byte_per_byte() {
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut layer = PhysLayer::new_mock(io);
+        let mut reader = FramedReader::ascii();
+        let mut task =
+            tokio_test::task::spawn(reader.next_frame(&mut layer, DecodeLevel::nothing()));
+
+        let (last, rest) = READ_HOLDING_REGISTERS_REQUEST.split_last().unwrap();
+        for byte in rest {
+            io_handle.read(&[*byte]);
+            assert!(matches!(task.poll(), Poll::Pending));
+        }
+        io_handle.read(&[*last]);
+        match task.poll() {
+            Poll::Ready(frame) => {
+                assert_eq!(frame.unwrap().payload(), &[0x03, 0x00, 0x6B, 0x00, 0x03])
+            }
+            Poll::Pending => panic!("Task not ready"),
+        }
+    }
+
+    #[test]
+    fn fails_on_wrong_lrc() {
+        assert_eq!(
+            parse(b":1103006B0003FF\r\n").err(),
+            Some(RequestError::BadFrame(
+                FrameParseError::LrcValidationFailure(0xFF, 0x7E)
+            ))
+        );
+    }
+
+    #[test]
+    fn fails_on_non_hex_character() {
+        assert_eq!(
+            parse(b":1103006B00G37E\r\n").err(),
+            Some(RequestError::BadFrame(
+                FrameParseError::InvalidAsciiCharacter(b'G')
+            ))
+        );
+    }
+
+    #[test]
+    fn fails_on_odd_number_of_characters() {
+        assert_eq!(
+            parse(b":1103006B00037\r\n").err(),
+            Some(RequestError::BadFrame(
+                FrameParseError::InvalidAsciiFrameLength(13)
+            ))
+        );
+    }
+}
@@ -1,16 +1,17 @@
 use crate::common::phys::PhysLayer;
 use crate::decode::DecodeLevel;
 use crate::serial::SerialSettings;
+use crate::types::Framing;
 
-use crate::client::message::Command;
+use crate::client::message::CommandReceiver;
 use crate::client::task::{ClientLoop, SessionError, StateChange};
 use crate::client::{Listener, PortState, RetryStrategy};
-use crate::common::frame::{FrameWriter, FramedReader};
 use crate::error::Shutdown;
 
 pub(crate) struct SerialChannelTask {
     path: String,
     serial_settings: SerialSettings,
+    framing: Framing,
     retry: Box<dyn RetryStrategy>,
     client_loop: ClientLoop,
     listener: Box<dyn Listener<PortState>>,
@@ -20,7 +21,8 @@ impl SerialChannelTask {
     pub(crate) fn new(
         path: &str,
         serial_settings: SerialSettings,
-        rx: crate::channel::Receiver<Command>,
+        framing: Framing,
+        rx: CommandReceiver,
         retry: Box<dyn RetryStrategy>,
         decode: DecodeLevel,
         listener: Box<dyn Listener<PortState>>,
@@ -28,13 +30,9 @@ impl SerialChannelTask {
         Self {
             path: path.to_string(),
             serial_settings,
+            framing,
             retry,
-            client_loop: ClientLoop::new(
-                rx,
-                FrameWriter::rtu(),
-                FramedReader::rtu_response(),
-                decode,
-            ),
+            client_loop: ClientLoop::new(rx, framing.writer(), framing.response_reader(), decode),
             listener,
         }
     }
@@ -64,7 +62,7 @@ impl SerialChannelTask {
     }
 
     pub(crate) async fn try_open_and_run(&mut self) -> Result<(), StateChange> {
-        match crate::serial::open(self.path.as_str(), self.serial_settings) {
+        match crate::serial::open(self.path.as_str(), self.serial_settings, self.framing) {
             Err(err) => {
                 let delay = self.retry.after_failed_connect();
                 self.listener.update(PortState::Wait(delay)).get().await;
@@ -74,11 +72,17 @@ impl SerialChannelTask {
             Ok(serial) => {
                 self.retry.reset();
                 self.listener.update(PortState::Open).get().await;
-                let mut phys = PhysLayer::new_serial(serial);
+                let mut phys =
+                    PhysLayer::new_serial(serial, self.serial_settings.inter_frame_delay);
                 tracing::info!("serial port open");
-                match self.client_loop.run(&mut phys).await {
+                let err = self.client_loop.run(&mut phys).await;
+                // close the port right away instead of keeping it while waiting
+                drop(phys);
+                match err {
                     // the mpsc was closed, end the task
                     SessionError::Shutdown => Err(StateChange::Shutdown),
+                    // reconnect once the next request is made
+                    SessionError::Idle => Ok(()),
                     // don't wait, we're disabled
                     SessionError::Disabled => Ok(()),
                     // wait before retrying
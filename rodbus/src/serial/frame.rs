@@ -32,6 +32,7 @@ enum ParseState {
     Start,
     ReadFullBody(FrameDestination, usize), // unit_id, length of rest
     ReadToOffsetForLength(FrameDestination, usize), // unit_id, length to length
+    ReadDeviceIdObjects(FrameDestination), // unit_id
 }
 
 #[derive(Clone, Copy)]
@@ -40,6 +41,8 @@ enum LengthMode {
     Fixed(usize),
     /// You need to read X more bytes. The last byte contains the number of extra bytes to read after that
     Offset(usize),
+    /// The length is the sum of the lengths of the device identification objects
+    DeviceIdObjects,
     /// Unknown function code, can't determine the size
     Unknown,
 }
@@ -47,6 +50,8 @@ enum LengthMode {
 pub(crate) struct RtuParser {
     state: ParseState,
     parser_type: ParserType,
+    /// longest function code and body that is accepted
+    pub(crate) max_adu_length: usize,
 }
 
 impl RtuParser {
@@ -54,6 +59,7 @@ impl RtuParser {
         Self {
             state: ParseState::Start,
             parser_type: ParserType::Request,
+            max_adu_length: crate::common::frame::constants::MAX_ADU_LENGTH,
         }
     }
 
@@ -61,6 +67,7 @@ impl RtuParser {
         Self {
             state: ParseState::Start,
             parser_type: ParserType::Response,
+            max_adu_length: crate::common::frame::constants::MAX_ADU_LENGTH,
         }
     }
 
@@ -85,8 +92,22 @@ impl RtuParser {
                 FunctionCode::ReadInputRegisters => LengthMode::Fixed(4),
                 FunctionCode::WriteSingleCoil => LengthMode::Fixed(4),
                 FunctionCode::WriteSingleRegister => LengthMode::Fixed(4),
+                FunctionCode::ReadExceptionStatus => LengthMode::Fixed(0),
+                // RTU frames don't carry the length of the diagnostic data, so
+                // only the single register used by most sub-functions can be framed
+                FunctionCode::Diagnostics => LengthMode::Fixed(4),
+                FunctionCode::GetCommEventCounter => LengthMode::Fixed(0),
+                FunctionCode::GetCommEventLog => LengthMode::Fixed(0),
                 FunctionCode::WriteMultipleCoils => LengthMode::Offset(5),
                 FunctionCode::WriteMultipleRegisters => LengthMode::Offset(5),
+                FunctionCode::ReportServerId => LengthMode::Fixed(0),
+                FunctionCode::ReadFileRecord => LengthMode::Offset(1),
+                FunctionCode::WriteFileRecord => LengthMode::Offset(1),
+                FunctionCode::MaskWriteRegister => LengthMode::Fixed(6),
+                FunctionCode::ReadWriteMultipleRegisters => LengthMode::Offset(9),
+                FunctionCode::ReadFifoQueue => LengthMode::Fixed(2),
+                // only MEI type 14 (read device identification) requests have a known length
+                FunctionCode::EncapsulatedInterfaceTransport => LengthMode::Fixed(3),
             },
             ParserType::Response => match function_code {
                 FunctionCode::ReadCoils => LengthMode::Offset(1),
@@ -95,12 +116,59 @@ impl RtuParser {
                 FunctionCode::ReadInputRegisters => LengthMode::Offset(1),
                 FunctionCode::WriteSingleCoil => LengthMode::Fixed(4),
                 FunctionCode::WriteSingleRegister => LengthMode::Fixed(4),
+                FunctionCode::ReadExceptionStatus => LengthMode::Fixed(1),
+                FunctionCode::Diagnostics => LengthMode::Fixed(4),
+                FunctionCode::GetCommEventCounter => LengthMode::Fixed(4),
+                FunctionCode::GetCommEventLog => LengthMode::Offset(1),
                 FunctionCode::WriteMultipleCoils => LengthMode::Fixed(4),
                 FunctionCode::WriteMultipleRegisters => LengthMode::Fixed(4),
+                FunctionCode::ReportServerId => LengthMode::Offset(1),
+                FunctionCode::ReadFileRecord => LengthMode::Offset(1),
+                FunctionCode::WriteFileRecord => LengthMode::Offset(1),
+                FunctionCode::MaskWriteRegister => LengthMode::Fixed(6),
+                FunctionCode::ReadWriteMultipleRegisters => LengthMode::Offset(1),
+                // the byte count is a u16, but never exceeds 64 so the low byte is enough
+                FunctionCode::ReadFifoQueue => LengthMode::Offset(2),
+                FunctionCode::EncapsulatedInterfaceTransport => LengthMode::DeviceIdObjects,
             },
         }
     }
 
+    // Walks the object list of a read device identification response to compute the length of
+    // the body (without function code). Returns None if more bytes are required.
+    fn device_id_length(&self, cursor: &mut ReadBuffer) -> Result<Option<usize>, RequestError> {
+        // function code, MEI type, read device id code, conformity level, more follows,
+        // next object id, number of objects
+        const HEADER_LENGTH: usize = 7;
+
+        if cursor.len() < HEADER_LENGTH {
+            return Ok(None);
+        }
+
+        let mei_type = cursor.peek_at(1)?;
+        if mei_type != crate::client::requests::read_device_id::MEI_TYPE_READ_DEVICE_ID {
+            return Err(RequestError::BadFrame(
+                FrameParseError::UnknownFunctionCode(cursor.peek_at(0)?),
+            ));
+        }
+
+        let num_objects = cursor.peek_at(HEADER_LENGTH - 1)?;
+        let mut position = HEADER_LENGTH;
+        for _ in 0..num_objects {
+            // each object is an id, a length and the value
+            if position > self.max_adu_length {
+                break;
+            }
+            if cursor.len() < position + 2 {
+                return Ok(None);
+            }
+            position += 2 + cursor.peek_at(position + 1)? as usize;
+        }
+
+        // the length check in ReadFullBody rejects frames that are too big
+        Ok(Some(position - constants::FUNCTION_CODE_LENGTH))
+    }
+
     pub(crate) fn parse(
         &mut self,
         cursor: &mut ReadBuffer,
@@ -131,6 +199,7 @@ impl RtuParser {
                     LengthMode::Offset(offset) => {
                         ParseState::ReadToOffsetForLength(destination, offset)
                     }
+                    LengthMode::DeviceIdObjects => ParseState::ReadDeviceIdObjects(destination),
                     LengthMode::Unknown => {
                         return Err(RequestError::BadFrame(
                             FrameParseError::UnknownFunctionCode(raw_function_code),
@@ -152,13 +221,20 @@ impl RtuParser {
 
                 self.parse(cursor, decode_level)
             }
+            ParseState::ReadDeviceIdObjects(destination) => {
+                let length = match self.device_id_length(cursor)? {
+                    Some(length) => length,
+                    None => return Ok(None),
+                };
+                self.state = ParseState::ReadFullBody(destination, length);
+
+                self.parse(cursor, decode_level)
+            }
             ParseState::ReadFullBody(destination, length) => {
-                if constants::FUNCTION_CODE_LENGTH + length
-                    > crate::common::frame::constants::MAX_ADU_LENGTH
-                {
+                if constants::FUNCTION_CODE_LENGTH + length > self.max_adu_length {
                     return Err(RequestError::BadFrame(FrameParseError::FrameLengthTooBig(
                         constants::FUNCTION_CODE_LENGTH + length,
-                        crate::common::frame::constants::MAX_ADU_LENGTH,
+                        self.max_adu_length,
                     )));
                 }
 
@@ -554,6 +630,29 @@ mod tests {
         assert_can_parse_frame(reader, &huge_response);
     }
 
+    #[test]
+    fn can_parse_read_device_id_response_byte_per_byte() {
+        let mut response = vec![
+            UNIT_ID, // unit id
+            0x2B,    // function code
+            0x0E,    // MEI type
+            0x01,    // read device id code
+            0x81,    // conformity level
+            0x00,    // more follows
+            0x00,    // next object id
+            0x02,    // number of objects
+            0x00, 0x03, b'f', b'o', b'o', // vendor name
+            0x01, 0x02, b'4', b'2', // product code
+        ];
+
+        let crc = CRC.checksum(&response);
+        response.push((crc & 0x00FF) as u8);
+        response.push(((crc & 0xFF00) >> 8) as u8);
+
+        let reader = FramedReader::rtu_response();
+        assert_can_parse_frame_byte_per_byte(reader, &response);
+    }
+
     fn assert_can_parse_frame_byte_per_byte(mut reader: FramedReader, frame: &[u8]) {
         let (io, mut io_handle) = sfio_tokio_mock_io::mock();
         let mut layer = PhysLayer::new_mock(io);
@@ -1,11 +1,16 @@
 use tokio_serial::SerialStream;
+
+use crate::types::Framing;
 pub use tokio_serial::{DataBits, FlowControl, Parity, StopBits};
 
+pub(crate) mod ascii;
 pub(crate) mod client;
 pub(crate) mod frame;
 pub(crate) mod server;
 
 /// Serial port settings
+///
+/// The default is 9600 baud, 8 data bits, no parity and 1 stop bit (9600-8-N-1)
 #[derive(Copy, Clone, Debug)]
 pub struct SerialSettings {
     /// Baud rate of the port
@@ -18,6 +23,11 @@ pub struct SerialSettings {
     pub stop_bits: StopBits,
     /// Parity setting
     pub parity: Parity,
+    /// Silent interval enforced between frames
+    ///
+    /// When `None`, the interval is 3.5 character times at the configured baud rate,
+    /// or a fixed 1.75 ms above 19200 baud as recommended by the specification.
+    pub inter_frame_delay: Option<std::time::Duration>,
 }
 
 impl SerialSettings {
@@ -32,6 +42,31 @@ impl SerialSettings {
             .stop_bits(self.stop_bits)
             .parity(self.parity)
     }
+
+    /// check that the settings can carry the specified framing
+    pub(crate) fn validate(&self, framing: Framing) -> Result<(), std::io::Error> {
+        if self.baud_rate == 0 {
+            return Err(invalid_settings("baud rate must be greater than zero"));
+        }
+        match framing {
+            // RTU transmits each byte as 8 data bits
+            Framing::Rtu if self.data_bits != DataBits::Eight => {
+                Err(invalid_settings("RTU framing requires 8 data bits"))
+            }
+            // ASCII characters only require 7 data bits, but some devices use 8
+            Framing::Ascii if !matches!(self.data_bits, DataBits::Seven | DataBits::Eight) => {
+                Err(invalid_settings("ASCII framing requires 7 or 8 data bits"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn invalid_settings(reason: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid serial settings: {reason}"),
+    )
 }
 
 impl Default for SerialSettings {
@@ -42,11 +77,47 @@ impl Default for SerialSettings {
             flow_control: FlowControl::None,
             stop_bits: StopBits::One,
             parity: Parity::None,
+            inter_frame_delay: None,
         }
     }
 }
 
-pub(crate) fn open(path: &str, settings: SerialSettings) -> tokio_serial::Result<SerialStream> {
+pub(crate) fn open(
+    path: &str,
+    settings: SerialSettings,
+    framing: Framing,
+) -> tokio_serial::Result<SerialStream> {
+    settings.validate(framing)?;
     let builder = settings.apply(tokio_serial::new(path, settings.baud_rate));
     SerialStream::open(&builder)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_are_valid_for_all_serial_framings() {
+        assert!(SerialSettings::default().validate(Framing::Rtu).is_ok());
+        assert!(SerialSettings::default().validate(Framing::Ascii).is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_baud_rate() {
+        let settings = SerialSettings {
+            baud_rate: 0,
+            ..Default::default()
+        };
+        assert!(settings.validate(Framing::Rtu).is_err());
+    }
+
+    #[test]
+    fn rtu_requires_eight_data_bits() {
+        let settings = SerialSettings {
+            data_bits: DataBits::Seven,
+            ..Default::default()
+        };
+        assert!(settings.validate(Framing::Rtu).is_err());
+        assert!(settings.validate(Framing::Ascii).is_ok());
+    }
+}
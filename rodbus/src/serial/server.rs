@@ -1,30 +1,32 @@
 use crate::common::phys::PhysLayer;
 use crate::server::task::SessionTask;
 use crate::server::RequestHandler;
+use crate::types::Framing;
 use crate::{RequestError, RetryStrategy, SerialSettings, Shutdown};
 
-pub(crate) struct RtuServerTask<T>
+pub(crate) struct SerialServerTask<T>
 where
     T: RequestHandler,
 {
     pub(crate) port: String,
     pub(crate) retry: Box<dyn RetryStrategy>,
     pub(crate) settings: SerialSettings,
+    pub(crate) framing: Framing,
     pub(crate) session: SessionTask<T>,
 }
 
-impl<T> RtuServerTask<T>
+impl<T> SerialServerTask<T>
 where
     T: RequestHandler,
 {
     pub(crate) async fn run(&mut self) -> Shutdown {
         loop {
-            match crate::serial::open(&self.port, self.settings) {
+            match crate::serial::open(&self.port, self.settings, self.framing) {
                 Ok(serial) => {
                     self.retry.reset();
                     tracing::info!("opened port");
                     // run an open port until shutdown or failure
-                    let mut phys = PhysLayer::new_serial(serial);
+                    let mut phys = PhysLayer::new_serial(serial, self.settings.inter_frame_delay);
                     if let RequestError::Shutdown = self.session.run(&mut phys).await {
                         return Shutdown;
                     }
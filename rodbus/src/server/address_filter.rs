@@ -60,6 +60,70 @@ impl WildcardIPv4 {
     }
 }
 
+/// IPv4 or IPv6 network in CIDR notation, e.g. `192.168.0.0/16` or `fd00::/8`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IpCidr {
+    addr: std::net::IpAddr,
+    prefix: u8,
+}
+
+/// Error returned when a network is not in CIDR notation or the prefix is too long
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BadCidr;
+
+impl IpCidr {
+    /// Create a network from an address and a prefix length
+    pub fn new(addr: std::net::IpAddr, prefix: u8) -> Result<Self, BadCidr> {
+        let max = match addr {
+            std::net::IpAddr::V4(_) => 32,
+            std::net::IpAddr::V6(_) => 128,
+        };
+        if prefix > max {
+            return Err(BadCidr);
+        }
+        Ok(Self { addr, prefix })
+    }
+
+    pub(crate) fn contains(&self, addr: std::net::IpAddr) -> bool {
+        fn mask(bits: u32, prefix: u8) -> u128 {
+            match prefix {
+                0 => 0,
+                _ => (u128::MAX << (bits - prefix as u32)) & (u128::MAX >> (128 - bits)),
+            }
+        }
+
+        // IPv4 clients of a dual-stack listener show up as IPv4-mapped IPv6 addresses
+        match (self.addr, addr.to_canonical()) {
+            (std::net::IpAddr::V4(net), std::net::IpAddr::V4(x)) => {
+                let mask = mask(32, self.prefix);
+                u32::from(net) as u128 & mask == u32::from(x) as u128 & mask
+            }
+            (std::net::IpAddr::V6(net), std::net::IpAddr::V6(x)) => {
+                let mask = mask(128, self.prefix);
+                u128::from(net) & mask == u128::from(x) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = BadCidr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').ok_or(BadCidr)?;
+        let addr = addr.parse().map_err(|_| BadCidr)?;
+        let prefix = prefix.parse().map_err(|_| BadCidr)?;
+        Self::new(addr, prefix)
+    }
+}
+
+impl std::fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
 /// Address filter used to control which master address(es) may connect to an outstation.
 ///
 /// Note: User code cannot exhaustively match against this enum as new variants may be added in the future.
@@ -74,6 +138,10 @@ pub enum AddressFilter {
     AnyOf(std::collections::HashSet<std::net::IpAddr>),
     /// Matches against an IPv4 address with wildcards
     WildcardIpv4(WildcardIPv4),
+    /// Allow addresses within any of the networks
+    Allow(Vec<IpCidr>),
+    /// Allow addresses except those within any of the networks
+    Deny(Vec<IpCidr>),
 }
 
 impl AddressFilter {
@@ -83,13 +151,15 @@ impl AddressFilter {
             AddressFilter::Exact(x) => *x == addr,
             AddressFilter::AnyOf(set) => set.contains(&addr),
             AddressFilter::WildcardIpv4(wc) => wc.matches(addr),
+            AddressFilter::Allow(networks) => networks.iter().any(|x| x.contains(addr)),
+            AddressFilter::Deny(networks) => !networks.iter().any(|x| x.contains(addr)),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{BadIpv4Wildcard, WildcardIPv4};
+    use super::{AddressFilter, BadCidr, BadIpv4Wildcard, IpCidr, WildcardIPv4};
     use std::net::IpAddr;
 
     #[test]
@@ -148,4 +218,55 @@ mod test {
         assert!(wc.matches(ip1));
         assert!(!wc.matches(ip2));
     }
+
+    #[test]
+    fn parses_cidr_notation() {
+        let net: IpCidr = "10.1.0.0/16".parse().unwrap();
+        assert_eq!(net.to_string(), "10.1.0.0/16");
+        assert!("fd00::/8".parse::<IpCidr>().is_ok());
+
+        for x in [
+            "10.1.0.0",
+            "10.1.0.0/33",
+            "fd00::/129",
+            "10.1.0/8",
+            "10.1.0.0/a",
+        ] {
+            assert_eq!(x.parse::<IpCidr>(), Err(BadCidr));
+        }
+    }
+
+    #[test]
+    fn cidr_matching_works() {
+        let net: IpCidr = "192.168.0.0/23".parse().unwrap();
+        assert!(net.contains("192.168.1.200".parse().unwrap()));
+        assert!(!net.contains("192.168.2.1".parse().unwrap()));
+        assert!(net.contains("::ffff:192.168.0.1".parse().unwrap()));
+        assert!(!net.contains("fd00::1".parse().unwrap()));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains("8.8.8.8".parse().unwrap()));
+
+        let v6: IpCidr = "fd00::/8".parse().unwrap();
+        assert!(v6.contains("fdab::1".parse().unwrap()));
+        assert!(!v6.contains("fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn allow_and_deny_lists_work() {
+        let networks = vec![
+            "10.0.0.0/8".parse().unwrap(),
+            "127.0.0.1/32".parse().unwrap(),
+        ];
+        let inside: IpAddr = "10.20.30.40".parse().unwrap();
+        let outside: IpAddr = "192.168.0.1".parse().unwrap();
+
+        let allow = AddressFilter::Allow(networks.clone());
+        assert!(allow.matches(inside));
+        assert!(!allow.matches(outside));
+
+        let deny = AddressFilter::Deny(networks);
+        assert!(!deny.matches(inside));
+        assert!(deny.matches(outside));
+    }
 }
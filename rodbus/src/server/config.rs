@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::decode::DecodeLevel;
+use crate::error::InvalidRange;
+use crate::exception::ExceptionCode;
+use crate::server::*;
+use crate::types::{AddressRange, UnitId};
+
+/// Declarative description of a TCP server backed by a [`ServerDatabase`] per unit id
+///
+/// It is typically loaded from a TOML file, which makes it possible to simulate a new
+/// register map without writing any code:
+///
+/// ```toml
+/// listen = "127.0.0.1:502"
+/// max_sessions = 10
+/// allowed_clients = ["10.0.0.0/8"]
+///
+/// [[unit]]
+/// id = 1
+/// read_only = false
+/// coils = { start = 0, count = 16 }
+/// holding_registers = { start = 100, count = 4, values = [1, 2, 3, 4] }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the server listens on
+    pub listen: SocketAddr,
+    /// Maximum number of concurrent sessions, defaults to 100
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize,
+    /// Networks in CIDR notation allowed to connect, or any client if absent
+    #[serde(default)]
+    pub allowed_clients: Option<Vec<String>>,
+    /// Unit ids answered by the server
+    #[serde(default, rename = "unit")]
+    pub units: Vec<UnitConfig>,
+}
+
+/// Points and access rules of a single unit id in a [`ServerConfig`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnitConfig {
+    /// Unit id of the database
+    pub id: u8,
+    /// Reject every write to this unit with [`ExceptionCode::IllegalFunction`]
+    #[serde(default)]
+    pub read_only: bool,
+    /// Coils of the unit, if any
+    pub coils: Option<TableConfig<bool>>,
+    /// Discrete inputs of the unit, if any
+    pub discrete_inputs: Option<TableConfig<bool>>,
+    /// Holding registers of the unit, if any
+    pub holding_registers: Option<TableConfig<u16>>,
+    /// Input registers of the unit, if any
+    pub input_registers: Option<TableConfig<u16>>,
+}
+
+/// Range of points and their initial values
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableConfig<T> {
+    /// Address of the first point
+    pub start: u16,
+    /// Number of points
+    pub count: u16,
+    /// Initial values starting at `start`. Points without a value are initialized to zero.
+    #[serde(default = "Vec::new")]
+    pub values: Vec<T>,
+}
+
+/// Errors that can occur when loading or applying a [`ServerConfig`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The file could not be read or the server could not be bound
+    Io(std::io::Error),
+    /// The file is not valid TOML or doesn't describe a server
+    Parse(String),
+    /// The same unit id is configured more than once
+    DuplicateUnitId(u8),
+    /// A unit contains an invalid range of points
+    InvalidRange(u8, InvalidRange),
+    /// A unit contains more initial values than points
+    TooManyValues(u8),
+    /// An allowed client is not in CIDR notation
+    BadCidr(String),
+}
+
+/// Server spawned from a [`ServerConfig`]
+#[derive(Debug)]
+pub struct ConfiguredServer {
+    /// Handle to the server task
+    pub handle: ServerHandle,
+    /// Database of each unit id, used to update or inspect values while the server runs
+    pub databases: BTreeMap<UnitId, DatabaseHandle>,
+}
+
+fn default_max_sessions() -> usize {
+    100
+}
+
+/// rejects writes to a set of unit ids
+struct ReadOnlyUnits(BTreeSet<UnitId>);
+
+impl WriteAuthorizationHandler for ReadOnlyUnits {
+    fn authorize_write(
+        &self,
+        unit_id: UnitId,
+        _function: WriteFunction,
+        _range: AddressRange,
+        _peer: &Peer,
+    ) -> Result<(), ExceptionCode> {
+        if self.0.contains(&unit_id) {
+            Err(ExceptionCode::IllegalFunction)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T> TableConfig<T> {
+    fn range(&self, unit: u8) -> Result<AddressRange, ConfigError> {
+        let range = AddressRange::try_from(self.start, self.count)
+            .map_err(|err| ConfigError::InvalidRange(unit, err))?;
+        if self.values.len() > self.count as usize {
+            return Err(ConfigError::TooManyValues(unit));
+        }
+        Ok(range)
+    }
+}
+
+impl UnitConfig {
+    fn database(&self) -> Result<DatabaseHandle, ConfigError> {
+        fn initial_values<T: Copy>(
+            table: &Option<TableConfig<T>>,
+        ) -> impl Iterator<Item = (u16, T)> + '_ {
+            // the range was validated, but it may end at 0xFFFF
+            table.iter().flat_map(|x| {
+                x.values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| (x.start + i as u16, *value))
+            })
+        }
+
+        fn range<T>(
+            unit: u8,
+            table: &Option<TableConfig<T>>,
+        ) -> Result<Option<AddressRange>, ConfigError> {
+            table.as_ref().map(|x| x.range(unit)).transpose()
+        }
+
+        let config = DatabaseConfig {
+            coils: range(self.id, &self.coils)?,
+            discrete_inputs: range(self.id, &self.discrete_inputs)?,
+            holding_registers: range(self.id, &self.holding_registers)?,
+            input_registers: range(self.id, &self.input_registers)?,
+        };
+
+        let handle = DatabaseHandle::new(config);
+        handle.transaction(|db| {
+            for (address, value) in initial_values(&self.coils) {
+                db.set_coil(address, value);
+            }
+            for (address, value) in initial_values(&self.discrete_inputs) {
+                db.set_discrete_input(address, value);
+            }
+            for (address, value) in initial_values(&self.holding_registers) {
+                db.set_holding_register(address, value);
+            }
+            for (address, value) in initial_values(&self.input_registers) {
+                db.set_input_register(address, value);
+            }
+        });
+        Ok(handle)
+    }
+}
+
+impl ServerConfig {
+    /// Parse a configuration from TOML
+    pub fn from_toml(s: &str) -> Result<Self, ConfigError> {
+        toml::from_str(s).map_err(|err| ConfigError::Parse(err.to_string()))
+    }
+
+    /// Read and parse a TOML configuration file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Spawn a TCP server as described by the configuration
+    ///
+    /// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+    pub async fn spawn(&self, decode: DecodeLevel) -> Result<ConfiguredServer, ConfigError> {
+        let filter = self.filter()?;
+        let databases = self.databases()?;
+
+        let mut handlers = ServerHandlerMap::new();
+        for (id, db) in &databases {
+            handlers.add(*id, db.handler());
+        }
+        let read_only: BTreeSet<UnitId> = self
+            .units
+            .iter()
+            .filter(|x| x.read_only)
+            .map(|x| UnitId::new(x.id))
+            .collect();
+        if !read_only.is_empty() {
+            handlers.set_write_authorization(ReadOnlyUnits(read_only).wrap());
+        }
+
+        let handle =
+            spawn_tcp_server_task(self.max_sessions, self.listen, handlers, filter, decode).await?;
+
+        Ok(ConfiguredServer { handle, databases })
+    }
+
+    fn filter(&self) -> Result<AddressFilter, ConfigError> {
+        match &self.allowed_clients {
+            None => Ok(AddressFilter::Any),
+            Some(networks) => networks
+                .iter()
+                .map(|x| x.parse().map_err(|_| ConfigError::BadCidr(x.clone())))
+                .collect::<Result<_, _>>()
+                .map(AddressFilter::Allow),
+        }
+    }
+
+    fn databases(&self) -> Result<BTreeMap<UnitId, DatabaseHandle>, ConfigError> {
+        let mut databases = BTreeMap::new();
+        for unit in &self.units {
+            if databases
+                .insert(UnitId::new(unit.id), unit.database()?)
+                .is_some()
+            {
+                return Err(ConfigError::DuplicateUnitId(unit.id));
+            }
+        }
+        Ok(databases)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ConfigError::Io(err) => write!(f, "{err}"),
+            ConfigError::Parse(err) => write!(f, "bad server configuration: {err}"),
+            ConfigError::DuplicateUnitId(id) => write!(f, "unit id {id} is configured twice"),
+            ConfigError::InvalidRange(id, err) => write!(f, "unit id {id}: {err}"),
+            ConfigError::TooManyValues(id) => {
+                write!(f, "unit id {id}: more initial values than points")
+            }
+            ConfigError::BadCidr(x) => write!(f, "{x} is not a network in CIDR notation"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        listen = "127.0.0.1:502"
+        allowed_clients = ["10.0.0.0/8"]
+
+        [[unit]]
+        id = 1
+        coils = { start = 0, count = 4, values = [true, false, true] }
+        holding_registers = { start = 100, count = 2, values = [0xCAFE] }
+
+        [[unit]]
+        id = 2
+        read_only = true
+        input_registers = { start = 0, count = 1 }
+    "#;
+
+    #[test]
+    fn parses_config() {
+        let config = ServerConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.listen, "127.0.0.1:502".parse().unwrap());
+        assert_eq!(config.max_sessions, 100);
+        assert_eq!(config.units.len(), 2);
+        assert!(config.units[1].read_only);
+        assert!(config.units[1].coils.is_none());
+    }
+
+    #[test]
+    fn initializes_databases() {
+        let config = ServerConfig::from_toml(CONFIG).unwrap();
+        let databases = config.databases().unwrap();
+        databases[&UnitId::new(1)].transaction(|db| {
+            assert_eq!(db.get_coil(0), Some(true));
+            assert_eq!(db.get_coil(1), Some(false));
+            assert_eq!(db.get_coil(3), Some(false));
+            assert_eq!(db.get_coil(4), None);
+            assert_eq!(db.get_holding_register(100), Some(0xCAFE));
+            assert_eq!(db.get_holding_register(101), Some(0));
+            assert_eq!(db.get_discrete_input(0), None);
+        });
+        databases[&UnitId::new(2)].transaction(|db| {
+            assert_eq!(db.get_input_register(0), Some(0));
+        });
+    }
+
+    #[test]
+    fn initializes_values_up_to_the_last_address() {
+        let config = ServerConfig::from_toml(
+            r#"
+            listen = "127.0.0.1:502"
+
+            [[unit]]
+            id = 1
+            holding_registers = { start = 65534, count = 2, values = [1, 2] }
+            "#,
+        )
+        .unwrap();
+        let databases = config.databases().unwrap();
+        databases[&UnitId::new(1)].transaction(|db| {
+            assert_eq!(db.get_holding_register(0xFFFE), Some(1));
+            assert_eq!(db.get_holding_register(0xFFFF), Some(2));
+        });
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        let units = |x: &str| format!("listen = \"127.0.0.1:502\"\n{x}");
+        let check = |x: &str| ServerConfig::from_toml(&units(x)).unwrap().databases();
+
+        assert!(matches!(
+            check("[[unit]]\nid = 1\n[[unit]]\nid = 1"),
+            Err(ConfigError::DuplicateUnitId(1))
+        ));
+        assert!(matches!(
+            check("[[unit]]\nid = 3\ncoils = { start = 0, count = 0 }"),
+            Err(ConfigError::InvalidRange(3, InvalidRange::CountOfZero))
+        ));
+        assert!(matches!(
+            check("[[unit]]\nid = 3\ncoils = { start = 0, count = 1, values = [true, true] }"),
+            Err(ConfigError::TooManyValues(3))
+        ));
+        assert!(matches!(
+            ServerConfig::from_toml(&units("[[unit]]\nid = 1\nholding = {}")),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            ServerConfig::from_toml(&units("allowed_clients = [\"10.0.0.0/33\"]"))
+                .unwrap()
+                .filter(),
+            Err(ConfigError::BadCidr(_))
+        ));
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::VecDeque;
+
+use crate::diagnostic::{CommEvent, CommEventLog, DiagnosticCounter, ReceiveEvent, SendEvent};
+use crate::exception::ExceptionCode;
+
+/// exception code 7, which has no variant in [`ExceptionCode`]
+const NEGATIVE_ACKNOWLEDGE: u8 = 0x07;
+
+/// number of events kept in the communication event log
+const EVENT_LOG_CAPACITY: usize = 64;
+
+/// Device that a received frame is addressed to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Recipient {
+    /// a unit id that the server doesn't answer
+    OtherDevice,
+    /// a unit id of the server
+    Server,
+    /// the broadcast address
+    Broadcast,
+}
+
+/// Diagnostic counters and communication event log of a session, maintained as frames are
+/// received and answered
+///
+/// All counters wrap around at 65535 as required by the specification.
+#[derive(Default)]
+pub(crate) struct DiagnosticCounters {
+    bus_message: u16,
+    bus_communication_error: u16,
+    bus_exception_error: u16,
+    server_message: u16,
+    server_no_response: u16,
+    server_nak: u16,
+    server_busy: u16,
+    bus_character_overrun: u16,
+    event_count: u16,
+    /// most recent event first
+    events: VecDeque<u8>,
+    /// state of the message being processed
+    current: Option<Message>,
+}
+
+struct Message {
+    addressed: bool,
+    counts_as_event: bool,
+    response: Option<Option<ExceptionCode>>,
+}
+
+fn increment(counter: &mut u16) {
+    *counter = counter.wrapping_add(1);
+}
+
+fn send_event(exception: Option<ExceptionCode>) -> SendEvent {
+    let code = exception.map(u8::from);
+    SendEvent {
+        read_exception: matches!(code, Some(1..=3)),
+        server_abort_exception: code == Some(4),
+        server_busy_exception: matches!(code, Some(5..=6)),
+        server_nak_exception: code == Some(NEGATIVE_ACKNOWLEDGE),
+        ..Default::default()
+    }
+}
+
+impl DiagnosticCounters {
+    pub(crate) fn get(&self, counter: DiagnosticCounter) -> u16 {
+        match counter {
+            DiagnosticCounter::BusMessage => self.bus_message,
+            DiagnosticCounter::BusCommunicationError => self.bus_communication_error,
+            DiagnosticCounter::BusExceptionError => self.bus_exception_error,
+            DiagnosticCounter::ServerMessage => self.server_message,
+            DiagnosticCounter::ServerNoResponse => self.server_no_response,
+            DiagnosticCounter::ServerNak => self.server_nak,
+            DiagnosticCounter::ServerBusy => self.server_busy,
+            // overruns are handled by the serial driver and never reported to the library
+            DiagnosticCounter::BusCharacterOverrun => self.bus_character_overrun,
+        }
+    }
+
+    /// count of messages completed without an exception
+    pub(crate) fn event_count(&self) -> u16 {
+        self.event_count
+    }
+
+    /// contents of the communication event log
+    pub(crate) fn event_log(&self) -> CommEventLog {
+        CommEventLog {
+            status: 0x0000,
+            event_count: self.event_count,
+            message_count: self.bus_message,
+            events: self.events.iter().map(|x| CommEvent::from(*x)).collect(),
+        }
+    }
+
+    /// reset every counter to zero, e.g. on a `clear counters` request
+    ///
+    /// The event log is not affected.
+    pub(crate) fn clear(&mut self) {
+        let current = self.current.take();
+        let events = std::mem::take(&mut self.events);
+        *self = Self {
+            current,
+            events,
+            ..Default::default()
+        };
+    }
+
+    fn record(&mut self, event: CommEvent) {
+        self.events.push_front(event.into());
+        self.events.truncate(EVENT_LOG_CAPACITY);
+    }
+
+    /// a frame that failed the CRC or LRC check was received
+    pub(crate) fn on_communication_error(&mut self) {
+        increment(&mut self.bus_communication_error);
+        self.record(CommEvent::Receive(ReceiveEvent {
+            communication_error: true,
+            ..Default::default()
+        }));
+    }
+
+    /// a valid frame was received
+    ///
+    /// `counts_as_event` is false for requests that don't increment the event counter
+    pub(crate) fn on_message(&mut self, recipient: Recipient, counts_as_event: bool) {
+        increment(&mut self.bus_message);
+        let addressed = recipient != Recipient::OtherDevice;
+        if addressed {
+            increment(&mut self.server_message);
+            self.record(CommEvent::Receive(ReceiveEvent {
+                broadcast_received: recipient == Recipient::Broadcast,
+                ..Default::default()
+            }));
+        }
+        self.current = Some(Message {
+            addressed,
+            counts_as_event,
+            response: None,
+        });
+    }
+
+    /// a response to the current message was sent
+    pub(crate) fn on_response(&mut self, exception: Option<ExceptionCode>) {
+        if let Some(current) = &mut self.current {
+            current.response = Some(exception);
+        }
+    }
+
+    /// processing of the current message is complete, returning the response that was sent
+    pub(crate) fn on_complete(&mut self) -> Option<Option<ExceptionCode>> {
+        let current = match self.current.take() {
+            Some(x) if x.addressed => x,
+            Some(x) => return x.response,
+            None => return None,
+        };
+
+        self.record(CommEvent::Send(send_event(current.response.flatten())));
+
+        match current.response {
+            None => increment(&mut self.server_no_response),
+            Some(None) => {}
+            Some(Some(ex)) => {
+                increment(&mut self.bus_exception_error);
+                match ex {
+                    ExceptionCode::ServerDeviceBusy => increment(&mut self.server_busy),
+                    ExceptionCode::Unknown(NEGATIVE_ACKNOWLEDGE) => increment(&mut self.server_nak),
+                    _ => {}
+                }
+                return current.response;
+            }
+        }
+
+        if current.counts_as_event {
+            increment(&mut self.event_count);
+        }
+        current.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(
+        counters: &mut DiagnosticCounters,
+        recipient: Recipient,
+        response: Option<Option<ExceptionCode>>,
+    ) {
+        counters.on_message(recipient, true);
+        if let Some(exception) = response {
+            counters.on_response(exception);
+        }
+        counters.on_complete();
+    }
+
+    #[test]
+    fn counts_messages() {
+        let mut counters = DiagnosticCounters::default();
+        message(&mut counters, Recipient::Server, Some(None));
+        message(&mut counters, Recipient::OtherDevice, None);
+        message(&mut counters, Recipient::Broadcast, None);
+
+        assert_eq!(counters.get(DiagnosticCounter::BusMessage), 3);
+        assert_eq!(counters.get(DiagnosticCounter::ServerMessage), 2);
+        assert_eq!(counters.get(DiagnosticCounter::ServerNoResponse), 1);
+        assert_eq!(counters.get(DiagnosticCounter::BusExceptionError), 0);
+        // the broadcast completed without an exception
+        assert_eq!(counters.event_count(), 2);
+    }
+
+    #[test]
+    fn counts_exceptions() {
+        let mut counters = DiagnosticCounters::default();
+        message(
+            &mut counters,
+            Recipient::Server,
+            Some(Some(ExceptionCode::IllegalFunction)),
+        );
+        message(
+            &mut counters,
+            Recipient::Server,
+            Some(Some(ExceptionCode::ServerDeviceBusy)),
+        );
+        message(
+            &mut counters,
+            Recipient::Server,
+            Some(Some(ExceptionCode::Unknown(0x07))),
+        );
+
+        assert_eq!(counters.get(DiagnosticCounter::BusExceptionError), 3);
+        assert_eq!(counters.get(DiagnosticCounter::ServerBusy), 1);
+        assert_eq!(counters.get(DiagnosticCounter::ServerNak), 1);
+        assert_eq!(counters.event_count(), 0);
+    }
+
+    #[test]
+    fn only_counts_events_when_requested() {
+        let mut counters = DiagnosticCounters::default();
+        counters.on_message(Recipient::Server, false);
+        counters.on_response(None);
+        counters.on_complete();
+        assert_eq!(counters.event_count(), 0);
+        assert_eq!(counters.get(DiagnosticCounter::ServerMessage), 1);
+    }
+
+    #[test]
+    fn clear_resets_counters_but_not_the_current_message() {
+        let mut counters = DiagnosticCounters::default();
+        counters.on_communication_error();
+        counters.on_message(Recipient::Server, true);
+        counters.clear();
+        counters.on_response(None);
+        counters.on_complete();
+
+        assert_eq!(counters.get(DiagnosticCounter::BusCommunicationError), 0);
+        assert_eq!(counters.get(DiagnosticCounter::BusMessage), 0);
+        assert_eq!(counters.event_count(), 1);
+    }
+
+    #[test]
+    fn counters_wrap_around() {
+        let mut counters = DiagnosticCounters {
+            bus_communication_error: u16::MAX,
+            ..Default::default()
+        };
+        counters.on_communication_error();
+        assert_eq!(counters.get(DiagnosticCounter::BusCommunicationError), 0);
+    }
+
+    #[test]
+    fn records_events_most_recent_first() {
+        let mut counters = DiagnosticCounters::default();
+        message(&mut counters, Recipient::Broadcast, None);
+        message(&mut counters, Recipient::OtherDevice, None);
+        message(
+            &mut counters,
+            Recipient::Server,
+            Some(Some(ExceptionCode::ServerDeviceBusy)),
+        );
+        counters.on_communication_error();
+        counters.clear();
+
+        let log = counters.event_log();
+        assert_eq!(log.message_count, 0);
+        assert_eq!(
+            log.events,
+            [
+                CommEvent::Receive(ReceiveEvent {
+                    communication_error: true,
+                    ..Default::default()
+                }),
+                CommEvent::Send(SendEvent {
+                    server_busy_exception: true,
+                    ..Default::default()
+                }),
+                CommEvent::Receive(ReceiveEvent::default()),
+                CommEvent::Send(SendEvent::default()),
+                CommEvent::Receive(ReceiveEvent {
+                    broadcast_received: true,
+                    ..Default::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_last_64_events() {
+        let mut counters = DiagnosticCounters::default();
+        for _ in 0..100 {
+            message(&mut counters, Recipient::Server, Some(None));
+        }
+        let log = counters.event_log();
+        assert_eq!(log.events.len(), 64);
+        assert_eq!(log.message_count, 100);
+        assert_eq!(log.event_count, 100);
+    }
+}
@@ -0,0 +1,832 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::Instrument;
+
+use crate::constants::limits::MAX_READ_FIFO_COUNT;
+use crate::exception::ExceptionCode;
+use crate::server::handler::{RequestHandler, ServerHandlerType};
+use crate::server::{WriteCoils, WriteRegisters};
+use crate::types::{AddressRange, FileRecord, FileRecordRange, Indexed};
+
+/// Address ranges of the points stored in a [`ServerDatabase`]
+///
+/// A point type left at `None` contains no points and every request for it is answered
+/// with [`ExceptionCode::IllegalDataAddress`]. A range that extends past address 0xFFFF only
+/// contains the points up to that address.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    /// Range of coils
+    pub coils: Option<AddressRange>,
+    /// Range of discrete inputs
+    pub discrete_inputs: Option<AddressRange>,
+    /// Range of holding registers
+    pub holding_registers: Option<AddressRange>,
+    /// Range of input registers
+    pub input_registers: Option<AddressRange>,
+}
+
+/// Contiguous block of points starting at an arbitrary address
+#[derive(Clone, Debug)]
+struct Table<T> {
+    start: u16,
+    values: Vec<T>,
+}
+
+impl<T> Table<T>
+where
+    T: Copy + Default,
+{
+    fn new(range: Option<AddressRange>) -> Self {
+        match range {
+            None => Self {
+                start: 0,
+                values: Vec::new(),
+            },
+            Some(range) => {
+                // the fields of the range are public, so it may extend past the last address
+                let end = range.to_std_range().end.min(u16::MAX as usize + 1);
+                Self {
+                    start: range.start,
+                    values: vec![T::default(); end - range.start as usize],
+                }
+            }
+        }
+    }
+
+    fn index(&self, address: u16) -> Option<usize> {
+        let index = address.checked_sub(self.start)? as usize;
+        if index < self.values.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, address: u16) -> Option<T> {
+        self.index(address).map(|i| self.values[i])
+    }
+
+    /// returns the previous value if the address is valid
+    fn replace(&mut self, address: u16, value: T) -> Option<T> {
+        let i = self.index(address)?;
+        Some(std::mem::replace(&mut self.values[i], value))
+    }
+
+    fn set(&mut self, address: u16, value: T) -> bool {
+        self.replace(address, value).is_some()
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (u16, T)> + '_ {
+        // the table never extends past 0xFFFF, but an open range of addresses would overflow
+        // after a last value at that address
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (self.start + i as u16, *x))
+    }
+
+    fn contains(&self, range: AddressRange) -> bool {
+        // ranges received by the server have a non-zero count and don't overflow u16
+        self.index(range.start).is_some() && self.index(range.start + (range.count - 1)).is_some()
+    }
+
+    fn read(&self, address: u16) -> Result<T, ExceptionCode> {
+        self.get(address).ok_or(ExceptionCode::IllegalDataAddress)
+    }
+
+    /// write a single value, calling `on_write` with the address, old and new values
+    fn write(
+        &mut self,
+        value: Indexed<T>,
+        mut on_write: impl FnMut(u16, T, T),
+    ) -> Result<(), ExceptionCode> {
+        let old = self
+            .replace(value.index, value.value)
+            .ok_or(ExceptionCode::IllegalDataAddress)?;
+        on_write(value.index, old, value.value);
+        Ok(())
+    }
+
+    /// write a range of values, calling `on_write` with the address, old and new values of each
+    fn write_all(
+        &mut self,
+        range: AddressRange,
+        values: impl Iterator<Item = Indexed<T>>,
+        mut on_write: impl FnMut(u16, T, T),
+    ) -> Result<(), ExceptionCode> {
+        // check the whole range first so that a rejected request doesn't partially apply
+        if !self.contains(range) {
+            return Err(ExceptionCode::IllegalDataAddress);
+        }
+        for x in values {
+            if let Some(old) = self.replace(x.index, x.value) {
+                on_write(x.index, old, x.value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value written to a [`ServerDatabase`] by a client
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatabaseChange {
+    /// A coil was written
+    Coil {
+        /// Address of the coil
+        address: u16,
+        /// Value before the write
+        old: bool,
+        /// Value written by the client
+        new: bool,
+    },
+    /// A holding register was written
+    HoldingRegister {
+        /// Address of the register
+        address: u16,
+        /// Value before the write
+        old: u16,
+        /// Value written by the client
+        new: u16,
+    },
+}
+
+/// Names of the point types in a snapshot file
+mod names {
+    pub(super) const COIL: &str = "coil";
+    pub(super) const DISCRETE_INPUT: &str = "discrete_input";
+    pub(super) const HOLDING_REGISTER: &str = "holding_register";
+    pub(super) const INPUT_REGISTER: &str = "input_register";
+}
+
+fn invalid_snapshot(line: usize, reason: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("invalid snapshot line {line}: {reason}"),
+    )
+}
+
+/// write to a temporary file first so that a crash never leaves a truncated snapshot
+fn write_snapshot(path: &Path, snapshot: &str) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, snapshot)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Number of changes buffered for each subscriber
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
+/// Ready-made [`RequestHandler`] that stores each point type in a contiguous array
+///
+/// Clients may read all four point types and write coils and holding registers. Discrete
+/// inputs and input registers can only be changed by the application via a [`DatabaseHandle`].
+/// Requests outside the configured ranges are answered with [`ExceptionCode::IllegalDataAddress`].
+///
+/// The database may also hold files of 16-bit records that clients read and write with
+/// Read File Record (FC20) and Write File Record (FC21) requests, and FIFO queues that the
+/// application pushes values to and that clients drain with Read FIFO Queue (FC24) requests.
+#[derive(Clone, Debug)]
+pub struct ServerDatabase {
+    coils: Table<bool>,
+    discrete_inputs: Table<bool>,
+    holding_registers: Table<u16>,
+    input_registers: Table<u16>,
+    files: BTreeMap<u16, Vec<u16>>,
+    fifo_queues: BTreeMap<u16, VecDeque<u16>>,
+    changes: tokio::sync::broadcast::Sender<DatabaseChange>,
+}
+
+impl ServerDatabase {
+    /// Create a database with every point set to `false` or zero
+    pub fn new(config: DatabaseConfig) -> Self {
+        Self {
+            coils: Table::new(config.coils),
+            discrete_inputs: Table::new(config.discrete_inputs),
+            holding_registers: Table::new(config.holding_registers),
+            input_registers: Table::new(config.input_registers),
+            files: BTreeMap::new(),
+            fifo_queues: BTreeMap::new(),
+            changes: tokio::sync::broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Save the value of every point to a file
+    ///
+    /// The file is a text snapshot with one `<type> <address> <value>` line per point.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        write_snapshot(path, &self.snapshot())
+    }
+
+    /// Restore values saved by [`ServerDatabase::save`]
+    ///
+    /// Points of the snapshot outside the configured ranges are ignored, so a snapshot can be
+    /// restored after ranges are changed.
+    pub fn restore(&mut self, path: &Path) -> std::io::Result<()> {
+        self.load(&std::fs::read_to_string(path)?)
+    }
+
+    fn snapshot(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        for (address, value) in self.coils.entries() {
+            let _ = writeln!(out, "{} {} {}", names::COIL, address, u8::from(value));
+        }
+        for (address, value) in self.discrete_inputs.entries() {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                names::DISCRETE_INPUT,
+                address,
+                u8::from(value)
+            );
+        }
+        for (address, value) in self.holding_registers.entries() {
+            let _ = writeln!(out, "{} {} {}", names::HOLDING_REGISTER, address, value);
+        }
+        for (address, value) in self.input_registers.entries() {
+            let _ = writeln!(out, "{} {} {}", names::INPUT_REGISTER, address, value);
+        }
+        out
+    }
+
+    fn load(&mut self, snapshot: &str) -> std::io::Result<()> {
+        fn parse_bit(value: &str) -> Option<bool> {
+            match value {
+                "0" => Some(false),
+                "1" => Some(true),
+                _ => None,
+            }
+        }
+
+        for (i, line) in snapshot.lines().enumerate() {
+            let line_number = i + 1;
+            let mut fields = line.split_whitespace();
+            let (kind, address, value) = match (fields.next(), fields.next(), fields.next()) {
+                (None, _, _) => continue,
+                (Some(kind), Some(address), Some(value)) if fields.next().is_none() => {
+                    (kind, address, value)
+                }
+                _ => return Err(invalid_snapshot(line_number, "expected 3 fields")),
+            };
+            let address: u16 = address
+                .parse()
+                .map_err(|_| invalid_snapshot(line_number, "bad address"))?;
+            let bad_value = || invalid_snapshot(line_number, "bad value");
+            let in_range = match kind {
+                names::COIL => self.set_coil(address, parse_bit(value).ok_or_else(bad_value)?),
+                names::DISCRETE_INPUT => {
+                    self.set_discrete_input(address, parse_bit(value).ok_or_else(bad_value)?)
+                }
+                names::HOLDING_REGISTER => {
+                    self.set_holding_register(address, value.parse().map_err(|_| bad_value())?)
+                }
+                names::INPUT_REGISTER => {
+                    self.set_input_register(address, value.parse().map_err(|_| bad_value())?)
+                }
+                _ => return Err(invalid_snapshot(line_number, "unknown point type")),
+            };
+            if !in_range {
+                tracing::warn!("ignoring {} {} outside of the database", kind, address);
+            }
+        }
+        Ok(())
+    }
+
+    /// Receive a [`DatabaseChange`] for every coil or holding register written by a client
+    ///
+    /// Every point of a request is reported, even if its value is unchanged. Updates made by the
+    /// application are not reported. A subscriber that falls more than 256 changes behind
+    /// receives [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<DatabaseChange> {
+        self.changes.subscribe()
+    }
+
+    /// Get the value of a coil, or `None` if the address is outside the configured range
+    pub fn get_coil(&self, address: u16) -> Option<bool> {
+        self.coils.get(address)
+    }
+
+    /// Get the value of a discrete input, or `None` if the address is outside the configured range
+    pub fn get_discrete_input(&self, address: u16) -> Option<bool> {
+        self.discrete_inputs.get(address)
+    }
+
+    /// Get the value of a holding register, or `None` if the address is outside the configured range
+    pub fn get_holding_register(&self, address: u16) -> Option<u16> {
+        self.holding_registers.get(address)
+    }
+
+    /// Get the value of an input register, or `None` if the address is outside the configured range
+    pub fn get_input_register(&self, address: u16) -> Option<u16> {
+        self.input_registers.get(address)
+    }
+
+    /// Set the value of a coil, returning `false` if the address is outside the configured range
+    pub fn set_coil(&mut self, address: u16, value: bool) -> bool {
+        self.coils.set(address, value)
+    }
+
+    /// Set the value of a discrete input, returning `false` if the address is outside the configured range
+    pub fn set_discrete_input(&mut self, address: u16, value: bool) -> bool {
+        self.discrete_inputs.set(address, value)
+    }
+
+    /// Set the value of a holding register, returning `false` if the address is outside the configured range
+    pub fn set_holding_register(&mut self, address: u16, value: u16) -> bool {
+        self.holding_registers.set(address, value)
+    }
+
+    /// Set the value of an input register, returning `false` if the address is outside the configured range
+    pub fn set_input_register(&mut self, address: u16, value: u16) -> bool {
+        self.input_registers.set(address, value)
+    }
+
+    /// Add a file of `record_count` records set to zero, replacing the file if it already exists
+    ///
+    /// Records past 0x270F, the highest record number of a request, can only be accessed by the
+    /// application. Files are not part of snapshots written by [`ServerDatabase::save`].
+    pub fn add_file(&mut self, file_number: u16, record_count: u16) {
+        self.files
+            .insert(file_number, vec![0; record_count as usize]);
+    }
+
+    /// Get the records of a file, or `None` if there is no such file
+    pub fn get_file(&self, file_number: u16) -> Option<&[u16]> {
+        self.files.get(&file_number).map(|x| x.as_slice())
+    }
+
+    /// Set the value of a record, returning `false` if the file or the record doesn't exist
+    pub fn set_file_record(&mut self, file_number: u16, record_number: u16, value: u16) -> bool {
+        match self
+            .files
+            .get_mut(&file_number)
+            .and_then(|x| x.get_mut(record_number as usize))
+        {
+            Some(record) => {
+                *record = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// records of a file addressed by a request, if they all exist
+    fn file_records(&self, file_number: u16, record_number: u16, count: usize) -> Option<&[u16]> {
+        let start = record_number as usize;
+        self.files.get(&file_number)?.get(start..start + count)
+    }
+
+    /// Add an empty FIFO queue at `address`, clearing the queue if it already exists
+    ///
+    /// FIFO queues are not part of snapshots written by [`ServerDatabase::save`].
+    pub fn add_fifo_queue(&mut self, address: u16) {
+        self.fifo_queues.insert(address, VecDeque::new());
+    }
+
+    /// Append a value to the FIFO queue at `address`
+    ///
+    /// Returns `false` if there is no queue at the address or if it already holds 31 values,
+    /// the most that fit in a Read FIFO Queue response.
+    pub fn push_fifo(&mut self, address: u16, value: u16) -> bool {
+        match self.fifo_queues.get_mut(&address) {
+            Some(queue) if queue.len() < MAX_READ_FIFO_COUNT as usize => {
+                queue.push_back(value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the values of the FIFO queue at `address`, oldest first, or `None` if there is no queue
+    pub fn get_fifo(&self, address: u16) -> Option<Vec<u16>> {
+        self.fifo_queues
+            .get(&address)
+            .map(|queue| queue.iter().copied().collect())
+    }
+}
+
+impl RequestHandler for ServerDatabase {
+    fn read_coil(&self, address: u16) -> Result<bool, ExceptionCode> {
+        self.coils.read(address)
+    }
+
+    fn read_discrete_input(&self, address: u16) -> Result<bool, ExceptionCode> {
+        self.discrete_inputs.read(address)
+    }
+
+    fn read_holding_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+        self.holding_registers.read(address)
+    }
+
+    fn read_input_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+        self.input_registers.read(address)
+    }
+
+    fn read_file_record(&self, range: FileRecordRange) -> Result<Vec<u16>, ExceptionCode> {
+        self.file_records(
+            range.file_number,
+            range.record_number,
+            range.record_length as usize,
+        )
+        .map(|x| x.to_vec())
+        .ok_or(ExceptionCode::IllegalDataAddress)
+    }
+
+    /// Nothing is written unless every record exists
+    fn write_file_records(&mut self, records: &[FileRecord]) -> Result<(), ExceptionCode> {
+        for record in records {
+            if self
+                .file_records(
+                    record.file_number,
+                    record.record_number,
+                    record.values.len(),
+                )
+                .is_none()
+            {
+                return Err(ExceptionCode::IllegalDataAddress);
+            }
+        }
+        for record in records {
+            // checked above
+            if let Some(file) = self.files.get_mut(&record.file_number) {
+                let start = record.record_number as usize;
+                file[start..start + record.values.len()].copy_from_slice(&record.values);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drain the queue, answering with [`ExceptionCode::IllegalDataAddress`] if there is none
+    fn read_fifo_queue(&mut self, address: u16) -> Result<Vec<u16>, ExceptionCode> {
+        self.fifo_queues
+            .get_mut(&address)
+            .map(|queue| queue.drain(..).collect())
+            .ok_or(ExceptionCode::IllegalDataAddress)
+    }
+
+    fn write_single_coil(&mut self, value: Indexed<bool>) -> Result<(), ExceptionCode> {
+        let changes = &self.changes;
+        self.coils.write(value, |address, old, new| {
+            let _ = changes.send(DatabaseChange::Coil { address, old, new });
+        })
+    }
+
+    fn write_single_register(&mut self, value: Indexed<u16>) -> Result<(), ExceptionCode> {
+        let changes = &self.changes;
+        self.holding_registers.write(value, |address, old, new| {
+            let _ = changes.send(DatabaseChange::HoldingRegister { address, old, new });
+        })
+    }
+
+    fn write_multiple_coils(&mut self, values: WriteCoils) -> Result<(), ExceptionCode> {
+        let changes = &self.changes;
+        self.coils
+            .write_all(values.range, values.iterator, |address, old, new| {
+                let _ = changes.send(DatabaseChange::Coil { address, old, new });
+            })
+    }
+
+    fn write_multiple_registers(&mut self, values: WriteRegisters) -> Result<(), ExceptionCode> {
+        let changes = &self.changes;
+        self.holding_registers
+            .write_all(values.range, values.iterator, |address, old, new| {
+                let _ = changes.send(DatabaseChange::HoldingRegister { address, old, new });
+            })
+    }
+}
+
+/// Thread-safe handle to a [`ServerDatabase`] shared with one or more servers
+///
+/// The handle may be cloned and moved to other threads so that the application can update
+/// values while the server is answering requests.
+#[derive(Clone, Debug)]
+pub struct DatabaseHandle {
+    inner: ServerHandlerType<ServerDatabase>,
+}
+
+impl DatabaseHandle {
+    /// Create a new database and return a handle to it
+    pub fn new(config: DatabaseConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Box::new(ServerDatabase::new(config)))),
+        }
+    }
+
+    /// Retrieve the database in the form expected by [`ServerHandlerMap`](crate::server::ServerHandlerMap)
+    pub fn handler(&self) -> ServerHandlerType<ServerDatabase> {
+        self.inner.clone()
+    }
+
+    /// Receive a [`DatabaseChange`] for every coil or holding register written by a client
+    ///
+    /// See [`ServerDatabase::subscribe`].
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<DatabaseChange> {
+        self.inner.lock().unwrap().subscribe()
+    }
+
+    /// Save the value of every point to a file, see [`ServerDatabase::save`]
+    ///
+    /// The database is only locked while the snapshot is taken, not while the file is written.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let snapshot = self.inner.lock().unwrap().snapshot();
+        write_snapshot(path, &snapshot)
+    }
+
+    /// Spawn a task that saves the database to a file every `interval` if any value changed
+    ///
+    /// The task stops when every handle to the database has been dropped. Call
+    /// [`DatabaseHandle::save`] before exiting to persist the latest values, and
+    /// [`ServerDatabase::restore`] at startup to load them.
+    ///
+    /// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+    pub fn spawn_snapshot_task(&self, path: PathBuf, interval: Duration) {
+        let database = Arc::downgrade(&self.inner);
+        let task = async move {
+            let mut last: Option<String> = None;
+            loop {
+                tokio::time::sleep(interval).await;
+                let snapshot = match database.upgrade() {
+                    None => return,
+                    Some(database) => database.lock().unwrap().snapshot(),
+                };
+                if last.as_ref() == Some(&snapshot) {
+                    continue;
+                }
+                let (path, contents) = (path.clone(), snapshot.clone());
+                match tokio::task::spawn_blocking(move || write_snapshot(&path, &contents)).await {
+                    Ok(Ok(())) => last = Some(snapshot),
+                    Ok(Err(err)) => tracing::warn!("unable to save database: {}", err),
+                    Err(err) => tracing::warn!("unable to save database: {}", err),
+                }
+            }
+        };
+        tokio::spawn(task.instrument(tracing::info_span!("Modbus-Database-Snapshot")));
+    }
+
+    /// Lock the database and apply a series of reads or updates atomically
+    ///
+    /// No request is processed by the server while the closure runs, so it should be short.
+    pub fn transaction<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce(&mut ServerDatabase) -> R,
+    {
+        let mut guard = self.inner.lock().unwrap();
+        func(guard.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle() -> DatabaseHandle {
+        DatabaseHandle::new(DatabaseConfig {
+            coils: Some(AddressRange::try_from(10, 5).unwrap()),
+            discrete_inputs: Some(AddressRange::try_from(0, 2).unwrap()),
+            holding_registers: Some(AddressRange::try_from(100, 3).unwrap()),
+            input_registers: None,
+        })
+    }
+
+    #[test]
+    fn reads_are_relative_to_start_address() {
+        let handler = handle().handler();
+        let db = handler.lock().unwrap();
+        assert_eq!(db.read_coil(9), Err(ExceptionCode::IllegalDataAddress));
+        assert_eq!(db.read_coil(10), Ok(false));
+        assert_eq!(db.read_coil(14), Ok(false));
+        assert_eq!(db.read_coil(15), Err(ExceptionCode::IllegalDataAddress));
+        assert_eq!(db.read_holding_register(102), Ok(0));
+        assert_eq!(
+            db.read_input_register(0),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+    }
+
+    #[test]
+    fn ranges_end_at_the_last_address() {
+        let mut db = ServerDatabase::new(DatabaseConfig {
+            coils: Some(AddressRange {
+                start: 0xFFFF,
+                count: 2,
+            }),
+            ..Default::default()
+        });
+        assert!(db.set_coil(0xFFFF, true));
+        assert!(!db.set_coil(0, true));
+        assert_eq!(db.get_coil(0), None);
+        assert_eq!(db.snapshot(), "coil 65535 1\n");
+    }
+
+    #[test]
+    fn application_updates_are_visible_to_the_server() {
+        let handle = handle();
+        let handler = handle.handler();
+        assert!(handle.transaction(|db| db.set_discrete_input(1, true)));
+        assert!(handle.transaction(|db| db.set_holding_register(101, 0xCAFE)));
+        assert!(!handle.transaction(|db| db.set_input_register(0, 1)));
+
+        let db = handler.lock().unwrap();
+        assert_eq!(db.read_discrete_input(1), Ok(true));
+        assert_eq!(db.read_holding_register(101), Ok(0xCAFE));
+    }
+
+    #[test]
+    fn client_writes_are_visible_to_the_application() {
+        let handle = handle();
+        handle
+            .handler()
+            .lock()
+            .unwrap()
+            .write_single_register(Indexed::new(100, 42))
+            .unwrap();
+        assert_eq!(
+            handle.transaction(|db| db.get_holding_register(100)),
+            Some(42)
+        );
+    }
+
+    #[test]
+    fn rejects_multiple_write_that_exceeds_range_without_applying_it() {
+        let mut db = ServerDatabase::new(DatabaseConfig {
+            holding_registers: Some(AddressRange::try_from(100, 3).unwrap()),
+            ..Default::default()
+        });
+        let data = [0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04];
+        let mut cursor = scursor::ReadCursor::new(&data);
+        let range = AddressRange::try_from(100, 4).unwrap();
+        let iterator = crate::types::RegisterIterator::parse_all(range, &mut cursor).unwrap();
+        let result = db.write_multiple_registers(WriteRegisters::new(range, iterator));
+        assert_eq!(result, Err(ExceptionCode::IllegalDataAddress));
+        assert_eq!(db.get_holding_register(100), Some(0));
+    }
+
+    #[test]
+    fn reads_and_writes_file_records() {
+        let mut db = ServerDatabase::new(DatabaseConfig::default());
+        db.add_file(4, 10);
+        assert!(db.set_file_record(4, 1, 0x0DFE));
+        assert!(!db.set_file_record(4, 10, 0));
+        assert!(!db.set_file_record(5, 0, 0));
+
+        assert_eq!(
+            db.read_file_record(FileRecordRange::new(4, 1, 2)),
+            Ok(vec![0x0DFE, 0x0000])
+        );
+        assert_eq!(
+            db.read_file_record(FileRecordRange::new(4, 9, 2)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+        assert_eq!(
+            db.read_file_record(FileRecordRange::new(3, 0, 1)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+
+        // the first record isn't written because the second doesn't fit
+        let records = [
+            FileRecord::new(4, 0, vec![0x1111]),
+            FileRecord::new(4, 9, vec![0x2222, 0x3333]),
+        ];
+        assert_eq!(
+            db.write_file_records(&records),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+        assert_eq!(db.get_file(4).unwrap()[0], 0x0000);
+
+        assert_eq!(db.write_file_records(&records[..1]), Ok(()));
+        assert_eq!(db.get_file(4).unwrap()[..2], [0x1111, 0x0DFE]);
+    }
+
+    #[test]
+    fn clients_drain_fifo_queues() {
+        let mut db = ServerDatabase::new(DatabaseConfig::default());
+        assert!(!db.push_fifo(7, 1));
+        db.add_fifo_queue(7);
+        for value in 0..31 {
+            assert!(db.push_fifo(7, value));
+        }
+        assert!(!db.push_fifo(7, 31));
+        assert_eq!(db.get_fifo(7).map(|x| x.len()), Some(31));
+
+        let values = db.read_fifo_queue(7).unwrap();
+        assert_eq!(values, (0..31).collect::<Vec<u16>>());
+        assert_eq!(db.read_fifo_queue(7), Ok(Vec::new()));
+        assert_eq!(
+            db.read_fifo_queue(8),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+    }
+
+    #[test]
+    fn reports_client_writes_to_subscribers() {
+        let handle = handle();
+        let mut changes = handle.subscribe();
+
+        // application updates are not reported
+        handle.transaction(|db| db.set_coil(10, true));
+
+        let handler = handle.handler();
+        let mut db = handler.lock().unwrap();
+        db.write_single_coil(Indexed::new(10, false)).unwrap();
+        db.write_single_register(Indexed::new(102, 7)).unwrap();
+        assert_eq!(
+            db.write_single_coil(Indexed::new(20, true)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            DatabaseChange::Coil {
+                address: 10,
+                old: true,
+                new: false
+            }
+        );
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            DatabaseChange::HoldingRegister {
+                address: 102,
+                old: 0,
+                new: 7
+            }
+        );
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let handle = handle();
+        handle.transaction(|db| {
+            db.set_coil(11, true);
+            db.set_discrete_input(1, true);
+            db.set_holding_register(102, 0xCAFE);
+        });
+        let snapshot = handle.transaction(|db| db.snapshot());
+        assert!(snapshot.contains("coil 11 1\n"));
+        assert!(snapshot.contains("holding_register 102 51966\n"));
+
+        let restored = self::handle();
+        restored.transaction(|db| db.load(&snapshot)).unwrap();
+        restored.transaction(|db| {
+            assert_eq!(db.get_coil(11), Some(true));
+            assert_eq!(db.get_coil(10), Some(false));
+            assert_eq!(db.get_discrete_input(1), Some(true));
+            assert_eq!(db.get_holding_register(102), Some(0xCAFE));
+        });
+    }
+
+    #[test]
+    fn snapshots_tables_that_end_at_the_last_address() {
+        let handle = DatabaseHandle::new(DatabaseConfig {
+            holding_registers: Some(AddressRange::try_from(0xFFF0, 16).unwrap()),
+            ..DatabaseConfig::default()
+        });
+        handle.transaction(|db| db.set_holding_register(0xFFFF, 42));
+        let snapshot = handle.transaction(|db| db.snapshot());
+        assert!(snapshot.contains("holding_register 65520 0\n"));
+        assert!(snapshot.contains("holding_register 65535 42\n"));
+    }
+
+    #[test]
+    fn restore_ignores_points_outside_database() {
+        let mut db = ServerDatabase::new(DatabaseConfig::default());
+        db.load("coil 3 1\n\ninput_register 7 42\n").unwrap();
+        assert_eq!(db.get_coil(3), None);
+    }
+
+    #[test]
+    fn restore_rejects_malformed_snapshot() {
+        let mut db = ServerDatabase::new(DatabaseConfig::default());
+        for snapshot in [
+            "coil 1",
+            "coil 1 2",
+            "coil x 1",
+            "holding_register 1 65536",
+            "register 1 1",
+            "coil 1 1 1",
+        ] {
+            let err = db.load(snapshot).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn saves_and_restores_file() {
+        let path = std::env::temp_dir().join(format!("rodbus-db-{}.txt", std::process::id()));
+        let handle = handle();
+        handle.transaction(|db| db.set_holding_register(100, 1234));
+        handle.save(&path).unwrap();
+
+        let restored = self::handle();
+        restored.transaction(|db| db.restore(&path)).unwrap();
+        assert_eq!(
+            restored.transaction(|db| db.get_holding_register(100)),
+            Some(1234)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use crate::common::random::random_u64;
+
+/// Delay applied by a server before answering requests with a given function code
+///
+/// This is meant for testing, e.g. to exercise the timeout and retry logic of a client
+/// against a server that answers slowly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseDelay {
+    /// Always wait for the same duration
+    Fixed(Duration),
+    /// Wait for a duration picked uniformly at random between `min` and `max` for each request
+    Random {
+        /// Shortest delay
+        min: Duration,
+        /// Longest delay
+        max: Duration,
+    },
+}
+
+impl ResponseDelay {
+    /// duration to wait before answering the next request
+    pub(crate) fn next(&self) -> Duration {
+        match *self {
+            ResponseDelay::Fixed(x) => x,
+            ResponseDelay::Random { min, max } => {
+                let (min, max) = (min.min(max), min.max(max));
+                let span = u64::try_from((max - min).as_nanos()).unwrap_or(u64::MAX);
+                min + Duration::from_nanos(random_u64() % span.saturating_add(1))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_delay_is_constant() {
+        let delay = ResponseDelay::Fixed(Duration::from_millis(250));
+        assert_eq!(delay.next(), Duration::from_millis(250));
+        assert_eq!(delay.next(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn random_delay_is_within_bounds() {
+        let min = Duration::from_millis(10);
+        let max = Duration::from_millis(20);
+        for _ in 0..100 {
+            let delay = ResponseDelay::Random { min, max }.next();
+            assert!(delay >= min && delay <= max, "{delay:?}");
+            // bounds given in the wrong order are swapped
+            let delay = ResponseDelay::Random { min: max, max: min }.next();
+            assert!(delay >= min && delay <= max, "{delay:?}");
+        }
+    }
+}
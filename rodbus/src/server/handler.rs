@@ -1,7 +1,13 @@
 use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
+use crate::device_id::DeviceIdentification;
 use crate::exception::ExceptionCode;
+use crate::server::delay::ResponseDelay;
+use crate::server::metrics::{ServerMetrics, SharedMetrics};
+use crate::server::observer::ServerObserver;
+use crate::server::rate_limit::{RateLimit, TokenBucket};
 use crate::server::{WriteCoils, WriteRegisters};
 use crate::types::*;
 
@@ -62,6 +68,111 @@ pub trait RequestHandler: Send + 'static {
     fn write_multiple_registers(&mut self, _values: WriteRegisters) -> Result<(), ExceptionCode> {
         Err(ExceptionCode::IllegalFunction)
     }
+
+    /// Read the registers of a file record (FC20)
+    ///
+    /// The handler is called once for each sub-request. Returning fewer or more values than
+    /// `range.record_length` results in [`ExceptionCode::ServerDeviceFailure`].
+    fn read_file_record(&self, _range: FileRecordRange) -> Result<Vec<u16>, ExceptionCode> {
+        Err(ExceptionCode::IllegalFunction)
+    }
+
+    /// Write the records of a Write File Record (FC21) request
+    ///
+    /// All the sub-requests are passed at once so that an implementation can reject the
+    /// request without applying any of them.
+    fn write_file_records(&mut self, _records: &[FileRecord]) -> Result<(), ExceptionCode> {
+        Err(ExceptionCode::IllegalFunction)
+    }
+
+    /// Read the contents of the FIFO queue at `address` (FC24)
+    ///
+    /// The values are returned oldest first. Reading a queue usually removes its contents, which
+    /// is why this method takes `&mut self`. The server answers with
+    /// [`ExceptionCode::IllegalDataValue`] if more than 31 values are returned.
+    fn read_fifo_queue(&mut self, _address: u16) -> Result<Vec<u16>, ExceptionCode> {
+        Err(ExceptionCode::IllegalFunction)
+    }
+
+    /// Objects returned to Read Device Identification (FC43 / MEI type 14) requests
+    ///
+    /// The server answers stream and individual access requests from these objects, splitting
+    /// them across several responses when they don't fit in one. The conformity level is derived
+    /// from the object ids, so the `conformity_level` field is ignored.
+    fn read_device_identification(&self) -> Result<&DeviceIdentification, ExceptionCode> {
+        Err(ExceptionCode::IllegalFunction)
+    }
+
+    /// Process a request with a function code the library doesn't implement, e.g. a vendor extension
+    ///
+    /// `data` is the request PDU following the function code. The returned bytes are sent back
+    /// following the same function code and may not exceed 252 bytes. Broadcast requests are
+    /// never passed to this method, nor are FC43 requests with MEI type 14, which are answered
+    /// from [`RequestHandler::read_device_identification`].
+    ///
+    /// Note: RTU framing depends on the function code, so on RTU servers only function codes that
+    /// the library knows how to frame reach this method.
+    fn process_raw_pdu(&mut self, _function: u8, _data: &[u8]) -> Result<Vec<u8>, ExceptionCode> {
+        Err(ExceptionCode::IllegalFunction)
+    }
+}
+
+/// Allows a [`ServerHandlerMap`] to hold different handler types for different unit ids
+impl<T> RequestHandler for Box<T>
+where
+    T: RequestHandler + ?Sized,
+{
+    fn read_coil(&self, address: u16) -> Result<bool, ExceptionCode> {
+        self.as_ref().read_coil(address)
+    }
+
+    fn read_discrete_input(&self, address: u16) -> Result<bool, ExceptionCode> {
+        self.as_ref().read_discrete_input(address)
+    }
+
+    fn read_holding_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+        self.as_ref().read_holding_register(address)
+    }
+
+    fn read_input_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+        self.as_ref().read_input_register(address)
+    }
+
+    fn write_single_coil(&mut self, value: Indexed<bool>) -> Result<(), ExceptionCode> {
+        self.as_mut().write_single_coil(value)
+    }
+
+    fn write_single_register(&mut self, value: Indexed<u16>) -> Result<(), ExceptionCode> {
+        self.as_mut().write_single_register(value)
+    }
+
+    fn write_multiple_coils(&mut self, values: WriteCoils) -> Result<(), ExceptionCode> {
+        self.as_mut().write_multiple_coils(values)
+    }
+
+    fn write_multiple_registers(&mut self, values: WriteRegisters) -> Result<(), ExceptionCode> {
+        self.as_mut().write_multiple_registers(values)
+    }
+
+    fn read_file_record(&self, range: FileRecordRange) -> Result<Vec<u16>, ExceptionCode> {
+        self.as_ref().read_file_record(range)
+    }
+
+    fn write_file_records(&mut self, records: &[FileRecord]) -> Result<(), ExceptionCode> {
+        self.as_mut().write_file_records(records)
+    }
+
+    fn read_fifo_queue(&mut self, address: u16) -> Result<Vec<u16>, ExceptionCode> {
+        self.as_mut().read_fifo_queue(address)
+    }
+
+    fn read_device_identification(&self) -> Result<&DeviceIdentification, ExceptionCode> {
+        self.as_ref().read_device_identification()
+    }
+
+    fn process_raw_pdu(&mut self, function: u8, data: &[u8]) -> Result<Vec<u8>, ExceptionCode> {
+        self.as_mut().process_raw_pdu(function, data)
+    }
 }
 
 /// Trait useful for converting None into IllegalDataAddress
@@ -85,11 +196,132 @@ where
 /// Server handler boxed inside a `Arc<Mutex>`.
 pub type ServerHandlerType<T> = Arc<Mutex<Box<T>>>;
 
+/// Controls how the server answers requests addressed to a unit id without a handler
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum UnmappedUnitIdPolicy {
+    /// Don't respond, as a device on a multi-drop serial line would
+    #[default]
+    Ignore,
+    /// Respond with [`ExceptionCode::GatewayTargetDeviceFailedToRespond`], as a gateway would
+    RespondGatewayTargetFailed,
+}
+
+/// Identity of the client that sent a request
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Peer {
+    addr: Option<SocketAddr>,
+    role: Option<String>,
+}
+
+impl Peer {
+    pub(crate) fn new(addr: Option<SocketAddr>, role: Option<String>) -> Self {
+        Self { addr, role }
+    }
+
+    /// Remote address of the client, or `None` on serial lines
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    /// Role extracted from the client certificate by a Modbus Security server
+    pub fn role(&self) -> Option<&str> {
+        self.role.as_deref()
+    }
+}
+
+/// Write function passed to a [`WriteAuthorizationHandler`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WriteFunction {
+    /// Write Single Coil (0x05)
+    WriteSingleCoil,
+    /// Write Single Register (0x06)
+    WriteSingleRegister,
+    /// Write Multiple Coils (0x0F)
+    WriteMultipleCoils,
+    /// Write Multiple Registers (0x10)
+    WriteMultipleRegisters,
+}
+
+impl WriteFunction {
+    /// Function code on the wire
+    pub fn code(self) -> u8 {
+        match self {
+            WriteFunction::WriteSingleCoil => 0x05,
+            WriteFunction::WriteSingleRegister => 0x06,
+            WriteFunction::WriteMultipleCoils => 0x0F,
+            WriteFunction::WriteMultipleRegisters => 0x10,
+        }
+    }
+}
+
+/// Hook called before a write request is passed to a [`RequestHandler`]
+///
+/// Unlike [`AuthorizationHandler`], it applies to every kind of server, sees the remote
+/// address of the client and chooses the exception returned when a write is rejected.
+pub trait WriteAuthorizationHandler: Send + Sync + 'static {
+    /// Moves a write authorization handler into an `Arc` suitable for passing to
+    /// [`ServerHandlerMap::set_write_authorization`]
+    fn wrap(self) -> Arc<dyn WriteAuthorizationHandler>
+    where
+        Self: Sized,
+    {
+        Arc::new(self)
+    }
+
+    /// Allow the write, or reject it with an exception such as [`ExceptionCode::IllegalFunction`]
+    /// or [`ExceptionCode::IllegalDataAddress`]
+    ///
+    /// Single writes are described by a range with a count of 1. Rejected broadcast writes
+    /// are discarded without a response.
+    fn authorize_write(
+        &self,
+        unit_id: UnitId,
+        function: WriteFunction,
+        range: AddressRange,
+        peer: &Peer,
+    ) -> Result<(), ExceptionCode>;
+}
+
 /// Type that hides the underlying map implementation
 /// and allows lookups of a [`RequestHandler`] from a [`UnitId`]
-#[derive(Debug, Default)]
+///
+/// A map of `Box<dyn RequestHandler>` can be used to serve different handler types
+/// behind the same server.
 pub struct ServerHandlerMap<T: RequestHandler> {
     handlers: BTreeMap<UnitId, ServerHandlerType<T>>,
+    /// shared handlers that replace `handlers` whenever their generation changes
+    registry: Option<UnitRegistry<T>>,
+    generation: u64,
+    unmapped: UnmappedUnitIdPolicy,
+    broadcast: bool,
+    lenient_coils: bool,
+    write_auth: Option<Arc<dyn WriteAuthorizationHandler>>,
+    session_rate_limit: Option<RateLimit>,
+    global_rate_limit: Option<Arc<Mutex<TokenBucket>>>,
+    response_delays: BTreeMap<u8, ResponseDelay>,
+    observer: Option<Arc<dyn ServerObserver>>,
+    metrics: Option<SharedMetrics>,
+}
+
+impl<T> std::fmt::Debug for ServerHandlerMap<T>
+where
+    T: RequestHandler,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerHandlerMap")
+            .field("unit_ids", &self.handlers.keys().collect::<Vec<_>>())
+            .field("registry", &self.registry.is_some())
+            .field("unmapped", &self.unmapped)
+            .field("broadcast", &self.broadcast)
+            .field("lenient_coils", &self.lenient_coils)
+            .field("write_auth", &self.write_auth.is_some())
+            .field("session_rate_limit", &self.session_rate_limit)
+            .field("global_rate_limit", &self.global_rate_limit.is_some())
+            .field("response_delays", &self.response_delays)
+            .field("observer", &self.observer.is_some())
+            .field("metrics", &self.metrics.is_some())
+            .finish()
+    }
 }
 
 // this couldn't be derived automatically
@@ -101,10 +333,30 @@ where
     fn clone(&self) -> Self {
         ServerHandlerMap {
             handlers: self.handlers.clone(),
+            registry: self.registry.clone(),
+            generation: self.generation,
+            unmapped: self.unmapped,
+            broadcast: self.broadcast,
+            lenient_coils: self.lenient_coils,
+            write_auth: self.write_auth.clone(),
+            session_rate_limit: self.session_rate_limit,
+            global_rate_limit: self.global_rate_limit.clone(),
+            response_delays: self.response_delays.clone(),
+            observer: self.observer.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
 
+impl<T> Default for ServerHandlerMap<T>
+where
+    T: RequestHandler,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> ServerHandlerMap<T>
 where
     T: RequestHandler,
@@ -113,6 +365,17 @@ where
     pub fn new() -> Self {
         Self {
             handlers: BTreeMap::new(),
+            registry: None,
+            generation: 0,
+            unmapped: UnmappedUnitIdPolicy::Ignore,
+            broadcast: true,
+            lenient_coils: false,
+            write_auth: None,
+            session_rate_limit: None,
+            global_rate_limit: None,
+            response_delays: BTreeMap::new(),
+            observer: None,
+            metrics: None,
         }
     }
 
@@ -120,28 +383,273 @@ where
     pub fn single(id: UnitId, handler: ServerHandlerType<T>) -> Self {
         let mut map: BTreeMap<UnitId, ServerHandlerType<T>> = BTreeMap::new();
         map.insert(id, handler);
-        Self { handlers: map }
+        Self {
+            handlers: map,
+            registry: None,
+            generation: 0,
+            unmapped: UnmappedUnitIdPolicy::Ignore,
+            broadcast: true,
+            lenient_coils: false,
+            write_auth: None,
+            session_rate_limit: None,
+            global_rate_limit: None,
+            response_delays: BTreeMap::new(),
+            observer: None,
+            metrics: None,
+        }
     }
 
     /// Retrieve a mutable reference to a [`RequestHandler`]
     pub fn get(&mut self, id: UnitId) -> Option<&mut ServerHandlerType<T>> {
+        self.sync();
         self.handlers.get_mut(&id)
     }
 
     /// Add a handler to the map
+    ///
+    /// Once a [`UnitRegistry`] has been created, the handler is added to the registry and
+    /// therefore also to the running servers that share it.
     pub fn add(
         &mut self,
         id: UnitId,
         server: ServerHandlerType<T>,
     ) -> Option<ServerHandlerType<T>> {
-        self.handlers.insert(id, server)
+        match &self.registry {
+            Some(registry) => registry.add(id, server),
+            None => self.handlers.insert(id, server),
+        }
+    }
+
+    /// Retrieve a registry that adds and removes handlers while the server runs
+    ///
+    /// The registry starts with the handlers currently in the map and is shared by every
+    /// clone of the map, i.e. by every session of the servers spawned with it. Changes apply
+    /// to the next request processed by each session.
+    pub fn registry(&mut self) -> UnitRegistry<T> {
+        let handlers = &self.handlers;
+        self.registry
+            .get_or_insert_with(|| UnitRegistry::new(handlers.clone()))
+            .clone()
+    }
+
+    /// replace the handlers with those of the registry if it changed
+    fn sync(&mut self) {
+        if let Some(registry) = &self.registry {
+            let units = registry.inner.lock().unwrap();
+            if units.generation != self.generation {
+                self.handlers = units.handlers.clone();
+                self.generation = units.generation;
+            }
+        }
+    }
+
+    /// Set how requests for unit ids that aren't in the map are answered
+    ///
+    /// Defaults to [`UnmappedUnitIdPolicy::Ignore`].
+    pub fn set_unmapped_unit_id_policy(&mut self, policy: UnmappedUnitIdPolicy) {
+        self.unmapped = policy;
+    }
+
+    pub(crate) fn unmapped_unit_id_policy(&self) -> UnmappedUnitIdPolicy {
+        self.unmapped
+    }
+
+    /// Enable or disable processing of broadcast requests
+    ///
+    /// When enabled (the default), write requests sent to the broadcast unit id are applied to
+    /// every handler in the map and never answered. When disabled, they are silently discarded.
+    pub fn set_broadcast_enabled(&mut self, enabled: bool) {
+        self.broadcast = enabled;
+    }
+
+    pub(crate) fn is_broadcast_enabled(&self) -> bool {
+        self.broadcast
+    }
+
+    /// Accept 0x0001 as ON in Write Single Coil requests
+    ///
+    /// By default, only 0xFF00 (ON) and 0x0000 (OFF) are accepted as required by the
+    /// specification and other values are answered with [`ExceptionCode::IllegalDataValue`].
+    /// Some clients send 0x0001 instead of 0xFF00, which this setting tolerates. The response
+    /// then contains 0xFF00 rather than an exact echo of the request.
+    pub fn set_lenient_coil_values(&mut self, enabled: bool) {
+        self.lenient_coils = enabled;
+    }
+
+    pub(crate) fn lenient_coil_values(&self) -> bool {
+        self.lenient_coils
+    }
+
+    /// Set a hook that authorizes every write request before it is applied
+    pub fn set_write_authorization(&mut self, handler: Arc<dyn WriteAuthorizationHandler>) {
+        self.write_auth = Some(handler);
+    }
+
+    /// Set an observer notified of the connections and requests processed by the server
+    pub fn set_observer(&mut self, observer: Arc<dyn ServerObserver>) {
+        self.observer = Some(observer);
+    }
+
+    pub(crate) fn observer(&self) -> Option<&Arc<dyn ServerObserver>> {
+        self.observer.as_ref()
+    }
+
+    /// Limit the rate of requests processed by each session, or remove the limit with `None`
+    pub fn set_session_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.session_rate_limit = limit;
+    }
+
+    /// Limit the rate of requests processed by all the sessions of a server together,
+    /// or remove the limit with `None`
+    ///
+    /// The limit is shared by the servers spawned with clones of this map.
+    pub fn set_global_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.global_rate_limit = limit.map(|x| Arc::new(Mutex::new(TokenBucket::new(x))));
+    }
+
+    /// Delay the responses to requests with a function code, or remove the delay with `None`
+    ///
+    /// This is intended for testing clients against slow servers. The delay is applied before
+    /// the request is processed, to both normal and exception responses. Broadcast requests
+    /// are not delayed.
+    pub fn set_response_delay(&mut self, function: u8, delay: Option<ResponseDelay>) {
+        match delay {
+            Some(delay) => self.response_delays.insert(function, delay),
+            None => self.response_delays.remove(&function),
+        };
+    }
+
+    pub(crate) fn response_delay(&self, function: u8) -> Option<std::time::Duration> {
+        self.response_delays.get(&function).map(|x| x.next())
+    }
+
+    /// attach a new metrics store shared by the sessions spawned with the returned map
+    pub(crate) fn with_new_metrics(mut self) -> (Self, SharedMetrics) {
+        let metrics = ServerMetrics::shared();
+        self.metrics = Some(metrics.clone());
+        (self, metrics)
+    }
+
+    /// record the outcome of a request in the metrics of the server, if any
+    pub(crate) fn record_metrics(
+        &self,
+        peer: &Peer,
+        function: u8,
+        exception: bool,
+        malformed: bool,
+    ) {
+        if let Some(metrics) = &self.metrics {
+            metrics.lock().unwrap().record(
+                peer.addr().map(|x| x.ip()),
+                function,
+                exception,
+                malformed,
+            );
+        }
+    }
+
+    pub(crate) fn session_rate_limit(&self) -> Option<RateLimit> {
+        self.session_rate_limit
+    }
+
+    /// consume a token from the global bucket, if any
+    pub(crate) fn take_global_token(&self, now: std::time::Instant) -> bool {
+        match &self.global_rate_limit {
+            None => true,
+            Some(bucket) => bucket.lock().unwrap().try_take(now),
+        }
+    }
+
+    pub(crate) fn write_authorization(&self) -> Option<&Arc<dyn WriteAuthorizationHandler>> {
+        self.write_auth.as_ref()
     }
 
     pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut ServerHandlerType<T>> {
+        self.sync();
         self.handlers.values_mut()
     }
 }
 
+/// Handle used to add and remove the [`RequestHandler`] of unit ids while a server runs,
+/// e.g. to emulate devices that come and go
+///
+/// It is retrieved from [`ServerHandlerMap::registry`] before spawning the server.
+pub struct UnitRegistry<T: RequestHandler> {
+    inner: Arc<Mutex<Units<T>>>,
+}
+
+struct Units<T: RequestHandler> {
+    /// incremented on every change so that sessions know when to refresh their handlers
+    generation: u64,
+    handlers: BTreeMap<UnitId, ServerHandlerType<T>>,
+}
+
+impl<T> std::fmt::Debug for UnitRegistry<T>
+where
+    T: RequestHandler,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnitRegistry")
+            .field("unit_ids", &self.unit_ids())
+            .finish()
+    }
+}
+
+impl<T> Clone for UnitRegistry<T>
+where
+    T: RequestHandler,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> UnitRegistry<T>
+where
+    T: RequestHandler,
+{
+    fn new(handlers: BTreeMap<UnitId, ServerHandlerType<T>>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Units {
+                generation: 0,
+                handlers,
+            })),
+        }
+    }
+
+    /// Add the handler of a unit id, returning the handler it replaces if any
+    pub fn add(&self, id: UnitId, handler: ServerHandlerType<T>) -> Option<ServerHandlerType<T>> {
+        let mut units = self.inner.lock().unwrap();
+        units.generation = units.generation.wrapping_add(1);
+        units.handlers.insert(id, handler)
+    }
+
+    /// Remove the handler of a unit id, returning it if it was present
+    ///
+    /// Requests for the unit id are then answered according to the [`UnmappedUnitIdPolicy`].
+    pub fn remove(&self, id: UnitId) -> Option<ServerHandlerType<T>> {
+        let mut units = self.inner.lock().unwrap();
+        let removed = units.handlers.remove(&id);
+        if removed.is_some() {
+            units.generation = units.generation.wrapping_add(1);
+        }
+        removed
+    }
+
+    /// Unit ids that currently have a handler
+    pub fn unit_ids(&self) -> Vec<UnitId> {
+        self.inner
+            .lock()
+            .unwrap()
+            .handlers
+            .keys()
+            .copied()
+            .collect()
+    }
+}
+
 /// Authorization result
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Authorization {
@@ -226,6 +734,47 @@ pub trait AuthorizationHandler: Send + Sync + 'static {
     ) -> Authorization {
         Authorization::Deny
     }
+
+    /// Authorize a Read File Record request
+    fn read_file_records(
+        &self,
+        _unit_id: UnitId,
+        _ranges: &[FileRecordRange],
+        _role: &str,
+    ) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a Write File Record request
+    fn write_file_records(
+        &self,
+        _unit_id: UnitId,
+        _records: &[FileRecord],
+        _role: &str,
+    ) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a Read FIFO Queue request
+    fn read_fifo_queue(&self, _unit_id: UnitId, _address: u16, _role: &str) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a Read Device Identification request
+    fn read_device_identification(&self, _unit_id: UnitId, _role: &str) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a Diagnostics or Get Comm Event Counter request answered from the
+    /// diagnostic counters of the server
+    fn diagnostics(&self, _unit_id: UnitId, _role: &str) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a request with a function code passed to [`RequestHandler::process_raw_pdu`]
+    fn raw_pdu(&self, _unit_id: UnitId, _function: u8, _role: &str) -> Authorization {
+        Authorization::Deny
+    }
 }
 
 /// Read-only authorization handler that blindly accepts
@@ -304,6 +853,36 @@ impl AuthorizationHandler for ReadOnlyAuthorizationHandler {
     ) -> Authorization {
         Authorization::Deny
     }
+
+    /// Authorize a Read File Record request
+    fn read_file_records(
+        &self,
+        _unit_id: UnitId,
+        _ranges: &[FileRecordRange],
+        _role: &str,
+    ) -> Authorization {
+        Authorization::Allow
+    }
+
+    /// Authorize a Write File Record request
+    fn write_file_records(
+        &self,
+        _unit_id: UnitId,
+        _records: &[FileRecord],
+        _role: &str,
+    ) -> Authorization {
+        Authorization::Deny
+    }
+
+    /// Authorize a Read FIFO Queue request
+    fn read_fifo_queue(&self, _unit_id: UnitId, _address: u16, _role: &str) -> Authorization {
+        Authorization::Allow
+    }
+
+    /// Authorize a Read Device Identification request
+    fn read_device_identification(&self, _unit_id: UnitId, _role: &str) -> Authorization {
+        Authorization::Allow
+    }
 }
 
 #[cfg(test)]
@@ -346,4 +925,47 @@ mod tests {
         assert!(map.add(UnitId::new(2), DefaultHandler {}.wrap()).is_none());
         assert!(map.add(UnitId::new(1), DefaultHandler {}.wrap()).is_some());
     }
+
+    struct CoilHandler;
+    impl RequestHandler for CoilHandler {
+        fn read_coil(&self, _address: u16) -> Result<bool, ExceptionCode> {
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn registry_changes_apply_to_every_clone_of_the_map() {
+        let mut map = ServerHandlerMap::single(UnitId::new(1), DefaultHandler {}.wrap());
+        let registry = map.registry();
+        let mut session = map.clone();
+        assert_eq!(registry.unit_ids(), [UnitId::new(1)]);
+
+        assert!(registry
+            .add(UnitId::new(2), DefaultHandler {}.wrap())
+            .is_none());
+        assert!(session.get(UnitId::new(2)).is_some());
+        assert!(map.add(UnitId::new(3), DefaultHandler {}.wrap()).is_none());
+        assert!(session.get(UnitId::new(3)).is_some());
+
+        assert!(registry.remove(UnitId::new(1)).is_some());
+        assert!(registry.remove(UnitId::new(1)).is_none());
+        assert!(session.get(UnitId::new(1)).is_none());
+        assert_eq!(session.iter_mut().count(), 2);
+        assert_eq!(registry.unit_ids(), [UnitId::new(2), UnitId::new(3)]);
+    }
+
+    #[test]
+    fn server_handler_map_can_hold_different_handler_types() {
+        let mut map: ServerHandlerMap<Box<dyn RequestHandler>> = ServerHandlerMap::new();
+        let default: Box<dyn RequestHandler> = Box::new(DefaultHandler);
+        let coils: Box<dyn RequestHandler> = Box::new(CoilHandler);
+        map.add(UnitId::new(1), default.wrap());
+        map.add(UnitId::new(2), coils.wrap());
+
+        let handler = map.get(UnitId::new(1)).unwrap().lock().unwrap();
+        assert_eq!(handler.read_coil(0), Err(ExceptionCode::IllegalFunction));
+        drop(handler);
+        let handler = map.get(UnitId::new(2)).unwrap().lock().unwrap();
+        assert_eq!(handler.read_coil(0), Ok(true));
+    }
 }
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+/// Counters of the requests with a function code processed by a server
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionMetrics {
+    /// Number of requests received, including malformed ones
+    pub requests: u64,
+    /// Number of requests answered with an exception
+    pub exceptions: u64,
+    /// Number of requests whose body could not be parsed
+    pub malformed: u64,
+}
+
+/// Snapshot of the request counters of a server, keyed by the IP address of the client and
+/// the function code
+///
+/// Serial servers have no client address, so their requests are recorded under `None`.
+/// Frames too malformed to carry a function code, e.g. with a bad MBAP header or CRC, aren't
+/// counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerMetrics {
+    counters: BTreeMap<(Option<IpAddr>, u8), FunctionMetrics>,
+}
+
+/// metrics shared by the sessions of a server and its handle
+pub(crate) type SharedMetrics = Arc<Mutex<ServerMetrics>>;
+
+impl std::ops::AddAssign for FunctionMetrics {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.exceptions += other.exceptions;
+        self.malformed += other.malformed;
+    }
+}
+
+impl ServerMetrics {
+    pub(crate) fn shared() -> SharedMetrics {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    /// Counters of the requests with `function` sent by `peer`
+    pub fn get(&self, peer: Option<IpAddr>, function: u8) -> FunctionMetrics {
+        self.counters
+            .get(&(peer, function))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Counters of the requests with `function` sent by every client
+    pub fn function(&self, function: u8) -> FunctionMetrics {
+        let mut total = FunctionMetrics::default();
+        for (_, _, metrics) in self.iter().filter(|(_, x, _)| *x == function) {
+            total += metrics;
+        }
+        total
+    }
+
+    /// Counters of each client address and function code for which a request was received
+    pub fn iter(&self) -> impl Iterator<Item = (Option<IpAddr>, u8, FunctionMetrics)> + '_ {
+        self.counters
+            .iter()
+            .map(|((peer, function), metrics)| (*peer, *function, *metrics))
+    }
+
+    /// record the outcome of a request
+    pub(crate) fn record(
+        &mut self,
+        peer: Option<IpAddr>,
+        function: u8,
+        exception: bool,
+        malformed: bool,
+    ) {
+        let metrics = self.counters.entry((peer, function)).or_default();
+        metrics.requests += 1;
+        metrics.exceptions += u64::from(exception);
+        metrics.malformed += u64::from(malformed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_requests_per_peer_and_function() {
+        let a = Some(IpAddr::from([10, 0, 0, 1]));
+        let b = Some(IpAddr::from([10, 0, 0, 2]));
+        let mut metrics = ServerMetrics::default();
+        metrics.record(a, 0x03, false, false);
+        metrics.record(a, 0x03, true, true);
+        metrics.record(b, 0x03, true, false);
+        metrics.record(b, 0x01, false, false);
+
+        assert_eq!(
+            metrics.get(a, 0x03),
+            FunctionMetrics {
+                requests: 2,
+                exceptions: 1,
+                malformed: 1
+            }
+        );
+        assert_eq!(metrics.get(None, 0x03), FunctionMetrics::default());
+        assert_eq!(
+            metrics.function(0x03),
+            FunctionMetrics {
+                requests: 3,
+                exceptions: 2,
+                malformed: 1
+            }
+        );
+        assert_eq!(metrics.iter().count(), 3);
+    }
+}
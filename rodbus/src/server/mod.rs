@@ -8,7 +8,15 @@ use crate::tcp::server::{ServerTask, TcpServerConnectionHandler};
 
 /// server handling
 mod address_filter;
+#[cfg(feature = "config")]
+mod config;
+pub(crate) mod counters;
+mod database;
+pub(crate) mod delay;
 pub(crate) mod handler;
+pub(crate) mod metrics;
+pub(crate) mod observer;
+pub(crate) mod rate_limit;
 pub(crate) mod request;
 pub(crate) mod response;
 pub(crate) mod task;
@@ -20,7 +28,14 @@ pub(crate) const SERVER_SETTING_CHANNEL_CAPACITY: usize = 8;
 use crate::error::Shutdown;
 
 pub use address_filter::*;
+#[cfg(feature = "config")]
+pub use config::*;
+pub use database::*;
+pub use delay::ResponseDelay;
 pub use handler::*;
+pub use metrics::{FunctionMetrics, ServerMetrics};
+pub use observer::{CloseReason, RequestOutcome, ServerObserver};
+pub use rate_limit::RateLimit;
 pub use types::*;
 
 // re-export to the public API
@@ -33,6 +48,8 @@ pub use crate::tcp::tls::*;
 #[derive(Debug)]
 pub struct ServerHandle {
     tx: tokio::sync::mpsc::Sender<ServerSetting>,
+    done: Option<tokio::sync::oneshot::Receiver<()>>,
+    metrics: metrics::SharedMetrics,
 }
 
 impl ServerHandle {
@@ -40,7 +57,50 @@ impl ServerHandle {
     ///
     /// This function is only required for the C bindings
     pub fn new(tx: tokio::sync::mpsc::Sender<ServerSetting>) -> Self {
-        ServerHandle { tx }
+        ServerHandle {
+            tx,
+            done: None,
+            metrics: ServerMetrics::shared(),
+        }
+    }
+
+    /// spawn the server task and return a handle that can wait for its completion
+    fn spawn<F>(
+        tx: tokio::sync::mpsc::Sender<ServerSetting>,
+        metrics: metrics::SharedMetrics,
+        task: F,
+    ) -> Self
+    where
+        F: std::future::Future + Send + 'static,
+    {
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            task.await;
+            // dropping the sender completes the receiver
+            drop(done_tx);
+        });
+        ServerHandle {
+            tx,
+            done: Some(done_rx),
+            metrics,
+        }
+    }
+
+    /// Snapshot of the counters of the requests processed by the server so far
+    pub fn metrics(&self) -> ServerMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    /// Shut down the server and wait until it has been torn down
+    ///
+    /// The server stops accepting connections, each session finishes the request it is
+    /// processing, and all sockets or the serial port are closed before this returns.
+    pub async fn shutdown(self) {
+        let Self { tx, done, .. } = self;
+        drop(tx);
+        if let Some(done) = done {
+            let _ = done.await;
+        }
     }
 
     /// Change the decoding level for future sessions and all active sessions
@@ -48,6 +108,74 @@ impl ServerHandle {
         self.tx.send(ServerSetting::ChangeDecoding(level)).await?;
         Ok(())
     }
+
+    /// Override the decoding level of sessions with a particular client address, or restore
+    /// the server-wide level with `None`
+    ///
+    /// This applies to active sessions and to future sessions from the same address, making it
+    /// possible to trace the traffic of a single client among many. Log events of each session
+    /// are tagged with the remote address. It has no effect on serial servers.
+    pub async fn set_peer_decode_level(
+        &mut self,
+        ip: std::net::IpAddr,
+        level: Option<DecodeLevel>,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(ServerSetting::ChangePeerDecoding(ip, level))
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the filter that decides which client addresses may connect
+    ///
+    /// The filter is evaluated when a connection is accepted, so active sessions are not affected.
+    /// It has no effect on serial servers.
+    pub async fn set_address_filter(&mut self, filter: AddressFilter) -> Result<(), Shutdown> {
+        self.tx
+            .send(ServerSetting::ChangeAddressFilter(filter))
+            .await?;
+        Ok(())
+    }
+
+    /// Change what happens when a connection is accepted while `max_sessions` are active
+    ///
+    /// Defaults to [`SessionLimitPolicy::CloseOldest`]. It has no effect on serial servers.
+    pub async fn set_session_limit_policy(
+        &mut self,
+        policy: SessionLimitPolicy,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(ServerSetting::ChangeSessionLimitPolicy(policy))
+            .await?;
+        Ok(())
+    }
+
+    /// Close sessions that receive no request for longer than `timeout`, or never close idle
+    /// sessions with `None` (the default)
+    ///
+    /// This frees the resources of clients that connect once and never poll. The timeout
+    /// applies to active sessions as well as to sessions accepted afterwards, and sessions
+    /// closed this way are reported to the [`ServerObserver`] with [`CloseReason::IdleTimeout`].
+    /// It has no effect on serial servers.
+    pub async fn set_idle_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), Shutdown> {
+        self.tx
+            .send(ServerSetting::ChangeIdleTimeout(timeout))
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the TLS configuration, e.g. to rotate the certificate and private key
+    ///
+    /// Active sessions are not affected. The new configuration is used for sessions accepted
+    /// afterwards. It has no effect on servers that don't use TLS.
+    #[cfg(feature = "tls")]
+    pub async fn set_tls_config(&mut self, config: TlsServerConfig) -> Result<(), Shutdown> {
+        self.tx.send(ServerSetting::ChangeTlsConfig(config)).await?;
+        Ok(())
+    }
 }
 
 /// Spawns a TCP server task onto the runtime. This method can only
@@ -69,6 +197,7 @@ pub async fn spawn_tcp_server_task<T: RequestHandler>(
     filter: AddressFilter,
     decode: DecodeLevel,
 ) -> Result<ServerHandle, std::io::Error> {
+    let (handlers, metrics) = handlers.with_new_metrics();
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     let (tx, rx) = tokio::sync::mpsc::channel(SERVER_SETTING_CHANNEL_CAPACITY);
@@ -87,15 +216,62 @@ pub async fn spawn_tcp_server_task<T: RequestHandler>(
         .await;
     };
 
-    tokio::spawn(task);
+    Ok(ServerHandle::spawn(tx, metrics, task))
+}
+
+/// Spawns a server task onto the runtime that answers MBAP framed requests received as UDP
+/// datagrams.
+///
+/// Each datagram must contain a single request, which is answered with a datagram sent back
+/// to its source. Datagrams that don't contain a valid ADU are discarded. A clone of the map
+/// passed to [`spawn_tcp_server_task`] can be used to serve the same handlers over both transports.
+///
+/// The server processes the requests of every client in order, as a single session. Its
+/// diagnostic counters are shared by all the clients and the connection callbacks of the
+/// [`ServerObserver`] are never invoked.
+///
+/// * `addr` - A socket address to bound to
+/// * `handlers` - A map of handlers keyed by a unit id
+/// * `decode` - Decode log level
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+pub async fn spawn_udp_server_task<T: RequestHandler>(
+    addr: SocketAddr,
+    handlers: ServerHandlerMap<T>,
+    decode: DecodeLevel,
+) -> Result<ServerHandle, std::io::Error> {
+    let (handlers, metrics) = handlers.with_new_metrics();
+    let socket = tokio::net::UdpSocket::bind(addr).await?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(SERVER_SETTING_CHANNEL_CAPACITY);
+    let session = crate::server::task::SessionTask::new(
+        handlers,
+        crate::server::task::AuthorizationType::None,
+        crate::common::frame::FrameWriter::tcp(),
+        crate::common::frame::FramedReader::udp(),
+        rx,
+        decode,
+    );
+
+    let mut server = crate::udp::server::UdpServerTask {
+        socket: crate::common::phys::PhysLayer::new_udp_server(socket),
+        session,
+    };
+
+    let task = async move {
+        server
+            .run()
+            .instrument(tracing::info_span!("Modbus-Server-UDP", "listen" = ?addr))
+            .await;
+    };
 
-    Ok(ServerHandle::new(tx))
+    Ok(ServerHandle::spawn(tx, metrics, task))
 }
 
 /// Spawns a RTU server task onto the runtime.
 ///
 /// * `path` - Path to the serial device. Generally `/dev/tty0` on Linux and `COM1` on Windows.
-/// * `settings` - Serial port settings
+/// * `settings` - Serial port settings. An error is returned if they are invalid for the framing
 /// * `retry` - A boxed trait object that controls when opening the serial port is retried after a failure
 /// * `handlers` - A map of handlers keyed by a unit id
 /// * `decode` - Decode log level
@@ -109,34 +285,88 @@ pub fn spawn_rtu_server_task<T: RequestHandler>(
     handlers: ServerHandlerMap<T>,
     decode: DecodeLevel,
 ) -> Result<ServerHandle, std::io::Error> {
+    spawn_serial_server_task(
+        path,
+        settings,
+        crate::types::Framing::Rtu,
+        retry,
+        handlers,
+        decode,
+    )
+}
+
+/// Spawns a Modbus ASCII server task onto the runtime.
+///
+/// * `path` - Path to the serial device. Generally `/dev/tty0` on Linux and `COM1` on Windows.
+/// * `settings` - Serial port settings. An error is returned if they are invalid for the framing
+/// * `retry` - A boxed trait object that controls when opening the serial port is retried after a failure
+/// * `handlers` - A map of handlers keyed by a unit id
+/// * `decode` - Decode log level
+///
+/// `WARNING`: This function must be called from with the context of the Tokio runtime or it will panic.
+#[cfg(feature = "serial")]
+pub fn spawn_ascii_server_task<T: RequestHandler>(
+    path: &str,
+    settings: crate::serial::SerialSettings,
+    retry: Box<dyn crate::retry::RetryStrategy>,
+    handlers: ServerHandlerMap<T>,
+    decode: DecodeLevel,
+) -> Result<ServerHandle, std::io::Error> {
+    spawn_serial_server_task(
+        path,
+        settings,
+        crate::types::Framing::Ascii,
+        retry,
+        handlers,
+        decode,
+    )
+}
+
+#[cfg(feature = "serial")]
+fn spawn_serial_server_task<T: RequestHandler>(
+    path: &str,
+    settings: crate::serial::SerialSettings,
+    framing: crate::types::Framing,
+    retry: Box<dyn crate::retry::RetryStrategy>,
+    handlers: ServerHandlerMap<T>,
+    decode: DecodeLevel,
+) -> Result<ServerHandle, std::io::Error> {
+    let (handlers, metrics) = handlers.with_new_metrics();
+    settings.validate(framing)?;
+
     let (tx, rx) = tokio::sync::mpsc::channel(SERVER_SETTING_CHANNEL_CAPACITY);
     let session = crate::server::task::SessionTask::new(
         handlers,
         crate::server::task::AuthorizationType::None,
-        crate::common::frame::FrameWriter::rtu(),
-        crate::common::frame::FramedReader::rtu_request(),
+        framing.writer(),
+        framing.request_reader(),
         rx,
         decode,
     );
 
-    let mut rtu = crate::serial::server::RtuServerTask {
+    let mut server = crate::serial::server::SerialServerTask {
         port: path.to_string(),
         retry,
         settings,
+        framing,
         session,
     };
 
-    let path = path.to_string();
-
-    let task = async move {
-        rtu.run()
-            .instrument(tracing::info_span!("Modbus-Server-RTU", "port" = ?path))
-            .await
+    let span = match framing {
+        crate::types::Framing::Rtu => {
+            tracing::info_span!("Modbus-Server-RTU", "port" = ?path)
+        }
+        crate::types::Framing::Ascii => {
+            tracing::info_span!("Modbus-Server-ASCII", "port" = ?path)
+        }
+        crate::types::Framing::Mbap => {
+            tracing::info_span!("Modbus-Server-Serial", "port" = ?path)
+        }
     };
 
-    tokio::spawn(task);
+    let task = async move { server.run().instrument(span).await };
 
-    Ok(ServerHandle::new(tx))
+    Ok(ServerHandle::spawn(tx, metrics, task))
 }
 
 /// Spawns a "raw" TLS server task onto the runtime. This TLS server does NOT require that
@@ -222,6 +452,7 @@ async fn spawn_tls_server_task_impl<T: RequestHandler>(
     filter: AddressFilter,
     decode: DecodeLevel,
 ) -> Result<ServerHandle, std::io::Error> {
+    let (handlers, metrics) = handlers.with_new_metrics();
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     let (tx, rx) = tokio::sync::mpsc::channel(SERVER_SETTING_CHANNEL_CAPACITY);
@@ -240,7 +471,5 @@ async fn spawn_tls_server_task_impl<T: RequestHandler>(
         .await
     };
 
-    tokio::spawn(task);
-
-    Ok(ServerHandle::new(tx))
+    Ok(ServerHandle::spawn(tx, metrics, task))
 }
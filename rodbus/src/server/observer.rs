@@ -0,0 +1,119 @@
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use crate::error::RequestError;
+use crate::exception::ExceptionCode;
+use crate::server::handler::Peer;
+
+/// Reason a connection to a server was closed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The client closed the connection
+    ClientDisconnected,
+    /// The connection was closed to make room for a new one, see [`SessionLimitPolicy`](crate::server::SessionLimitPolicy)
+    SessionLimit,
+    /// The client sent nothing for longer than the idle timeout, see
+    /// [`ServerHandle::set_idle_timeout`](crate::server::ServerHandle::set_idle_timeout)
+    IdleTimeout,
+    /// The server was shut down
+    Shutdown,
+    /// The connection failed, e.g. because of an I/O error, a malformed frame or a failed TLS handshake
+    Error(String),
+}
+
+/// Outcome of a request processed by a server
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// A normal response was sent
+    Response,
+    /// An exception response was sent
+    Exception(ExceptionCode),
+    /// No response was sent, e.g. to a broadcast request or a request for another unit id
+    NoResponse,
+}
+
+/// Observer of the connections accepted and the requests processed by a server, e.g. to
+/// audit who connects to it
+///
+/// Every method does nothing by default. The connection callbacks are only called by TCP
+/// and TLS servers. Callbacks are invoked from the server tasks and should return quickly.
+pub trait ServerObserver: Send + Sync + 'static {
+    /// Moves an observer into an `Arc` suitable for passing to
+    /// [`ServerHandlerMap::set_observer`](crate::server::ServerHandlerMap::set_observer)
+    fn wrap(self) -> Arc<dyn ServerObserver>
+    where
+        Self: Sized,
+    {
+        Arc::new(self)
+    }
+
+    /// A connection passed the address filter and the session limit
+    ///
+    /// The role of the peer is not known yet, as the TLS handshake hasn't been performed.
+    fn connection_accepted(&self, _peer: &Peer) {}
+
+    /// A connection previously reported by [`ServerObserver::connection_accepted`] was closed
+    fn connection_closed(&self, _peer: &Peer, _reason: &CloseReason) {}
+
+    /// A request with the function code `function` was processed
+    fn request_handled(&self, _peer: &Peer, _function: u8, _outcome: RequestOutcome) {}
+}
+
+impl From<RequestError> for CloseReason {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::Shutdown => CloseReason::Shutdown,
+            RequestError::Io(
+                ErrorKind::UnexpectedEof
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe,
+            ) => CloseReason::ClientDisconnected,
+            err => CloseReason::Error(err.to_string()),
+        }
+    }
+}
+
+impl RequestOutcome {
+    /// outcome of the response recorded by the diagnostic counters
+    pub(crate) fn new(response: Option<Option<ExceptionCode>>) -> Self {
+        match response {
+            None => RequestOutcome::NoResponse,
+            Some(None) => RequestOutcome::Response,
+            Some(Some(ex)) => RequestOutcome::Exception(ex),
+        }
+    }
+}
+
+impl std::fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CloseReason::ClientDisconnected => f.write_str("closed by the client"),
+            CloseReason::SessionLimit => f.write_str("closed to respect the session limit"),
+            CloseReason::IdleTimeout => f.write_str("idle timeout"),
+            CloseReason::Shutdown => f.write_str("server shutdown"),
+            CloseReason::Error(err) => f.write_str(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_session_errors() {
+        assert_eq!(
+            CloseReason::from(RequestError::Io(ErrorKind::UnexpectedEof)),
+            CloseReason::ClientDisconnected
+        );
+        assert_eq!(
+            CloseReason::from(RequestError::Shutdown),
+            CloseReason::Shutdown
+        );
+        assert_eq!(
+            CloseReason::from(RequestError::Io(ErrorKind::TimedOut)),
+            CloseReason::Error(RequestError::Io(ErrorKind::TimedOut).to_string())
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+/// Token bucket rate limit applied to requests received by a server
+///
+/// Up to `burst` requests are accepted back-to-back, after which requests are accepted at
+/// `requests_per_second` on average. Other requests are answered with
+/// [`ExceptionCode::ServerDeviceBusy`](crate::ExceptionCode::ServerDeviceBusy).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Rate at which the bucket is refilled
+    pub requests_per_second: f64,
+    /// Capacity of the bucket
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Create a rate limit
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+        }
+    }
+}
+
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// consume a token if one is available
+    pub(crate) fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens =
+            (self.tokens + elapsed * self.limit.requests_per_second).min(self.limit.burst as f64);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn allows_burst_then_refills_at_rate() {
+        let mut bucket = TokenBucket::new(RateLimit::new(10.0, 3));
+        let start = bucket.last;
+        assert!(bucket.try_take(start));
+        assert!(bucket.try_take(start));
+        assert!(bucket.try_take(start));
+        assert!(!bucket.try_take(start));
+
+        let later = start + Duration::from_millis(100);
+        assert!(bucket.try_take(later));
+        assert!(!bucket.try_take(later));
+    }
+
+    #[test]
+    fn refill_is_capped_at_burst() {
+        let mut bucket = TokenBucket::new(RateLimit::new(100.0, 2));
+        let later = bucket.last + Duration::from_secs(60);
+        assert!(bucket.try_take(later));
+        assert!(bucket.try_take(later));
+        assert!(!bucket.try_take(later));
+    }
+}
@@ -1,11 +1,19 @@
+use crate::client::requests::read_device_id::MEI_TYPE_READ_DEVICE_ID;
+use crate::client::requests::read_file_record::ReadFileRecords;
+use crate::client::requests::write_file_record::WriteFileRecords;
 use crate::common::frame::{FrameHeader, FrameWriter, FunctionField};
 use crate::common::function::FunctionCode;
 use crate::common::traits::{Loggable, Parse, Serialize};
 use crate::decode::AppDecodeLevel;
+use crate::diagnostic::{DiagnosticCounter, DiagnosticSubFunction};
 use crate::error::RequestError;
 use crate::exception::ExceptionCode;
+use crate::server::counters::DiagnosticCounters;
 use crate::server::handler::RequestHandler;
-use crate::server::response::{BitWriter, RegisterWriter};
+use crate::server::response::{
+    BitWriter, DeviceIdWriter, DiagnosticsWriter, FifoWriter, FileRecordEchoWriter,
+    FileRecordWriter, RegisterWriter,
+};
 use crate::server::*;
 use crate::types::*;
 
@@ -21,6 +29,28 @@ pub(crate) enum Request<'a> {
     WriteSingleRegister(Indexed<u16>),
     WriteMultipleCoils(WriteCoils<'a>),
     WriteMultipleRegisters(WriteRegisters<'a>),
+    ReadDeviceIdentification(ReadDeviceId),
+    Diagnostics(DiagnosticsRequest),
+    GetCommEventCounter,
+    GetCommEventLog,
+    ReadFileRecord(Vec<FileRecordRange>),
+    WriteFileRecord(Vec<FileRecord>),
+    ReadFifoQueue(u16),
+}
+
+/// Diagnostics sub-functions answered by the server itself
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum DiagnosticsRequest {
+    /// clear the counters, echoing the data
+    ClearCounters(u16),
+    ReadCounter(DiagnosticCounter),
+}
+
+/// Read Device Identification request following the MEI type
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ReadDeviceId {
+    pub(crate) read_device_id_code: u8,
+    pub(crate) object_id: u8,
 }
 
 /// All requests that support broadcast
@@ -31,6 +61,7 @@ pub(crate) enum BroadcastRequest<'a> {
     WriteSingleRegister(Indexed<u16>),
     WriteMultipleCoils(WriteCoils<'a>),
     WriteMultipleRegisters(WriteRegisters<'a>),
+    WriteFileRecord(Vec<FileRecord>),
 }
 
 impl<'a> BroadcastRequest<'a> {
@@ -49,6 +80,9 @@ impl<'a> BroadcastRequest<'a> {
             BroadcastRequest::WriteMultipleRegisters(x) => {
                 let _ = handler.write_multiple_registers(*x);
             }
+            BroadcastRequest::WriteFileRecord(x) => {
+                let _ = handler.write_file_records(x);
+            }
         }
     }
 }
@@ -64,6 +98,42 @@ impl<'a> Request<'a> {
             Request::WriteSingleRegister(_) => FunctionCode::WriteSingleRegister,
             Request::WriteMultipleCoils(_) => FunctionCode::WriteMultipleCoils,
             Request::WriteMultipleRegisters(_) => FunctionCode::WriteMultipleRegisters,
+            Request::ReadDeviceIdentification(_) => FunctionCode::EncapsulatedInterfaceTransport,
+            Request::Diagnostics(_) => FunctionCode::Diagnostics,
+            Request::GetCommEventCounter => FunctionCode::GetCommEventCounter,
+            Request::GetCommEventLog => FunctionCode::GetCommEventLog,
+            Request::ReadFileRecord(_) => FunctionCode::ReadFileRecord,
+            Request::WriteFileRecord(_) => FunctionCode::WriteFileRecord,
+            Request::ReadFifoQueue(_) => FunctionCode::ReadFifoQueue,
+        }
+    }
+
+    pub(crate) fn get_write(&self) -> Option<(WriteFunction, AddressRange)> {
+        let single = |index| AddressRange {
+            start: index,
+            count: 1,
+        };
+        match self {
+            Request::ReadCoils(_) => None,
+            Request::ReadDiscreteInputs(_) => None,
+            Request::ReadHoldingRegisters(_) => None,
+            Request::ReadInputRegisters(_) => None,
+            Request::WriteSingleCoil(x) => Some((WriteFunction::WriteSingleCoil, single(x.index))),
+            Request::WriteSingleRegister(x) => {
+                Some((WriteFunction::WriteSingleRegister, single(x.index)))
+            }
+            Request::WriteMultipleCoils(x) => Some((WriteFunction::WriteMultipleCoils, x.range)),
+            Request::WriteMultipleRegisters(x) => {
+                Some((WriteFunction::WriteMultipleRegisters, x.range))
+            }
+            Request::ReadDeviceIdentification(_) => None,
+            Request::Diagnostics(_) => None,
+            Request::GetCommEventCounter => None,
+            Request::GetCommEventLog => None,
+            // file records aren't addressed by an AddressRange
+            Request::ReadFileRecord(_) => None,
+            Request::WriteFileRecord(_) => None,
+            Request::ReadFifoQueue(_) => None,
         }
     }
 
@@ -77,6 +147,13 @@ impl<'a> Request<'a> {
             Request::WriteSingleRegister(x) => Some(BroadcastRequest::WriteSingleRegister(x)),
             Request::WriteMultipleCoils(x) => Some(BroadcastRequest::WriteMultipleCoils(x)),
             Request::WriteMultipleRegisters(x) => Some(BroadcastRequest::WriteMultipleRegisters(x)),
+            Request::ReadDeviceIdentification(_) => None,
+            Request::Diagnostics(_) => None,
+            Request::GetCommEventCounter => None,
+            Request::GetCommEventLog => None,
+            Request::ReadFileRecord(_) => None,
+            Request::WriteFileRecord(x) => Some(BroadcastRequest::WriteFileRecord(x)),
+            Request::ReadFifoQueue(_) => None,
         }
     }
 
@@ -84,6 +161,7 @@ impl<'a> Request<'a> {
         &self,
         header: FrameHeader,
         handler: &mut dyn RequestHandler,
+        counters: &mut DiagnosticCounters,
         writer: &'b mut FrameWriter,
         level: DecodeLevel,
     ) -> Result<&'b [u8], RequestError> {
@@ -141,12 +219,83 @@ impl<'a> Request<'a> {
                     .map(|_| items.range);
                 write_result(function, header, writer, result, level)
             }
+            Request::ReadDeviceIdentification(request) => {
+                let result = handler
+                    .read_device_identification()
+                    .and_then(|x| DeviceIdWriter::new(x, *request));
+                write_result(function, header, writer, result, level)
+            }
+            Request::Diagnostics(DiagnosticsRequest::ClearCounters(data)) => {
+                counters.clear();
+                let response = DiagnosticsWriter::new(DiagnosticSubFunction::ClearCounters, *data);
+                writer.format_reply(header, function, &response, level)
+            }
+            Request::Diagnostics(DiagnosticsRequest::ReadCounter(counter)) => {
+                let response = DiagnosticsWriter::new((*counter).into(), counters.get(*counter));
+                writer.format_reply(header, function, &response, level)
+            }
+            Request::GetCommEventCounter => {
+                let response = CommEventCounter::new(0x0000, counters.event_count());
+                writer.format_reply(header, function, &response, level)
+            }
+            Request::GetCommEventLog => {
+                writer.format_reply(header, function, &counters.event_log(), level)
+            }
+            Request::ReadFileRecord(ranges) => {
+                let result = FileRecordWriter::new(ranges, |x| handler.read_file_record(x));
+                write_result(function, header, writer, result, level)
+            }
+            Request::WriteFileRecord(records) => {
+                let result = handler
+                    .write_file_records(records)
+                    .map(|_| FileRecordEchoWriter::new(records));
+                write_result(function, header, writer, result, level)
+            }
+            Request::ReadFifoQueue(address) => {
+                let result = handler.read_fifo_queue(*address).and_then(FifoWriter::new);
+                write_result(function, header, writer, result, level)
+            }
+        }
+    }
+
+    /// requests parsed by the server, others are passed to the handler as raw PDUs
+    pub(crate) fn is_implemented(function: FunctionCode, data: &[u8]) -> bool {
+        match function {
+            FunctionCode::ReadCoils
+            | FunctionCode::ReadDiscreteInputs
+            | FunctionCode::ReadHoldingRegisters
+            | FunctionCode::ReadInputRegisters
+            | FunctionCode::WriteSingleCoil
+            | FunctionCode::WriteSingleRegister
+            | FunctionCode::WriteMultipleCoils
+            | FunctionCode::WriteMultipleRegisters
+            | FunctionCode::GetCommEventCounter
+            | FunctionCode::GetCommEventLog
+            | FunctionCode::ReadFileRecord
+            | FunctionCode::WriteFileRecord
+            | FunctionCode::ReadFifoQueue => true,
+            // other sub-functions are passed to the handler
+            FunctionCode::Diagnostics => match data {
+                [high, low, ..] => {
+                    let sub_function = u16::from_be_bytes([*high, *low]);
+                    sub_function == u16::from(DiagnosticSubFunction::ClearCounters)
+                        || DiagnosticCounter::from_sub_function(sub_function).is_some()
+                }
+                _ => false,
+            },
+            // other MEI types are passed to the handler
+            FunctionCode::EncapsulatedInterfaceTransport => {
+                data.first() == Some(&MEI_TYPE_READ_DEVICE_ID)
+            }
+            _ => false,
         }
     }
 
+    /// `lenient_coils` accepts 0x0001 as ON in Write Single Coil requests
     pub(crate) fn parse(
         function: FunctionCode,
         cursor: &'a mut ReadCursor,
+        lenient_coils: bool,
     ) -> Result<Self, RequestError> {
         match function {
             FunctionCode::ReadCoils => {
@@ -173,7 +322,12 @@ impl<'a> Request<'a> {
                 Ok(x)
             }
             FunctionCode::WriteSingleCoil => {
-                let x = Request::WriteSingleCoil(Indexed::<bool>::parse(cursor)?);
+                let index = cursor.read_u16_be()?;
+                let value = match cursor.read_u16_be()? {
+                    crate::constants::coil::LENIENT_ON if lenient_coils => true,
+                    x => coil_from_u16(x)?,
+                };
+                let x = Request::WriteSingleCoil(Indexed::new(index, value));
                 cursor.expect_empty()?;
                 Ok(x)
             }
@@ -183,7 +337,7 @@ impl<'a> Request<'a> {
                 Ok(x)
             }
             FunctionCode::WriteMultipleCoils => {
-                let range = AddressRange::parse(cursor)?;
+                let range = AddressRange::parse(cursor)?.of_write_coils()?;
                 // don't care about the count, validated b/c all bytes are consumed
                 cursor.read_u8()?;
                 Ok(Request::WriteMultipleCoils(WriteCoils::new(
@@ -192,7 +346,7 @@ impl<'a> Request<'a> {
                 )))
             }
             FunctionCode::WriteMultipleRegisters => {
-                let range = AddressRange::parse(cursor)?;
+                let range = AddressRange::parse(cursor)?.of_write_registers()?;
                 // don't care about the count, validated b/c all bytes are consumed
                 cursor.read_u8()?;
                 Ok(Request::WriteMultipleRegisters(WriteRegisters::new(
@@ -200,6 +354,64 @@ impl<'a> Request<'a> {
                     RegisterIterator::parse_all(range, cursor)?,
                 )))
             }
+            FunctionCode::EncapsulatedInterfaceTransport => {
+                if cursor.read_u8()? != MEI_TYPE_READ_DEVICE_ID {
+                    return Err(RequestError::Exception(ExceptionCode::IllegalFunction));
+                }
+                let x = Request::ReadDeviceIdentification(ReadDeviceId {
+                    read_device_id_code: cursor.read_u8()?,
+                    object_id: cursor.read_u8()?,
+                });
+                cursor.expect_empty()?;
+                Ok(x)
+            }
+            FunctionCode::Diagnostics => {
+                let sub_function = cursor.read_u16_be()?;
+                let data = cursor.read_u16_be()?;
+                cursor.expect_empty()?;
+                if sub_function == u16::from(DiagnosticSubFunction::ClearCounters) {
+                    return Ok(Request::Diagnostics(DiagnosticsRequest::ClearCounters(
+                        data,
+                    )));
+                }
+                match DiagnosticCounter::from_sub_function(sub_function) {
+                    Some(counter) => Ok(Request::Diagnostics(DiagnosticsRequest::ReadCounter(
+                        counter,
+                    ))),
+                    None => Err(RequestError::Exception(ExceptionCode::IllegalFunction)),
+                }
+            }
+            FunctionCode::GetCommEventCounter => {
+                cursor.expect_empty()?;
+                Ok(Request::GetCommEventCounter)
+            }
+            FunctionCode::GetCommEventLog => {
+                cursor.expect_empty()?;
+                Ok(Request::GetCommEventLog)
+            }
+            FunctionCode::ReadFileRecord => {
+                let ranges = ReadFileRecords::parse_request(cursor)?;
+                // the response must fit in a PDU
+                ReadFileRecords::validate(&ranges)?;
+                Ok(Request::ReadFileRecord(ranges))
+            }
+            FunctionCode::WriteFileRecord => {
+                let records = WriteFileRecords::parse_records(cursor)?;
+                WriteFileRecords::validate(&records)?;
+                Ok(Request::WriteFileRecord(records))
+            }
+            FunctionCode::ReadFifoQueue => {
+                let x = Request::ReadFifoQueue(cursor.read_u16_be()?);
+                cursor.expect_empty()?;
+                Ok(x)
+            }
+            // never parsed: `is_implemented` passes these to `RequestHandler::process_raw_pdu`
+            FunctionCode::ReadExceptionStatus
+            | FunctionCode::ReportServerId
+            | FunctionCode::MaskWriteRegister
+            | FunctionCode::ReadWriteMultipleRegisters => {
+                Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+            }
         }
     }
 }
@@ -253,6 +465,44 @@ impl std::fmt::Display for RequestDisplay<'_, '_> {
                         RegisterIteratorDisplay::new(self.level, items.iterator)
                     )?;
                 }
+                Request::ReadDeviceIdentification(request) => {
+                    write!(
+                        f,
+                        " read device id code: {:#04X} object id: {:#04X}",
+                        request.read_device_id_code, request.object_id
+                    )?;
+                }
+                Request::Diagnostics(DiagnosticsRequest::ClearCounters(data)) => {
+                    write!(
+                        f,
+                        " {} data: {:#06X}",
+                        DiagnosticSubFunction::ClearCounters,
+                        data
+                    )?;
+                }
+                Request::Diagnostics(DiagnosticsRequest::ReadCounter(counter)) => {
+                    write!(f, " {}", DiagnosticSubFunction::from(*counter))?;
+                }
+                Request::GetCommEventCounter => {}
+                Request::GetCommEventLog => {}
+                Request::ReadFileRecord(ranges) => {
+                    for range in ranges {
+                        write!(f, "\n{range}")?;
+                    }
+                }
+                Request::WriteFileRecord(records) => {
+                    for record in records {
+                        write!(f, "\n{record}")?;
+                        if self.level.data_values() {
+                            for value in record.values.iter() {
+                                write!(f, "\nvalue: {value:#06X}")?;
+                            }
+                        }
+                    }
+                }
+                Request::ReadFifoQueue(address) => {
+                    write!(f, " address: {address:#06X}")?;
+                }
             }
         }
 
@@ -266,13 +516,13 @@ mod tests {
         use scursor::ReadCursor;
 
         use super::super::*;
-        use crate::error::AduParseError;
+        use crate::error::{AduParseError, InvalidRange};
         use crate::types::Indexed;
 
         #[test]
         fn fails_when_too_few_bytes_for_coil_byte_count() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x08, 0x00]);
-            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
@@ -281,7 +531,7 @@ mod tests {
         #[test]
         fn fails_when_too_many_bytes_for_coil_byte_count() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x08, 0x02]);
-            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
@@ -290,7 +540,7 @@ mod tests {
         #[test]
         fn fails_when_specified_byte_count_not_present() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x08, 0x01]);
-            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
@@ -299,16 +549,29 @@ mod tests {
         #[test]
         fn fails_when_too_many_bytes_present() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x08, 0x01, 0xFF, 0xFF]);
-            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::TrailingBytes(1).into());
         }
 
+        #[test]
+        fn fails_when_count_exceeds_write_limit() {
+            let mut cursor = ReadCursor::new(&[0x00, 0x00, 0x07, 0xB1, 0xF7]);
+            let err = Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
+                .err()
+                .unwrap();
+            assert_eq!(
+                err,
+                RequestError::BadRequest(InvalidRange::CountTooLargeForType(0x07B1, 0x07B0).into())
+            );
+        }
+
         #[test]
         fn can_parse_coils() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x03, 0x01, 0x05]);
-            let coils = match Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor).unwrap()
+            let coils = match Request::parse(FunctionCode::WriteMultipleCoils, &mut cursor, false)
+                .unwrap()
             {
                 Request::WriteMultipleCoils(write) => write,
                 _ => panic!("bad match"),
@@ -330,13 +593,13 @@ mod tests {
         use scursor::ReadCursor;
 
         use super::super::*;
-        use crate::error::AduParseError;
+        use crate::error::{AduParseError, InvalidRange};
         use crate::types::Indexed;
 
         #[test]
         fn fails_when_too_few_bytes_for_coil_byte_count() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x01, 0x00]);
-            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
@@ -345,16 +608,28 @@ mod tests {
         #[test]
         fn fails_when_too_many_bytes_for_coil_byte_count() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x01, 0x03]);
-            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
         }
 
+        #[test]
+        fn fails_when_count_exceeds_write_limit() {
+            let mut cursor = ReadCursor::new(&[0x00, 0x00, 0x00, 0x7C, 0xF8]);
+            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
+                .err()
+                .unwrap();
+            assert_eq!(
+                err,
+                RequestError::BadRequest(InvalidRange::CountTooLargeForType(0x7C, 0x7B).into())
+            );
+        }
+
         #[test]
         fn fails_when_specified_byte_count_not_present() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x01, 0x02, 0xFF]);
-            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::InsufficientBytes.into());
@@ -363,7 +638,7 @@ mod tests {
         #[test]
         fn fails_when_too_many_bytes_present() {
             let mut cursor = ReadCursor::new(&[0x00, 0x01, 0x00, 0x01, 0x02, 0xFF, 0xFF, 0xFF]);
-            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor)
+            let err = Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
                 .err()
                 .unwrap();
             assert_eq!(err, AduParseError::TrailingBytes(1).into());
@@ -374,7 +649,9 @@ mod tests {
             let mut cursor =
                 ReadCursor::new(&[0x00, 0x01, 0x00, 0x02, 0x04, 0xCA, 0xFE, 0xBB, 0xDD]);
             let registers =
-                match Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor).unwrap() {
+                match Request::parse(FunctionCode::WriteMultipleRegisters, &mut cursor, false)
+                    .unwrap()
+                {
                     Request::WriteMultipleRegisters(write) => write,
                     _ => panic!("bad match"),
                 };
@@ -386,4 +663,118 @@ mod tests {
             )
         }
     }
+
+    mod fifo {
+        use scursor::ReadCursor;
+
+        use super::super::*;
+        use crate::error::AduParseError;
+
+        #[test]
+        fn can_parse_fifo_pointer() {
+            let mut cursor = ReadCursor::new(&[0x04, 0xDE]);
+            let address =
+                match Request::parse(FunctionCode::ReadFifoQueue, &mut cursor, false).unwrap() {
+                    Request::ReadFifoQueue(address) => address,
+                    _ => panic!("bad match"),
+                };
+            assert_eq!(address, 0x04DE);
+        }
+
+        #[test]
+        fn fails_when_too_many_bytes_present() {
+            let mut cursor = ReadCursor::new(&[0x04, 0xDE, 0xFF]);
+            let err = Request::parse(FunctionCode::ReadFifoQueue, &mut cursor, false)
+                .err()
+                .unwrap();
+            assert_eq!(err, AduParseError::TrailingBytes(1).into());
+        }
+
+        #[test]
+        fn rejects_more_than_31_values() {
+            assert!(FifoWriter::new(vec![0; 31]).is_ok());
+            assert_eq!(
+                FifoWriter::new(vec![0; 32]).err(),
+                Some(ExceptionCode::IllegalDataValue)
+            );
+        }
+    }
+
+    mod file_records {
+        use scursor::ReadCursor;
+
+        use super::super::*;
+        use crate::error::InvalidRequest;
+
+        #[test]
+        fn can_parse_write_file_record() {
+            let mut cursor = ReadCursor::new(&[
+                0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE, 0x10, 0x0D,
+            ]);
+            let records =
+                match Request::parse(FunctionCode::WriteFileRecord, &mut cursor, false).unwrap() {
+                    Request::WriteFileRecord(records) => records,
+                    _ => panic!("bad match"),
+                };
+            assert_eq!(
+                records,
+                vec![FileRecord::new(4, 7, vec![0x06AF, 0x04BE, 0x100D])]
+            );
+        }
+
+        #[test]
+        fn fails_when_response_would_not_fit() {
+            let mut cursor = ReadCursor::new(&[0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x7D]);
+            let err = Request::parse(FunctionCode::ReadFileRecord, &mut cursor, false)
+                .err()
+                .unwrap();
+            assert_eq!(err, InvalidRequest::PduTooLarge(254, 253).into());
+        }
+
+        #[test]
+        fn writer_rejects_records_of_the_wrong_length() {
+            let ranges = [FileRecordRange::new(4, 1, 2)];
+            assert!(FileRecordWriter::new(&ranges, |_| Ok(vec![0, 0])).is_ok());
+            assert_eq!(
+                FileRecordWriter::new(&ranges, |_| Ok(vec![0])).err(),
+                Some(ExceptionCode::ServerDeviceFailure)
+            );
+        }
+    }
+
+    mod single_coil {
+        use scursor::ReadCursor;
+
+        use super::super::*;
+        use crate::error::AduParseError;
+
+        fn parse(value: [u8; 2], lenient: bool) -> Result<Indexed<bool>, RequestError> {
+            let data = [0x00, 0x01, value[0], value[1]];
+            let mut cursor = ReadCursor::new(&data);
+            match Request::parse(FunctionCode::WriteSingleCoil, &mut cursor, lenient)? {
+                Request::WriteSingleCoil(x) => Ok(x),
+                _ => panic!("bad match"),
+            }
+        }
+
+        #[test]
+        fn only_accepts_on_and_off_by_default() {
+            assert_eq!(parse([0xFF, 0x00], false), Ok(Indexed::new(1, true)));
+            assert_eq!(parse([0x00, 0x00], false), Ok(Indexed::new(1, false)));
+            assert_eq!(
+                parse([0x00, 0x01], false),
+                Err(AduParseError::UnknownCoilState(0x0001).into())
+            );
+        }
+
+        #[test]
+        fn lenient_mode_accepts_one_as_on() {
+            assert_eq!(parse([0x00, 0x01], true), Ok(Indexed::new(1, true)));
+            assert_eq!(parse([0x00, 0x00], true), Ok(Indexed::new(1, false)));
+            assert_eq!(
+                parse([0x00, 0x02], true),
+                Err(AduParseError::UnknownCoilState(0x0002).into())
+            );
+        }
+    }
 }
@@ -1,5 +1,8 @@
+use crate::device_id::DeviceIdentification;
+use crate::diagnostic::DiagnosticSubFunction;
 use crate::exception::ExceptionCode;
-use crate::types::{ReadBitsRange, ReadRegistersRange};
+use crate::server::request::ReadDeviceId;
+use crate::types::{FileRecord, FileRecordRange, ReadBitsRange, ReadRegistersRange};
 
 pub(crate) struct BitWriter<T>
 where
@@ -34,3 +37,292 @@ where
         Self { range, getter }
     }
 }
+
+/// Response body returned by [`crate::server::RequestHandler::process_raw_pdu`]
+pub(crate) struct RawWriter<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl<'a> RawWriter<'a> {
+    /// maximum length of the data following the function code in a PDU
+    pub(crate) const MAX_LENGTH: usize = crate::common::frame::constants::MAX_ADU_LENGTH - 1;
+
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+/// Response to a diagnostics request answered by the server
+pub(crate) struct DiagnosticsWriter {
+    pub(crate) sub_function: DiagnosticSubFunction,
+    pub(crate) data: u16,
+}
+
+impl DiagnosticsWriter {
+    pub(crate) fn new(sub_function: DiagnosticSubFunction, data: u16) -> Self {
+        Self { sub_function, data }
+    }
+}
+
+/// Response to a Read File Record request
+pub(crate) struct FileRecordWriter {
+    pub(crate) records: Vec<FileRecord>,
+}
+
+impl FileRecordWriter {
+    /// read each sub-request from the handler
+    pub(crate) fn new<T>(ranges: &[FileRecordRange], read: T) -> Result<Self, ExceptionCode>
+    where
+        T: Fn(FileRecordRange) -> Result<Vec<u16>, ExceptionCode>,
+    {
+        let mut records = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let values = read(*range)?;
+            if values.len() != range.record_length as usize {
+                return Err(ExceptionCode::ServerDeviceFailure);
+            }
+            records.push(FileRecord::new(
+                range.file_number,
+                range.record_number,
+                values,
+            ));
+        }
+        Ok(Self { records })
+    }
+}
+
+/// Response to a Write File Record request, which echoes the request
+pub(crate) struct FileRecordEchoWriter<'a> {
+    pub(crate) records: &'a [FileRecord],
+}
+
+impl<'a> FileRecordEchoWriter<'a> {
+    pub(crate) fn new(records: &'a [FileRecord]) -> Self {
+        Self { records }
+    }
+}
+
+/// Response to a Read FIFO Queue request
+pub(crate) struct FifoWriter {
+    pub(crate) values: Vec<u16>,
+}
+
+impl FifoWriter {
+    /// a queue may not hold more values than fit in a response
+    pub(crate) fn new(values: Vec<u16>) -> Result<Self, ExceptionCode> {
+        if values.len() > crate::constants::limits::MAX_READ_FIFO_COUNT as usize {
+            return Err(ExceptionCode::IllegalDataValue);
+        }
+        Ok(Self { values })
+    }
+}
+
+/// Response to a Read Device Identification request
+pub(crate) struct DeviceIdWriter<'a> {
+    pub(crate) read_device_id_code: u8,
+    pub(crate) conformity_level: u8,
+    pub(crate) next_object_id: Option<u8>,
+    pub(crate) objects: Vec<(u8, &'a [u8])>,
+}
+
+impl<'a> DeviceIdWriter<'a> {
+    /// MEI type, read device id code, conformity level, more follows, next object id, number of objects
+    const HEADER_LENGTH: usize = 6;
+
+    /// individual access is always supported
+    const INDIVIDUAL_ACCESS: u8 = 0x80;
+
+    pub(crate) fn new(
+        identification: &'a DeviceIdentification,
+        request: ReadDeviceId,
+    ) -> Result<Self, ExceptionCode> {
+        let objects = &identification.objects;
+
+        let last_object_id = match request.read_device_id_code {
+            0x01 => 0x02,
+            0x02 => 0x7F,
+            0x03 => 0xFF,
+            // individual access
+            0x04 => {
+                let value = objects
+                    .get(&request.object_id)
+                    .ok_or(ExceptionCode::IllegalDataAddress)?;
+                let mut writer = Self::empty(identification, request);
+                writer.push(request.object_id, value)?;
+                return Ok(writer);
+            }
+            _ => return Err(ExceptionCode::IllegalDataValue),
+        };
+
+        // restart at the beginning if the object doesn't exist
+        let first_object_id =
+            if request.object_id <= last_object_id && objects.contains_key(&request.object_id) {
+                request.object_id
+            } else {
+                0
+            };
+
+        let mut writer = Self::empty(identification, request);
+        for (id, value) in objects.range(first_object_id..=last_object_id) {
+            if writer.objects.is_empty() {
+                writer.push(*id, value)?;
+            } else if writer.push(*id, value).is_err() {
+                writer.next_object_id = Some(*id);
+                break;
+            }
+        }
+        Ok(writer)
+    }
+
+    fn empty(identification: &DeviceIdentification, request: ReadDeviceId) -> Self {
+        let conformity_level = match identification.objects.keys().next_back() {
+            Some(0x80..=0xFF) => 0x03,
+            Some(0x03..=0x7F) => 0x02,
+            _ => 0x01,
+        };
+        Self {
+            read_device_id_code: request.read_device_id_code,
+            conformity_level: conformity_level | Self::INDIVIDUAL_ACCESS,
+            next_object_id: None,
+            objects: Vec::new(),
+        }
+    }
+
+    fn length(&self) -> usize {
+        Self::HEADER_LENGTH
+            + self
+                .objects
+                .iter()
+                .map(|(_, value)| 2 + value.len())
+                .sum::<usize>()
+    }
+
+    fn push(&mut self, id: u8, value: &'a [u8]) -> Result<(), ExceptionCode> {
+        if self.length() + 2 + value.len() > RawWriter::MAX_LENGTH {
+            tracing::warn!(
+                "device identification object {:#04X} doesn't fit in the response",
+                id
+            );
+            return Err(ExceptionCode::ServerDeviceFailure);
+        }
+        self.objects.push((id, value));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_id::object_id;
+
+    fn identification() -> DeviceIdentification {
+        let mut identification = DeviceIdentification::default();
+        identification
+            .objects
+            .insert(object_id::VENDOR_NAME, b"vendor".to_vec());
+        identification
+            .objects
+            .insert(object_id::PRODUCT_CODE, b"product".to_vec());
+        identification
+            .objects
+            .insert(object_id::MAJOR_MINOR_REVISION, b"1.0".to_vec());
+        identification
+    }
+
+    fn read(
+        identification: &DeviceIdentification,
+        read_device_id_code: u8,
+        object_id: u8,
+    ) -> Result<DeviceIdWriter<'_>, ExceptionCode> {
+        DeviceIdWriter::new(
+            identification,
+            ReadDeviceId {
+                read_device_id_code,
+                object_id,
+            },
+        )
+    }
+
+    fn ids(writer: &DeviceIdWriter<'_>) -> Vec<u8> {
+        writer.objects.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn conformity_level_depends_on_objects() {
+        let mut identification = identification();
+        assert_eq!(read(&identification, 1, 0).unwrap().conformity_level, 0x81);
+        identification
+            .objects
+            .insert(object_id::PRODUCT_NAME, b"name".to_vec());
+        assert_eq!(read(&identification, 1, 0).unwrap().conformity_level, 0x82);
+        identification.objects.insert(0x80, b"extended".to_vec());
+        assert_eq!(read(&identification, 1, 0).unwrap().conformity_level, 0x83);
+    }
+
+    #[test]
+    fn stream_access_returns_objects_of_category() {
+        let mut identification = identification();
+        identification
+            .objects
+            .insert(object_id::PRODUCT_NAME, b"name".to_vec());
+        identification.objects.insert(0x80, b"extended".to_vec());
+
+        assert_eq!(ids(&read(&identification, 1, 0).unwrap()), [0, 1, 2]);
+        assert_eq!(ids(&read(&identification, 2, 0).unwrap()), [0, 1, 2, 4]);
+        assert_eq!(ids(&read(&identification, 3, 1).unwrap()), [1, 2, 4, 0x80]);
+    }
+
+    #[test]
+    fn stream_access_restarts_at_unknown_object() {
+        let identification = identification();
+        assert_eq!(ids(&read(&identification, 1, 0x03).unwrap()), [0, 1, 2]);
+        assert_eq!(ids(&read(&identification, 1, 0x80).unwrap()), [0, 1, 2]);
+    }
+
+    #[test]
+    fn individual_access_returns_one_object() {
+        let identification = identification();
+        assert_eq!(ids(&read(&identification, 4, 0x01).unwrap()), [1]);
+        assert_eq!(
+            read(&identification, 4, 0x03).err(),
+            Some(ExceptionCode::IllegalDataAddress)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_read_device_id_code() {
+        for code in [0x00, 0x05] {
+            assert_eq!(
+                read(&identification(), code, 0).err(),
+                Some(ExceptionCode::IllegalDataValue)
+            );
+        }
+    }
+
+    #[test]
+    fn sets_next_object_id_when_objects_do_not_fit() {
+        let mut identification = identification();
+        for id in 0x80..0x84 {
+            identification.objects.insert(id, vec![0; 100]);
+        }
+
+        let first = read(&identification, 3, 0).unwrap();
+        assert_eq!(ids(&first), [0, 1, 2, 0x80, 0x81]);
+        assert_eq!(first.next_object_id, Some(0x82));
+        assert!(first.length() <= RawWriter::MAX_LENGTH);
+
+        let second = read(&identification, 3, 0x82).unwrap();
+        assert_eq!(ids(&second), [0x82, 0x83]);
+        assert_eq!(second.next_object_id, None);
+    }
+
+    #[test]
+    fn fails_when_single_object_does_not_fit() {
+        let mut identification = identification();
+        identification.objects.insert(0x80, vec![0; 250]);
+        assert_eq!(
+            read(&identification, 4, 0x80).err(),
+            Some(ExceptionCode::ServerDeviceFailure)
+        );
+    }
+}
@@ -8,16 +8,28 @@ use crate::common::frame::{
 use crate::common::function::FunctionCode;
 use crate::error::*;
 use crate::exception::ExceptionCode;
-use crate::server::handler::{RequestHandler, ServerHandlerMap};
+use crate::server::counters::{DiagnosticCounters, Recipient};
+use crate::server::handler::{Peer, RequestHandler, ServerHandlerMap, UnmappedUnitIdPolicy};
+use crate::server::observer::{CloseReason, RequestOutcome};
+use crate::server::rate_limit::TokenBucket;
 use crate::server::request::{Request, RequestDisplay};
+use crate::server::response::RawWriter;
 
 use scursor::ReadCursor;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// Messages that can be sent to change server settings dynamically
-#[derive(Copy, Clone)]
+#[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum ServerSetting {
     ChangeDecoding(DecodeLevel),
+    ChangePeerDecoding(std::net::IpAddr, Option<DecodeLevel>),
+    ChangeAddressFilter(crate::server::AddressFilter),
+    ChangeSessionLimitPolicy(crate::server::SessionLimitPolicy),
+    ChangeIdleTimeout(Option<std::time::Duration>),
+    #[cfg(feature = "tls")]
+    ChangeTlsConfig(crate::tcp::tls::TlsServerConfig),
 }
 
 pub(crate) struct SessionTask<T>
@@ -26,6 +38,15 @@ where
 {
     handlers: ServerHandlerMap<T>,
     auth: AuthorizationType,
+    peer: Peer,
+    activity: Option<SessionActivity>,
+    idle_timeout: Option<std::time::Duration>,
+    /// set when the session is closed because it was idle
+    idle_expired: bool,
+    rate_limit: Option<TokenBucket>,
+    /// set when the body of the current request can't be parsed
+    malformed: bool,
+    counters: DiagnosticCounters,
     commands: tokio::sync::mpsc::Receiver<ServerSetting>,
     writer: FrameWriter,
     reader: FramedReader,
@@ -45,8 +66,15 @@ where
         decode: DecodeLevel,
     ) -> Self {
         Self {
+            rate_limit: handlers.session_rate_limit().map(TokenBucket::new),
             handlers,
             auth,
+            peer: Peer::default(),
+            activity: None,
+            idle_timeout: None,
+            idle_expired: false,
+            malformed: false,
+            counters: DiagnosticCounters::default(),
             commands,
             writer,
             reader,
@@ -54,6 +82,54 @@ where
         }
     }
 
+    /// identify the remote client to the write authorization hook
+    pub(crate) fn with_peer(mut self, peer: Peer) -> Self {
+        self.peer = peer;
+        self
+    }
+
+    /// record the time of each received frame so that idle sessions can be found
+    pub(crate) fn with_activity(mut self, activity: SessionActivity) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
+    /// close the session if it receives no frame for this long, which requires the activity
+    pub(crate) fn with_idle_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// reason for closing the session after [`SessionTask::run`] returned `err`
+    pub(crate) fn close_reason(&self, err: RequestError) -> CloseReason {
+        if self.idle_expired {
+            CloseReason::IdleTimeout
+        } else {
+            err.into()
+        }
+    }
+
+    /// instant at which the session will have been idle for longer than the timeout, if any
+    fn idle_deadline(&self) -> Option<std::time::Instant> {
+        match (&self.activity, self.idle_timeout) {
+            (Some(activity), Some(timeout)) => Some(activity.last() + timeout),
+            _ => None,
+        }
+    }
+
+    /// true if the request exceeds the session or global rate limit
+    fn is_rate_limited(&mut self) -> bool {
+        let now = Instant::now();
+        let limited = match &mut self.rate_limit {
+            Some(bucket) => !bucket.try_take(now),
+            None => false,
+        } || !self.handlers.take_global_token(now);
+        if limited {
+            tracing::warn!("request rate limit exceeded");
+        }
+        limited
+    }
+
     async fn reply_with_error(
         &mut self,
         io: &mut PhysLayer,
@@ -76,6 +152,7 @@ where
         if header.destination != FrameDestination::Broadcast {
             let bytes = self.writer.format_ex(header, func, ex, self.decode)?;
             io.write(bytes, self.decode.physical).await?;
+            self.counters.on_response(Some(ex));
         }
         Ok(())
     }
@@ -89,7 +166,6 @@ where
         }
     }
 
-    #[cfg(feature = "serial")]
     pub(crate) async fn sleep_for(
         &mut self,
         duration: std::time::Duration,
@@ -102,7 +178,6 @@ where
         }
     }
 
-    #[cfg(feature = "serial")]
     async fn process_settings(&mut self) -> Shutdown {
         loop {
             match self.commands.recv().await {
@@ -115,9 +190,33 @@ where
     }
 
     async fn run_one(&mut self, io: &mut PhysLayer) -> Result<(), RequestError> {
+        let deadline = self.idle_deadline();
+        let idle = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+
         tokio::select! {
             frame = self.reader.next_frame(io, self.decode) => {
-                let frame = frame?;
+                let frame = match frame {
+                    Ok(x) => x,
+                    Err(err) => {
+                        if let RequestError::BadFrame(
+                            FrameParseError::CrcValidationFailure(_, _)
+                            | FrameParseError::LrcValidationFailure(_, _),
+                        ) = err
+                        {
+                            self.counters.on_communication_error();
+                        }
+                        return Err(err);
+                    }
+                };
+                // each datagram received by a UDP server may come from a different client
+                if let Some(addr) = io.datagram_source() {
+                    self.peer = Peer::new(Some(addr), None);
+                }
                 self.handle_frame(io, frame).await
             }
             cmd = self.commands.recv() => {
@@ -129,6 +228,11 @@ where
                     }
                }
             }
+            _ = idle => {
+                tracing::warn!("closing session idle for more than {:?}", self.idle_timeout);
+                self.idle_expired = true;
+                Err(RequestError::Io(std::io::ErrorKind::TimedOut))
+            }
         }
     }
 
@@ -137,37 +241,104 @@ where
             ServerSetting::ChangeDecoding(level) => {
                 self.decode = level;
             }
+            // translated into ChangeDecoding by the TCP server task
+            ServerSetting::ChangePeerDecoding(_, _) => {}
+            // only used when accepting new connections
+            ServerSetting::ChangeAddressFilter(_) => {}
+            ServerSetting::ChangeSessionLimitPolicy(_) => {}
+            ServerSetting::ChangeIdleTimeout(timeout) => {
+                self.idle_timeout = timeout;
+            }
+            #[cfg(feature = "tls")]
+            ServerSetting::ChangeTlsConfig(_) => {}
         }
     }
 
     async fn handle_frame(&mut self, io: &mut PhysLayer, frame: Frame) -> Result<(), RequestError> {
+        let recipient = match frame.header.destination {
+            FrameDestination::UnitId(unit_id) => match self.handlers.get(unit_id) {
+                Some(_) => Recipient::Server,
+                None => Recipient::OtherDevice,
+            },
+            FrameDestination::Broadcast => Recipient::Broadcast,
+        };
+        // fetching the event counter doesn't change it
+        let counts_as_event =
+            frame.payload().first() != Some(&FunctionCode::GetCommEventCounter.get_value());
+        let function = frame.payload().first().copied();
+        self.counters.on_message(recipient, counts_as_event);
+        self.malformed = false;
+        let result = self.process_frame(io, frame).await;
+        let response = self.counters.on_complete();
+        if let Some(function) = function {
+            let exception = matches!(response, Some(Some(_)));
+            self.handlers
+                .record_metrics(&self.peer, function, exception, self.malformed);
+            if let Some(observer) = self.handlers.observer() {
+                observer.request_handled(&self.peer, function, RequestOutcome::new(response));
+            }
+        }
+        result
+    }
+
+    async fn process_frame(
+        &mut self,
+        io: &mut PhysLayer,
+        frame: Frame,
+    ) -> Result<(), RequestError> {
+        if let Some(activity) = &self.activity {
+            activity.update();
+        }
+
         let mut cursor = ReadCursor::new(frame.payload());
 
-        let function = match cursor.read_u8() {
+        let value = match cursor.read_u8() {
             Err(_) => {
                 tracing::warn!("received an empty frame");
                 return Ok(());
             }
-            Ok(value) => match FunctionCode::get(value) {
-                Some(x) => x,
-                None => {
-                    tracing::warn!("received unknown function code: {}", value);
-                    return self
-                        .reply_with_error_generic(
-                            io,
-                            frame.header,
-                            FunctionField::unknown(value),
-                            ExceptionCode::IllegalFunction,
-                        )
-                        .await;
-                }
-            },
+            Ok(value) => value,
         };
 
-        let request = match Request::parse(function, &mut cursor) {
+        // frames for other devices on a multi-drop line must not be delayed, rate limited or
+        // authorized, so resolve the unit id before doing anything else with the request
+        if let FrameDestination::UnitId(unit_id) = frame.header.destination {
+            if self.handlers.get(unit_id).is_none() {
+                return self
+                    .reply_to_unmapped(io, frame.header, FunctionField::unknown(value), unit_id)
+                    .await;
+            }
+        }
+
+        if !frame.header.destination.is_broadcast() {
+            if let Some(delay) = self.handlers.response_delay(value) {
+                tracing::info!("delaying response by {:?}", delay);
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        let function = match FunctionCode::get(value)
+            .filter(|x| Request::is_implemented(*x, &frame.payload()[1..]))
+        {
+            Some(x) => x,
+            // let the handler process function codes the library doesn't implement
+            None => {
+                return self
+                    .handle_raw(io, frame.header, value, &frame.payload()[1..])
+                    .await;
+            }
+        };
+
+        let lenient_coils = self.handlers.lenient_coil_values();
+        let request = match Request::parse(function, &mut cursor, lenient_coils) {
             Ok(x) => x,
+            Err(RequestError::Exception(ex)) => {
+                tracing::warn!("unsupported {:?} request", function);
+                return self.reply_with_error(io, frame.header, function, ex).await;
+            }
             Err(err) => {
                 tracing::warn!("error parsing {:?} request: {}", function, err);
+                self.malformed = true;
                 return self
                     .reply_with_error(io, frame.header, function, ExceptionCode::IllegalDataValue)
                     .await;
@@ -181,6 +352,12 @@ where
             );
         }
 
+        if self.is_rate_limited() {
+            return self
+                .reply_with_error(io, frame.header, function, ExceptionCode::ServerDeviceBusy)
+                .await;
+        }
+
         // check authorization
         if let Authorization::Deny = self
             .auth
@@ -203,19 +380,38 @@ where
             FrameDestination::UnitId(unit_id) => {
                 let handler = match self.handlers.get(unit_id) {
                     None => {
-                        tracing::warn!("received frame for unmapped unit id: {}", unit_id);
-                        return Ok(());
+                        return self
+                            .reply_to_unmapped(
+                                io,
+                                frame.header,
+                                FunctionField::Exception(function),
+                                unit_id,
+                            )
+                            .await;
                     }
-                    Some(handler) => handler,
+                    Some(handler) => handler.clone(),
                 };
+                if let Err(ex) = self.authorize_write(unit_id, &request) {
+                    return self.reply_with_error(io, frame.header, function, ex).await;
+                }
                 // get the reply data (or exception reply)
                 let reply: &[u8] = request.get_reply(
                     frame.header,
                     handler.lock().unwrap().as_mut(),
+                    &mut self.counters,
                     &mut self.writer,
                     self.decode,
                 )?;
                 io.write(reply, self.decode.physical).await?;
+                self.counters.on_response(self.writer.last_exception());
+            }
+            FrameDestination::Broadcast if !self.handlers.is_broadcast_enabled() => {
+                tracing::warn!("ignoring broadcast {} request", function);
+            }
+            FrameDestination::Broadcast
+                if self.authorize_write(UnitId::broadcast(), &request).is_err() =>
+            {
+                // nobody answers a broadcast, so a rejected write is silently dropped
             }
             FrameDestination::Broadcast => match request.into_broadcast_request() {
                 None => {
@@ -231,6 +427,153 @@ where
 
         Ok(())
     }
+
+    fn authorize_write(&self, unit_id: UnitId, request: &Request) -> Result<(), ExceptionCode> {
+        if let (Some(handler), Some((write, range))) =
+            (self.handlers.write_authorization(), request.get_write())
+        {
+            if let Err(ex) = handler.authorize_write(unit_id, write, range, &self.peer) {
+                tracing::warn!("write {:?} rejected with {:?}", range, ex);
+                return Err(ex);
+            }
+        }
+        Ok(())
+    }
+
+    async fn reply_to_unmapped(
+        &mut self,
+        io: &mut PhysLayer,
+        header: FrameHeader,
+        func: FunctionField,
+        unit_id: UnitId,
+    ) -> Result<(), RequestError> {
+        tracing::warn!("received frame for unmapped unit id: {}", unit_id);
+        match self.handlers.unmapped_unit_id_policy() {
+            UnmappedUnitIdPolicy::Ignore => Ok(()),
+            UnmappedUnitIdPolicy::RespondGatewayTargetFailed => {
+                self.reply_with_error_generic(
+                    io,
+                    header,
+                    func,
+                    ExceptionCode::GatewayTargetDeviceFailedToRespond,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn handle_raw(
+        &mut self,
+        io: &mut PhysLayer,
+        header: FrameHeader,
+        function: u8,
+        data: &[u8],
+    ) -> Result<(), RequestError> {
+        if self.decode.app.enabled() {
+            tracing::info!(
+                "PDU RX - {} length: {}",
+                FunctionField::Raw(function),
+                data.len()
+            );
+        }
+
+        let unit_id = match header.destination {
+            FrameDestination::UnitId(x) => x,
+            FrameDestination::Broadcast => {
+                tracing::warn!(
+                    "broadcast is not supported for {}",
+                    FunctionField::Raw(function)
+                );
+                return Ok(());
+            }
+        };
+
+        if self.is_rate_limited() {
+            return self
+                .reply_with_error_generic(
+                    io,
+                    header,
+                    FunctionField::unknown(function),
+                    ExceptionCode::ServerDeviceBusy,
+                )
+                .await;
+        }
+
+        if let Authorization::Deny = self.auth.is_raw_authorized(unit_id, function) {
+            return self
+                .reply_with_error_generic(
+                    io,
+                    header,
+                    FunctionField::unknown(function),
+                    ExceptionCode::IllegalFunction,
+                )
+                .await;
+        }
+
+        let handler = match self.handlers.get(unit_id) {
+            None => {
+                return self
+                    .reply_to_unmapped(io, header, FunctionField::unknown(function), unit_id)
+                    .await;
+            }
+            Some(handler) => handler,
+        };
+
+        let result = handler.lock().unwrap().process_raw_pdu(function, data);
+        match result {
+            Ok(reply) if reply.len() > RawWriter::MAX_LENGTH => {
+                tracing::warn!(
+                    "raw response of {} bytes exceeds the maximum of {}",
+                    reply.len(),
+                    RawWriter::MAX_LENGTH
+                );
+                self.reply_with_error_generic(
+                    io,
+                    header,
+                    FunctionField::unknown(function),
+                    ExceptionCode::ServerDeviceFailure,
+                )
+                .await
+            }
+            Ok(reply) => {
+                let bytes = self.writer.format_request(
+                    header,
+                    FunctionField::Raw(function),
+                    &RawWriter::new(&reply),
+                    self.decode,
+                )?;
+                io.write(bytes, self.decode.physical).await?;
+                self.counters.on_response(None);
+                Ok(())
+            }
+            Err(ex) => {
+                self.reply_with_error_generic(io, header, FunctionField::unknown(function), ex)
+                    .await
+            }
+        }
+    }
+}
+
+/// Time at which a session last received a frame, shared with the server task
+#[derive(Clone)]
+pub(crate) struct SessionActivity {
+    last: Arc<Mutex<Instant>>,
+}
+
+impl SessionActivity {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub(crate) fn update(&self) {
+        *self.last.lock().unwrap() = Instant::now();
+    }
+
+    pub(crate) fn last(&self) -> Instant {
+        *self.last.lock().unwrap()
+    }
 }
 
 /// Determines how authorization of user defined requests are handled
@@ -264,6 +607,39 @@ impl AuthorizationType {
             Request::WriteMultipleRegisters(x) => {
                 handler.write_multiple_registers(unit_id, x.range, role)
             }
+            Request::ReadDeviceIdentification(_) => {
+                handler.read_device_identification(unit_id, role)
+            }
+            Request::Diagnostics(_) | Request::GetCommEventCounter | Request::GetCommEventLog => {
+                handler.diagnostics(unit_id, role)
+            }
+            Request::ReadFileRecord(x) => handler.read_file_records(unit_id, x, role),
+            Request::WriteFileRecord(x) => handler.write_file_records(unit_id, x, role),
+            Request::ReadFifoQueue(x) => handler.read_fifo_queue(unit_id, *x, role),
+        }
+    }
+
+    pub(crate) fn role(&self) -> Option<String> {
+        match self {
+            AuthorizationType::None => None,
+            AuthorizationType::Handler(_, role) => Some(role.clone()),
+        }
+    }
+
+    pub(crate) fn is_raw_authorized(&self, unit_id: UnitId, function: u8) -> Authorization {
+        match self {
+            AuthorizationType::None => Authorization::Allow,
+            AuthorizationType::Handler(handler, role) => {
+                let result = handler.raw_pdu(unit_id, function, role);
+                if let Authorization::Deny = result {
+                    tracing::warn!(
+                        "Role \"{}\" not authorized for raw function: {:#04X}",
+                        role,
+                        function
+                    );
+                }
+                result
+            }
         }
     }
 
@@ -284,3 +660,198 @@ impl AuthorizationType {
         }
     }
 }
+
+#[cfg(all(test, feature = "serial"))]
+mod tests {
+    use std::task::Poll;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::server::database::{DatabaseConfig, DatabaseHandle, ServerDatabase};
+    use crate::server::{RateLimit, ResponseDelay};
+    use crate::types::AddressRange;
+
+    // write single register 0x0000 = 0x1234 to the broadcast address
+    const BROADCAST_WRITE: &[u8] = b":000600001234B4\r\n";
+
+    // read holding register 0x0000 from unit 1 and unit 2, and the response from unit 1
+    const READ_UNIT_1: &[u8] = b":010300000001FB\r\n";
+    const READ_UNIT_2: &[u8] = b":020300000001FA\r\n";
+    const RESPONSE_UNIT_1: &[u8] = b":0103020000FA\r\n";
+
+    fn database() -> DatabaseHandle {
+        DatabaseHandle::new(DatabaseConfig {
+            holding_registers: Some(AddressRange::try_from(0, 1).unwrap()),
+            ..Default::default()
+        })
+    }
+
+    // the sender must outlive the session, which shuts down when its settings channel closes
+    fn session(
+        handlers: ServerHandlerMap<ServerDatabase>,
+    ) -> (
+        SessionTask<ServerDatabase>,
+        tokio::sync::mpsc::Sender<ServerSetting>,
+    ) {
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let session = SessionTask::new(
+            handlers,
+            AuthorizationType::None,
+            FrameWriter::ascii(),
+            FramedReader::ascii(),
+            rx,
+            DecodeLevel::nothing(),
+        );
+        (session, tx)
+    }
+
+    fn write_broadcast(enabled: bool) -> Option<u16> {
+        let db = database();
+        let mut handlers = ServerHandlerMap::single(UnitId::new(1), db.handler());
+        handlers.set_broadcast_enabled(enabled);
+        let (mut session, _tx) = session(handlers);
+
+        // the mock fails the test if the session writes a response
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut phys = PhysLayer::new_mock(io);
+        let mut task = tokio_test::task::spawn(session.run_one(&mut phys));
+        io_handle.read(BROADCAST_WRITE);
+        match task.poll() {
+            Poll::Ready(result) => result.unwrap(),
+            Poll::Pending => panic!("Task not ready"),
+        }
+
+        db.transaction(|db| db.get_holding_register(0))
+    }
+
+    #[test]
+    fn applies_broadcast_write_without_responding() {
+        assert_eq!(write_broadcast(true), Some(0x1234));
+    }
+
+    #[test]
+    fn ignores_broadcast_write_when_disabled() {
+        assert_eq!(write_broadcast(false), Some(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ignores_other_units_before_delaying_or_rate_limiting() {
+        let db = database();
+        let mut handlers = ServerHandlerMap::single(UnitId::new(1), db.handler());
+        handlers.set_session_rate_limit(Some(RateLimit::new(0.001, 1)));
+        handlers.set_response_delay(0x03, Some(ResponseDelay::Fixed(Duration::from_secs(1))));
+        let (mut session, _tx) = session(handlers);
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut phys = PhysLayer::new_mock(io);
+
+        // a request for another slave on the line is neither delayed nor answered
+        let start = tokio::time::Instant::now();
+        io_handle.read(READ_UNIT_2);
+        session.run_one(&mut phys).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(io_handle.pop_event(), Some(sfio_tokio_mock_io::Event::Read));
+        assert_eq!(io_handle.pop_event(), None);
+
+        // and doesn't use up the token meant for requests addressed to this server
+        io_handle.read(READ_UNIT_1);
+        session.run_one(&mut phys).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(io_handle.pop_event(), Some(sfio_tokio_mock_io::Event::Read));
+        assert_eq!(
+            io_handle.pop_event(),
+            Some(sfio_tokio_mock_io::Event::Write(RESPONSE_UNIT_1.to_vec()))
+        );
+    }
+
+    #[tokio::test]
+    async fn answers_other_units_according_to_the_unmapped_policy() {
+        let db = database();
+        let mut handlers = ServerHandlerMap::single(UnitId::new(1), db.handler());
+        handlers.set_session_rate_limit(Some(RateLimit::new(0.001, 0)));
+        handlers.set_unmapped_unit_id_policy(UnmappedUnitIdPolicy::RespondGatewayTargetFailed);
+        let (mut session, _tx) = session(handlers);
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut phys = PhysLayer::new_mock(io);
+
+        // the policy decides the reply, not the exhausted rate limit
+        io_handle.read(READ_UNIT_2);
+        session.run_one(&mut phys).await.unwrap();
+        assert_eq!(io_handle.pop_event(), Some(sfio_tokio_mock_io::Event::Read));
+        assert_eq!(
+            io_handle.pop_event(),
+            Some(sfio_tokio_mock_io::Event::Write(b":02830B70\r\n".to_vec()))
+        );
+    }
+
+    // write single register 0x0000 = 0x1234 to unit 1 and unit 2
+    const WRITE_UNIT_1: &[u8] = b":010600001234B3\r\n";
+    const WRITE_UNIT_2: &[u8] = b":020600001234B2\r\n";
+
+    #[derive(Default)]
+    struct DenyWrites {
+        count: std::sync::atomic::AtomicUsize,
+    }
+
+    impl crate::server::WriteAuthorizationHandler for DenyWrites {
+        fn authorize_write(
+            &self,
+            _unit_id: UnitId,
+            _function: crate::server::WriteFunction,
+            _range: AddressRange,
+            _peer: &Peer,
+        ) -> Result<(), ExceptionCode> {
+            self.count
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Err(ExceptionCode::IllegalDataAddress)
+        }
+    }
+
+    async fn write_denied(request: &[u8]) -> (Vec<sfio_tokio_mock_io::Event>, usize) {
+        let db = database();
+        let auth = Arc::new(DenyWrites::default());
+        let mut handlers = ServerHandlerMap::single(UnitId::new(1), db.handler());
+        handlers.set_broadcast_enabled(true);
+        handlers.set_write_authorization(auth.clone());
+        let (mut session, _tx) = session(handlers);
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut phys = PhysLayer::new_mock(io);
+
+        io_handle.read(request);
+        session.run_one(&mut phys).await.unwrap();
+        assert_eq!(db.transaction(|db| db.get_holding_register(0)), Some(0));
+
+        let mut events = Vec::new();
+        while let Some(event) = io_handle.pop_event() {
+            events.push(event);
+        }
+        let count = auth.count.load(std::sync::atomic::Ordering::Relaxed);
+        (events, count)
+    }
+
+    #[tokio::test]
+    async fn answers_rejected_write_with_the_exception() {
+        let (events, count) = write_denied(WRITE_UNIT_1).await;
+        assert_eq!(
+            events,
+            [
+                sfio_tokio_mock_io::Event::Read,
+                sfio_tokio_mock_io::Event::Write(b":01860277\r\n".to_vec())
+            ]
+        );
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn doesnt_authorize_writes_to_other_units() {
+        let (events, count) = write_denied(WRITE_UNIT_2).await;
+        assert_eq!(events, [sfio_tokio_mock_io::Event::Read]);
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn discards_rejected_broadcast_write_without_responding() {
+        let (events, count) = write_denied(BROADCAST_WRITE).await;
+        assert_eq!(events, [sfio_tokio_mock_io::Event::Read]);
+        assert_eq!(count, 1);
+    }
+}
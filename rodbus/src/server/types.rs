@@ -29,3 +29,15 @@ impl<'a> WriteRegisters<'a> {
         Self { range, iterator }
     }
 }
+
+/// Controls what a TCP server does when a connection is accepted while `max_sessions` are active
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SessionLimitPolicy {
+    /// Close the session that was accepted first
+    #[default]
+    CloseOldest,
+    /// Close the session that has gone the longest without receiving a request
+    CloseIdlest,
+    /// Keep the existing sessions and close the new connection
+    RejectNew,
+}
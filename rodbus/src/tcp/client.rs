@@ -1,58 +1,77 @@
 use tracing::Instrument;
 
-use crate::client::{Channel, ClientState, HostAddr, Listener};
+use crate::client::{
+    Channel, ClientState, ConnectionEvent, EndpointList, HostAddr, Listener, TcpOptions,
+};
 use crate::common::phys::PhysLayer;
 use crate::decode::DecodeLevel;
 
-use crate::client::message::Command;
+use crate::client::message::CommandReceiver;
 use crate::client::task::{ClientLoop, SessionError, StateChange};
-use crate::common::frame::{FrameWriter, FramedReader};
 use crate::error::Shutdown;
 use crate::retry::RetryStrategy;
+use crate::types::Framing;
 
 use tokio::net::TcpStream;
 
 pub(crate) fn spawn_tcp_channel(
-    host: HostAddr,
+    endpoints: EndpointList,
+    framing: Framing,
+    options: TcpOptions,
     max_queued_requests: usize,
     connect_retry: Box<dyn RetryStrategy>,
     decode: DecodeLevel,
     listener: Box<dyn Listener<ClientState>>,
 ) -> Channel {
-    let (handle, task) =
-        create_tcp_channel(host, max_queued_requests, connect_retry, decode, listener);
+    let (handle, task) = create_tcp_channel(
+        endpoints,
+        framing,
+        options,
+        max_queued_requests,
+        connect_retry,
+        decode,
+        listener,
+    );
     tokio::spawn(task);
     handle
 }
 
 pub(crate) fn create_tcp_channel(
-    host: HostAddr,
+    endpoints: EndpointList,
+    framing: Framing,
+    options: TcpOptions,
     max_queued_requests: usize,
     connect_retry: Box<dyn RetryStrategy>,
     decode: DecodeLevel,
     listener: Box<dyn Listener<ClientState>>,
 ) -> (Channel, impl std::future::Future<Output = ()>) {
-    let (tx, rx) = tokio::sync::mpsc::channel(max_queued_requests);
+    let (channel, rx) = Channel::create(max_queued_requests);
     let task = async move {
+        let span =
+            tracing::info_span!("Modbus-Client-TCP", endpoint = %endpoints, framing = ?framing);
         TcpChannelTask::new(
-            host.clone(),
-            rx.into(),
+            endpoints,
+            rx,
             TcpTaskConnectionHandler::Tcp,
+            framing,
+            options,
             connect_retry,
             decode,
             listener,
         )
         .run()
-        .instrument(tracing::info_span!("Modbus-Client-TCP", endpoint = ?host))
+        .instrument(span)
         .await;
     };
-    (Channel { tx }, task)
+    (channel, task)
 }
 
 pub(crate) enum TcpTaskConnectionHandler {
     Tcp,
     #[cfg(feature = "tls")]
     Tls(crate::tcp::tls::TlsClientConfig),
+    #[cfg(feature = "native-tls")]
+    NativeTls(crate::tcp::native_tls::NativeTlsClientConfig),
 }
 
 impl TcpTaskConnectionHandler {
@@ -65,32 +84,39 @@ impl TcpTaskConnectionHandler {
             Self::Tcp => Ok(PhysLayer::new_tcp(socket)),
             #[cfg(feature = "tls")]
             Self::Tls(config) => config.handle_connection(socket, _endpoint).await,
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(config) => config.handle_connection(socket, _endpoint).await,
         }
     }
 }
 
 pub(crate) struct TcpChannelTask {
-    host: HostAddr,
+    endpoints: EndpointList,
     connect_retry: Box<dyn RetryStrategy>,
     connection_handler: TcpTaskConnectionHandler,
+    options: TcpOptions,
     client_loop: ClientLoop,
     listener: Box<dyn Listener<ClientState>>,
 }
 
 impl TcpChannelTask {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
-        host: HostAddr,
-        rx: crate::channel::Receiver<Command>,
+        endpoints: EndpointList,
+        rx: CommandReceiver,
         connection_handler: TcpTaskConnectionHandler,
+        framing: Framing,
+        options: TcpOptions,
         connect_retry: Box<dyn RetryStrategy>,
         decode: DecodeLevel,
         listener: Box<dyn Listener<ClientState>>,
     ) -> Self {
         Self {
-            host,
+            endpoints,
             connect_retry,
             connection_handler,
-            client_loop: ClientLoop::new(rx, FrameWriter::tcp(), FramedReader::tcp(), decode),
+            options,
+            client_loop: ClientLoop::new(rx, framing.writer(), framing.response_reader(), decode),
             listener,
         }
     }
@@ -120,8 +146,21 @@ impl TcpChannelTask {
     }
 
     async fn connect(&mut self) -> Result<Result<TcpStream, std::io::Error>, StateChange> {
+        let connect = self.endpoints.current().connect(self.options.local_address);
+        let connect = async {
+            match self.options.connect_timeout {
+                None => connect.await,
+                Some(timeout) => match tokio::time::timeout(timeout, connect).await {
+                    Ok(res) => res,
+                    Err(_) => Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("no connection within {} ms", timeout.as_millis()),
+                    )),
+                },
+            }
+        };
         tokio::select! {
-            res = self.host.connect() => {
+            res = connect => {
                 Ok(res)
             }
             res = self.client_loop.fail_requests() => {
@@ -131,62 +170,77 @@ impl TcpChannelTask {
     }
 
     async fn try_connect_and_run(&mut self) -> Result<(), StateChange> {
+        #[cfg(feature = "tls")]
+        if let Some(config) = self.client_loop.take_tls_config() {
+            match &mut self.connection_handler {
+                TcpTaskConnectionHandler::Tls(x) => *x = config,
+                _ => tracing::warn!("ignoring TLS configuration on a channel without rustls"),
+            }
+        }
+
         self.listener.update(ClientState::Connecting).get().await;
+        self.client_loop
+            .notify(ConnectionEvent::Connecting(
+                self.endpoints.current().clone(),
+            ))
+            .await;
         match self.connect().await? {
             Err(err) => {
-                let delay = self.connect_retry.after_failed_connect();
-                tracing::warn!(
-                    "failed to connect to {}: {} - waiting {} ms before next attempt",
-                    self.host,
-                    err,
-                    delay.as_millis()
-                );
-                self.listener
-                    .update(ClientState::WaitAfterFailedConnect(delay))
-                    .get()
-                    .await;
-                self.client_loop.fail_requests_for(delay).await
+                let err = format!("failed to connect to {}: {}", self.endpoints.current(), err);
+                self.wait_after_failed_connect(err).await
             }
             Ok(socket) => {
-                if let Ok(addr) = socket.peer_addr() {
-                    tracing::info!("connected to: {}", addr);
-                }
-                if let Err(err) = socket.set_nodelay(true) {
-                    tracing::warn!("unable to enable TCP_NODELAY: {}", err);
-                }
-                match self.connection_handler.handle(socket, &self.host).await {
+                let peer = match socket.peer_addr() {
+                    Ok(addr) => addr,
                     Err(err) => {
-                        let delay = self.connect_retry.after_failed_connect();
-                        tracing::warn!(
-                            "{} - waiting {} ms before next attempt",
-                            err,
-                            delay.as_millis()
-                        );
-                        self.listener
-                            .update(ClientState::WaitAfterFailedConnect(delay))
-                            .get()
-                            .await;
-                        self.client_loop.fail_requests_for(delay).await
+                        let err =
+                            format!("connection to {} lost: {}", self.endpoints.current(), err);
+                        return self.wait_after_failed_connect(err).await;
                     }
+                };
+                tracing::info!("connected to: {}", peer);
+                self.options.apply(&socket);
+                match self
+                    .connection_handler
+                    .handle(socket, self.endpoints.current())
+                    .await
+                {
+                    Err(err) => self.wait_after_failed_connect(err).await,
                     Ok(mut phys) => {
                         self.listener.update(ClientState::Connected).get().await;
+                        self.client_loop
+                            .notify(ConnectionEvent::Connected(peer))
+                            .await;
                         // reset the retry strategy now that we have a successful connection
                         // we do this here so that the reset happens after a TLS handshake
                         self.connect_retry.reset();
+                        self.endpoints.on_connected();
                         // run the physical layer independent processing loop
-                        match self.client_loop.run(&mut phys).await {
+                        let err = self.client_loop.run(&mut phys).await;
+                        // close the connection right away instead of keeping it while waiting
+                        drop(phys);
+                        self.client_loop
+                            .notify(ConnectionEvent::Disconnected(err.disconnect_reason()))
+                            .await;
+                        match err {
                             // the mpsc was closed, end the task
                             SessionError::Shutdown => Err(StateChange::Shutdown),
+                            // reconnect once the next request is made
+                            SessionError::Idle => Ok(()),
                             // re-establish the connection
                             SessionError::Disabled
                             | SessionError::IoError(_)
                             | SessionError::BadFrame => {
+                                self.endpoints.on_disconnect();
                                 let delay = self.connect_retry.after_disconnect();
                                 tracing::warn!("waiting {:?} to reconnect", delay);
                                 self.listener
                                     .update(ClientState::WaitAfterDisconnect(delay))
                                     .get()
                                     .await;
+                                self.client_loop
+                                    .notify(ConnectionEvent::WaitingToRetry(delay))
+                                    .await;
                                 self.client_loop.fail_requests_for(delay).await
                             }
                         }
@@ -195,4 +249,27 @@ impl TcpChannelTask {
             }
         }
     }
+
+    async fn wait_after_failed_connect(&mut self, err: String) -> Result<(), StateChange> {
+        // only wait once every endpoint has been tried
+        if !self.endpoints.on_failed_connect() {
+            tracing::warn!("{} - trying {}", err, self.endpoints.current());
+            return Ok(());
+        }
+
+        let delay = self.connect_retry.after_failed_connect();
+        tracing::warn!(
+            "{} - waiting {} ms before next attempt",
+            err,
+            delay.as_millis()
+        );
+        self.listener
+            .update(ClientState::WaitAfterFailedConnect(delay))
+            .get()
+            .await;
+        self.client_loop
+            .notify(ConnectionEvent::WaitingToRetry(delay))
+            .await;
+        self.client_loop.fail_requests_for(delay).await
+    }
 }
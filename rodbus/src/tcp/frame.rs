@@ -11,8 +11,6 @@ pub(crate) mod constants {
     pub(crate) const HEADER_LENGTH: usize = 7;
     pub(crate) const MAX_FRAME_LENGTH: usize =
         HEADER_LENGTH + crate::common::frame::constants::MAX_ADU_LENGTH;
-    // cannot be < 1 b/c of the unit identifier
-    pub(crate) const MAX_LENGTH_FIELD: usize = crate::common::frame::constants::MAX_ADU_LENGTH + 1;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -31,17 +29,23 @@ enum ParseState {
 
 pub(crate) struct MbapParser {
     state: ParseState,
+    /// accept frames whose protocol id isn't 0, as sent by some converters
+    pub(crate) lenient_protocol_id: bool,
+    /// longest function code and body that is accepted
+    pub(crate) max_adu_length: usize,
 }
 
 impl MbapParser {
     pub(crate) fn new() -> Self {
         Self {
             state: ParseState::Begin,
+            lenient_protocol_id: false,
+            max_adu_length: crate::common::frame::constants::MAX_ADU_LENGTH,
         }
     }
 
     // returns some header fields and the length of the ADU
-    fn parse_header(cursor: &mut ReadBuffer) -> Result<(MbapHeader, usize), RequestError> {
+    fn parse_header(&self, cursor: &mut ReadBuffer) -> Result<(MbapHeader, usize), RequestError> {
         let tx_id = TxId::new(cursor.read_u16_be()?);
         let protocol_id = cursor.read_u16_be()?;
         let len_field = cursor.read_u16_be()?;
@@ -49,13 +53,16 @@ impl MbapParser {
         let unit_id = UnitId::new(cursor.read_u8()?);
 
         if protocol_id != 0 {
-            return Err(FrameParseError::UnknownProtocolId(protocol_id).into());
+            if !self.lenient_protocol_id {
+                return Err(FrameParseError::UnknownProtocolId(protocol_id).into());
+            }
+            tracing::warn!("ignoring protocol id {:#06X}", protocol_id);
         }
 
-        if length > constants::MAX_LENGTH_FIELD {
-            return Err(
-                FrameParseError::FrameLengthTooBig(length, constants::MAX_LENGTH_FIELD).into(),
-            );
+        // cannot be < 1 b/c of the unit identifier
+        let max_length = self.max_adu_length + 1;
+        if length > max_length {
+            return Err(FrameParseError::FrameLengthTooBig(length, max_length).into());
         }
 
         // The ADU length is the function code + body
@@ -112,7 +119,7 @@ impl MbapParser {
                     return Ok(None);
                 }
 
-                let (header, adu_len) = Self::parse_header(cursor)?;
+                let (header, adu_len) = self.parse_header(cursor)?;
                 self.state = ParseState::Header(header, adu_len);
                 self.parse(cursor, decode_level)
             }
@@ -330,6 +337,33 @@ mod tests {
         tokio_test::assert_ready!(task.poll());
     }
 
+    #[test]
+    fn can_parse_frame_larger_than_the_spec_when_configured() {
+        // an ADU of 300 bytes has an MBAP length of 301 which is 0x012D
+        let header = &[0x00, 0x07, 0x00, 0x00, 0x01, 0x2D, 0x2A];
+        let payload = &[0xCC; 300];
+
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut reader = FramedReader::tcp();
+        reader.set_max_adu_length(300);
+        let mut task = tokio_test::task::spawn(async {
+            assert_eq!(
+                reader
+                    .next_frame(&mut PhysLayer::new_mock(io), DecodeLevel::nothing())
+                    .await
+                    .unwrap()
+                    .payload(),
+                payload.as_ref()
+            );
+        });
+
+        tokio_test::assert_pending!(task.poll());
+        io_handle.read(header);
+        tokio_test::assert_pending!(task.poll());
+        io_handle.read(payload);
+        tokio_test::assert_ready!(task.poll());
+    }
+
     #[test]
     fn can_parse_frame_if_segmented_in_header() {
         test_segmented_parse(4);
@@ -349,6 +383,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ignores_bad_protocol_id_when_lenient() {
+        let (io, mut io_handle) = sfio_tokio_mock_io::mock();
+        let mut reader = FramedReader::tcp();
+        reader.set_lenient_protocol_id(true);
+        let mut layer = PhysLayer::new_mock(io);
+        let mut task =
+            tokio_test::task::spawn(reader.next_frame(&mut layer, DecodeLevel::nothing()));
+
+        io_handle.read(&[0x00, 0x07, 0xCA, 0xFE, 0x00, 0x03, 0x2A, 0x03, 0x04]);
+        if let Poll::Ready(frame) = task.poll() {
+            assert_eq!(frame.unwrap().payload(), &[0x03, 0x04]);
+        } else {
+            panic!("Task not ready");
+        }
+    }
+
     #[test]
     fn errors_on_length_of_zero() {
         let frame = &[0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x2A];
@@ -365,7 +416,7 @@ mod tests {
             test_error(frame),
             RequestError::BadFrame(FrameParseError::FrameLengthTooBig(
                 0xFF,
-                constants::MAX_LENGTH_FIELD,
+                crate::common::frame::constants::MAX_ADU_LENGTH + 1,
             ))
         );
     }
@@ -2,5 +2,7 @@ pub(crate) mod client;
 pub(crate) mod frame;
 pub(crate) mod server;
 
+#[cfg(feature = "native-tls")]
+pub(crate) mod native_tls;
 #[cfg(feature = "tls")]
 pub(crate) mod tls;
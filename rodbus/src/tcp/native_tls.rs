@@ -0,0 +1,74 @@
+use tokio::net::TcpStream;
+use tokio_native_tls::native_tls;
+use tracing::Instrument;
+
+use crate::client::{Channel, ClientState, EndpointList, HostAddr, Listener, TcpOptions};
+use crate::common::phys::PhysLayer;
+use crate::retry::RetryStrategy;
+use crate::tcp::client::{TcpChannelTask, TcpTaskConnectionHandler};
+use crate::{DecodeLevel, Framing};
+
+/// TLS configuration that uses the platform TLS implementation
+///
+/// This is SChannel on Windows, Secure Transport on macOS and OpenSSL elsewhere. Server
+/// certificates are validated against the trust anchors of the operating system unless the
+/// connector is configured otherwise.
+pub struct NativeTlsClientConfig {
+    domain: String,
+    connector: tokio_native_tls::TlsConnector,
+}
+
+impl NativeTlsClientConfig {
+    /// Create a configuration from a connector built by the user
+    ///
+    /// * `domain` - Name used for SNI and to validate the server certificate
+    /// * `connector` - Connector with the client identity and validation settings
+    pub fn new(domain: &str, connector: native_tls::TlsConnector) -> Self {
+        Self {
+            domain: domain.to_string(),
+            connector: connector.into(),
+        }
+    }
+
+    pub(crate) async fn handle_connection(
+        &mut self,
+        socket: TcpStream,
+        endpoint: &HostAddr,
+    ) -> Result<PhysLayer, String> {
+        match self.connector.connect(&self.domain, socket).await {
+            Err(err) => Err(format!(
+                "failed to establish TLS session with {endpoint}: {err}"
+            )),
+            Ok(stream) => Ok(PhysLayer::new_native_tls(stream)),
+        }
+    }
+}
+
+pub(crate) fn spawn_native_tls_channel(
+    host: HostAddr,
+    options: TcpOptions,
+    max_queued_requests: usize,
+    connect_retry: Box<dyn RetryStrategy>,
+    tls_config: NativeTlsClientConfig,
+    decode: DecodeLevel,
+    listener: Box<dyn Listener<ClientState>>,
+) -> Channel {
+    let (channel, rx) = Channel::create(max_queued_requests);
+    let task = async move {
+        TcpChannelTask::new(
+            EndpointList::single(host.clone()),
+            rx,
+            TcpTaskConnectionHandler::NativeTls(tls_config),
+            Framing::Mbap,
+            options,
+            connect_retry,
+            decode,
+            listener,
+        )
+        .run()
+        .instrument(tracing::info_span!("Modbus-Client-TCP", endpoint = ?host))
+        .await;
+    };
+    tokio::spawn(task);
+    channel
+}
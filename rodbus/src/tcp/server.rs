@@ -1,27 +1,68 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use tracing::Instrument;
 
 use crate::common::frame::{FrameWriter, FramedReader};
 use crate::common::phys::PhysLayer;
 use crate::decode::DecodeLevel;
-use crate::server::handler::{RequestHandler, ServerHandlerMap};
-use crate::server::task::{AuthorizationType, ServerSetting};
+use crate::server::handler::{Peer, RequestHandler, ServerHandlerMap};
+use crate::server::observer::CloseReason;
+use crate::server::task::{AuthorizationType, ServerSetting, SessionActivity};
 
-use crate::server::AddressFilter;
-use std::net::SocketAddr;
+use crate::server::{AddressFilter, SessionLimitPolicy};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 use tokio::net::TcpListener;
 
 #[cfg(feature = "tls")]
 use crate::server::AuthorizationHandler;
 
 /// event sent back to the server task when a session ends
-struct SessionClose(u128);
+struct SessionClose {
+    id: u128,
+    peer: Peer,
+    reason: CloseReason,
+}
+
+struct SessionRecord {
+    addr: SocketAddr,
+    sender: tokio::sync::mpsc::Sender<ServerSetting>,
+    activity: SessionActivity,
+}
+
+/// Decode level of the server and the overrides for specific client addresses
+struct DecodeLevels {
+    default: DecodeLevel,
+    peers: BTreeMap<IpAddr, DecodeLevel>,
+}
+
+impl DecodeLevels {
+    fn new(default: DecodeLevel) -> Self {
+        Self {
+            default,
+            peers: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, ip: IpAddr) -> DecodeLevel {
+        self.peers.get(&ip).copied().unwrap_or(self.default)
+    }
+
+    fn set(&mut self, ip: IpAddr, level: Option<DecodeLevel>) {
+        match level {
+            Some(level) => self.peers.insert(ip, level),
+            None => self.peers.remove(&ip),
+        };
+    }
+}
 
 struct SessionTracker {
     max_sessions: usize,
+    policy: SessionLimitPolicy,
     id: u128,
-    sessions: BTreeMap<u128, tokio::sync::mpsc::Sender<ServerSetting>>,
+    sessions: BTreeMap<u128, SessionRecord>,
+    /// sessions closed to make room for new ones whose tasks haven't completed yet
+    evicted: BTreeSet<u128>,
 }
 
 impl SessionTracker {
@@ -34,8 +75,10 @@ impl SessionTracker {
         };
         Self {
             max_sessions,
+            policy: SessionLimitPolicy::CloseOldest,
             id: 0,
             sessions: BTreeMap::new(),
+            evicted: BTreeSet::new(),
         }
     }
 
@@ -45,26 +88,57 @@ impl SessionTracker {
         ret
     }
 
-    pub(crate) fn add(&mut self, sender: tokio::sync::mpsc::Sender<ServerSetting>) -> u128 {
+    /// true if a new session can be added, possibly by closing an existing one
+    fn can_add(&self) -> bool {
+        self.sessions.len() < self.max_sessions || self.policy != SessionLimitPolicy::RejectNew
+    }
+
+    fn add(
+        &mut self,
+        addr: SocketAddr,
+        sender: tokio::sync::mpsc::Sender<ServerSetting>,
+        activity: SessionActivity,
+    ) -> u128 {
         if self.sessions.len() >= self.max_sessions {
-            if let Some(oldest) = self.sessions.keys().next().copied() {
+            let victim = match self.policy {
+                SessionLimitPolicy::CloseIdlest => self
+                    .sessions
+                    .iter()
+                    .min_by_key(|(_, record)| record.activity.last())
+                    .map(|(id, _)| *id),
+                SessionLimitPolicy::CloseOldest | SessionLimitPolicy::RejectNew => {
+                    self.sessions.keys().next().copied()
+                }
+            };
+            if let Some(id) = victim {
                 tracing::warn!(
-                    "exceeded max connections, closing oldest session: {}",
-                    oldest
+                    "exceeded max connections, closing session: {} ({:?})",
+                    id,
+                    self.policy
                 );
                 // when the record drops, and there are no more senders,
                 // the other end will stop the task
-                self.sessions.remove(&oldest);
+                self.sessions.remove(&id);
+                self.evicted.insert(id);
             }
         }
 
         let id = self.get_next_id();
-        self.sessions.insert(id, sender);
+        self.sessions.insert(
+            id,
+            SessionRecord {
+                addr,
+                sender,
+                activity,
+            },
+        );
         id
     }
 
-    pub(crate) fn remove(&mut self, id: u128) {
+    /// remove a session whose task has completed, returning true if it had been evicted
+    pub(crate) fn remove(&mut self, id: u128) -> bool {
         self.sessions.remove(&id);
+        self.evicted.remove(&id)
     }
 }
 
@@ -103,9 +177,12 @@ pub(crate) struct ServerTask<T: RequestHandler> {
     tracker: SessionTracker,
     connection_handler: TcpServerConnectionHandler,
     filter: AddressFilter,
-    decode: DecodeLevel,
+    decode: DecodeLevels,
+    idle_timeout: Option<Duration>,
     tx: tokio::sync::mpsc::Sender<SessionClose>,
     rx: tokio::sync::mpsc::Receiver<SessionClose>,
+    /// number of spawned session tasks that haven't completed, including evicted ones
+    running: usize,
 }
 
 impl<T> ServerTask<T>
@@ -128,29 +205,96 @@ where
             tracker: SessionTracker::new(max_sessions),
             connection_handler,
             filter,
-            decode,
+            decode: DecodeLevels::new(decode),
+            idle_timeout: None,
             tx,
             rx,
+            running: 0,
         }
     }
 
     async fn change_setting(&mut self, setting: ServerSetting) {
-        // first, change it locally so that it is applied to new sessions
         match setting {
             ServerSetting::ChangeDecoding(level) => {
+                // first, change it locally so that it is applied to new sessions
                 tracing::info!("changed decoding level to {:?}", level);
-                self.decode = level;
+                self.decode.default = level;
+                self.update_session_decoding(|_| true).await;
             }
+            ServerSetting::ChangePeerDecoding(ip, level) => {
+                tracing::info!("changed decoding level of {} to {:?}", ip, level);
+                self.decode.set(ip, level);
+                self.update_session_decoding(|x| x == ip).await;
+            }
+            ServerSetting::ChangeAddressFilter(filter) => {
+                tracing::info!("changed address filter to {:?}", filter);
+                self.filter = filter;
+            }
+            ServerSetting::ChangeSessionLimitPolicy(policy) => {
+                tracing::info!("changed session limit policy to {:?}", policy);
+                self.tracker.policy = policy;
+            }
+            ServerSetting::ChangeIdleTimeout(timeout) => {
+                tracing::info!("changed idle timeout to {:?}", timeout);
+                self.idle_timeout = timeout;
+                for record in self.tracker.sessions.values_mut() {
+                    let _ = record
+                        .sender
+                        .send(ServerSetting::ChangeIdleTimeout(timeout))
+                        .await;
+                }
+            }
+            // existing sessions have already completed their handshake
+            #[cfg(feature = "tls")]
+            ServerSetting::ChangeTlsConfig(config) => match &mut self.connection_handler {
+                TcpServerConnectionHandler::Tls(x, _) => {
+                    tracing::info!("changed TLS configuration for new sessions");
+                    *x = config;
+                }
+                TcpServerConnectionHandler::Tcp => {
+                    tracing::warn!("ignoring TLS configuration on a server without TLS");
+                }
+            },
         }
+    }
 
-        for sender in self.tracker.sessions.values_mut() {
-            // best effort to send the setting to each session this isn't critical so we wouldn't
-            // want to slow the server down by awaiting it
-            let _ = sender.send(setting).await;
+    async fn update_session_decoding<F>(&mut self, filter: F)
+    where
+        F: Fn(IpAddr) -> bool,
+    {
+        for record in self.tracker.sessions.values_mut() {
+            let ip = record.addr.ip();
+            if filter(ip) {
+                // best effort to send the setting to each session this isn't critical so we wouldn't
+                // want to slow the server down by awaiting it
+                let _ = record
+                    .sender
+                    .send(ServerSetting::ChangeDecoding(self.decode.get(ip)))
+                    .await;
+            }
         }
     }
 
-    pub(crate) async fn run(&mut self, mut commands: tokio::sync::mpsc::Receiver<ServerSetting>) {
+    pub(crate) async fn run(&mut self, commands: tokio::sync::mpsc::Receiver<ServerSetting>) {
+        self.run_inner(commands).await;
+        self.close_sessions().await;
+    }
+
+    /// close every session and wait until their tasks have completed
+    async fn close_sessions(&mut self) {
+        // when the records drop, the sessions finish any request in progress and stop
+        self.tracker.sessions.clear();
+        while self.running > 0 {
+            // this will never be None b/c we always keep a tx live
+            if let Some(close) = self.rx.recv().await {
+                self.on_session_closed(close);
+            }
+            self.running -= 1;
+        }
+        tracing::info!("all sessions closed");
+    }
+
+    async fn run_inner(&mut self, mut commands: tokio::sync::mpsc::Receiver<ServerSetting>) {
         loop {
             tokio::select! {
                setting = commands.recv() => {
@@ -164,9 +308,10 @@ where
                }
                shutdown = self.rx.recv() => {
                    // this will never be None b/c we always keep a tx live
-                   let id = shutdown.unwrap().0;
-
-                   self.tracker.remove(id);
+                   if let Some(close) = shutdown {
+                       self.on_session_closed(close);
+                   }
+                   self.running -= 1;
                }
                result = self.listener.accept() => {
                    match result {
@@ -190,60 +335,110 @@ where
         }
     }
 
+    fn on_session_closed(&mut self, close: SessionClose) {
+        let evicted = self.tracker.remove(close.id);
+        let reason = match close.reason {
+            // the session saw its settings channel close when it was evicted
+            CloseReason::Shutdown if evicted => CloseReason::SessionLimit,
+            reason => reason,
+        };
+        if let Some(observer) = self.handlers.observer() {
+            observer.connection_closed(&close.peer, &reason);
+        }
+    }
+
     async fn handle(&mut self, socket: tokio::net::TcpStream, addr: SocketAddr) {
+        if !self.tracker.can_add() {
+            tracing::warn!(
+                "exceeded max connections, rejecting connection from: {}",
+                addr
+            );
+            return;
+        }
+
         let (tx, rx) = tokio::sync::mpsc::channel(8); // all we do is change settings, so a constant is fine
-        let id = self.tracker.add(tx);
+        let activity = SessionActivity::new();
+        let id = self.tracker.add(addr, tx, activity.clone());
         tracing::info!(
             "accepted connection from: {} - assigned session id: {}",
             addr,
             id
         );
+        if let Some(observer) = self.handlers.observer() {
+            observer.connection_accepted(&Peer::new(Some(addr), None));
+        }
 
         #[allow(unused_mut)]
         let mut notify_close = self.tx.clone();
         let connection_handler = self.connection_handler.clone();
         let handler_map = self.handlers.clone();
-        let decode_level = self.decode;
+        let decode_level = self.decode.get(addr.ip());
+        let idle_timeout = self.idle_timeout;
 
         let session = async move {
-            run_session(
+            let (peer, reason) = run_session(
                 socket,
                 addr,
                 connection_handler,
                 decode_level,
                 handler_map,
+                activity,
+                idle_timeout,
                 rx,
             )
             .await;
 
-            // no matter what happens, we send the id back to the server
-            let _ = notify_close.send(SessionClose(id)).await;
+            tracing::info!("session shutdown: {}", reason);
 
-            tracing::info!("session shutdown");
+            // no matter what happens, we send the id back to the server
+            let _ = notify_close.send(SessionClose { id, peer, reason }).await;
         };
 
         let session =
             session.instrument(tracing::info_span!("Session", "id" = ?id, "remote" = ?addr));
 
         // spawn the session off onto another task
+        self.running += 1;
         tokio::spawn(session);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_session<T: RequestHandler>(
     socket: tokio::net::TcpStream,
     addr: SocketAddr,
     mut handler: TcpServerConnectionHandler,
-    decode: DecodeLevel,
+    mut decode: DecodeLevel,
     handlers: ServerHandlerMap<T>,
-    commands: tokio::sync::mpsc::Receiver<ServerSetting>,
-) {
-    match handler.handle(socket).await {
+    activity: SessionActivity,
+    mut idle_timeout: Option<Duration>,
+    mut commands: tokio::sync::mpsc::Receiver<ServerSetting>,
+) -> (Peer, CloseReason) {
+    // a client can stall the TLS handshake forever, so keep listening to the server meanwhile
+    let handshake = handler.handle(socket);
+    tokio::pin!(handshake);
+    let result = loop {
+        tokio::select! {
+            result = &mut handshake => break result,
+            setting = commands.recv() => match setting {
+                // the server is shutting down or evicted the session
+                None => return (Peer::new(Some(addr), None), CloseReason::Shutdown),
+                Some(ServerSetting::ChangeDecoding(level)) => decode = level,
+                Some(ServerSetting::ChangeIdleTimeout(timeout)) => idle_timeout = timeout,
+                // the others only apply to the server task
+                Some(_) => {}
+            }
+        }
+    };
+
+    match result {
         Err(err) => {
             tracing::warn!("error from {}: {}", addr, err);
+            (Peer::new(Some(addr), None), CloseReason::Error(err))
         }
         Ok((mut phys, auth)) => {
-            let _ = crate::server::task::SessionTask::new(
+            let peer = Peer::new(Some(addr), auth.role());
+            let mut session = crate::server::task::SessionTask::new(
                 handlers,
                 auth,
                 FrameWriter::tcp(),
@@ -251,8 +446,80 @@ async fn run_session<T: RequestHandler>(
                 commands,
                 decode,
             )
-            .run(&mut phys)
-            .await;
+            .with_peer(peer.clone())
+            .with_activity(activity)
+            .with_idle_timeout(idle_timeout);
+            let err = session.run(&mut phys).await;
+            (peer, session.close_reason(err))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::AppDecodeLevel;
+
+    fn add(tracker: &mut SessionTracker) -> (u128, SessionActivity) {
+        let (tx, _) = tokio::sync::mpsc::channel(1);
+        let activity = SessionActivity::new();
+        let addr = SocketAddr::from(([127, 0, 0, 1], 50000));
+        (tracker.add(addr, tx, activity.clone()), activity)
+    }
+
+    fn ids(tracker: &SessionTracker) -> Vec<u128> {
+        tracker.sessions.keys().copied().collect()
+    }
+
+    #[test]
+    fn closes_oldest_session_by_default() {
+        let mut tracker = SessionTracker::new(2);
+        add(&mut tracker);
+        add(&mut tracker);
+        assert!(tracker.can_add());
+        add(&mut tracker);
+        assert_eq!(ids(&tracker), vec![1, 2]);
+        // the evicted session is reported as such once its task completes
+        assert!(tracker.remove(0));
+        assert!(!tracker.remove(1));
+    }
+
+    #[test]
+    fn closes_idlest_session() {
+        let mut tracker = SessionTracker::new(2);
+        tracker.policy = SessionLimitPolicy::CloseIdlest;
+        let (_, first) = add(&mut tracker);
+        add(&mut tracker);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        first.update();
+        add(&mut tracker);
+        assert_eq!(ids(&tracker), vec![0, 2]);
+    }
+
+    #[test]
+    fn rejects_new_sessions_when_full() {
+        let mut tracker = SessionTracker::new(2);
+        tracker.policy = SessionLimitPolicy::RejectNew;
+        add(&mut tracker);
+        assert!(tracker.can_add());
+        add(&mut tracker);
+        assert!(!tracker.can_add());
+        tracker.remove(0);
+        assert!(tracker.can_add());
+    }
+
+    #[test]
+    fn peer_decode_level_overrides_default() {
+        let ip = IpAddr::from([192, 168, 0, 1]);
+        let other = IpAddr::from([192, 168, 0, 2]);
+        let mut levels = DecodeLevels::new(DecodeLevel::nothing());
+        levels.set(
+            ip,
+            Some(DecodeLevel::default().application(AppDecodeLevel::DataValues)),
+        );
+        assert_eq!(levels.get(ip).app, AppDecodeLevel::DataValues);
+        assert_eq!(levels.get(other), DecodeLevel::nothing());
+        levels.set(ip, None);
+        assert_eq!(levels.get(ip), DecodeLevel::nothing());
+    }
+}
@@ -10,12 +10,14 @@ use tokio_rustls::rustls;
 use tokio_rustls::rustls::pki_types::InvalidDnsNameError;
 use tracing::Instrument;
 
-use crate::client::{Channel, ClientState, HostAddr, Listener, RetryStrategy};
+use crate::client::{
+    Channel, ClientState, EndpointList, HostAddr, Listener, RetryStrategy, TcpOptions,
+};
 use crate::common::phys::PhysLayer;
 use crate::tcp::client::{TcpChannelTask, TcpTaskConnectionHandler};
 use crate::tcp::tls::{CertificateMode, MinTlsVersion, TlsError};
 
-use crate::DecodeLevel;
+use crate::{DecodeLevel, Framing};
 
 /// TLS configuration
 pub struct TlsClientConfig {
@@ -25,6 +27,7 @@ pub struct TlsClientConfig {
 
 pub(crate) fn spawn_tls_channel(
     host: HostAddr,
+    options: TcpOptions,
     max_queued_requests: usize,
     connect_retry: Box<dyn RetryStrategy>,
     tls_config: TlsClientConfig,
@@ -33,6 +36,7 @@ pub(crate) fn spawn_tls_channel(
 ) -> Channel {
     let (handle, task) = create_tls_channel(
         host,
+        options,
         max_queued_requests,
         connect_retry,
         tls_config,
@@ -45,18 +49,21 @@ pub(crate) fn spawn_tls_channel(
 
 pub(crate) fn create_tls_channel(
     host: HostAddr,
+    options: TcpOptions,
     max_queued_requests: usize,
     connect_retry: Box<dyn RetryStrategy>,
     tls_config: TlsClientConfig,
     decode: DecodeLevel,
     listener: Box<dyn Listener<ClientState>>,
 ) -> (Channel, impl std::future::Future<Output = ()>) {
-    let (tx, rx) = tokio::sync::mpsc::channel(max_queued_requests);
+    let (channel, rx) = Channel::create(max_queued_requests);
     let task = async move {
         TcpChannelTask::new(
-            host.clone(),
-            rx.into(),
+            EndpointList::single(host.clone()),
+            rx,
             TcpTaskConnectionHandler::Tls(tls_config),
+            Framing::Mbap,
+            options,
             connect_retry,
             decode,
             listener,
@@ -65,7 +72,7 @@ pub(crate) fn create_tls_channel(
         .instrument(tracing::info_span!("Modbus-Client-TCP", endpoint = ?host))
         .await;
     };
-    (Channel { tx }, task)
+    (channel, task)
 }
 
 impl TlsClientConfig {
@@ -178,6 +185,35 @@ impl TlsClientConfig {
         })
     }
 
+    /// Create a TLS client configuration from a `rustls` configuration built by the user
+    ///
+    /// This makes it possible to plug in a custom certificate verifier with
+    /// `rustls::ClientConfig::builder().dangerous().with_custom_certificate_verifier(..)`,
+    /// e.g. to pin the fingerprint of a self-signed certificate shipped with a field device.
+    ///
+    /// The allowed cipher suites can be restricted by building the configuration with
+    /// `rustls::ClientConfig::builder_with_provider` and a `rustls::crypto::CryptoProvider`
+    /// whose `cipher_suites` only contains the suites to allow.
+    ///
+    /// `server_name` is sent in the SNI extension and passed to the verifier. If it is set to
+    /// None, no SNI extension is sent.
+    pub fn from_rustls(
+        server_name: Option<String>,
+        config: Arc<rustls::ClientConfig>,
+    ) -> Result<Self, TlsError> {
+        let server_name = match server_name {
+            None => rustls::pki_types::ServerName::IpAddress(rustls::pki_types::IpAddr::V4(
+                Ipv4Addr::UNSPECIFIED.into(),
+            )),
+            Some(x) => rustls::pki_types::ServerName::try_from(x)?,
+        };
+
+        Ok(Self {
+            server_name,
+            config,
+        })
+    }
+
     pub(crate) async fn handle_connection(
         &mut self,
         socket: TcpStream,
@@ -202,8 +238,56 @@ impl From<InvalidDnsNameError> for TlsError {
 impl From<MinTlsVersion> for ProtocolVersions {
     fn from(value: MinTlsVersion) -> Self {
         match value {
-            MinTlsVersion::V1_2 => ProtocolVersions::v12_only(),
-            MinTlsVersion::V1_3 => ProtocolVersions::new().enable_v12().enable_v13(),
+            MinTlsVersion::V1_2 => ProtocolVersions::new().enable_v12().enable_v13(),
+            MinTlsVersion::V1_3 => ProtocolVersions::v13_only(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rustls_config() -> Arc<rustls::ClientConfig> {
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(rustls::RootCertStore::empty())
+        .with_no_client_auth();
+        Arc::new(config)
+    }
+
+    #[test]
+    fn accepts_user_supplied_rustls_config() {
+        let config =
+            TlsClientConfig::from_rustls(Some("device.local".to_string()), rustls_config())
+                .unwrap();
+        assert_eq!(
+            config.server_name,
+            rustls::pki_types::ServerName::try_from("device.local").unwrap()
+        );
+        assert!(TlsClientConfig::from_rustls(None, rustls_config()).is_ok());
+    }
+
+    #[test]
+    fn min_tls_version_allows_newer_versions() {
+        assert_eq!(
+            ProtocolVersions::from(MinTlsVersion::V1_2),
+            ProtocolVersions::new().enable_v12().enable_v13()
+        );
+        assert_eq!(
+            ProtocolVersions::from(MinTlsVersion::V1_3),
+            ProtocolVersions::v13_only()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_server_name() {
+        assert!(matches!(
+            TlsClientConfig::from_rustls(Some("not a name!".to_string()), rustls_config()),
+            Err(TlsError::InvalidDnsName)
+        ));
+    }
+}
@@ -69,9 +69,9 @@ impl From<sfio_rustls_config::Error> for TlsError {
 /// Minimum TLS version to allow
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MinTlsVersion {
-    /// TLS 1.2
+    /// TLS 1.2 and TLS 1.3
     V1_2,
-    /// TLS 1.3
+    /// TLS 1.3 only
     V1_3,
 }
 
@@ -49,6 +49,20 @@ impl TlsServerConfig {
         })
     }
 
+    /// Create a TLS server configuration from a `rustls` configuration built by the user
+    ///
+    /// This makes it possible to restrict the allowed cipher suites by building the configuration
+    /// with `rustls::ServerConfig::builder_with_provider` and a `rustls::crypto::CryptoProvider`
+    /// whose `cipher_suites` only contains the suites to allow, or to plug in a custom client
+    /// certificate verifier.
+    ///
+    /// The configuration should require client authentication. Servers created with
+    /// [`crate::server::spawn_tls_server_task_with_authz`] reject clients that do not present
+    /// a certificate with a Modbus role extension.
+    pub fn from_rustls(config: Arc<rustls::ServerConfig>) -> Self {
+        Self { inner: config }
+    }
+
     pub(crate) async fn handle_connection(
         &mut self,
         socket: TcpStream,
@@ -1,5 +1,5 @@
 use crate::decode::AppDecodeLevel;
-use crate::error::{AduParseError, InvalidRange};
+use crate::error::{AduParseError, InvalidRange, InvalidRequest};
 
 use scursor::ReadCursor;
 
@@ -59,6 +59,86 @@ pub struct Indexed<T> {
     pub value: T,
 }
 
+/// Register address and the masks used in a `mask write register` request
+///
+/// The server computes `(current AND and_mask) OR (or_mask AND (NOT and_mask))`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaskWriteRegister {
+    /// Address of the register
+    pub address: u16,
+    /// Mask ANDed with the current value of the register
+    pub and_mask: u16,
+    /// Mask ORed into the bits cleared by the AND mask
+    pub or_mask: u16,
+}
+
+/// Eight exception status outputs returned by a `read exception status` request
+///
+/// The meaning of each output is device specific
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExceptionStatus {
+    /// Raw value with output 0 in the least significant bit
+    pub value: u8,
+}
+
+/// Status word and event count returned by a `get comm event counter` request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommEventCounter {
+    /// Status word, 0xFFFF if the server is still processing a previous command
+    pub status: u16,
+    /// Number of successfully completed messages since the last restart or counter reset
+    pub event_count: u16,
+}
+
+/// Response to a `report server id` request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerId {
+    /// Device specific server id and additional data
+    pub data: Vec<u8>,
+    /// Run indicator status, 0xFF if the device is running and 0x00 if it is not
+    pub run_indicator: u8,
+}
+
+/// Location and length of a record to read from a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileRecordRange {
+    /// File number
+    pub file_number: u16,
+    /// Starting record number within the file
+    pub record_number: u16,
+    /// Number of registers to read
+    pub record_length: u16,
+}
+
+/// Record of registers within a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileRecord {
+    /// File number
+    pub file_number: u16,
+    /// Starting record number within the file
+    pub record_number: u16,
+    /// Register values of the record
+    pub values: Vec<u16>,
+}
+
+/// Framing used to delimit Modbus messages, independent of the physical transport
+///
+/// Note: User code cannot exhaustively match against this enum as new variants may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Modbus TCP framing using the MBAP header
+    Mbap,
+    /// RTU framing: unit id, PDU and CRC-16
+    ///
+    /// Over TCP, this allows communicating with devices behind serial-to-Ethernet converters
+    #[cfg(feature = "serial")]
+    Rtu,
+    /// ASCII framing: hex encoded unit id, PDU and LRC delimited by ':' and CR/LF
+    #[cfg(feature = "serial")]
+    Ascii,
+}
+
 /// Zero-copy type used to iterate over a collection of bits
 #[derive(Debug, Copy, Clone)]
 pub struct BitIterator<'a> {
@@ -281,6 +361,29 @@ impl AddressRange {
         })
     }
 
+    pub(crate) fn of_write_coils(self) -> Result<Self, InvalidRange> {
+        self.limited_count(crate::constants::limits::MAX_WRITE_COILS_COUNT)
+    }
+
+    pub(crate) fn of_write_registers(self) -> Result<Self, InvalidRange> {
+        self.limited_count(crate::constants::limits::MAX_WRITE_REGISTERS_COUNT)
+    }
+
+    pub(crate) fn of_read_write_registers(self) -> Result<Self, InvalidRange> {
+        self.limited_count(crate::constants::limits::MAX_READ_WRITE_WRITE_REGISTERS_COUNT)
+    }
+
+    /// consecutive ranges of at most `max` points covering this range
+    pub(crate) fn split(self, max: u16) -> impl Iterator<Item = AddressRange> {
+        let end = self.start as u32 + self.count as u32;
+        (self.start as u32..end)
+            .step_by(max.max(1) as usize)
+            .map(move |start| AddressRange {
+                start: start as u16,
+                count: (end - start).min(max.max(1) as u32) as u16,
+            })
+    }
+
     fn limited_count(self, limit: u16) -> Result<Self, InvalidRange> {
         if self.count > limit {
             return Err(InvalidRange::CountTooLargeForType(self.count, limit));
@@ -341,6 +444,185 @@ impl std::fmt::Display for Indexed<u16> {
     }
 }
 
+impl MaskWriteRegister {
+    /// Create a new mask write request
+    pub fn new(address: u16, and_mask: u16, or_mask: u16) -> Self {
+        Self {
+            address,
+            and_mask,
+            or_mask,
+        }
+    }
+
+    /// Request that sets the bits in `mask` and leaves the others unchanged
+    pub fn set_bits(address: u16, mask: u16) -> Self {
+        Self::new(address, !mask, mask)
+    }
+
+    /// Request that clears the bits in `mask` and leaves the others unchanged
+    pub fn clear_bits(address: u16, mask: u16) -> Self {
+        Self::new(address, !mask, 0)
+    }
+
+    /// Request that changes a register from `current` to `new` by only writing the bits that
+    /// differ, so that the other bits keep whatever value the register has when it's applied
+    pub fn change(address: u16, current: u16, new: u16) -> Self {
+        Self::new(address, !(current ^ new), new)
+    }
+
+    /// Compute the value the server writes given the current value of the register
+    pub fn apply(&self, current: u16) -> u16 {
+        (current & self.and_mask) | (self.or_mask & !self.and_mask)
+    }
+}
+
+impl std::fmt::Display for MaskWriteRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "idx: {:#06X} and_mask: {:#06X} or_mask: {:#06X}",
+            self.address, self.and_mask, self.or_mask
+        )
+    }
+}
+
+impl ExceptionStatus {
+    /// Create a new exception status from its raw value
+    pub fn new(value: u8) -> Self {
+        Self { value }
+    }
+
+    /// Retrieve the state of an output by index (0 to 7)
+    ///
+    /// Returns false for indices greater than 7
+    pub fn get(&self, index: u8) -> bool {
+        index < 8 && self.value & (1 << index) != 0
+    }
+}
+
+impl std::fmt::Display for ExceptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "status: {:#04X}", self.value)
+    }
+}
+
+impl CommEventCounter {
+    /// Create a new comm event counter from its status word and event count
+    pub fn new(status: u16, event_count: u16) -> Self {
+        Self {
+            status,
+            event_count,
+        }
+    }
+
+    /// True if the server reported that it is still processing a previous command
+    pub fn is_busy(&self) -> bool {
+        self.status == 0xFFFF
+    }
+}
+
+impl std::fmt::Display for CommEventCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "status: {:#06X} event count: {}",
+            self.status, self.event_count
+        )
+    }
+}
+
+impl ServerId {
+    /// Create a new server id from the device specific data and the run indicator
+    pub fn new(data: Vec<u8>, run_indicator: u8) -> Self {
+        Self {
+            data,
+            run_indicator,
+        }
+    }
+
+    /// True if the run indicator reports that the device is running
+    pub fn is_running(&self) -> bool {
+        self.run_indicator == 0xFF
+    }
+}
+
+impl std::fmt::Display for ServerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "data:")?;
+        for byte in self.data.iter() {
+            write!(f, " {byte:02X}")?;
+        }
+        write!(f, " run indicator: {:#04X}", self.run_indicator)
+    }
+}
+
+impl FileRecordRange {
+    /// Create a new file record range
+    pub fn new(file_number: u16, record_number: u16, record_length: u16) -> Self {
+        Self {
+            file_number,
+            record_number,
+            record_length,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), InvalidRequest> {
+        if self.record_length == 0 {
+            return Err(InvalidRange::CountOfZero.into());
+        }
+        if self.record_number > crate::constants::limits::MAX_FILE_RECORD_NUMBER {
+            return Err(InvalidRequest::RecordNumberTooLarge(
+                self.record_number,
+                crate::constants::limits::MAX_FILE_RECORD_NUMBER,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for FileRecordRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "file: {:#06X} record: {:#06X} length: {}",
+            self.file_number, self.record_number, self.record_length
+        )
+    }
+}
+
+impl FileRecord {
+    /// Create a new file record
+    pub fn new(file_number: u16, record_number: u16, values: Vec<u16>) -> Self {
+        Self {
+            file_number,
+            record_number,
+            values,
+        }
+    }
+}
+
+impl FileRecord {
+    pub(crate) fn validate(&self) -> Result<(), InvalidRequest> {
+        let record_length = match u16::try_from(self.values.len()) {
+            Ok(x) => x,
+            Err(_) => return Err(InvalidRequest::CountTooBigForU16(self.values.len())),
+        };
+        FileRecordRange::new(self.file_number, self.record_number, record_length).validate()
+    }
+}
+
+impl std::fmt::Display for FileRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "file: {:#06X} record: {:#06X} length: {}",
+            self.file_number,
+            self.record_number,
+            self.values.len()
+        )
+    }
+}
+
 impl UnitId {
     /// Create a new UnitId
     pub fn new(value: u8) -> Self {
@@ -375,7 +657,7 @@ mod tests {
 
     #[test]
     fn address_start_max_count_of_one_is_allowed() {
-        AddressRange::try_from(std::u16::MAX, 1).unwrap();
+        AddressRange::try_from(u16::MAX, 1).unwrap();
     }
 
     #[test]
@@ -396,6 +678,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn splits_ranges_in_consecutive_chunks() {
+        let chunks: Vec<AddressRange> = AddressRange::try_from(10, 251)
+            .unwrap()
+            .split(125)
+            .collect();
+        assert_eq!(
+            chunks,
+            [
+                AddressRange::try_from(10, 125).unwrap(),
+                AddressRange::try_from(135, 125).unwrap(),
+                AddressRange::try_from(260, 1).unwrap(),
+            ]
+        );
+        // the last address is included
+        let chunks: Vec<AddressRange> = AddressRange::try_from(u16::MAX - 1, 2)
+            .unwrap()
+            .split(1)
+            .collect();
+        assert_eq!(
+            chunks.last(),
+            Some(&AddressRange::try_from(u16::MAX, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn write_coils_count_is_limited() {
+        AddressRange::try_from(0, 0x07B0)
+            .unwrap()
+            .of_write_coils()
+            .unwrap();
+        assert_eq!(
+            AddressRange::try_from(0, 0x07B1).unwrap().of_write_coils(),
+            Err(InvalidRange::CountTooLargeForType(0x07B1, 0x07B0))
+        );
+    }
+
+    #[test]
+    fn write_registers_count_is_limited() {
+        AddressRange::try_from(0, 0x7B)
+            .unwrap()
+            .of_write_registers()
+            .unwrap();
+        assert_eq!(
+            AddressRange::try_from(0, 0x7C)
+                .unwrap()
+                .of_write_registers(),
+            Err(InvalidRange::CountTooLargeForType(0x7C, 0x7B))
+        );
+    }
+
+    #[test]
+    fn mask_write_register_applies_masks() {
+        // example from the Modbus application protocol specification
+        assert_eq!(
+            MaskWriteRegister::new(4, 0x00F2, 0x0025).apply(0x0012),
+            0x0017
+        );
+        assert_eq!(MaskWriteRegister::set_bits(4, 0x0101).apply(0x1010), 0x1111);
+        assert_eq!(
+            MaskWriteRegister::clear_bits(4, 0x0101).apply(0xFFFF),
+            0xFEFE
+        );
+        let change = MaskWriteRegister::change(4, 0x00F0, 0x0F00);
+        assert_eq!(change.apply(0x00F0), 0x0F00);
+        // the bits that weren't changed keep their value
+        assert_eq!(change.apply(0x80F1), 0x8F01);
+    }
+
+    #[test]
+    fn exception_status_reports_outputs_by_index() {
+        let status = ExceptionStatus::new(0x6D);
+        let outputs: Vec<bool> = (0..8).map(|i| status.get(i)).collect();
+        assert_eq!(
+            outputs,
+            vec![true, false, true, true, false, true, true, false]
+        );
+        assert!(!status.get(8));
+    }
+
     #[test]
     fn correctly_iterates_over_low_order_bits() {
         let mut cursor = ReadCursor::new(&[0x03]);
@@ -0,0 +1,140 @@
+use tracing::Instrument;
+
+use crate::client::{Channel, ClientState, HostAddr, Listener};
+use crate::common::frame::{FrameWriter, FramedReader};
+use crate::common::phys::PhysLayer;
+use crate::decode::DecodeLevel;
+
+use crate::client::message::CommandReceiver;
+use crate::client::task::{ClientLoop, SessionError, StateChange};
+use crate::error::Shutdown;
+use crate::retry::RetryStrategy;
+
+pub(crate) fn spawn_udp_channel(
+    host: HostAddr,
+    max_queued_requests: usize,
+    retransmissions: usize,
+    retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Box<dyn Listener<ClientState>>,
+) -> Channel {
+    let (channel, rx) = Channel::create(max_queued_requests);
+    let task = async move {
+        UdpChannelTask::new(host.clone(), rx, retransmissions, retry, decode, listener)
+            .run()
+            .instrument(tracing::info_span!("Modbus-Client-UDP", endpoint = ?host))
+            .await;
+    };
+    tokio::spawn(task);
+    channel
+}
+
+pub(crate) struct UdpChannelTask {
+    host: HostAddr,
+    retry: Box<dyn RetryStrategy>,
+    client_loop: ClientLoop,
+    listener: Box<dyn Listener<ClientState>>,
+}
+
+impl UdpChannelTask {
+    pub(crate) fn new(
+        host: HostAddr,
+        rx: CommandReceiver,
+        retransmissions: usize,
+        retry: Box<dyn RetryStrategy>,
+        decode: DecodeLevel,
+        listener: Box<dyn Listener<ClientState>>,
+    ) -> Self {
+        Self {
+            host,
+            retry,
+            client_loop: ClientLoop::new(rx, FrameWriter::tcp(), FramedReader::tcp(), decode)
+                .with_retransmissions(retransmissions),
+            listener,
+        }
+    }
+
+    // runs until it is shut down
+    pub(crate) async fn run(&mut self) -> Shutdown {
+        self.listener.update(ClientState::Disabled).get().await;
+        let ret = self.run_inner().await;
+        self.listener.update(ClientState::Shutdown).get().await;
+        ret
+    }
+
+    async fn run_inner(&mut self) -> Shutdown {
+        loop {
+            if let Err(Shutdown) = self.client_loop.wait_for_enabled().await {
+                return Shutdown;
+            }
+
+            if let Err(StateChange::Shutdown) = self.try_bind_and_run().await {
+                return Shutdown;
+            }
+
+            if !self.client_loop.is_enabled() {
+                self.listener.update(ClientState::Disabled).get().await;
+            }
+        }
+    }
+
+    async fn bind(&mut self) -> Result<std::io::Result<tokio::net::UdpSocket>, StateChange> {
+        tokio::select! {
+            res = self.host.bind_udp() => {
+                Ok(res)
+            }
+            res = self.client_loop.fail_requests() => {
+                Err(res)
+            }
+        }
+    }
+
+    async fn try_bind_and_run(&mut self) -> Result<(), StateChange> {
+        self.listener.update(ClientState::Connecting).get().await;
+        match self.bind().await? {
+            Err(err) => {
+                let delay = self.retry.after_failed_connect();
+                tracing::warn!(
+                    "failed to open UDP socket to {}: {} - waiting {} ms before next attempt",
+                    self.host,
+                    err,
+                    delay.as_millis()
+                );
+                self.listener
+                    .update(ClientState::WaitAfterFailedConnect(delay))
+                    .get()
+                    .await;
+                self.client_loop.fail_requests_for(delay).await
+            }
+            Ok(socket) => {
+                if let Ok(addr) = socket.local_addr() {
+                    tracing::info!("sending datagrams from: {}", addr);
+                }
+                self.retry.reset();
+                self.listener.update(ClientState::Connected).get().await;
+                let mut phys = PhysLayer::new_udp(socket);
+                let err = self.client_loop.run(&mut phys).await;
+                // close the connection right away instead of keeping it while waiting
+                drop(phys);
+                match err {
+                    // the mpsc was closed, end the task
+                    SessionError::Shutdown => Err(StateChange::Shutdown),
+                    // reconnect once the next request is made
+                    SessionError::Idle => Ok(()),
+                    // don't wait, we're disabled
+                    SessionError::Disabled => Ok(()),
+                    // re-open the socket, e.g. after an ICMP port unreachable
+                    SessionError::IoError(_) | SessionError::BadFrame => {
+                        let delay = self.retry.after_disconnect();
+                        tracing::warn!("waiting {:?} to re-open socket", delay);
+                        self.listener
+                            .update(ClientState::WaitAfterDisconnect(delay))
+                            .get()
+                            .await;
+                        self.client_loop.fail_requests_for(delay).await
+                    }
+                }
+            }
+        }
+    }
+}
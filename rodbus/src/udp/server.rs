@@ -0,0 +1,38 @@
+use crate::common::phys::PhysLayer;
+use crate::server::task::SessionTask;
+use crate::server::RequestHandler;
+use crate::{RequestError, Shutdown};
+
+/// pause after an I/O error on the socket to prevent a rapid retry loop
+const IO_ERROR_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub(crate) struct UdpServerTask<T>
+where
+    T: RequestHandler,
+{
+    pub(crate) socket: PhysLayer,
+    pub(crate) session: SessionTask<T>,
+}
+
+impl<T> UdpServerTask<T>
+where
+    T: RequestHandler,
+{
+    pub(crate) async fn run(&mut self) -> Shutdown {
+        loop {
+            // a bad datagram only affects the client that sent it, so keep serving the others
+            match self.session.run(&mut self.socket).await {
+                RequestError::Shutdown => return Shutdown,
+                // Windows reports an ICMP port unreachable for a previous reply this way
+                RequestError::Io(std::io::ErrorKind::ConnectionReset) => {}
+                RequestError::Io(kind) => {
+                    tracing::warn!("socket error: {:?}, waiting {:?}", kind, IO_ERROR_DELAY);
+                    if let Err(Shutdown) = self.session.sleep_for(IO_ERROR_DELAY).await {
+                        return Shutdown;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
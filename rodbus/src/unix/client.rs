@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use tracing::Instrument;
+
+use crate::client::transport::TransportChannelTask;
+use crate::client::{Channel, ClientState, Listener};
+use crate::decode::DecodeLevel;
+use crate::retry::RetryStrategy;
+use crate::types::Framing;
+
+use tokio::net::UnixStream;
+
+pub(crate) fn spawn_unix_channel(
+    path: &Path,
+    framing: Framing,
+    max_queued_requests: usize,
+    connect_retry: Box<dyn RetryStrategy>,
+    decode: DecodeLevel,
+    listener: Box<dyn Listener<ClientState>>,
+) -> Channel {
+    let path = path.to_path_buf();
+    let (channel, rx) = Channel::create(max_queued_requests);
+    let task = async move {
+        let span = tracing::info_span!("Modbus-Client-Unix", path = ?path, framing = ?framing);
+        let connect = move || UnixStream::connect(path.clone());
+        TransportChannelTask::new(connect, rx, framing, connect_retry, decode, listener)
+            .run()
+            .instrument(span)
+            .await;
+    };
+    tokio::spawn(task);
+    channel
+}
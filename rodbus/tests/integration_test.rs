@@ -1,3 +1,5 @@
+//! Integration tests that run a client against a server over TCP
+
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::time::Duration;
@@ -42,6 +44,22 @@ impl Listener<ClientState> for ClientStateListener {
     }
 }
 
+struct ConnectionEventListener {
+    tx: tokio::sync::mpsc::Sender<ConnectionEvent>,
+}
+
+impl Listener<ConnectionEvent> for ConnectionEventListener {
+    fn update(&mut self, value: ConnectionEvent) -> MaybeAsync<()> {
+        let update = {
+            let tx = self.tx.clone();
+            async move {
+                let _ = tx.send(value).await;
+            }
+        };
+        MaybeAsync::asynchronous(update)
+    }
+}
+
 impl RequestHandler for Handler {
     fn read_coil(&self, address: u16) -> Result<bool, ExceptionCode> {
         match self.coils.get(address as usize) {
@@ -249,6 +267,108 @@ async fn test_requests_and_responses() {
             Indexed::new(2, 0x0506)
         ]
     );
+
+    // the server doesn't implement mask write register
+    assert_eq!(
+        channel.set_register_bits(params, 0, 0x0001).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement read file record
+    assert_eq!(
+        channel
+            .read_file_records(params, vec![FileRecordRange::new(1, 0, 2)])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement read exception status
+    assert_eq!(
+        channel.read_exception_status(params).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement diagnostics
+    assert_eq!(
+        channel.return_query_data(params, vec![0xA537]).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server answers get comm event counter from its diagnostic counters
+    assert!(channel.get_comm_event_counter(params).await.is_ok());
+
+    // the server answers get comm event log from its event log
+    assert!(channel.get_comm_event_log(params).await.is_ok());
+
+    // the server doesn't implement report server id
+    assert_eq!(
+        channel.report_server_id(params).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement any MEI type
+    assert_eq!(
+        channel
+            .encapsulated_interface_transport(params, 0x0D, &[0x00])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // raw PDUs are answered like the equivalent typed request
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x03, &[0x00, 0x00, 0x00, 0x01])
+            .await
+            .unwrap(),
+        // byte count followed by the value written to register 0 above
+        vec![0x02, 0x01, 0x02]
+    );
+    // write single coil only accepts 0xFF00 and 0x0000 by default
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x05, &[0x00, 0x00, 0x00, 0x01])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataValue))
+    );
+    // and vendor-specific function codes get an exception
+    assert_eq!(
+        channel.send_raw_pdu(params, 0x41, &[0x01, 0x02]).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement read device identification
+    assert_eq!(
+        channel
+            .read_device_identification(params, DeviceIdCategory::Basic)
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement write file record
+    assert_eq!(
+        channel
+            .write_file_records(params, vec![FileRecord::new(1, 0, vec![0xCAFE])])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement read FIFO queue
+    assert_eq!(
+        channel.read_fifo_queue(params, 0).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // the server doesn't implement read/write multiple registers
+    assert_eq!(
+        channel
+            .read_write_multiple_registers(
+                params,
+                ReadWriteMultiple::new(AddressRange::try_from(0, 3).unwrap(), 0, vec![0xFFFF])
+                    .unwrap()
+            )
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
 }
 
 #[test]
@@ -256,3 +376,1760 @@ fn can_read_and_write_values() {
     let rt = Runtime::new().unwrap();
     rt.block_on(test_requests_and_responses())
 }
+
+#[cfg(feature = "serial")]
+async fn test_rtu_framing_over_tcp() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // emulates a serial-to-Ethernet converter that forwards RTU frames as-is
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:40001")
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let mut config = TcpClientConfig::new(HostAddr::ip(addr.ip(), addr.port()));
+    config.framing = Framing::Rtu;
+    let mut channel = spawn_tcp_client_task_with_config(
+        config,
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 8];
+        socket.read_exact(&mut request).await.unwrap();
+        assert_eq!(request, [0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]);
+        socket
+            .write_all(&[0x01, 0x03, 0x02, 0x12, 0x34, 0xB5, 0x33])
+            .await
+            .unwrap();
+    });
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0x1234)]
+    );
+
+    server.await.unwrap();
+}
+
+#[cfg(feature = "serial")]
+#[test]
+fn can_use_rtu_framing_over_tcp() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_rtu_framing_over_tcp())
+}
+
+async fn test_udp_retransmission() {
+    // emulates a server that answers only the second copy of the request
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut request = [0u8; 260];
+        let (count, _) = socket.recv_from(&mut request).await.unwrap();
+        let first = request[..count].to_vec();
+
+        let (count, peer) = socket.recv_from(&mut request).await.unwrap();
+        assert_eq!(&request[..count], first.as_slice());
+        assert_eq!(
+            &request[2..count],
+            &[0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x01]
+        );
+
+        let response = [
+            request[0], request[1], 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x12, 0x34,
+        ];
+        socket.send_to(&response, peer).await.unwrap();
+    });
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let mut channel = spawn_udp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        1,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until the socket is open
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_millis(200));
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0x1234)]
+    );
+
+    server.await.unwrap();
+}
+
+#[test]
+fn retransmits_unanswered_udp_requests() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_udp_retransmission())
+}
+
+#[cfg(unix)]
+async fn test_unix_socket() {
+    let path = std::env::temp_dir().join(format!("rodbus-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let server = tokio::spawn(async move {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 12];
+        socket.read_exact(&mut request).await.unwrap();
+        assert_eq!(
+            &request[2..],
+            &[0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x01]
+        );
+        socket
+            .write_all(&[
+                request[0], request[1], 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x12, 0x34,
+            ])
+            .await
+            .unwrap();
+    });
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let mut channel = spawn_unix_client_task(
+        &path,
+        Framing::Mbap,
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0x1234)]
+    );
+
+    server.await.unwrap();
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[test]
+fn can_connect_over_unix_socket() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_unix_socket())
+}
+
+async fn test_custom_transport() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // each connection is an in-memory pipe whose server half is handed to the test
+    let (server_tx, mut server_rx) = tokio::sync::mpsc::unbounded_channel();
+    let connect = move || {
+        let (client, server) = tokio::io::duplex(260);
+        let _ = server_tx.send(server);
+        std::future::ready(Ok(client))
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let mut channel = spawn_custom_client_task(
+        connect,
+        Framing::Mbap,
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let mut server = server_rx.recv().await.unwrap();
+    let server = tokio::spawn(async move {
+        let mut request = [0u8; 12];
+        server.read_exact(&mut request).await.unwrap();
+        assert_eq!(
+            &request[2..],
+            &[0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x01]
+        );
+        server
+            .write_all(&[
+                request[0], request[1], 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x12, 0x34,
+            ])
+            .await
+            .unwrap();
+        server
+    });
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0x1234)]
+    );
+
+    server.await.unwrap();
+}
+
+#[test]
+fn can_use_custom_transport() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_custom_transport())
+}
+
+async fn test_failover() {
+    // nothing listens on the primary endpoint
+    let primary = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let primary_addr = primary.local_addr().unwrap();
+    drop(primary);
+
+    let backup = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let backup_addr = backup.local_addr().unwrap();
+
+    let mut endpoints = EndpointList::new(primary_addr.into(), FailoverPolicy::PreferPrimary);
+    endpoints.add(backup_addr.into());
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let channel = spawn_tcp_client_task_with_config(
+        endpoints.into(),
+        10,
+        // long enough that the test would time out if the channel waited before trying the backup
+        Box::new(FixedDelay(Duration::from_secs(60))),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    let (_socket, _) = tokio::time::timeout(Duration::from_secs(5), backup.accept())
+        .await
+        .unwrap()
+        .unwrap();
+
+    loop {
+        match rx.recv().await.unwrap() {
+            ClientState::Connected => break,
+            ClientState::WaitAfterFailedConnect(_) => panic!("waited before trying the backup"),
+            _ => {}
+        }
+    }
+}
+
+struct FixedDelay(Duration);
+
+impl RetryStrategy for FixedDelay {
+    fn reset(&mut self) {}
+
+    fn after_failed_connect(&mut self) -> Duration {
+        self.0
+    }
+
+    fn after_disconnect(&mut self) -> Duration {
+        self.0
+    }
+}
+
+#[test]
+fn fails_over_to_backup_endpoint() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_failover())
+}
+
+async fn test_unit_id_routing() {
+    let addr = SocketAddr::from_str("127.0.0.1:40002").unwrap();
+
+    let mut map: ServerHandlerMap<Box<dyn RequestHandler>> = ServerHandlerMap::new();
+    let first: Box<dyn RequestHandler> = Box::new(Handler::new());
+    let mut second = ServerDatabase::new(DatabaseConfig {
+        holding_registers: Some(AddressRange::try_from(0, 1).unwrap()),
+        ..Default::default()
+    });
+    second.set_holding_register(0, 0xCAFE);
+    let second: Box<dyn RequestHandler> = Box::new(second);
+    map.add(UnitId::new(1), first.wrap());
+    map.add(UnitId::new(2), second.wrap());
+    map.set_unmapped_unit_id_policy(UnmappedUnitIdPolicy::RespondGatewayTargetFailed);
+
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let range = AddressRange::try_from(0, 1).unwrap();
+    let read = |id: u8| RequestParam::new(UnitId::new(id), Duration::from_secs(1));
+
+    assert_eq!(
+        channel
+            .read_holding_registers(read(1), range)
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0)]
+    );
+    assert_eq!(
+        channel
+            .read_holding_registers(read(2), range)
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0xCAFE)]
+    );
+    assert_eq!(
+        channel.read_holding_registers(read(3), range).await,
+        Err(RequestError::Exception(
+            ExceptionCode::GatewayTargetDeviceFailedToRespond
+        ))
+    );
+}
+
+#[test]
+fn routes_requests_by_unit_id() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_unit_id_routing())
+}
+
+struct ReadOnlyZone;
+
+impl WriteAuthorizationHandler for ReadOnlyZone {
+    fn authorize_write(
+        &self,
+        _unit_id: UnitId,
+        function: WriteFunction,
+        range: AddressRange,
+        peer: &Peer,
+    ) -> Result<(), ExceptionCode> {
+        assert!(peer.addr().unwrap().ip().is_loopback());
+        assert_eq!(peer.role(), None);
+        match function {
+            WriteFunction::WriteSingleCoil | WriteFunction::WriteMultipleCoils => {
+                Err(ExceptionCode::IllegalFunction)
+            }
+            _ if range.start + range.count > 5 => Err(ExceptionCode::IllegalDataAddress),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn test_write_authorization() {
+    let addr = SocketAddr::from_str("127.0.0.1:40003").unwrap();
+
+    let db = DatabaseHandle::new(DatabaseConfig {
+        coils: Some(AddressRange::try_from(0, 10).unwrap()),
+        holding_registers: Some(AddressRange::try_from(0, 10).unwrap()),
+        ..Default::default()
+    });
+    let mut map = ServerHandlerMap::single(UnitId::new(1), db.handler());
+    map.set_write_authorization(ReadOnlyZone.wrap());
+
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    assert_eq!(
+        channel
+            .write_single_register(params, Indexed::new(4, 0xCAFE))
+            .await
+            .unwrap(),
+        Indexed::new(4, 0xCAFE)
+    );
+    assert_eq!(
+        channel
+            .write_multiple_registers(
+                params,
+                WriteMultiple::from(4, vec![0x0001, 0x0002]).unwrap()
+            )
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataAddress))
+    );
+    assert_eq!(
+        channel
+            .write_single_coil(params, Indexed::new(0, true))
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    db.transaction(|db| {
+        assert_eq!(db.get_holding_register(4), Some(0xCAFE));
+        assert_eq!(db.get_holding_register(5), Some(0));
+        assert_eq!(db.get_coil(0), Some(false));
+    });
+}
+
+#[test]
+fn rejects_unauthorized_writes() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_write_authorization())
+}
+
+struct VendorHandler;
+
+impl RequestHandler for VendorHandler {
+    fn process_raw_pdu(&mut self, function: u8, data: &[u8]) -> Result<Vec<u8>, ExceptionCode> {
+        match function {
+            0x41 => Ok(data.iter().rev().copied().collect()),
+            0x42 => Err(ExceptionCode::ServerDeviceBusy),
+            _ => Err(ExceptionCode::IllegalFunction),
+        }
+    }
+}
+
+async fn test_raw_pdu_handler() {
+    let addr = SocketAddr::from_str("127.0.0.1:40004").unwrap();
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), VendorHandler.wrap()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x41, &[1, 2, 3])
+            .await
+            .unwrap(),
+        vec![3, 2, 1]
+    );
+    assert_eq!(
+        channel.send_raw_pdu(params, 0x42, &[]).await,
+        Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+    );
+    // known to the library, but not implemented by the server
+    assert_eq!(
+        channel.read_exception_status(params).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+}
+
+#[test]
+fn passes_unimplemented_function_codes_to_handler() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_raw_pdu_handler())
+}
+
+async fn test_server_shutdown() {
+    let addr = SocketAddr::from_str("127.0.0.1:40005").unwrap();
+
+    let server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    channel
+        .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), server.shutdown())
+        .await
+        .unwrap();
+
+    // the session was closed
+    loop {
+        if let ClientState::WaitAfterDisconnect(_) = rx.recv().await.unwrap() {
+            break;
+        }
+    }
+
+    // the listener was closed, so the address can be bound again
+    tokio::net::TcpListener::bind(addr).await.unwrap();
+}
+
+#[test]
+fn shuts_down_server_gracefully() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_server_shutdown())
+}
+
+#[cfg(feature = "tls")]
+async fn test_server_shutdown_during_tls_handshake() {
+    let addr = SocketAddr::from_str("127.0.0.1:40023").unwrap();
+    let certs = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../certs/self_signed");
+    let tls_config = TlsServerConfig::new(
+        &certs.join("entity1_cert.pem"),
+        &certs.join("entity2_cert.pem"),
+        &certs.join("entity2_key.pem"),
+        None,
+        MinTlsVersion::V1_2,
+        CertificateMode::SelfSigned,
+    )
+    .unwrap();
+
+    let server = spawn_tls_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap()),
+        tls_config,
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    // connect, but never send a ClientHello
+    let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+    // give the server time to accept the connection and start the handshake
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    tokio::time::timeout(Duration::from_secs(5), server.shutdown())
+        .await
+        .unwrap();
+
+    // the session closed the connection
+    let mut buffer = [0u8; 1];
+    use tokio::io::AsyncReadExt;
+    assert_eq!(socket.read(&mut buffer).await.unwrap(), 0);
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn shuts_down_server_with_a_stalled_tls_handshake() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_server_shutdown_during_tls_handshake())
+}
+
+async fn test_rate_limit() {
+    let addr = SocketAddr::from_str("127.0.0.1:40006").unwrap();
+
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_session_rate_limit(Some(RateLimit::new(0.001, 2)));
+
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    let range = AddressRange::try_from(0, 1).unwrap();
+
+    assert!(channel.read_coils(params, range).await.is_ok());
+    assert!(channel.read_coils(params, range).await.is_ok());
+    assert_eq!(
+        channel.read_coils(params, range).await,
+        Err(RequestError::Exception(ExceptionCode::ServerDeviceBusy))
+    );
+}
+
+#[test]
+fn rejects_requests_above_rate_limit() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_rate_limit())
+}
+
+struct IdentifiedHandler {
+    identification: DeviceIdentification,
+}
+
+impl RequestHandler for IdentifiedHandler {
+    fn read_device_identification(&self) -> Result<&DeviceIdentification, ExceptionCode> {
+        Ok(&self.identification)
+    }
+}
+
+async fn test_device_identification() {
+    let addr = SocketAddr::from_str("127.0.0.1:40007").unwrap();
+
+    let mut identification = DeviceIdentification::default();
+    identification
+        .objects
+        .insert(object_id::VENDOR_NAME, b"Step Function".to_vec());
+    identification
+        .objects
+        .insert(object_id::PRODUCT_CODE, b"RODBUS".to_vec());
+    identification
+        .objects
+        .insert(object_id::MAJOR_MINOR_REVISION, b"1.5".to_vec());
+    // too large to fit in a single response
+    for id in 0x80..0x84 {
+        identification.objects.insert(id, vec![id; 100]);
+    }
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(
+            UnitId::new(1),
+            IdentifiedHandler {
+                identification: identification.clone(),
+            }
+            .wrap(),
+        ),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    let basic = channel
+        .read_device_identification(params, DeviceIdCategory::Basic)
+        .await
+        .unwrap();
+    assert_eq!(basic.conformity_level, 0x83);
+    assert_eq!(basic.objects.len(), 3);
+    assert_eq!(basic.vendor_name(), Some("Step Function"));
+
+    let extended = channel
+        .read_device_identification(params, DeviceIdCategory::Extended)
+        .await
+        .unwrap();
+    assert_eq!(extended.objects, identification.objects);
+}
+
+#[test]
+fn reads_device_identification_across_responses() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_device_identification())
+}
+
+async fn test_diagnostic_counters() {
+    let addr = SocketAddr::from_str("127.0.0.1:40008").unwrap();
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    channel
+        .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        channel.read_exception_status(params).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalFunction))
+    );
+
+    // only the read completed without an exception
+    assert_eq!(
+        channel.get_comm_event_counter(params).await.unwrap(),
+        CommEventCounter::new(0x0000, 1)
+    );
+    // the request reading the counter is included
+    assert_eq!(
+        channel
+            .read_diagnostic_counter(params, DiagnosticCounter::BusMessage)
+            .await
+            .unwrap(),
+        4
+    );
+    assert_eq!(
+        channel
+            .read_diagnostic_counter(params, DiagnosticCounter::BusExceptionError)
+            .await
+            .unwrap(),
+        1
+    );
+
+    channel.clear_diagnostic_counters(params).await.unwrap();
+    assert_eq!(
+        channel
+            .read_diagnostic_counter(params, DiagnosticCounter::BusMessage)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        channel
+            .read_diagnostic_counter(params, DiagnosticCounter::BusExceptionError)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // the log isn't cleared, and the request reading it has only been received so far
+    let log = channel.get_comm_event_log(params).await.unwrap();
+    assert_eq!(log.message_count, 3);
+    assert_eq!(log.events.len(), 2 * 8 + 1);
+    assert_eq!(log.events[0], CommEvent::Receive(ReceiveEvent::default()));
+    assert!(log.events.contains(&CommEvent::Send(SendEvent {
+        read_exception: true,
+        ..Default::default()
+    })));
+}
+
+#[test]
+fn maintains_diagnostic_counters() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_diagnostic_counters())
+}
+
+async fn test_fifo_queue() {
+    let addr = SocketAddr::from_str("127.0.0.1:40009").unwrap();
+
+    let db = DatabaseHandle::new(DatabaseConfig::default());
+    db.transaction(|db| {
+        db.add_fifo_queue(0x04DE);
+        db.push_fifo(0x04DE, 0x01B8);
+        db.push_fifo(0x04DE, 0x1284);
+    });
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), db.handler()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    assert_eq!(
+        channel.read_fifo_queue(params, 0x04DE).await.unwrap(),
+        vec![0x01B8, 0x1284]
+    );
+    // reading the queue drains it
+    assert_eq!(
+        channel.read_fifo_queue(params, 0x04DE).await.unwrap(),
+        Vec::<u16>::new()
+    );
+    assert_eq!(
+        channel.read_fifo_queue(params, 0x0000).await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataAddress))
+    );
+}
+
+#[test]
+fn drains_fifo_queues() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_fifo_queue())
+}
+
+async fn test_file_records() {
+    let addr = SocketAddr::from_str("127.0.0.1:40010").unwrap();
+
+    let db = DatabaseHandle::new(DatabaseConfig::default());
+    db.transaction(|db| {
+        db.add_file(4, 20);
+        db.add_file(3, 20);
+        db.set_file_record(4, 1, 0x0DFE);
+        db.set_file_record(4, 2, 0x0020);
+    });
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), db.handler()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    channel
+        .write_file_records(params, vec![FileRecord::new(3, 9, vec![0x33CD, 0x0040])])
+        .await
+        .unwrap();
+    assert_eq!(
+        channel
+            .read_file_records(
+                params,
+                vec![FileRecordRange::new(4, 1, 2), FileRecordRange::new(3, 9, 2)]
+            )
+            .await
+            .unwrap(),
+        vec![
+            FileRecord::new(4, 1, vec![0x0DFE, 0x0020]),
+            FileRecord::new(3, 9, vec![0x33CD, 0x0040]),
+        ]
+    );
+    assert_eq!(
+        channel
+            .read_file_records(params, vec![FileRecordRange::new(5, 0, 1)])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataAddress))
+    );
+    assert_eq!(
+        channel
+            .write_file_records(params, vec![FileRecord::new(4, 19, vec![0x0001, 0x0002])])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataAddress))
+    );
+}
+
+#[test]
+fn reads_and_writes_file_records() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_file_records())
+}
+
+async fn test_lenient_coil_values() {
+    let addr = SocketAddr::from_str("127.0.0.1:40011").unwrap();
+
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_lenient_coil_values(true);
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+
+    // the response contains the value that was written
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x05, &[0x00, 0x02, 0x00, 0x01])
+            .await
+            .unwrap(),
+        vec![0x00, 0x02, 0xFF, 0x00]
+    );
+    assert_eq!(
+        channel
+            .read_coils(params, AddressRange::try_from(2, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(2, true)]
+    );
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x05, &[0x00, 0x02, 0x00, 0x02])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataValue))
+    );
+}
+
+#[test]
+fn accepts_lenient_coil_values() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_lenient_coil_values())
+}
+
+async fn test_response_delay() {
+    let addr = SocketAddr::from_str("127.0.0.1:40012").unwrap();
+
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_response_delay(0x03, Some(ResponseDelay::Fixed(Duration::from_millis(500))));
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_millis(100));
+
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await,
+        Err(RequestError::ResponseTimeout)
+    );
+    // other function codes aren't delayed, but the session answers requests in order
+    // so the next response is only sent once the delayed one is
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    assert_eq!(
+        channel
+            .read_input_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0)]
+    );
+}
+
+#[test]
+fn delays_responses() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_response_delay())
+}
+
+struct Observer {
+    tx: tokio::sync::mpsc::UnboundedSender<String>,
+}
+
+impl ServerObserver for Observer {
+    fn connection_accepted(&self, peer: &Peer) {
+        let _ = self.tx.send(format!("accepted {}", peer.addr().is_some()));
+    }
+
+    fn connection_closed(&self, _peer: &Peer, reason: &CloseReason) {
+        let _ = self.tx.send(format!("closed {reason:?}"));
+    }
+
+    fn request_handled(&self, _peer: &Peer, function: u8, outcome: RequestOutcome) {
+        let _ = self.tx.send(format!("{function:#04X} {outcome:?}"));
+    }
+}
+
+async fn test_server_observer() {
+    let addr = SocketAddr::from_str("127.0.0.1:40013").unwrap();
+
+    let (events_tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_observer(Observer { tx: events_tx }.wrap());
+    let server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    channel
+        .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        channel
+            .read_coils(params, AddressRange::try_from(10, 1).unwrap())
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataAddress))
+    );
+    // a coil value other than ON or OFF
+    assert_eq!(
+        channel
+            .send_raw_pdu(params, 0x05, &[0x00, 0x00, 0x00, 0x01])
+            .await,
+        Err(RequestError::Exception(ExceptionCode::IllegalDataValue))
+    );
+    // shuts down the client task, which closes the connection
+    drop(channel);
+
+    let mut expected = vec![
+        "accepted true",
+        "0x01 Response",
+        "0x01 Exception(IllegalDataAddress)",
+        "0x05 Exception(IllegalDataValue)",
+        "closed ClientDisconnected",
+    ];
+    expected.reverse();
+    while let Some(event) = expected.pop() {
+        assert_eq!(events.recv().await.unwrap(), event);
+    }
+
+    let metrics = server.metrics();
+    let client = Some(addr.ip());
+    assert_eq!(
+        metrics.get(client, 0x01),
+        FunctionMetrics {
+            requests: 2,
+            exceptions: 1,
+            malformed: 0
+        }
+    );
+    assert_eq!(
+        metrics.get(client, 0x05),
+        FunctionMetrics {
+            requests: 1,
+            exceptions: 1,
+            malformed: 1
+        }
+    );
+    assert_eq!(metrics.function(0x03), FunctionMetrics::default());
+}
+
+#[test]
+fn reports_connections_and_requests_to_observer() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_server_observer())
+}
+
+async fn test_idle_timeout() {
+    let addr = SocketAddr::from_str("127.0.0.1:40014").unwrap();
+
+    let (events_tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_observer(Observer { tx: events_tx }.wrap());
+    let mut server =
+        spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+            .await
+            .unwrap();
+    server
+        .set_idle_timeout(Some(Duration::from_millis(200)))
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    channel
+        .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+        .await
+        .unwrap();
+
+    // the client never polls again, so the server closes the connection
+    let mut expected = vec!["accepted true", "0x01 Response", "closed IdleTimeout"];
+    expected.reverse();
+    while let Some(event) = expected.pop() {
+        assert_eq!(events.recv().await.unwrap(), event);
+    }
+    // the client notices that the server closed the connection
+    loop {
+        if let ClientState::WaitAfterDisconnect(_) = rx.recv().await.unwrap() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn closes_idle_connections() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_idle_timeout())
+}
+
+async fn test_unit_registry() {
+    let addr = SocketAddr::from_str("127.0.0.1:40015").unwrap();
+
+    let mut map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    map.set_unmapped_unit_id_policy(UnmappedUnitIdPolicy::RespondGatewayTargetFailed);
+    let registry = map.registry();
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until we're connected
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let range = AddressRange::try_from(0, 1).unwrap();
+    let unit = |id| RequestParam::new(UnitId::new(id), Duration::from_secs(1));
+    let unmapped = Err(RequestError::Exception(
+        ExceptionCode::GatewayTargetDeviceFailedToRespond,
+    ));
+
+    assert_eq!(channel.read_coils(unit(2), range).await, unmapped);
+
+    // the running session picks up the new unit id
+    registry.add(UnitId::new(2), Handler::new().wrap());
+    assert_eq!(
+        channel.read_coils(unit(2), range).await.unwrap(),
+        vec![Indexed::new(0, false)]
+    );
+
+    assert!(registry.remove(UnitId::new(1)).is_some());
+    assert_eq!(channel.read_coils(unit(1), range).await, unmapped);
+}
+
+#[test]
+fn adds_and_removes_unit_ids_at_runtime() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_unit_registry())
+}
+
+async fn test_udp_server() {
+    let addr = SocketAddr::from_str("127.0.0.1:40016").unwrap();
+
+    let map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    let _server = spawn_udp_server_task(addr, map, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    // a truncated ADU is discarded instead of being completed by the next datagram
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.send_to(&[0x00, 0x07, 0x00], addr).await.unwrap();
+    // so are the bytes that follow the ADU in a datagram
+    socket
+        .send_to(
+            &[
+                0x00, 0x07, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0xFF, 0xFF,
+            ],
+            addr,
+        )
+        .await
+        .unwrap();
+    let mut response = [0u8; 260];
+    let (count, _) = socket.recv_from(&mut response).await.unwrap();
+    assert_eq!(
+        &response[..count],
+        &[0x00, 0x07, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00]
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let mut channel = spawn_udp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        0,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(ClientStateListener { tx })),
+    );
+    channel.enable().await.unwrap();
+
+    // wait until the socket is open
+    loop {
+        if rx.recv().await.unwrap() == ClientState::Connected {
+            break;
+        }
+    }
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    channel
+        .write_single_register(params, Indexed::new(0, 0xCAFE))
+        .await
+        .unwrap();
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, 0xCAFE)]
+    );
+}
+
+#[test]
+fn answers_requests_over_udp() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_udp_server())
+}
+
+async fn test_polls() {
+    let addr = SocketAddr::from_str("127.0.0.1:40017").unwrap();
+
+    let mut handler = Handler::new();
+    handler.holding_registers[1] = 0xCAFE;
+    let map = ServerHandlerMap::single(UnitId::new(1), handler.wrap());
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        None,
+    );
+    channel.enable().await.unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let polls = PollHandle::spawn(channel.clone(), Box::new(tx));
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    let registers = polls
+        .add_poll(
+            params,
+            PollRequest::ReadHoldingRegisters(AddressRange::try_from(1, 1).unwrap()),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+    let coils = polls
+        .add_poll(
+            params,
+            PollRequest::ReadCoils(AddressRange::try_from(0, 1).unwrap()),
+            Duration::from_secs(3600),
+        )
+        .await
+        .unwrap();
+    polls.disable_poll(coils).await.unwrap();
+
+    // the registers are polled repeatedly
+    for _ in 0..3 {
+        let (id, result) = rx.recv().await.unwrap();
+        assert_eq!(id, registers);
+        assert_eq!(
+            result,
+            Ok(PollValues::Registers(vec![Indexed::new(1, 0xCAFE)]))
+        );
+    }
+
+    // a disabled poll can still be performed on demand
+    polls.remove_poll(registers).await.unwrap();
+    polls.poll_now(coils).await.unwrap();
+    let result = loop {
+        match rx.recv().await.unwrap() {
+            (id, _) if id == registers => continue,
+            (id, result) => {
+                assert_eq!(id, coils);
+                break result;
+            }
+        }
+    };
+    assert_eq!(result, Ok(PollValues::Bits(vec![Indexed::new(0, false)])));
+
+    // a poll task bound to a cancellation token stops when it's cancelled
+    let token = tokio_util::sync::CancellationToken::new();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let polls = PollHandle::spawn_until(
+        channel.clone(),
+        Box::new(tx),
+        token.clone().cancelled_owned(),
+    );
+    polls
+        .add_poll(
+            params,
+            PollRequest::ReadCoils(AddressRange::try_from(0, 1).unwrap()),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+    assert!(rx.recv().await.unwrap().1.is_ok());
+    token.cancel();
+    while rx.recv().await.is_some() {}
+    let mut channel = channel;
+    assert_eq!(
+        channel
+            .read_holding_registers(params, AddressRange::try_from(1, 1).unwrap())
+            .await,
+        Ok(vec![Indexed::new(1, 0xCAFE)])
+    );
+}
+
+#[test]
+fn performs_recurring_polls() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_polls())
+}
+
+#[test]
+fn performs_blocking_requests() {
+    let addr = SocketAddr::from_str("127.0.0.1:40018").unwrap();
+
+    let rt = Runtime::new().unwrap();
+    let map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    let _server = rt
+        .block_on(spawn_tcp_server_task(
+            1,
+            addr,
+            map,
+            AddressFilter::Any,
+            DecodeLevel::default(),
+        ))
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::watch::channel(ClientState::Disabled);
+    let mut channel = rodbus::blocking::SyncChannel::new(|| {
+        spawn_tcp_client_task(
+            HostAddr::ip(addr.ip(), addr.port()),
+            10,
+            default_retry_strategy(),
+            DecodeLevel::default(),
+            Some(Box::new(tx)),
+        )
+    })
+    .unwrap();
+    channel.enable().unwrap();
+    channel
+        .block_on(rx.wait_for(|x| *x == ClientState::Connected))
+        .unwrap();
+
+    let mut session = channel.session(RequestParam::new(UnitId::new(1), Duration::from_secs(1)));
+    session
+        .write_multiple_registers(WriteMultiple::from(2, vec![0xCAFE, 0xBEEF]).unwrap())
+        .unwrap();
+    assert_eq!(
+        session
+            .read_holding_registers(AddressRange::try_from(2, 2).unwrap())
+            .unwrap(),
+        vec![Indexed::new(2, 0xCAFE), Indexed::new(3, 0xBEEF)]
+    );
+}
+
+async fn test_lazy_connection() {
+    let addr = SocketAddr::from_str("127.0.0.1:40019").unwrap();
+
+    let map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::watch::channel(ClientState::Disabled);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(tx)),
+    );
+    channel.set_connect_mode(ConnectMode::Lazy).await.unwrap();
+    channel.enable().await.unwrap();
+
+    // nothing is requested, so the channel doesn't connect
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(*rx.borrow_and_update(), ClientState::Disabled);
+
+    // the first request establishes the connection and is then performed
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    assert_eq!(
+        channel
+            .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+            .await
+            .unwrap(),
+        vec![Indexed::new(0, false)]
+    );
+    assert_eq!(*rx.borrow_and_update(), ClientState::Connected);
+
+    // a disabled channel fails requests right away
+    channel.disable().await.unwrap();
+    assert_eq!(
+        channel
+            .read_coils(params, AddressRange::try_from(0, 1).unwrap())
+            .await,
+        Err(RequestError::NoConnection)
+    );
+}
+
+#[test]
+fn connects_lazily() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_lazy_connection())
+}
+
+async fn test_disconnected_policy() {
+    let addr = SocketAddr::from_str("127.0.0.1:40020").unwrap();
+
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        doubling_retry_strategy(Duration::from_millis(50), Duration::from_millis(50)),
+        DecodeLevel::default(),
+        None,
+    );
+    channel
+        .set_disconnected_policy(DisconnectedPolicy::Queue {
+            max_queued: 1,
+            max_age: Duration::from_secs(5),
+        })
+        .await
+        .unwrap();
+    channel.enable().await.unwrap();
+
+    // the server isn't up yet, so the first request is queued and the next one fails
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    let range = AddressRange::try_from(0, 1).unwrap();
+    let mut queued = channel.clone();
+    let queued = tokio::spawn(async move { queued.read_coils(params, range).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        channel.read_coils(params, range).await,
+        Err(RequestError::NoConnection)
+    );
+
+    let map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+    assert_eq!(queued.await.unwrap().unwrap(), vec![Indexed::new(0, false)]);
+}
+
+#[test]
+fn queues_requests_while_disconnected() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_disconnected_policy())
+}
+
+async fn test_link_down() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let addr = SocketAddr::from_str("127.0.0.1:40021").unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+
+    let (tx, mut rx) = tokio::sync::watch::channel(ClientState::Disabled);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(tx)),
+    );
+    channel.enable().await.unwrap();
+
+    // answer the request with a frame of an unknown protocol
+    let (mut socket, _) = listener.accept().await.unwrap();
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    let range = AddressRange::try_from(0, 1).unwrap();
+    let mut first = channel.clone();
+    let first = tokio::spawn(async move { first.read_coils(params, range).await });
+    let mut request = [0u8; 12];
+    socket.read_exact(&mut request).await.unwrap();
+    socket
+        .write_all(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00])
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        first.await.unwrap(),
+        Err(RequestError::BadFrame(_))
+    ));
+    rx.wait_for(|x| matches!(x, ClientState::WaitAfterDisconnect(_)))
+        .await
+        .unwrap();
+    // the requests made while waiting to reconnect fail right away
+    assert_eq!(
+        channel.read_coils(params, range).await,
+        Err(RequestError::NoConnection)
+    );
+    // and the socket is closed before the next attempt
+    let read = tokio::time::timeout(Duration::from_millis(500), socket.read(&mut request));
+    assert_eq!(read.await.unwrap().unwrap(), 0);
+}
+
+#[test]
+fn closes_the_socket_when_the_link_is_down() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_link_down())
+}
+
+async fn test_idle_disconnect() {
+    let addr = SocketAddr::from_str("127.0.0.1:40022").unwrap();
+
+    let map = ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap());
+    let _server = spawn_tcp_server_task(1, addr, map, AddressFilter::Any, DecodeLevel::default())
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::watch::channel(ClientState::Disabled);
+    let mut channel = spawn_tcp_client_task(
+        HostAddr::ip(addr.ip(), addr.port()),
+        10,
+        default_retry_strategy(),
+        DecodeLevel::default(),
+        Some(Box::new(tx)),
+    );
+    channel
+        .set_idle_disconnect(Some(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    channel.enable().await.unwrap();
+    rx.wait_for(|x| *x == ClientState::Connected).await.unwrap();
+
+    let params = RequestParam::new(UnitId::new(1), Duration::from_secs(1));
+    let range = AddressRange::try_from(0, 1).unwrap();
+    assert!(channel.read_coils(params, range).await.is_ok());
+
+    // the connection is closed and only re-established by the next request
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(channel.statistics().reconnects, 0);
+    assert!(channel.read_coils(params, range).await.is_ok());
+    assert_eq!(channel.statistics().reconnects, 1);
+}
+
+#[test]
+fn closes_idle_client_connections() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_idle_disconnect())
+}
+
+async fn test_connection_events() {
+    let addr = SocketAddr::from_str("127.0.0.1:40024").unwrap();
+
+    let _server = spawn_tcp_server_task(
+        1,
+        addr,
+        ServerHandlerMap::single(UnitId::new(1), Handler::new().wrap()),
+        AddressFilter::Any,
+        DecodeLevel::default(),
+    )
+    .await
+    .unwrap();
+
+    let host = HostAddr::ip(addr.ip(), addr.port());
+    let mut channel = spawn_tcp_client_task(
+        host.clone(),
+        10,
+        Box::new(FixedDelay(Duration::from_secs(60))),
+        DecodeLevel::default(),
+        None,
+    );
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    channel
+        .set_connection_listener(Box::new(ConnectionEventListener { tx }))
+        .await
+        .unwrap();
+    channel.enable().await.unwrap();
+
+    assert_eq!(rx.recv().await.unwrap(), ConnectionEvent::Connecting(host));
+    assert_eq!(rx.recv().await.unwrap(), ConnectionEvent::Connected(addr));
+
+    channel.disable().await.unwrap();
+    assert_eq!(
+        rx.recv().await.unwrap(),
+        ConnectionEvent::Disconnected(DisconnectReason::Disabled)
+    );
+    assert_eq!(
+        rx.recv().await.unwrap(),
+        ConnectionEvent::WaitingToRetry(Duration::from_secs(60))
+    );
+}
+
+#[test]
+fn reports_connection_events_with_peer_and_reason() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(test_connection_events())
+}